@@ -0,0 +1,43 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use tela::uri::{index, index_patterns, Pattern};
+
+/// Routes like a small app's, with the catch all last so most patterns are tried
+fn routes() -> Vec<String> {
+    [
+        "/",
+        "/about",
+        "/blog",
+        "/blog/:slug",
+        "/blog/:slug/comments/:id<uint>",
+        "/users/:id<uuid>",
+        "/users/:id<uuid>/settings",
+        "/api/v1/items/:id",
+        "/api/v1/items/:id/tags/:tag",
+        "/files/:...path/raw",
+        "/:...rest",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+fn matching(c: &mut Criterion) {
+    let routes = routes();
+    let patterns: Vec<Pattern> = routes
+        .iter()
+        .map(|route| Pattern::parse(route).unwrap())
+        .collect();
+    let uri = "/blog/hello-world/comments/42".to_string();
+
+    let mut group = c.benchmark_group("match");
+    group.bench_function("strings", |b| {
+        b.iter(|| index(black_box(&uri), black_box(&routes)))
+    });
+    group.bench_function("patterns", |b| {
+        b.iter(|| index_patterns(black_box(&uri), black_box(&patterns)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, matching);
+criterion_main!(benches);
@@ -2,8 +2,9 @@ extern crate tela;
 
 use serde::{Deserialize, Serialize};
 use tela::{
+    layer::DebugPanel,
     prelude::*,
-    request::{Body, Query},
+    request::{Body, Form, FormErrors, HtmlForm, Query},
     response::{HTML, JSON},
     Server,
 };
@@ -17,6 +18,10 @@ use tela::{
 /// the uri, or until the next static part of the uri. A single capture can not follow a catch all
 /// capture.
 ///
+/// A capture can also require a type, `:id<uuid>`, so the route only matches when the value has
+/// that type. `int`, `uint`, `float`, `bool`, and `uuid` are built in and more can be added with
+/// `tela::uri::register_type` and `tela::uri::register_enum`.
+///
 /// Captures can be wrapped in Option or Result to prevent automatic failure and 500 error when the
 /// capture is attempting to be parsed to it's respective type. Option will give None if it fails
 /// while Result will give a tuple, `(code, message)`, where it can be returned in a Result
@@ -50,13 +55,14 @@ pub fn query(Query(q): Query<String>) -> HTML<String> {
 }
 
 /// Used for the optional_query endpoint
-/// Default is required for debug mode to display a rich error page.
-#[derive(Deserialize, Serialize, Default)]
+#[derive(Deserialize, Serialize)]
 struct UserQuery {
     name: String,
 }
 /// This endpoints shows additional ways of using the Query parameter. See `query` endpoint for
 /// base usage.
+///
+/// `Result<Query<T>, QueryError>` can be used instead to inspect why the query failed to parse.
 #[get("/api/optional-query")]
 pub fn optional_query(q: Result<Query<UserQuery>>) -> Result<JSON<UserQuery>> {
     q.map(|Query(q)| response!(JSON(q)))?
@@ -96,6 +102,36 @@ pub fn optional_body(b: Result<Body<u32>>) -> Result<HTML<String>> {
     })
 }
 
+/// Deriving HtmlForm next to Deserialize renders the struct as form inputs with the same
+/// names that `Form` parses the request body from. Errors are shown next to their input.
+#[derive(Deserialize, Default, HtmlForm)]
+struct Signup {
+    username: String,
+    #[form(label = "Pick a password", input = "password")]
+    password: String,
+    age: Option<u8>,
+}
+
+#[get("/signup")]
+fn signup() -> HTML<String> {
+    HTML(Signup::default().render("/signup", &FormErrors::new()))
+}
+
+/// Form parses a `application/x-www-form-urlencoded` body. Like Body and Query it can be
+/// wrapped in an Option or Result.
+#[post("/signup")]
+fn submit_signup(Form(form): Form<Signup>) -> HTML<String> {
+    let mut errors = FormErrors::new();
+    if form.username.is_empty() {
+        errors.insert("username", "Username is required");
+    }
+
+    match errors.is_empty() {
+        true => html! { <h4>"Welcome "{form.username}</h4> },
+        false => HTML(form.render("/signup", &errors)),
+    }
+}
+
 #[get("/")]
 fn home() -> HTML<String> {
     html! {
@@ -143,12 +179,15 @@ fn home() -> HTML<String> {
 #[tela::main]
 async fn main() {
     Server::new()
+        // Inspect recent requests at http://localhost:3000/_tela/debug
+        .layer(DebugPanel::new(50))
         //                GET    POST
         // .route(group![blog, get_blog])
         .route(home)
         .route(uri_capture)
         .routes(group![query, optional_query])
         .routes(group![_body, optional_body])
+        .routes(group![signup, submit_signup])
         .serve(3000)
         .await
 }
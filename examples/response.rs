@@ -2,7 +2,7 @@ extern crate tela;
 
 use tela::{
     prelude::*,
-    response::{Raw, Redirect, HTML, JSON},
+    response::{Blocks, Raw, Redirect, HTML, JSON},
     Server, StatusCode,
 };
 
@@ -50,6 +50,36 @@ pub fn html() -> HTML<String> {
     }
 }
 
+/// Layouts are functions that take the named blocks of a page and place them in shared markup.
+/// `Blocks::get_or` can be used to give a block default markup when a page doesn't override it.
+fn base(blocks: &Blocks) -> HTML<String> {
+    html! {
+        <!DOCTYPE html>
+        <html lang="en">
+            <head>
+                <title>{blocks.get_or("title", "Tela")}</title>
+            </head>
+            <body>
+                <nav><a href="/">"Home"</a></nav>
+                {blocks.get("content")}
+            </body>
+        </html>
+    }
+}
+
+/// The `html!` macro can extend a layout. Start the markup with `<extends layout={...} />`
+/// and follow it with named `<block>` elements to fill in the layout.
+#[get("/layout")]
+pub fn layout() -> HTML<String> {
+    html! {
+        <extends layout={base} />
+        <block name="title">"Layout"</block>
+        <block name="content">
+            <h1>"Rendered inside of the base layout"</h1>
+        </block>
+    }
+}
+
 #[tela::main]
 async fn main() {
     Server::new()
@@ -61,6 +91,8 @@ async fn main() {
         .route(json)
         // `/`
         .route(html)
+        // `/layout`
+        .route(layout)
         .serve(3000)
         .await
 }
@@ -0,0 +1,392 @@
+use proc_macro2::{Delimiter, Ident, Span, TokenStream as TokenStream2, TokenTree};
+use proc_macro_error::abort;
+use quote::{quote, quote_spanned};
+use syn::{spanned::Spanned, LitStr};
+
+use crate::tags::{self, Tag, SVG_NAMESPACE};
+
+/// Elements that never have children and don't need to be closed
+const VOID: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+fn is_punct(token: Option<&TokenTree>, ch: char) -> bool {
+    matches!(token, Some(TokenTree::Punct(p)) if p.as_char() == ch)
+}
+
+enum Value {
+    Text(LitStr),
+    Expr(TokenStream2),
+}
+
+struct Attribute {
+    name: String,
+    span: Span,
+    value: Option<Value>,
+}
+
+enum Prop {
+    Attribute(Attribute),
+    /// `{..attributes}`; every attribute from the iterator
+    Spread(TokenStream2),
+}
+
+enum Node {
+    Element {
+        name: String,
+        span: Span,
+        /// Created in the SVG namespace
+        svg: bool,
+        attributes: Vec<Prop>,
+        children: Vec<Node>,
+    },
+    Text(LitStr),
+    Expr(TokenStream2),
+}
+
+struct Parser {
+    tokens: Vec<TokenTree>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self, offset: usize) -> Option<&TokenTree> {
+        self.tokens.get(self.index + offset)
+    }
+
+    fn span(&self) -> Span {
+        match self.peek(0).or(self.tokens.last()) {
+            Some(token) => token.span(),
+            None => Span::call_site(),
+        }
+    }
+
+    /// Parse a dashed or namespaced name; ex: `my-element` or `on:click`
+    fn name(&mut self) -> (String, Span) {
+        let (mut name, span) = match self.peek(0) {
+            Some(TokenTree::Ident(ident)) => (ident.to_string(), ident.span()),
+            _ => abort!(self.span(), "Expected a name"),
+        };
+        self.index += 1;
+
+        while (is_punct(self.peek(0), '-') || is_punct(self.peek(0), ':'))
+            && matches!(self.peek(1), Some(TokenTree::Ident(_)))
+        {
+            name.push_str(&self.peek(0).unwrap().to_string());
+            name.push_str(&self.peek(1).unwrap().to_string());
+            self.index += 2;
+        }
+        (name, span)
+    }
+
+    fn value(&mut self) -> Value {
+        let value = match self.peek(0) {
+            Some(TokenTree::Literal(literal)) => {
+                match syn::parse2::<LitStr>(TokenTree::Literal(literal.clone()).into()) {
+                    Ok(text) => Value::Text(text),
+                    Err(_) => Value::Expr(TokenTree::Literal(literal.clone()).into()),
+                }
+            }
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+                Value::Expr(g.stream())
+            }
+            _ => abort!(
+                self.span(),
+                "Expected a string or `{...}` as the attribute value"
+            ),
+        };
+        self.index += 1;
+        value
+    }
+
+    /// Parse the children of an element. `svg` is whether they are inside of an `<svg>`
+    fn nodes(&mut self, parent: Option<&str>, svg: bool) -> Vec<Node> {
+        let mut nodes = Vec::new();
+        while self.index < self.tokens.len() {
+            if is_punct(self.peek(0), '<') && is_punct(self.peek(1), '/') {
+                self.index += 2;
+                let (name, span) = self.name();
+                match parent {
+                    Some(parent) if parent == name => {}
+                    _ => abort!(span, format!("Unexpected closing tag `</{}>`", name)),
+                }
+                if !is_punct(self.peek(0), '>') {
+                    abort!(self.span(), "Expected `>`");
+                }
+                self.index += 1;
+                return nodes;
+            }
+
+            match self.peek(0).cloned() {
+                Some(TokenTree::Punct(p)) if p.as_char() == '<' => {
+                    self.index += 1;
+                    nodes.push(self.element(svg));
+                }
+                Some(TokenTree::Literal(literal)) => {
+                    match syn::parse2::<LitStr>(TokenTree::Literal(literal.clone()).into()) {
+                        Ok(text) => nodes.push(Node::Text(text)),
+                        Err(_) => nodes.push(Node::Expr(TokenTree::Literal(literal).into())),
+                    }
+                    self.index += 1;
+                }
+                Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+                    nodes.push(Node::Expr(g.stream()));
+                    self.index += 1;
+                }
+                Some(token) => abort!(token, "Expected an element, string, or `{...}`"),
+                None => break,
+            }
+        }
+
+        if let Some(parent) = parent {
+            abort!(self.span(), format!("Missing closing tag `</{}>`", parent));
+        }
+        nodes
+    }
+
+    /// Parse an element after its `<`. `svg` is whether its parent is inside of an `<svg>`
+    fn element(&mut self, svg: bool) -> Node {
+        let raw = is_punct(self.peek(0), '@')
+            && matches!(self.peek(1), Some(TokenTree::Ident(i)) if i == "raw");
+        if raw {
+            self.index += 2;
+        }
+        let (name, span) = self.name();
+        let tag = tags::check(&name, raw, span);
+        if tag == Tag::Svg && name != "svg" && !svg {
+            abort!(
+                span,
+                format!(
+                    "`<{}>` is an SVG element and must be inside of `<svg>`",
+                    name
+                )
+            );
+        }
+        // `foreignObject` is an SVG element whose children are html
+        let svg = svg || name == "svg";
+        let children_svg = svg && name != "foreignObject";
+        let mut attributes = Vec::new();
+
+        loop {
+            if is_punct(self.peek(0), '/') && is_punct(self.peek(1), '>') {
+                self.index += 2;
+                return Node::Element {
+                    name,
+                    span,
+                    svg,
+                    attributes,
+                    children: Vec::new(),
+                };
+            }
+            if is_punct(self.peek(0), '>') {
+                self.index += 1;
+                break;
+            }
+
+            if let Some(TokenTree::Group(g)) = self.peek(0) {
+                let tokens: Vec<TokenTree> = g.stream().into_iter().collect();
+                if g.delimiter() == Delimiter::Brace
+                    && is_punct(tokens.first(), '.')
+                    && is_punct(tokens.get(1), '.')
+                {
+                    if tokens.len() == 2 {
+                        abort!(g, "Expected the attributes to spread; ex: `{..attributes}`");
+                    }
+                    attributes.push(Prop::Spread(tokens[2..].iter().cloned().collect()));
+                    self.index += 1;
+                    continue;
+                }
+            }
+
+            let (attribute, span) = self.name();
+            let mut value = None;
+            if is_punct(self.peek(0), '=') {
+                self.index += 1;
+                value = Some(self.value());
+            }
+            attributes.push(Prop::Attribute(Attribute {
+                name: attribute,
+                span,
+                value,
+            }));
+        }
+
+        let children = if VOID.contains(&name.as_str()) {
+            Vec::new()
+        } else {
+            self.nodes(Some(&name), children_svg)
+        };
+
+        Node::Element {
+            name,
+            span,
+            svg,
+            attributes,
+            children,
+        }
+    }
+}
+
+/// Generate the code that creates a node, returning the name of the variable it is stored in
+fn create(node: &Node, count: &mut usize, code: &mut Vec<TokenStream2>) -> Ident {
+    *count += 1;
+    let var = Ident::new(&format!("__node{}", count), Span::call_site());
+
+    match node {
+        Node::Text(text) => code.push(quote! {
+            let #var = __document.create_text_node(#text);
+        }),
+        Node::Expr(expr) => code.push(quote! {
+            let #var = __document.create_text_node(&(#expr).to_string());
+        }),
+        Node::Element {
+            name,
+            span,
+            svg,
+            attributes,
+            children,
+        } => {
+            let tag = LitStr::new(name, *span);
+            code.push(match svg {
+                true => quote! {
+                    let #var = __document
+                        .create_element_ns(Some(#SVG_NAMESPACE), #tag)
+                        .unwrap_throw();
+                },
+                false => quote! {
+                    let #var = __document.create_element(#tag).unwrap_throw();
+                },
+            });
+
+            for prop in attributes {
+                let attribute = match prop {
+                    Prop::Attribute(attribute) => attribute,
+                    // Spanned so a value that can't be spread points at the spread
+                    Prop::Spread(spread) => {
+                        code.push(quote_spanned! {spread.span()=>
+                            ::tela_wasm::dom::spread(&#var, #spread);
+                        });
+                        continue;
+                    }
+                };
+                match attribute.name.strip_prefix("on:") {
+                    Some(event) => {
+                        let event = LitStr::new(event, attribute.span);
+                        match &attribute.value {
+                            Some(Value::Expr(handler)) => code.push(quote! {
+                                __view.listen(&#var, #event, #handler);
+                            }),
+                            _ => abort!(attribute.span, "Expected a `{...}` event handler"),
+                        }
+                    }
+                    None => {
+                        let name = LitStr::new(&attribute.name, attribute.span);
+                        code.push(match &attribute.value {
+                            Some(Value::Text(text)) => quote! {
+                                ::tela_wasm::dom::set_attribute(&#var, #name, Some(String::from(#text)));
+                            },
+                            Some(Value::Expr(expr)) => quote_spanned! {expr.span()=>
+                                ::tela_wasm::dom::set_attribute(
+                                    &#var,
+                                    #name,
+                                    ::tela_wasm::dom::Attribute::into_attribute(#expr),
+                                );
+                            },
+                            None => quote! {
+                                ::tela_wasm::dom::set_attribute(&#var, #name, Some(String::new()));
+                            },
+                        });
+                    }
+                }
+            }
+
+            for child in children {
+                let child = create(child, count, code);
+                code.push(quote! {
+                    #var.append_child(&#child).unwrap_throw();
+                });
+            }
+        }
+    }
+
+    var
+}
+
+/// Compile markup into code that builds a `tela_wasm::dom::View` of DOM nodes.
+///
+/// `on:event={handler}` attributes add event listeners that are owned by the view. Elements
+/// inside of `<svg>` are created in the SVG namespace, except for the children of
+/// `<foreignObject>` which are html.
+/// `{..attributes}` sets every attribute from an iterator of pairs; see
+/// `tela_wasm::dom::spread` for how attributes are merged.
+pub(crate) fn compile_dom(input: TokenStream2) -> TokenStream2 {
+    let mut parser = Parser {
+        tokens: input.into_iter().collect(),
+        index: 0,
+    };
+    let nodes = parser.nodes(None, false);
+
+    let mut count = 0;
+    let mut code = Vec::new();
+    for node in nodes.iter() {
+        let var = create(node, &mut count, &mut code);
+        code.push(quote! {
+            __view.append(&#var);
+        });
+    }
+
+    quote! {
+        {
+            use ::tela_wasm::bump::wasm_bindgen::UnwrapThrowExt;
+            let __document = ::tela_wasm::dom::document();
+            #[allow(unused_mut)]
+            let mut __view = ::tela_wasm::dom::View::new(&__document);
+            #(#code)*
+            __view
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Calls that create elements in the order they are made
+    fn created(input: &str) -> Vec<String> {
+        let code = compile_dom(input.parse().unwrap()).to_string();
+        code.split("let __node")
+            .skip(1)
+            .filter_map(|statement| {
+                let create = statement.split_once("= __document")?.1;
+                let create = create.split_once(';')?.0;
+                Some(
+                    create
+                        .replace([' ', '\n'], "")
+                        .replace(".unwrap_throw()", ""),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn svg_elements_are_namespaced() {
+        assert_eq!(
+            created(concat!(
+                r#"<svg><clipPath id="c"><rect/></clipPath>"#,
+                r#"<foreignObject><div>"Text"</div></foreignObject></svg>"#,
+                r#"<my-widget></my-widget><@raw widget/>"#,
+            )),
+            [
+                r#".create_element_ns(Some("http://www.w3.org/2000/svg"),"svg")"#,
+                r#".create_element_ns(Some("http://www.w3.org/2000/svg"),"clipPath")"#,
+                r#".create_element_ns(Some("http://www.w3.org/2000/svg"),"rect")"#,
+                r#".create_element_ns(Some("http://www.w3.org/2000/svg"),"foreignObject")"#,
+                r#".create_element("div")"#,
+                r#".create_text_node("Text")"#,
+                r#".create_element("my-widget")"#,
+                r#".create_element("widget")"#,
+            ]
+        );
+    }
+}
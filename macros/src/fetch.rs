@@ -0,0 +1,228 @@
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::quote;
+use syn::{
+    braced,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Expr, Ident, LitStr, Token,
+};
+
+use crate::url::compile_url;
+
+/// `"name": value` in `headers: { ... }`
+struct Header {
+    name: LitStr,
+    value: Expr,
+}
+
+impl Parse for Header {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![:]>()?;
+        Ok(Header {
+            name,
+            value: input.parse()?,
+        })
+    }
+}
+
+/// Options every `fetch!` accepts, in any order
+#[derive(Default)]
+struct Options {
+    method: Option<Ident>,
+    headers: Vec<Header>,
+    body: Option<Expr>,
+    sign: Option<Expr>,
+    client: Option<Expr>,
+}
+
+/// `fetch_request!(target path::to::Request; url, options...)`
+///
+/// `target` is `server` or `wasm`. The request type is built with `Request::new(url)` and the
+/// options are set with its builder methods before it is sent.
+struct Fetch {
+    request: TokenStream2,
+    url: TokenStream2,
+    options: Options,
+}
+
+impl Parse for Fetch {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let target: Ident = input.parse()?;
+        let request = input.step(|cursor| {
+            let mut tokens = TokenStream2::new();
+            let mut rest = *cursor;
+            while let Some((token, next)) = rest.token_tree() {
+                match &token {
+                    TokenTree::Punct(p) if p.as_char() == ';' => return Ok((tokens, next)),
+                    _ => tokens.extend([token]),
+                }
+                rest = next;
+            }
+            Err(cursor.error("Expected `;` after the request type"))
+        })?;
+
+        let url = match input.peek(LitStr) {
+            true => compile_url(input.parse()?),
+            false => {
+                let url: Expr = input.parse()?;
+                quote!(#url)
+            }
+        };
+
+        let mut options = Options::default();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let name: Ident = input.parse()?;
+            input.parse::<Token![:]>()?;
+
+            let duplicate = match name.to_string().as_str() {
+                "method" => options.method.replace(input.parse()?).is_some(),
+                "headers" => {
+                    let content;
+                    braced!(content in input);
+                    let headers = Punctuated::<Header, Token![,]>::parse_terminated(&content)?;
+                    let duplicate = !options.headers.is_empty();
+                    options.headers.extend(headers);
+                    duplicate
+                }
+                "body" => options.body.replace(input.parse()?).is_some(),
+                "sign" | "client" if target == "wasm" => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        format!(
+                            "`{}` is only supported by the server's `fetch!`; there is no secret or `Client` in the browser",
+                            name
+                        ),
+                    ))
+                }
+                "sign" => options.sign.replace(input.parse()?).is_some(),
+                "client" => options.client.replace(input.parse()?).is_some(),
+                _ => {
+                    return Err(syn::Error::new(
+                        name.span(),
+                        format!(
+                            "Unknown `fetch!` option `{}`; expected `method`, `headers`, `body`, `sign`, or `client`",
+                            name
+                        ),
+                    ))
+                }
+            };
+            if duplicate {
+                return Err(syn::Error::new(
+                    name.span(),
+                    format!("`{}` was already given", name),
+                ));
+            }
+        }
+
+        Ok(Fetch {
+            request,
+            url,
+            options,
+        })
+    }
+}
+
+/// Compile a `fetch!` invocation into a request that is built and sent
+pub fn compile_fetch(input: TokenStream2) -> TokenStream2 {
+    let fetch = match syn::parse2::<Fetch>(input) {
+        Ok(fetch) => fetch,
+        Err(error) => return error.to_compile_error(),
+    };
+    let Fetch {
+        request,
+        url,
+        options,
+    } = fetch;
+
+    let method = options.method.map(|method| {
+        let method = LitStr::new(&method.to_string(), method.span());
+        quote!(let __request = __request.method(#method);)
+    });
+    let headers = options
+        .headers
+        .iter()
+        .map(|Header { name, value }| quote!(let __request = __request.header(#name, #value);));
+    let body = options
+        .body
+        .map(|body| quote!(let __request = __request.body(#body);));
+    let sign = options
+        .sign
+        .map(|sign| quote!(let __request = __request.sign(&#sign);));
+    let client = options
+        .client
+        .map(|client| quote!(let __request = __request.client(&#client);));
+
+    quote! {{
+        let __request = #request::new(#url);
+        #method
+        #(#headers)*
+        #body
+        #sign
+        #client
+        __request.send()
+    }}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(target: &str, invocation: &str) -> syn::Result<Fetch> {
+        syn::parse_str(&format!("{} Request; {}", target, invocation))
+    }
+
+    #[test]
+    fn same_invocation_parses_on_both_targets() {
+        let invocation = r#"
+            "{base}/users/{id}",
+            method: POST,
+            headers: { "Accept": "application/json", "X-Id": id.to_string() },
+            body: serde_json::json!({ "name": "Tela" }),
+        "#;
+        for target in ["server", "wasm"] {
+            let fetch = parse(target, invocation).unwrap();
+            assert_eq!(fetch.options.method.unwrap().to_string(), "POST");
+            assert_eq!(fetch.options.headers.len(), 2);
+            assert!(fetch.options.body.is_some());
+        }
+        for target in ["server", "wasm"] {
+            assert!(parse(target, "url").unwrap().options.method.is_none());
+            assert!(parse(target, "format!(\"/users/{}\", id),").is_ok());
+        }
+    }
+
+    #[test]
+    fn options_can_be_in_any_order() {
+        let fetch = parse("server", r#""/hook", sign: hook, body: "{}", method: PUT"#).unwrap();
+        assert!(fetch.options.sign.is_some());
+        assert!(fetch.options.body.is_some());
+        assert!(fetch.options.method.is_some());
+    }
+
+    #[test]
+    fn server_only_options_are_rejected_in_the_browser() {
+        for option in ["sign: hook", "client: upstream"] {
+            let invocation = format!(r#""/hook", {}"#, option);
+            assert!(parse("server", &invocation).is_ok());
+            let error = parse("wasm", &invocation).err().unwrap();
+            assert!(error.to_string().contains("only supported by the server"));
+        }
+    }
+
+    #[test]
+    fn unknown_and_repeated_options_are_errors() {
+        let error = parse("server", r#""/", timeout: 5"#).err().unwrap();
+        assert!(error
+            .to_string()
+            .contains("Unknown `fetch!` option `timeout`"));
+        let error = parse("wasm", r#""/", method: GET, method: POST"#)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("`method` was already given"));
+    }
+}
@@ -0,0 +1,117 @@
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::abort;
+use quote::quote;
+use syn::{meta::ParseNestedMeta, Data, DeriveInput, Fields, LitStr, Token};
+
+/// Consume the value of a nested meta item that isn't used
+fn skip_meta(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|nested| skip_meta(&nested))?;
+    }
+    Ok(())
+}
+
+/// Default label from the field name; ex: `first_name` -> `First name`
+fn label(name: &str) -> String {
+    let name = name.trim_start_matches("r#").replace('_', " ");
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => name,
+    }
+}
+
+/// Implement `tela::request::HtmlForm` for a struct with named fields.
+///
+/// The input name matches the name serde deserializes the field from so the rendered form
+/// parses back into the struct with `Form<T>`.
+pub(crate) fn derive_html_form(input: DeriveInput) -> TokenStream2 {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect::<Vec<_>>(),
+            _ => abort!(
+                input.ident,
+                "HtmlForm can only be derived for structs with named fields"
+            ),
+        },
+        _ => abort!(input.ident, "HtmlForm can only be derived for structs"),
+    };
+
+    let mut inputs = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let mut name = ident.to_string().trim_start_matches("r#").to_string();
+        let mut label_text = label(&name);
+        let mut kind = None;
+        let mut skip = false;
+
+        for attr in field.attrs.iter() {
+            if attr.path().is_ident("serde") {
+                let result = attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("rename") && meta.input.peek(Token![=]) {
+                        name = meta.value()?.parse::<LitStr>()?.value();
+                        Ok(())
+                    } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing")
+                    {
+                        skip = true;
+                        Ok(())
+                    } else {
+                        skip_meta(&meta)
+                    }
+                });
+                if let Err(err) = result {
+                    abort!(attr, err.to_string());
+                }
+            } else if attr.path().is_ident("form") {
+                let result = attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("label") {
+                        label_text = meta.value()?.parse::<LitStr>()?.value();
+                    } else if meta.path.is_ident("input") {
+                        kind = Some(meta.value()?.parse::<LitStr>()?);
+                    } else if meta.path.is_ident("skip") {
+                        skip = true;
+                    } else {
+                        return Err(meta.error("Expected `label`, `input`, or `skip`"));
+                    }
+                    Ok(())
+                });
+                if let Err(err) = result {
+                    abort!(attr, err.to_string());
+                }
+            }
+        }
+
+        if skip {
+            continue;
+        }
+
+        let kind = match kind {
+            Some(kind) => quote!(#kind),
+            None => quote!(<#ty as ::tela::request::FormValue>::INPUT),
+        };
+        inputs.push(quote! {
+            __html.push_str(&::tela::request::render_input(
+                #name,
+                #label_text,
+                #kind,
+                ::tela::request::FormValue::form_value(&self.#ident),
+                errors.get(#name),
+            ));
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::tela::request::HtmlForm for #ident #ty_generics #where_clause {
+            fn inputs(&self, errors: &::tela::request::FormErrors) -> String {
+                let mut __html = String::new();
+                #(#inputs)*
+                __html
+            }
+        }
+    }
+}
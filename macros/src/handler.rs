@@ -0,0 +1,125 @@
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::emit_error;
+use quote::{quote, quote_spanned};
+use syn::{spanned::Spanned, FnArg, ItemFn, PatType, ReturnType, Type};
+
+use super::helpers::get_path_name;
+
+/// Most arguments a handler can take
+const MAX_ARGUMENTS: usize = 16;
+
+/// Extractors that read the request body
+const BODY: [&str; 8] = [
+    "Body",
+    "Form",
+    "Json",
+    "Bytes",
+    "BodyStream",
+    "JsonPatch",
+    "MergePatch",
+    "Protobuf",
+];
+
+/// Name of the extractor an argument uses; ex: `Form` for `Option<Form<User>>`
+fn extractor(ty: &Type) -> String {
+    match get_path_name(ty).as_str() {
+        "Option" | "Result" => match ty {
+            Type::Path(path) => match &path.path.segments.last().unwrap().arguments {
+                syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+                    Some(syn::GenericArgument::Type(inner)) => get_path_name(inner),
+                    _ => String::new(),
+                },
+                _ => String::new(),
+            },
+            _ => String::new(),
+        },
+        name => name.to_string(),
+    }
+}
+
+pub fn validate_handler(function: ItemFn) -> TokenStream2 {
+    let sig = &function.sig;
+
+    if let Some(asyncness) = &sig.asyncness {
+        emit_error!(
+            asyncness,
+            "Handlers can't be async; endpoints call them synchronously";
+            help = "Use `tela::sync::block_on` to wait on a future inside of the handler"
+        );
+    }
+    if !sig.generics.params.is_empty() {
+        emit_error!(
+            sig.generics,
+            "Handlers can't be generic; each one becomes a single endpoint"
+        );
+    }
+    if let ReturnType::Default = sig.output {
+        emit_error!(
+            sig.ident,
+            "Handlers must return a response";
+            help = "Return a type that implements `ToResponse`; ex: `-> String` or `-> Result<HTML<String>>`"
+        );
+    }
+
+    let arguments: Vec<&PatType> = sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            FnArg::Receiver(receiver) => {
+                emit_error!(receiver, "Handlers can't take `self`");
+                None
+            }
+            FnArg::Typed(arg) => Some(arg),
+        })
+        .collect();
+
+    if let Some(extra) = arguments.get(MAX_ARGUMENTS) {
+        emit_error!(
+            extra,
+            "Handlers can take at most {} arguments", MAX_ARGUMENTS;
+            help = "Group related values into one extractor; ex: a `Query<T>` struct"
+        );
+    }
+
+    let mut reader: Option<String> = None;
+    for (index, arg) in arguments.iter().enumerate() {
+        let name = extractor(&arg.ty);
+        if !BODY.contains(&name.as_str()) {
+            continue;
+        }
+        match &reader {
+            Some(first) => emit_error!(
+                arg.ty,
+                "Only one argument can read the request body; `{}` already reads it",
+                first
+            ),
+            None if index + 1 != arguments.len() => emit_error!(
+                arg.ty,
+                "`{}` reads the request body so it must be the last argument",
+                name
+            ),
+            None => {}
+        }
+        reader.get_or_insert(name);
+    }
+
+    // Checked where each argument is written so the error points at the argument
+    let send = arguments.iter().map(|arg| {
+        let ty = &arg.ty;
+        quote_spanned! {ty.span()=>
+            __assert_send::<#ty>();
+        }
+    });
+
+    quote! {
+        #function
+
+        const _: () = {
+            fn __assert_send<T: Send>() {}
+            #[allow(dead_code)]
+            fn __check() {
+                #(#send)*
+            }
+        };
+    }
+}
@@ -0,0 +1,868 @@
+use proc_macro2::{Delimiter, LineColumn, TokenStream as TokenStream2, TokenTree};
+use proc_macro_error::abort;
+use quote::quote;
+use syn::LitStr;
+
+use crate::tags;
+
+fn is_punct(token: Option<&TokenTree>, ch: char) -> bool {
+    matches!(token, Some(TokenTree::Punct(p)) if p.as_char() == ch)
+}
+
+fn is_ident(token: Option<&TokenTree>, name: &str) -> bool {
+    matches!(token, Some(TokenTree::Ident(i)) if i == name)
+}
+
+/// Check if the tokens at `index` open an element with the given tag. ex: `<block`
+///
+/// Custom elements and namespaced names that start with the tag are not matched;
+/// ex: `<pre-view>` or `<svg:rect>` do not open `pre` or `svg`.
+fn opens(tokens: &[TokenTree], index: usize, tag: &str) -> bool {
+    is_punct(tokens.get(index), '<')
+        && is_ident(tokens.get(index + 1), tag)
+        && name_end(tokens, index + 1) == index + 2
+}
+
+/// Find the end of an element or attribute name starting at `index`.
+///
+/// Names may be dashed, `my-element`, or namespaced, `svg:rect`.
+fn name_end(tokens: &[TokenTree], index: usize) -> usize {
+    let mut i = index + 1;
+    while (is_punct(tokens.get(i), '-') || is_punct(tokens.get(i), ':'))
+        && matches!(tokens.get(i + 1), Some(TokenTree::Ident(_)))
+    {
+        i += 2;
+    }
+    i
+}
+
+/// Check if the tokens at `index` close an element with the given tag. ex: `</block>`
+fn closes(tokens: &[TokenTree], index: usize, tag: &str) -> bool {
+    is_punct(tokens.get(index), '<')
+        && is_punct(tokens.get(index + 1), '/')
+        && is_ident(tokens.get(index + 2), tag)
+        && is_punct(tokens.get(index + 3), '>')
+}
+
+/// Parse `<extends layout={expr} />` followed by any number of
+/// `<block name="...">...</block>` elements.
+///
+/// Returns `None` if the markup doesn't start with an `extends` element.
+fn parse_extends(tokens: &[TokenTree]) -> Option<TokenStream2> {
+    if !opens(tokens, 0, "extends") {
+        return None;
+    }
+
+    let layout = match (tokens.get(2), tokens.get(3), tokens.get(4)) {
+        (Some(TokenTree::Ident(name)), Some(TokenTree::Punct(eq)), Some(TokenTree::Group(g)))
+            if name == "layout" && eq.as_char() == '=' && g.delimiter() == Delimiter::Brace =>
+        {
+            g.stream()
+        }
+        _ => abort!(
+            tokens[1],
+            "Expected `extends` to have a `layout={...}` attribute"
+        ),
+    };
+
+    if !(is_punct(tokens.get(5), '/') && is_punct(tokens.get(6), '>')) {
+        abort!(
+            tokens[1],
+            "Expected `extends` to be self closing; `<extends layout={...} />`"
+        );
+    }
+
+    let mut blocks = Vec::new();
+    let mut i = 7;
+    while i < tokens.len() {
+        if !opens(tokens, i, "block") {
+            abort!(
+                tokens[i],
+                "Only `<block name=\"...\">` elements may follow `<extends />`"
+            );
+        }
+
+        let name = match (tokens.get(i + 2), tokens.get(i + 3), tokens.get(i + 4)) {
+            (Some(TokenTree::Ident(attr)), Some(TokenTree::Punct(eq)), Some(lit))
+                if attr == "name" && eq.as_char() == '=' =>
+            {
+                match syn::parse2::<LitStr>(lit.clone().into()) {
+                    Ok(name) => name,
+                    Err(_) => abort!(lit, "Expected block name to be a string literal"),
+                }
+            }
+            _ => abort!(
+                tokens[i + 1],
+                "Expected `block` to have a `name=\"...\"` attribute"
+            ),
+        };
+
+        if !is_punct(tokens.get(i + 5), '>') {
+            abort!(tokens[i + 1], "Expected `>` after block name");
+        }
+
+        let start = i + 6;
+        let mut end = start;
+        while end < tokens.len() && !closes(tokens, end, "block") {
+            if opens(tokens, end, "block") {
+                abort!(tokens[end + 1], "Blocks can not be nested");
+            }
+            end += 1;
+        }
+        if end >= tokens.len() {
+            abort!(tokens[i + 1], "Missing closing `</block>`");
+        }
+
+        let content: TokenStream2 = tokens[start..end].iter().cloned().collect();
+        blocks.push(quote! {
+            .block(#name, ::tela::prelude::html_raw! { #content })
+        });
+        i = end + 4;
+    }
+
+    Some(quote! {
+        ::tela::response::HTML(
+            ::tela::response::Blocks::new()
+                #(#blocks)*
+                .extend(&(#layout))
+        )
+    })
+}
+
+/// Insert a `data-key={...}` attribute into the first element of the markup
+fn insert_key(tokens: &mut Vec<TokenTree>, key: TokenStream2) {
+    let position = (0..tokens.len()).find(|i| {
+        is_punct(tokens.get(*i), '<') && matches!(tokens.get(i + 1), Some(TokenTree::Ident(_)))
+    });
+
+    if let Some(i) = position {
+        let i = name_end(tokens, i + 1);
+        tokens.splice(i..i, quote!(data-key={ #key }));
+    }
+}
+
+/// Expand `<for pattern in {iter} key={expr}>...</for>` elements into expressions that render
+/// the children once per item. The `key` is optional and is emitted as a `data-key` attribute
+/// on the first element of each item.
+fn expand_for(tokens: Vec<TokenTree>) -> Vec<TokenTree> {
+    let mut result = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if !opens(&tokens, i, "for") {
+            result.push(tokens[i].clone());
+            i += 1;
+            continue;
+        }
+
+        let element = tokens[i + 1].clone();
+        let mut j = i + 2;
+
+        let mut pattern = Vec::new();
+        while j < tokens.len() && !is_ident(tokens.get(j), "in") {
+            pattern.push(tokens[j].clone());
+            j += 1;
+        }
+        if pattern.is_empty() || j >= tokens.len() {
+            abort!(element, "Expected `<for pattern in {...}>`");
+        }
+        let pattern: TokenStream2 = pattern.into_iter().collect();
+
+        let iter = match tokens.get(j + 1) {
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => g.stream(),
+            _ => abort!(element, "Expected the iterator to be wrapped in `{...}`"),
+        };
+        j += 2;
+
+        let mut key = None;
+        if is_ident(tokens.get(j), "key") && is_punct(tokens.get(j + 1), '=') {
+            match tokens.get(j + 2) {
+                Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => {
+                    key = Some(g.stream());
+                }
+                _ => abort!(element, "Expected the key to be wrapped in `{...}`"),
+            }
+            j += 3;
+        }
+
+        if !is_punct(tokens.get(j), '>') {
+            abort!(element, "Expected `>` to end the `for` element");
+        }
+
+        // Find the matching `</for>` accounting for nested `for` elements
+        let start = j + 1;
+        let mut depth = 0;
+        let mut end = start;
+        while end < tokens.len() {
+            if opens(&tokens, end, "for") {
+                depth += 1;
+            } else if closes(&tokens, end, "for") {
+                if depth == 0 {
+                    break;
+                }
+                depth -= 1;
+            }
+            end += 1;
+        }
+        if end >= tokens.len() {
+            abort!(element, "Missing closing `</for>`");
+        }
+
+        let mut body = expand_for(tokens[start..end].to_vec());
+        if let Some(key) = key {
+            insert_key(&mut body, key);
+        }
+        let body: TokenStream2 = body.into_iter().collect();
+
+        result.extend(quote! {
+            {
+                (#iter)
+                    .into_iter()
+                    .map(|#pattern| ::tela::prelude::html_raw! { #body })
+                    .collect::<String>()
+            }
+        });
+        i = end + 4;
+    }
+
+    result
+}
+
+/// Remove the indentation shared by every line that isn't blank so markdown written inside of
+/// the macro isn't read as code blocks
+#[cfg(feature = "markdown")]
+fn dedent(text: &str) -> String {
+    let indent = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    text.lines()
+        .map(|line| line.get(indent..).unwrap_or(line.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render markdown the same way `tela::response::HTML::markdown` does
+#[cfg(feature = "markdown")]
+fn render_markdown(source: &str) -> String {
+    use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+
+    const SCHEMES: [&str; 4] = ["http", "https", "mailto", "tel"];
+    let safe_url = |url: CowStr<'static>| -> CowStr<'static> {
+        let scheme = url
+            .split(['/', '?', '#'])
+            .next()
+            .and_then(|start| start.split_once(':'))
+            .map(|(scheme, _)| {
+                scheme
+                    .chars()
+                    .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+                    .collect::<String>()
+                    .to_ascii_lowercase()
+            });
+        match scheme {
+            Some(scheme) if !SCHEMES.contains(&scheme.as_str()) => CowStr::Borrowed("#"),
+            _ => url,
+        }
+    };
+
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let events = Parser::new_ext(source, options).map(|event| match event.into_static() {
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: safe_url(dest_url),
+            title,
+            id,
+        }),
+        event => event,
+    });
+
+    let mut output = String::new();
+    html::push_html(&mut output, events);
+    output
+}
+
+/// Expand `<markdown>...</markdown>` elements into the html rendered from their markdown.
+///
+/// A string literal is rendered when compiling and `{expr}` is rendered when the markup is
+/// built with `tela::response::HTML::markdown`.
+fn expand_markdown(tokens: Vec<TokenTree>) -> Vec<TokenTree> {
+    let mut result = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if !opens(&tokens, i, "markdown") {
+            result.push(tokens[i].clone());
+            i += 1;
+            continue;
+        }
+
+        let element = tokens[i + 1].clone();
+        if !cfg!(feature = "markdown") {
+            abort!(
+                element,
+                "`<markdown>` requires the `markdown` feature";
+                help = "Enable it with `tela = { features = [\"markdown\"] }`"
+            );
+        }
+        if !is_punct(tokens.get(i + 2), '>') || !closes(&tokens, i + 4, "markdown") {
+            abort!(
+                element,
+                "Expected `<markdown>` to contain only a string or `{...}` followed by `</markdown>`"
+            );
+        }
+
+        let html = match &tokens[i + 3] {
+            #[cfg(feature = "markdown")]
+            TokenTree::Literal(literal) => {
+                match syn::parse2::<LitStr>(TokenTree::Literal(literal.clone()).into()) {
+                    Ok(source) => {
+                        let html =
+                            LitStr::new(&render_markdown(&dedent(&source.value())), source.span());
+                        quote!({ #html })
+                    }
+                    Err(_) => abort!(literal, "Expected a string of markdown"),
+                }
+            }
+            TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
+                let source = g.stream();
+                quote!({ ::tela::response::HTML::markdown(#source).0 })
+            }
+            token => abort!(token, "Expected a string of markdown or `{...}`"),
+        };
+        result.extend(html);
+        i += 8;
+    }
+
+    result
+}
+
+/// Elements whose children may be written as raw, unquoted text
+const RAW: [&str; 3] = ["pre", "script", "style"];
+
+/// Write a token's text to the output, padding it with the whitespace that
+/// separated it from the previous token in the source.
+fn write_spaced(
+    text: &mut String,
+    last: &mut Option<LineColumn>,
+    base: usize,
+    start: LineColumn,
+    end: LineColumn,
+    value: &str,
+) {
+    if let Some(prev) = last {
+        if start.line > prev.line {
+            text.push_str(&"\n".repeat(start.line - prev.line));
+            text.push_str(&" ".repeat(start.column.saturating_sub(base)));
+        } else if start.column > prev.column {
+            text.push_str(&" ".repeat(start.column - prev.column));
+        }
+    }
+    text.push_str(value);
+    *last = Some(end);
+}
+
+fn write_tokens(
+    tokens: TokenStream2,
+    text: &mut String,
+    last: &mut Option<LineColumn>,
+    base: usize,
+) {
+    for token in tokens {
+        match token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => ("", ""),
+                };
+                let (o, c) = (group.span_open(), group.span_close());
+                write_spaced(text, last, base, o.start(), o.end(), open);
+                write_tokens(group.stream(), text, last, base);
+                write_spaced(text, last, base, c.start(), c.end(), close);
+            }
+            token => {
+                let span = token.span();
+                write_spaced(
+                    text,
+                    last,
+                    base,
+                    span.start(),
+                    span.end(),
+                    &token.to_string(),
+                );
+            }
+        }
+    }
+}
+
+/// Convert tokens back into text keeping the whitespace from the source.
+///
+/// Indentation is relative to the first token. The compiler only provides source locations
+/// from Rust 1.88; older compilers get an error asking for the text to be quoted, since the
+/// whitespace can't be kept.
+fn source_text(tokens: &[TokenTree]) -> String {
+    let first = match tokens.first() {
+        Some(token) => token,
+        None => return String::new(),
+    };
+    let start = first.span().start();
+    if start.line == 0 {
+        abort!(
+            first.span(),
+            "Unquoted text in `pre`, `script`, and `style` needs Rust 1.88 or newer to keep its whitespace";
+            help = "Quote the text as a string; ex: `<pre>\"...\"</pre>`"
+        );
+    }
+    let stream: TokenStream2 = tokens.iter().cloned().collect();
+
+    let mut text = String::new();
+    write_tokens(stream, &mut text, &mut None, start.column);
+    text
+}
+
+/// Convert unquoted children of `RAW` elements into string literals and normalize the
+/// doctype so it can be written in any case; `<!doctype html>`.
+///
+/// Children are left alone if they are a single string or expression, or if a `pre`
+/// element contains other elements.
+fn preserve_raw(tokens: Vec<TokenTree>) -> Vec<TokenTree> {
+    let mut result = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if is_punct(tokens.get(i), '<')
+            && is_punct(tokens.get(i + 1), '!')
+            && matches!(tokens.get(i + 2), Some(TokenTree::Ident(d)) if d.to_string().eq_ignore_ascii_case("doctype"))
+            && is_ident(tokens.get(i + 3), "html")
+            && is_punct(tokens.get(i + 4), '>')
+        {
+            let doctype = LitStr::new("<!DOCTYPE html>", tokens[i + 2].span());
+            result.extend(quote!(#doctype));
+            i += 5;
+            continue;
+        }
+
+        let tag = match RAW.iter().find(|tag| opens(&tokens, i, tag)) {
+            Some(tag) => *tag,
+            None => {
+                result.push(tokens[i].clone());
+                i += 1;
+                continue;
+            }
+        };
+
+        // Copy over the opening tag with its attributes
+        while i < tokens.len() && !is_punct(tokens.get(i), '>') {
+            result.push(tokens[i].clone());
+            i += 1;
+        }
+        if i >= tokens.len() || is_punct(result.last(), '/') {
+            continue;
+        }
+        result.push(tokens[i].clone());
+        i += 1;
+
+        let start = i;
+        while i < tokens.len() && !closes(&tokens, i, tag) {
+            i += 1;
+        }
+        let children = &tokens[start..i];
+
+        let single = children.len() == 1
+            && match &children[0] {
+                TokenTree::Literal(_) => true,
+                TokenTree::Group(g) => g.delimiter() == Delimiter::Brace,
+                _ => false,
+            };
+        let elements = tag == "pre"
+            && (0..children.len()).any(|c| {
+                is_punct(children.get(c), '<')
+                    && (matches!(children.get(c + 1), Some(TokenTree::Ident(_)))
+                        || is_punct(children.get(c + 1), '/'))
+            });
+
+        if children.is_empty() || single || elements {
+            result.extend(children.iter().cloned());
+        } else {
+            let text = LitStr::new(&source_text(children), children[0].span());
+            result.extend(quote!(#text));
+        }
+    }
+
+    result
+}
+
+/// Check the name of every opening tag and remove the `@raw` prefix from names that should be
+/// rendered as written; ex: `<@raw widget>`.
+///
+/// Html, SVG, and custom elements keep their case and dashes. Any other name is a compile error
+/// so a typo isn't sent to the browser.
+fn check_tags(tokens: Vec<TokenTree>) -> Vec<TokenTree> {
+    let mut result = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if !is_punct(tokens.get(i), '<') {
+            result.push(tokens[i].clone());
+            i += 1;
+            continue;
+        }
+        result.push(tokens[i].clone());
+        i += 1;
+
+        let raw = is_punct(tokens.get(i), '@') && is_ident(tokens.get(i + 1), "raw");
+        if raw {
+            i += 2;
+        }
+        let start = i;
+        if !matches!(tokens.get(start), Some(TokenTree::Ident(_))) {
+            continue;
+        }
+        i = name_end(&tokens, start);
+        let name: String = tokens[start..i].iter().map(|t| t.to_string()).collect();
+        tags::check(&name, raw, tokens[start].span());
+        result.extend(tokens[start..i].iter().cloned());
+    }
+
+    result
+}
+
+/// Elements where whitespace in the text is significant and must not be collapsed
+const PRESERVE: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+/// Collapse runs of whitespace into a single space
+fn collapse_whitespace(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut whitespace = false;
+    for ch in text.chars() {
+        if ch.is_whitespace() {
+            if !whitespace {
+                result.push(' ');
+            }
+            whitespace = true;
+        } else {
+            result.push(ch);
+            whitespace = false;
+        }
+    }
+    result
+}
+
+/// Index after the `-->` of a comment that starts at `i`, if there is one
+fn comment_end(tokens: &[TokenTree], i: usize) -> Option<usize> {
+    if !(is_punct(tokens.get(i), '<')
+        && is_punct(tokens.get(i + 1), '!')
+        && is_punct(tokens.get(i + 2), '-')
+        && is_punct(tokens.get(i + 3), '-'))
+    {
+        return None;
+    }
+    (i + 4..tokens.len())
+        .find(|&j| {
+            is_punct(tokens.get(j), '-')
+                && is_punct(tokens.get(j + 1), '-')
+                && is_punct(tokens.get(j + 2), '>')
+        })
+        .map(|j| j + 3)
+}
+
+/// Strip comments and collapse insignificant whitespace in text nodes.
+///
+/// Attribute values, expressions, and the contents of elements in `PRESERVE`
+/// are left untouched.
+fn minify(tokens: Vec<TokenTree>) -> Vec<TokenTree> {
+    let mut result = Vec::with_capacity(tokens.len());
+    let mut preserve = 0usize;
+    let mut i = 0;
+
+    while i < tokens.len() {
+        // Comments: <!-- any tokens -->
+        if let Some(end) = comment_end(&tokens, i) {
+            i = end;
+            continue;
+        }
+
+        if PRESERVE.iter().any(|tag| opens(&tokens, i, tag)) {
+            preserve += 1;
+        } else if PRESERVE.iter().any(|tag| closes(&tokens, i, tag)) {
+            preserve = preserve.saturating_sub(1);
+        }
+
+        match &tokens[i] {
+            TokenTree::Literal(literal) if preserve == 0 && !is_punct(result.last(), '=') => {
+                match syn::parse2::<LitStr>(TokenTree::Literal(literal.clone()).into()) {
+                    Ok(text) => {
+                        let text = LitStr::new(&collapse_whitespace(&text.value()), text.span());
+                        result.extend(quote!(#text));
+                    }
+                    Err(_) => result.push(tokens[i].clone()),
+                }
+            }
+            token => result.push(token.clone()),
+        }
+        i += 1;
+    }
+
+    result
+}
+
+/// Compile the markup passed to `html!` into a `tela::response::HTML` expression
+///
+/// `<markdown>` elements are rendered to html first. Raw text in `pre`, `script`, and `style`
+/// elements is then converted to strings and tag names are checked. With the `minify` feature
+/// comments are removed and whitespace in text is collapsed at compile time.
+pub(crate) fn compile_html(input: TokenStream2) -> TokenStream2 {
+    let mut tokens = check_tags(preserve_raw(expand_markdown(input.into_iter().collect())));
+    if cfg!(feature = "minify") {
+        tokens = minify(tokens);
+    }
+    let tokens = expand_for(tokens);
+    let input: TokenStream2 = tokens.iter().cloned().collect();
+
+    if let Some(extends) = parse_extends(&tokens) {
+        return extends;
+    }
+
+    quote! {
+        ::tela::response::HTML(
+            ::tela::prelude::html_raw! {
+                #input
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minified(input: &str) -> String {
+        let tokens: TokenStream2 = input.parse().unwrap();
+        minify(tokens.into_iter().collect())
+            .into_iter()
+            .collect::<TokenStream2>()
+            .to_string()
+            .replace(' ', "")
+    }
+
+    #[test]
+    fn raw_text_keeps_whitespace() {
+        let tokens: TokenStream2 =
+            "<pre>\n    fn main() {\n        println!(\"hi\");\n    }\n</pre>"
+                .parse()
+                .unwrap();
+        let tokens = preserve_raw(tokens.into_iter().collect());
+        let text = tokens
+            .iter()
+            .find_map(|token| syn::parse2::<LitStr>(token.clone().into()).ok())
+            .unwrap();
+        assert_eq!(text.value(), "fn main() {\n    println!(\"hi\");\n}");
+    }
+
+    #[test]
+    fn minify_strips_comments() {
+        for (input, expected) in [
+            (
+                r#"<p>"a"</p> <!-- "one" --> <p>"b"</p>"#,
+                r#"<p>"a"</p><p>"b"</p>"#,
+            ),
+            (r#"<!-- several words, and {braces} --> <br/>"#, "<br/>"),
+            (r#"<!----> <br/>"#, "<br/>"),
+            (r#"<!-- a --> <br/> <!-- b c -->"#, "<br/>"),
+        ] {
+            assert_eq!(minified(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn minify_keeps_unclosed_comment() {
+        assert_eq!(minified("<!-- a"), "<!--a");
+    }
+
+    fn checked(input: &str) -> String {
+        let tokens: TokenStream2 = input.parse().unwrap();
+        check_tags(tokens.into_iter().collect())
+            .into_iter()
+            .collect::<TokenStream2>()
+            .to_string()
+            .replace(' ', "")
+    }
+
+    #[test]
+    fn svg_and_custom_elements_keep_their_names() {
+        assert_eq!(
+            checked(concat!(
+                r#"<svg><clipPath id="c"><rect width="10"/></clipPath>"#,
+                r#"<foreignObject><div>"Text"</div></foreignObject></svg>"#,
+                r#"<my-widget data-id="1"></my-widget>"#,
+            )),
+            concat!(
+                r#"<svg><clipPathid="c"><rectwidth="10"/></clipPath>"#,
+                r#"<foreignObject><div>"Text"</div></foreignObject></svg>"#,
+                r#"<my-widgetdata-id="1"></my-widget>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn raw_prefix_is_removed() {
+        assert_eq!(
+            checked(r#"<@raw widget class="a">"Hi"</widget><@raw svg:rect/>"#),
+            r#"<widgetclass="a">"Hi"</widget><svg:rect/>"#
+        );
+    }
+
+    #[test]
+    fn extends_builds_blocks_into_the_layout() {
+        let tokens: TokenStream2 = r#"
+            <extends layout={base} />
+            <block name="title">"Home"</block>
+            <block name="content"><p>"Hello"</p></block>
+        "#
+        .parse()
+        .unwrap();
+        let expanded = parse_extends(&tokens.into_iter().collect::<Vec<_>>())
+            .unwrap()
+            .to_string()
+            .replace(' ', "");
+        assert_eq!(
+            expanded,
+            concat!(
+                r#"::tela::response::HTML(::tela::response::Blocks::new()"#,
+                r#".block("title",::tela::prelude::html_raw!{"Home"})"#,
+                r#".block("content",::tela::prelude::html_raw!{<p>"Hello"</p>})"#,
+                r#".extend(&(base)))"#,
+            )
+        );
+    }
+
+    #[test]
+    fn extends_is_only_used_at_the_start() {
+        let tokens: TokenStream2 = r#"<p>"a"</p><extends layout={base} />"#.parse().unwrap();
+        assert!(parse_extends(&tokens.into_iter().collect::<Vec<_>>()).is_none());
+    }
+
+    fn expanded_for(input: &str) -> String {
+        let tokens: TokenStream2 = input.parse().unwrap();
+        expand_for(tokens.into_iter().collect())
+            .into_iter()
+            .collect::<TokenStream2>()
+            .to_string()
+            .replace(' ', "")
+    }
+
+    #[test]
+    fn for_renders_each_item_with_a_key() {
+        assert_eq!(
+            expanded_for(
+                r#"<ul><for user in {users} key={user.id}><li>{user.name}</li></for></ul>"#
+            ),
+            concat!(
+                r#"<ul>{(users).into_iter().map(|user|::tela::prelude::html_raw!{"#,
+                r#"<lidata-key={user.id}>{user.name}</li>"#,
+                r#"}).collect::<String>()}</ul>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn for_key_goes_after_dashed_names() {
+        assert_eq!(
+            expanded_for(
+                r#"<for (i, item) in {items} key={i}><todo-item>{item}</todo-item></for>"#
+            ),
+            concat!(
+                r#"{(items).into_iter().map(|(i,item)|::tela::prelude::html_raw!{"#,
+                r#"<todo-itemdata-key={i}>{item}</todo-item>"#,
+                r#"}).collect::<String>()}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn for_without_a_key_and_nested() {
+        assert_eq!(
+            expanded_for(r#"<for row in {rows}><for cell in {row}><td>{cell}</td></for></for>"#),
+            concat!(
+                r#"{(rows).into_iter().map(|row|::tela::prelude::html_raw!{"#,
+                r#"{(row).into_iter().map(|cell|::tela::prelude::html_raw!{<td>{cell}</td>})"#,
+                r#".collect::<String>()}"#,
+                r#"}).collect::<String>()}"#,
+            )
+        );
+    }
+
+    #[cfg(feature = "markdown")]
+    fn expanded_markdown(input: &str) -> String {
+        let tokens: TokenStream2 = input.parse().unwrap();
+        expand_markdown(tokens.into_iter().collect())
+            .into_iter()
+            .collect::<TokenStream2>()
+            .to_string()
+    }
+
+    /// Html a `<markdown>` string was rendered to
+    #[cfg(feature = "markdown")]
+    fn rendered_markdown(input: &str) -> String {
+        let tokens: TokenStream2 = input.parse().unwrap();
+        expand_markdown(tokens.into_iter().collect())
+            .into_iter()
+            .find_map(|token| match token {
+                TokenTree::Group(g) => syn::parse2::<LitStr>(g.stream()).ok(),
+                _ => None,
+            })
+            .unwrap()
+            .value()
+    }
+
+    #[cfg(feature = "markdown")]
+    #[test]
+    fn markdown_literal_renders_at_compile_time() {
+        assert_eq!(
+            rendered_markdown(
+                "<article><markdown>\"\n    # Title\n\n    Some *text*\n\"</markdown></article>"
+            ),
+            "<h1>Title</h1>\n<p>Some <em>text</em></p>\n"
+        );
+    }
+
+    #[cfg(feature = "markdown")]
+    #[test]
+    fn markdown_literal_is_sanitized() {
+        assert_eq!(
+            rendered_markdown(r#"<markdown>"<b>hi</b> [a](javascript:alert(1))"</markdown>"#),
+            "<p>&lt;b&gt;hi&lt;/b&gt; <a href=\"#\">a</a></p>\n"
+        );
+    }
+
+    #[cfg(feature = "markdown")]
+    #[test]
+    fn markdown_expression_renders_at_runtime() {
+        assert_eq!(
+            expanded_markdown("<p>\"a\"</p><markdown>{post.body}</markdown>").replace(' ', ""),
+            r#"<p>"a"</p>{::tela::response::HTML::markdown(post.body).0}"#
+        );
+    }
+}
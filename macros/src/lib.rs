@@ -1,14 +1,23 @@
 extern crate proc_macro;
 mod docs;
+mod dom;
+mod fetch;
+mod form;
+mod handler;
 mod helpers;
+mod html;
+mod memo;
 mod request;
+mod response;
+mod tags;
+mod url;
 
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
-use proc_macro_error::proc_macro_error;
+use proc_macro_error::{abort, proc_macro_error};
 
 use quote::quote;
-use syn::{parse_macro_input, ItemFn};
+use syn::{parse_macro_input, DeriveInput, ItemFn};
 
 use request::{request_catch, request_endpoint, CatchArgs, RequestArgs};
 
@@ -54,30 +63,258 @@ pub fn catch(args: TokenStream, function: TokenStream) -> TokenStream {
     )
 }
 
+/// Check a handler's signature at compile time with clear errors instead of the trait bound
+/// errors from the generated endpoint. Put it above or below the request attribute.
+///
+/// Handlers must not be async or generic and must return a response. They take at most 16
+/// arguments, each of which is `Send`, and only one argument can read the request body, ex:
+/// `Body<T>` or `Form<T>`, which must be the last.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, request::Body};
+///
+/// #[derive(serde::Deserialize)]
+/// struct User { name: String }
+///
+/// #[tela::handler]
+/// #[post("/users/:id")]
+/// fn update(id: u32, Body(user): Body<User>) -> Result<String> {
+///     response!(format!("Updated user {} to {}", id, user.name))
+/// }
+/// ```
 #[proc_macro_error]
 #[proc_macro_attribute]
-pub fn main(_: TokenStream, function: TokenStream) -> TokenStream {
+pub fn handler(_: TokenStream, function: TokenStream) -> TokenStream {
+    handler::validate_handler(parse_macro_input!(function as ItemFn)).into()
+}
+
+/// Reuse a component's markup for the same arguments instead of rendering it again; ex: for a
+/// nav bar or footer rendered on every page. Renders are kept in `tela::response::cache` for
+/// `ttl` seconds, 60 by default, and are keyed by the component and a hash of its arguments.
+///
+/// The component must return `HTML<String>` or `String` and every argument must be `Hash`.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, response::HTML};
+///
+/// #[tela::memo(ttl = 300)]
+/// fn nav(user: &str) -> HTML<String> {
+///     html! { <nav>"Signed in as "{user}</nav> }
+/// }
+/// ```
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn memo(args: TokenStream, function: TokenStream) -> TokenStream {
+    let mut memo_args = memo::MemoArgs::default();
+    let parser = memo_args.parser();
+    parse_macro_input!(args with parser);
+    memo::memo_component(memo_args, parse_macro_input!(function as ItemFn)).into()
+}
+
+/// Start a tokio runtime and run the body of `main` on it. The body returns a
+/// `Result<(), Box<dyn Error + Send + Sync>>`.
+///
+/// Accepts the runtime settings `tokio::main` does:
+/// * `flavor = "multi_thread"`, the default, or `"current_thread"`
+/// * `workers = 4`; number of worker threads for the multi thread flavor
+/// * `thread_name = "..."`; name of the runtime's threads
+///
+/// # Example
+/// ```no_run
+/// use tela::Server;
+///
+/// #[tela::main(flavor = "current_thread")]
+/// async fn main() {
+///     Server::new().serve(3000).await
+/// }
+/// ```
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn main(args: TokenStream, function: TokenStream) -> TokenStream {
+    let mut flavor = quote!(::tela::Flavor::MultiThread);
+    let mut current_thread = None;
+    let mut workers = None;
+    let mut thread_name = quote!(None);
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("flavor") {
+            let value = meta.value()?.parse::<syn::LitStr>()?;
+            match value.value().as_str() {
+                "multi_thread" => flavor = quote!(::tela::Flavor::MultiThread),
+                "current_thread" => {
+                    flavor = quote!(::tela::Flavor::CurrentThread);
+                    current_thread = Some(value);
+                }
+                _ => abort!(value, "Expected `multi_thread` or `current_thread`"),
+            }
+        } else if meta.path.is_ident("workers") {
+            let value = meta.value()?.parse::<syn::LitInt>()?;
+            if value.base10_parse::<usize>()? == 0 {
+                abort!(value, "Expected at least one worker thread");
+            }
+            workers = Some(value);
+        } else if meta.path.is_ident("thread_name") {
+            let value = meta.value()?.parse::<syn::LitStr>()?;
+            thread_name = quote!(Some(String::from(#value)));
+        } else {
+            return Err(meta.error("Expected `flavor`, `workers`, or `thread_name`"));
+        }
+        Ok(())
+    });
+    parse_macro_input!(args with parser);
+    if let (Some(flavor), Some(_)) = (&current_thread, &workers) {
+        abort!(
+            flavor,
+            "`workers` can't be used with the `current_thread` flavor";
+            help = "Remove `workers` or use `flavor = \"multi_thread\"`"
+        );
+    }
+    let workers = match workers {
+        Some(workers) => quote!(Some(#workers)),
+        None => quote!(None),
+    };
+
     let function = parse_macro_input!(function as ItemFn);
     let body = *function.block;
 
     quote! {
-        #[tela::bump::tokio::main]
-        async fn main() -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
-            #body
+        fn main() -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            ::tela::runtime_entry(
+                ::tela::RuntimeConfig {
+                    flavor: #flavor,
+                    workers: #workers,
+                    thread_name: #thread_name,
+                },
+                None,
+                async { #body },
+            )?
         }
     }
     .into()
 }
 
+/// Build a `tela::response::HTML<String>` from markup.
+///
+/// Along with normal elements the markup supports:
+/// * `<extends layout={...} />` followed by `<block name="...">` elements to render into a layout
+/// * `<for item in {iter} key={...}>` to render the children once per item. The optional key is
+///   added as a `data-key` attribute on the first child element.
+/// * Unquoted text inside of `pre`, `script`, and `style` elements, which keeps its whitespace
+/// * `<!DOCTYPE html>` in any case
+/// * `<markdown>{source}</markdown>` to render markdown to sanitized html with the `markdown`
+///   feature. A string literal, `<markdown>"# Title"</markdown>`, is rendered when compiling.
+///
+/// Html, SVG, and custom elements are rendered with their names as written; ex: `<clipPath>` or
+/// `<my-widget>`. Custom element names are lowercase with a dash. Any other name is a compile
+/// error unless it's prefixed with `@raw`, `<@raw widget>...</widget>`, which renders it as is.
+#[proc_macro_error]
 #[proc_macro]
 pub fn html(input: TokenStream) -> TokenStream {
     let input: TokenStream2 = input.into();
-    quote! {
-        ::tela::response::HTML(
-            ::tela::prelude::html_raw! {
-                #input
-            }
-        )
-    }
-    .into()
+    html::compile_html(input).into()
+}
+
+/// Build DOM nodes from markup for client side (wasm) apps. Re-exported by `tela-wasm` as `html!`.
+///
+/// Returns a `tela_wasm::dom::View`. Attributes starting with `on:` add event listeners
+/// that live as long as the view; ex: `<button on:click={move |_| count.update(|c| *c += 1)}>`.
+///
+/// `{..attributes}` spreads any iterator of `(name, value)` pairs onto an element. Attribute
+/// values given as `{...}` and in spreads can be strings, numbers, `bool`, or `Option`; `false`
+/// and `None` leave the attribute off. `class` and `style` merge instead of replacing; see
+/// `tela_wasm::dom::spread`.
+#[proc_macro_error]
+#[proc_macro]
+pub fn dom(input: TokenStream) -> TokenStream {
+    let input: TokenStream2 = input.into();
+    dom::compile_dom(input).into()
+}
+
+/// Build and send a request for `tela::fetch!` and `tela_wasm::fetch!` so both accept the same
+/// options; `fetch_request!(server path::to::Request; url, options...)`.
+#[doc(hidden)]
+#[proc_macro_error]
+#[proc_macro]
+pub fn fetch_request(input: TokenStream) -> TokenStream {
+    fetch::compile_fetch(input.into()).into()
+}
+
+/// Build a url from a template with `{name}` placeholders for variables in scope.
+///
+/// Substituted values are percent-encoded so they can't add segments or query parameters,
+/// ex: `{id}` of `"a/b"` becomes `a%2Fb`. A placeholder at the start of the template is the
+/// base of the url and is inserted as is. `{{` and `}}` are literal braces.
+///
+/// # Example
+/// ```
+/// use tela::prelude::url;
+///
+/// let base = "https://api.example.com";
+/// let name = "Ada Lovelace";
+/// let sort = "name&desc";
+/// assert_eq!(
+///     url!("{base}/users/{name}?sort={sort}"),
+///     "https://api.example.com/users/Ada%20Lovelace?sort=name%26desc"
+/// );
+/// ```
+#[proc_macro_error]
+#[proc_macro]
+pub fn url(input: TokenStream) -> TokenStream {
+    url::compile_url(parse_macro_input!(input as syn::LitStr)).into()
+}
+
+/// Derive `tela::request::HtmlForm` to render a struct as form inputs.
+///
+/// Derive it alongside `serde::Deserialize` so the rendered form and the `Form<T>`
+/// extractor use the same field names, including `#[serde(rename = "...")]`.
+/// Fields accept `#[form(label = "...", input = "...", skip)]`.
+#[proc_macro_error]
+#[proc_macro_derive(HtmlForm, attributes(form))]
+pub fn html_form(input: TokenStream) -> TokenStream {
+    form::derive_html_form(parse_macro_input!(input as DeriveInput)).into()
+}
+
+/// Derive `tela::response::ToResponse` for an enum, ex: an application's errors, so each
+/// variant is sent with its own status and body.
+///
+/// Variants and the enum, for defaults, accept `#[response(...)]` with:
+/// * `status = 404`; the status code, `500` by default
+/// * `message = "..."`; the message, which can use the variant's fields, ex: `"User {id} not found"`,
+///   or `{_0}` for unnamed fields. Defaults to the status's reason.
+/// * `error`; the default, send the status and message through the catches and error renderer
+/// * `text`; send the message as plain text
+/// * `json`; send `{ "error": message, ...fields }`, or the field of a variant with one unnamed field
+///
+/// The enum also implements `ResponseError` so handlers can return `std::result::Result<T, E>`.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, response::{ToResponse, JSON}};
+///
+/// #[derive(ToResponse)]
+/// #[response(json)]
+/// enum ApiError {
+///     #[response(status = 404, message = "User {id} not found")]
+///     NotFound { id: u32 },
+///     #[response(status = 409)]
+///     Conflict,
+///     #[response(error)]
+///     Internal,
+/// }
+///
+/// #[get("/users/:id")]
+/// fn user(id: u32) -> std::result::Result<JSON<u32>, ApiError> {
+///     match id {
+///         0 => Err(ApiError::Internal),
+///         1 => Ok(JSON(id)),
+///         2 => Err(ApiError::Conflict),
+///         _ => Err(ApiError::NotFound { id }),
+///     }
+/// }
+/// ```
+#[proc_macro_error]
+#[proc_macro_derive(ToResponse, attributes(response))]
+pub fn to_response(input: TokenStream) -> TokenStream {
+    response::derive_to_response(parse_macro_input!(input as DeriveInput)).into()
 }
@@ -0,0 +1,114 @@
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::{abort, emit_error};
+use quote::{quote, quote_spanned};
+use syn::{spanned::Spanned, FnArg, ItemFn, LitInt, Pat, ReturnType};
+
+use super::helpers::get_path_name;
+
+/// Seconds a render is reused when `ttl` isn't given
+const DEFAULT_TTL: u64 = 60;
+
+/// Settings from `#[memo(ttl = 300)]`
+#[derive(Default)]
+pub struct MemoArgs {
+    ttl: Option<LitInt>,
+}
+
+impl MemoArgs {
+    pub fn parser(&mut self) -> impl syn::parse::Parser<Output = ()> + '_ {
+        syn::meta::parser(|meta| {
+            if meta.path.is_ident("ttl") {
+                let ttl = meta.value()?.parse::<LitInt>()?;
+                ttl.base10_parse::<u64>()?;
+                self.ttl = Some(ttl);
+                Ok(())
+            } else {
+                Err(meta.error("Expected `ttl`"))
+            }
+        })
+    }
+}
+
+/// Wrap a component so its markup is rendered once per set of arguments and reused from
+/// `tela::response::cache` until the ttl has passed.
+pub fn memo_component(args: MemoArgs, function: ItemFn) -> TokenStream2 {
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = function;
+
+    if let Some(asyncness) = &sig.asyncness {
+        emit_error!(asyncness, "Memoized components can't be async");
+    }
+
+    // The output is rebuilt from the cached markup so only markup types can be memoized
+    let output = match &sig.output {
+        ReturnType::Type(_, ty) => ty,
+        ReturnType::Default => abort!(
+            sig.ident,
+            "Memoized components must return markup";
+            help = "Return `HTML<String>` or `String`"
+        ),
+    };
+    let wrap = match get_path_name(output).as_str() {
+        "HTML" => quote!(::tela::response::HTML),
+        "String" => quote!(),
+        _ => abort!(
+            output,
+            "Memoized components must return `HTML<String>` or `String`"
+        ),
+    };
+
+    let mut names = Vec::new();
+    for arg in sig.inputs.iter() {
+        match arg {
+            FnArg::Receiver(receiver) => {
+                emit_error!(receiver, "Memoized components can't take `self`")
+            }
+            FnArg::Typed(arg) => match &*arg.pat {
+                Pat::Ident(pat) => names.push((pat.ident.clone(), arg.ty.clone())),
+                pat => emit_error!(
+                    pat,
+                    "Memoized components must name their arguments";
+                    help = "Destructure the argument inside of the component instead"
+                ),
+            },
+        }
+    }
+
+    // Hashed where each argument is written so a missing `Hash` points at the argument
+    let hashes = names.iter().map(|(name, ty)| {
+        quote_spanned! {ty.span()=>
+            ::std::hash::Hash::hash(&#name, &mut __hasher);
+        }
+    });
+
+    let ident = &sig.ident;
+    let ttl = match &args.ttl {
+        Some(ttl) => quote!(#ttl),
+        None => quote!(#DEFAULT_TTL),
+    };
+
+    quote! {
+        #(#attrs)*
+        #vis #sig {
+            let __key = {
+                let mut __hasher = ::std::collections::hash_map::DefaultHasher::new();
+                ::std::hash::Hash::hash(&(module_path!(), stringify!(#ident)), &mut __hasher);
+                #(#hashes)*
+                ::std::hash::Hasher::finish(&__hasher)
+            };
+            let __render = || -> #output #block;
+            #wrap(
+                ::tela::response::cache::render(
+                    ("memo", __key),
+                    ::std::time::Duration::from_secs(#ttl),
+                    || Ok(::std::string::String::from(__render())),
+                )
+                .unwrap_or_default()
+            )
+        }
+    }
+}
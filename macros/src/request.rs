@@ -48,30 +48,119 @@ impl Parse for RequestArgs {
 }
 
 pub struct CatchArgs {
-    pub code: syn::LitInt,
+    pub codes: Vec<syn::LitInt>,
 }
 
 impl Parse for CatchArgs {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         if input.is_empty() {
             return Ok(CatchArgs {
-                code: LitInt::new("0", Span::call_site()),
+                codes: vec![LitInt::new("0", Span::call_site())],
             });
         }
 
-        let code = match input.parse::<LitInt>() {
-            Ok(c) => c,
-            _ => match input.parse::<Ident>() {
-                Ok(c) if c.to_string().as_str() == "all" => LitInt::new("0", Span::call_site()),
-                _ => abort!(
-                    input.span(),
-                    "Expected single u16 or `all` identifier argument"
-                ),
-            },
+        let mut codes = Vec::new();
+        while !input.is_empty() {
+            let span = input.span();
+            let code = match input.parse::<LitInt>() {
+                Ok(c) => {
+                    if c.base10_parse::<u16>().is_err() {
+                        abort!(c, "Expected a u16 status code")
+                    }
+                    c
+                }
+                _ => match input.parse::<Ident>() {
+                    Ok(c) if c.to_string().as_str() == "all" => LitInt::new("0", c.span()),
+                    _ => abort!(
+                        span,
+                        "Expected u16 codes or the `all` identifier";
+                        help = "ex: `#[catch(404)]`, `#[catch(500, 503)]`, or `#[catch(all)]`"
+                    ),
+                },
+            };
+            codes.push(code);
+            if !input.is_empty() {
+                let _: Token![,] = input.parse()?;
+            }
+        }
+
+        Ok(CatchArgs { codes })
+    }
+}
+
+/// Check a route pattern for the same mistakes as `tela::uri::Pattern::parse` so they are
+/// reported when compiling instead of the route never matching.
+fn check_path(path: &LitStr) {
+    if let Err((span, message)) = validate_path(&path.value()) {
+        // Point at the segment when the compiler supports it, otherwise the whole path
+        let start = usize::from(path.token().to_string().starts_with('"'));
+        let span = path
+            .token()
+            .subspan(start + span.0..start + span.1)
+            .unwrap_or(path.span());
+        abort!(span, message)
+    }
+}
+
+/// Byte range of the segment with the first mistake in the pattern and the mistake
+fn validate_path(value: &str) -> std::result::Result<(), ((usize, usize), String)> {
+    let uri = value.split(['?', '#']).next().unwrap();
+    let mut offset = usize::from(uri.starts_with('/'));
+    let trimmed = &uri[offset..];
+    let trimmed = trimmed.strip_suffix('/').unwrap_or(trimmed);
+
+    let mut names: Vec<String> = Vec::new();
+    let mut catch_all: Option<(usize, usize)> = None;
+    for segment in trimmed.split('/') {
+        let span = (offset, offset + segment.len());
+        offset = span.1 + 1;
+
+        if let Some(span) = catch_all.take() {
+            if segment.starts_with(':') {
+                return Err((
+                    span,
+                    "A catch all must be followed by a plain segment or end the pattern"
+                        .to_string(),
+                ));
+            }
+        }
+
+        let name = if let Some(name) = segment.strip_prefix(":...") {
+            if name.contains('<') {
+                return Err((span, format!("Catch all `{}` can't have a type", segment)));
+            }
+            catch_all = Some(span);
+            name.trim_start_matches('%')
+        } else if let Some(capture) = segment.strip_prefix(':') {
+            match capture.strip_suffix('>').and_then(|c| c.split_once('<')) {
+                Some((_, kind)) if kind.trim().is_empty() => {
+                    return Err((span, format!("Capture `{}` has an empty type", segment)))
+                }
+                Some((name, _)) if !name.contains(['<', '>']) => name,
+                _ if capture.contains(['<', '>']) => {
+                    return Err((
+                        span,
+                        format!(
+                            "Capture type in `{}` must be closed with `>` at the end",
+                            segment
+                        ),
+                    ))
+                }
+                _ => capture,
+            }
+        } else {
+            continue;
         };
 
-        Ok(CatchArgs { code })
+        if name.is_empty() {
+            return Err((span, format!("Capture `{}` is missing a name", segment)));
+        }
+        if names.iter().any(|n| n == name) {
+            return Err((span, format!("Capture `{}` is used more than once", name)));
+        }
+        names.push(name.to_string());
     }
+    Ok(())
 }
 
 fn parse_props(path: String, function: &ItemFn) -> TokenStream2 {
@@ -80,9 +169,17 @@ fn parse_props(path: String, function: &ItemFn) -> TokenStream2 {
         .split("/")
         .filter_map(|p| {
             if p.starts_with(":...") {
-                Some(p.strip_prefix(":...").unwrap().to_string())
+                // `%` marks a catch all that isn't decoded
+                Some(
+                    p.strip_prefix(":...")
+                        .unwrap()
+                        .trim_start_matches('%')
+                        .to_string(),
+                )
             } else if p.starts_with(":") {
-                Some(p.strip_prefix(":").unwrap().to_string())
+                // Typed captures, ex: `:id<uuid>`, are named without their type
+                let capture = p.strip_prefix(":").unwrap();
+                Some(capture.split('<').next().unwrap().to_string())
             } else {
                 None
             }
@@ -92,7 +189,9 @@ fn parse_props(path: String, function: &ItemFn) -> TokenStream2 {
     let error = |a: FnArg| {
         abort!(
             a,
-            format!("Invalid endpoint argument: expected Query or Body types; or a uri capture")
+            format!(
+                "Invalid endpoint argument: expected Query, Body, or Form types; or a uri capture"
+            )
         )
     };
 
@@ -187,7 +286,15 @@ pub fn request_endpoint(args: RequestArgs, mut function: ItemFn) -> TokenStream
     .parse::<TokenStream2>()
     .unwrap();
 
+    // Read by `routes!` to check the table against the handlers at compile time
+    let method_names = args.methods.iter().map(|m| m.to_uppercase());
+
+    check_path(&path);
     let props = parse_props(path.value().to_string(), &function);
+    let streams = function.sig.inputs.iter().any(|arg| match arg {
+        FnArg::Typed(PatType { ty, .. }) => get_path_name(ty) == "BodyStream",
+        _ => false,
+    });
     let name = function.sig.ident.clone();
     let vis = function.vis.clone();
     function.sig.ident = Ident::new("__call", function.sig.ident.span());
@@ -198,6 +305,12 @@ pub fn request_endpoint(args: RequestArgs, mut function: ItemFn) -> TokenStream
         #[allow(non_camel_case_types)]
         #[derive(Debug)]
         #vis struct #name;
+        impl #name {
+            #[doc(hidden)]
+            pub const PATH: &'static str = #path;
+            #[doc(hidden)]
+            pub const METHODS: &'static [&'static str] = &[#(#method_names),*];
+        }
         impl ::tela::request::Endpoint for #name {
             #[inline]
             fn methods(&self) -> Vec<::tela::bump::hyper::Method> {
@@ -209,22 +322,33 @@ pub fn request_endpoint(args: RequestArgs, mut function: ItemFn) -> TokenStream
                 String::from(#path)
             }
 
+            #[inline]
+            fn streams(&self) -> bool {
+                #streams
+            }
+
             fn execute(
                 &self,
                 __method: &::tela::bump::hyper::Method,
                 __uri: &mut ::tela::bump::hyper::Uri,
-                __body: &mut Vec<u8>,
+                __headers: &::tela::bump::hyper::HeaderMap,
+                __body: &::tela::bump::bytes::Bytes,
+                __trailers: &::tela::bump::hyper::HeaderMap,
+                __stream: &::tela::request::BodyStream,
             ) -> ::tela::response::Result<::tela::bump::hyper::Response<::tela::bump::http_body_util::Full<::tela::bump::bytes::Bytes>>> {
                 #[inline]
                 #function
 
                 let __captures = ::tela::uri::props(&__uri.path().to_string(), &self.path());
-                let mut __data = ::tela::request::RequestData(__uri.clone(), __method.clone(), __body.clone());
-                __call(#props).to_response(
-                    __method,
-                    __uri,
-                    std::str::from_utf8(__body.as_slice()).unwrap_or("").to_string()
-                )
+                let mut __data = ::tela::request::RequestData(
+                    __uri.clone(),
+                    __method.clone(),
+                    __body.clone(),
+                    __headers.clone(),
+                    __trailers.clone(),
+                    __stream.clone(),
+                );
+                __call(#props).to_response(__method, __uri, __headers, __body)
             }
         }
     }
@@ -234,18 +358,60 @@ pub fn request_endpoint(args: RequestArgs, mut function: ItemFn) -> TokenStream
 pub fn request_catch(args: CatchArgs, mut function: ItemFn) -> TokenStream {
     let name = function.sig.ident.clone();
     let vis = function.vis.clone();
-    let code = args.code;
+    let codes = args.codes;
+    let code = &codes[0];
     let docs = format!(
         "#[doc=\"Catches {} errors and handles them\n\n{}\"]",
-        match code.to_string().as_str() {
-            "0" => "any",
-            val => val,
+        match codes
+            .iter()
+            .map(|code| code.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+            .as_str()
+        {
+            "0" => "any".to_string(),
+            val => val.to_string(),
         },
         compile_docs(&mut function)
     )
     .parse::<TokenStream2>()
     .unwrap();
 
+    // Arguments are given by their type. Strings are the message then the reason so the
+    // `fn(code: u16, message: String, reason: String)` form still works.
+    let mut strings = 0;
+    let params = function.sig.inputs.iter().map(|arg| match arg {
+        FnArg::Typed(PatType { ty, .. }) => match get_path_name(ty).as_str() {
+            "u16" => quote!(code),
+            "StatusCode" => quote!(::tela::StatusCode::from(code)),
+            "Error" => quote!(::tela::Error {
+                code,
+                message: message.clone(),
+                reason: reason.clone(),
+            }),
+            "Head" => quote!(head.clone()),
+            "String" => {
+                strings += 1;
+                match strings {
+                    1 => quote!(message.clone()),
+                    2 => quote!(reason.clone()),
+                    _ => abort!(ty, "Catches take at most two strings, the message and reason"),
+                }
+            }
+            _ => abort!(
+                ty,
+                "Invalid catch argument";
+                help = "Expected `StatusCode`, `Error`, `Head`, `u16` for the code, or `String` for the message and reason"
+            ),
+        },
+        FnArg::Receiver(receiver) => abort!(receiver, "Catches can't take `self`"),
+    });
+    let params: Vec<TokenStream2> = params.collect();
+    let call = match function.sig.asyncness {
+        Some(_) => quote!(__callback(#(#params),*).await),
+        None => quote!(__callback(#(#params),*)),
+    };
+
     function.sig.ident = proc_macro2::Ident::new("__callback", function.sig.ident.span());
     function.vis = syn::Visibility::Inherited;
 
@@ -257,22 +423,73 @@ pub fn request_catch(args: CatchArgs, mut function: ItemFn) -> TokenStream {
 
         #[allow(non_camel_case_types)]
         impl ::tela::request::Catch for #name {
-            fn execute(
-                &self,
+            fn execute<'a>(
+                &'a self,
                 code: u16,
                 message: String,
-                reason: String
-            ) -> ::tela::response::Result<::tela::bump::hyper::Response<::tela::bump::http_body_util::Full<bytes::Bytes>>> {
+                reason: String,
+                head: &'a ::tela::request::Head,
+            ) -> ::std::pin::Pin<::std::boxed::Box<
+                dyn ::std::future::Future<
+                    Output = ::tela::response::Result<::tela::bump::hyper::Response<::tela::bump::http_body_util::Full<::tela::bump::bytes::Bytes>>>
+                > + ::std::marker::Send + 'a
+            >> {
                 #function
 
-                __callback(code.clone(), message, reason.clone()).to_error_response(code, reason)
+                Box::pin(async move {
+                    #call.to_error_response(code, reason)
+                })
             }
 
             #[inline]
             fn code(&self) -> u16 {
                 #code
             }
+
+            fn codes(&self) -> Vec<u16> {
+                vec![#(#codes),*]
+            }
         }
     }
     .into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The check is a copy of `Pattern::parse` since a proc macro crate can't share code with
+    /// tela, so both are run on the same patterns
+    #[test]
+    fn paths_are_checked_like_tela() {
+        let cases = [
+            "/",
+            "",
+            "/blog/:slug",
+            "/users/:id<u32>/posts/:post<uuid>",
+            "/files/:...path",
+            "/files/:...%path/raw",
+            "/files/:...path/raw/",
+            "/blog/:slug?draft=true#top",
+            "/files/:...path/:...rest",
+            "/files/:...path/:id",
+            "/files/:...path<u32>",
+            "/users/:id<>",
+            "/users/:id< >",
+            "/users/:id<u32",
+            "/users/:id>",
+            "/users/:<u32>",
+            "/users/:",
+            "/users/:...",
+            "/users/:id/posts/:id",
+            "/users/:id/files/:...id",
+            "/users/:...%id/:id",
+        ];
+        for case in cases {
+            let tela = tela::uri::Pattern::parse(case)
+                .err()
+                .map(|error| ((error.span.start, error.span.end), error.message));
+            assert_eq!(validate_path(case).err(), tela, "{}", case);
+        }
+    }
+}
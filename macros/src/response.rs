@@ -0,0 +1,177 @@
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+use syn::{Attribute, Data, DeriveInput, Fields, LitInt, LitStr};
+
+/// How a variant is sent
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    /// Through the catches and error renderer like any other error
+    Error,
+    Text,
+    Json,
+}
+
+#[derive(Clone)]
+struct Options {
+    status: u16,
+    format: Format,
+    message: Option<LitStr>,
+}
+
+/// Read `#[response(status = 404, json, message = "...")]` over the defaults
+fn options(attrs: &[Attribute], mut options: Options) -> Options {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("response")) {
+        let result = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("status") {
+                options.status = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+            } else if meta.path.is_ident("message") {
+                options.message = Some(meta.value()?.parse::<LitStr>()?);
+            } else if meta.path.is_ident("json") {
+                options.format = Format::Json;
+            } else if meta.path.is_ident("text") {
+                options.format = Format::Text;
+            } else if meta.path.is_ident("error") {
+                options.format = Format::Error;
+            } else {
+                return Err(meta.error("Expected `status`, `message`, `json`, `text`, or `error`"));
+            }
+            Ok(())
+        });
+        if let Err(err) = result {
+            abort!(attr, err.to_string());
+        }
+    }
+    if !(100..=599).contains(&options.status) {
+        abort!(
+            attrs.first().map(|attr| quote!(#attr)).unwrap_or_default(),
+            "Expected a status code from 100 to 599"
+        );
+    }
+    options
+}
+
+/// Implement `tela::response::ToResponse` and `ResponseError` for an enum so each variant is
+/// sent with its own status and body.
+pub(crate) fn derive_to_response(input: DeriveInput) -> TokenStream2 {
+    let Data::Enum(data) = &input.data else {
+        abort!(input.ident, "ToResponse can only be derived for enums");
+    };
+    let defaults = options(
+        &input.attrs,
+        Options {
+            status: 500,
+            format: Format::Error,
+            message: None,
+        },
+    );
+
+    let arms = data.variants.iter().map(|variant| {
+        let Options {
+            status,
+            format,
+            message,
+        } = options(&variant.attrs, defaults.clone());
+        let ident = &variant.ident;
+
+        // Fields are bound by name, or `_0`, `_1`, ... for tuple variants, so messages can
+        // use them; ex: `message = "User {id} not found"`
+        let (pattern, names) = match &variant.fields {
+            Fields::Named(fields) => {
+                let names: Vec<_> = fields
+                    .named
+                    .iter()
+                    .map(|field| field.ident.clone().unwrap())
+                    .collect();
+                (quote!(Self::#ident { #(#names),* }), names)
+            }
+            Fields::Unnamed(fields) => {
+                let names: Vec<_> = (0..fields.unnamed.len())
+                    .map(|index| format_ident!("_{}", index))
+                    .collect();
+                (quote!(Self::#ident(#(#names),*)), names)
+            }
+            Fields::Unit => (quote!(Self::#ident), Vec::new()),
+        };
+
+        let message = match &message {
+            Some(message) => quote!(format!(#message)),
+            None => quote!(::tela::StatusCode::from(#status).message()),
+        };
+
+        let body = match format {
+            Format::Error => quote!(Err((#status, #message))),
+            Format::Text => quote! {
+                ::tela::response::ToResponse::to_response(
+                    (#status, #message),
+                    __method,
+                    __uri,
+                    __headers,
+                    __body,
+                )
+            },
+            Format::Json => {
+                let value = match &variant.fields {
+                    // A single unnamed field is the body; ex: `NotFound(Details)`
+                    Fields::Unnamed(fields) if fields.unnamed.len() == 1 => quote! {
+                        ::tela::bump::serde_json::to_value(&_0)
+                            .map_err(|error| (500, error.to_string()))?
+                    },
+                    Fields::Named(_) => {
+                        let keys = names
+                            .iter()
+                            .map(|name| name.to_string().trim_start_matches("r#").to_string());
+                        quote! {{
+                            let mut __object = ::tela::bump::serde_json::Map::new();
+                            __object.insert(
+                                "error".to_string(),
+                                ::tela::bump::serde_json::Value::from(#message),
+                            );
+                            #(
+                                __object.insert(
+                                    #keys.to_string(),
+                                    ::tela::bump::serde_json::to_value(&#names)
+                                        .map_err(|error| (500, error.to_string()))?,
+                                );
+                            )*
+                            ::tela::bump::serde_json::Value::Object(__object)
+                        }}
+                    }
+                    _ => quote!(::tela::bump::serde_json::json!({ "error": #message })),
+                };
+                quote! {
+                    ::tela::response::ToResponse::to_response(
+                        (#status, ::tela::response::JSON(#value)),
+                        __method,
+                        __uri,
+                        __headers,
+                        __body,
+                    )
+                }
+            }
+        };
+
+        quote!(#pattern => #body,)
+    });
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        impl #impl_generics ::tela::response::ToResponse for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn to_response(
+                self,
+                __method: &::tela::bump::hyper::Method,
+                __uri: &::tela::bump::hyper::Uri,
+                __headers: &::tela::bump::hyper::HeaderMap,
+                __body: &::tela::bump::bytes::Bytes,
+            ) -> ::tela::response::Result<::tela::bump::hyper::Response<::tela::bump::http_body_util::Full<::tela::bump::bytes::Bytes>>> {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+
+        impl #impl_generics ::tela::response::ResponseError for #name #ty_generics #where_clause {}
+    }
+}
@@ -0,0 +1,273 @@
+use proc_macro2::Span;
+use proc_macro_error::abort;
+
+/// Html elements. Matched in any case like the browser does.
+const HTML: &[&str] = &[
+    "a",
+    "abbr",
+    "address",
+    "area",
+    "article",
+    "aside",
+    "audio",
+    "b",
+    "base",
+    "bdi",
+    "bdo",
+    "blockquote",
+    "body",
+    "br",
+    "button",
+    "canvas",
+    "caption",
+    "cite",
+    "code",
+    "col",
+    "colgroup",
+    "data",
+    "datalist",
+    "dd",
+    "del",
+    "details",
+    "dfn",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "embed",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "i",
+    "iframe",
+    "img",
+    "input",
+    "ins",
+    "kbd",
+    "label",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "map",
+    "mark",
+    "math",
+    "menu",
+    "meta",
+    "meter",
+    "nav",
+    "noscript",
+    "object",
+    "ol",
+    "optgroup",
+    "option",
+    "output",
+    "p",
+    "param",
+    "picture",
+    "pre",
+    "progress",
+    "q",
+    "rp",
+    "rt",
+    "ruby",
+    "s",
+    "samp",
+    "script",
+    "search",
+    "section",
+    "select",
+    "slot",
+    "small",
+    "source",
+    "span",
+    "strong",
+    "style",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "template",
+    "textarea",
+    "tfoot",
+    "th",
+    "thead",
+    "time",
+    "title",
+    "tr",
+    "track",
+    "u",
+    "ul",
+    "var",
+    "video",
+    "wbr",
+];
+
+/// SVG elements. Matched exactly since their names keep their case; ex: `clipPath`.
+const SVG: &[&str] = &[
+    "svg",
+    "a",
+    "animate",
+    "animateMotion",
+    "animateTransform",
+    "circle",
+    "clipPath",
+    "defs",
+    "desc",
+    "ellipse",
+    "feBlend",
+    "feColorMatrix",
+    "feComponentTransfer",
+    "feComposite",
+    "feConvolveMatrix",
+    "feDiffuseLighting",
+    "feDisplacementMap",
+    "feDistantLight",
+    "feDropShadow",
+    "feFlood",
+    "feFuncA",
+    "feFuncB",
+    "feFuncG",
+    "feFuncR",
+    "feGaussianBlur",
+    "feImage",
+    "feMerge",
+    "feMergeNode",
+    "feMorphology",
+    "feOffset",
+    "fePointLight",
+    "feSpecularLighting",
+    "feSpotLight",
+    "feTile",
+    "feTurbulence",
+    "filter",
+    "foreignObject",
+    "g",
+    "image",
+    "line",
+    "linearGradient",
+    "marker",
+    "mask",
+    "metadata",
+    "mpath",
+    "path",
+    "pattern",
+    "polygon",
+    "polyline",
+    "radialGradient",
+    "rect",
+    "script",
+    "set",
+    "stop",
+    "style",
+    "switch",
+    "symbol",
+    "text",
+    "textPath",
+    "title",
+    "tspan",
+    "use",
+    "view",
+];
+
+/// Elements `html!` handles itself before the markup is rendered
+const TELA: [&str; 4] = ["extends", "block", "for", "markdown"];
+
+/// Namespace elements inside of `<svg>` are created in
+pub(crate) const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+
+/// What an element's tag name refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Tag {
+    Html,
+    /// Only an SVG element; ex: `path`. Names both elements share, `a` or `title`, are `Html`.
+    Svg,
+    /// Custom element; a lowercase name with a dash, ex: `my-widget`
+    Custom,
+    /// Any name prefixed with `@raw`, rendered as written
+    Raw,
+}
+
+/// Whether a name is a valid custom element name; it starts with a lowercase letter, contains a
+/// dash, and has no uppercase letters.
+fn is_custom(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.contains('-')
+        && !name.contains(|c: char| c.is_ascii_uppercase() || c == ':')
+}
+
+/// Kind of element a tag name is, or `None` if the name isn't known
+pub(crate) fn kind(name: &str) -> Option<Tag> {
+    if HTML.iter().any(|tag| tag.eq_ignore_ascii_case(name)) || TELA.contains(&name) {
+        Some(Tag::Html)
+    } else if SVG.contains(&name) {
+        Some(Tag::Svg)
+    } else if is_custom(name) {
+        Some(Tag::Custom)
+    } else {
+        None
+    }
+}
+
+/// Kind of element for an opening tag, or a compile error for a name that isn't an html, SVG,
+/// or custom element and isn't prefixed with `@raw`.
+pub(crate) fn check(name: &str, raw: bool, span: Span) -> Tag {
+    if raw {
+        return Tag::Raw;
+    }
+    match kind(name) {
+        Some(tag) => tag,
+        None => match SVG.iter().find(|tag| tag.eq_ignore_ascii_case(name)) {
+            Some(svg) => abort!(
+                span,
+                format!("Unknown element `<{}>`", name);
+                help = format!("SVG element names are case sensitive; use `<{}>`", svg)
+            ),
+            None => abort!(
+                span,
+                format!("Unknown element `<{}>`", name);
+                help = format!(
+                    "Custom elements need a dash, ex: `<x-{}>`. Prefix the name with `@raw` to render it as written; `<@raw {}>`",
+                    name, name
+                )
+            ),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds_of_names() {
+        assert_eq!(kind("div"), Some(Tag::Html));
+        assert_eq!(kind("DIV"), Some(Tag::Html));
+        assert_eq!(kind("a"), Some(Tag::Html));
+        assert_eq!(kind("clipPath"), Some(Tag::Svg));
+        assert_eq!(kind("foreignObject"), Some(Tag::Svg));
+        assert_eq!(kind("my-widget"), Some(Tag::Custom));
+
+        assert_eq!(kind("clippath"), None);
+        assert_eq!(kind("widget"), None);
+        assert_eq!(kind("My-Widget"), None);
+        assert_eq!(kind("-widget"), None);
+        assert_eq!(kind("svg:rect"), None);
+    }
+}
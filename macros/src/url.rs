@@ -0,0 +1,87 @@
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro_error::abort;
+use quote::quote;
+use syn::{Ident, LitStr};
+
+enum Piece {
+    Text(String),
+    /// Variable to insert and whether it is inserted without encoding
+    Capture(Ident, bool),
+}
+
+/// Split a template into text and `{name}` captures. `{{` and `}}` are literal braces.
+fn parse(template: &LitStr) -> Vec<Piece> {
+    let value = template.value();
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => abort!(template, "Unclosed `{{` in url template"),
+                    }
+                }
+                let name = name.trim();
+                if syn::parse_str::<Ident>(name).is_err() {
+                    abort!(
+                        template,
+                        "Expected a variable name in `{{{}}}`; use `{{{{` for a literal brace",
+                        name
+                    );
+                }
+                // A capture that starts the url is its base, ex: `https://api.example.com`
+                let raw = pieces.is_empty() && text.is_empty();
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(Piece::Capture(Ident::new(name, template.span()), raw));
+            }
+            '}' => abort!(
+                template,
+                "Unmatched `}}` in url template; use `}}}}` for a literal brace"
+            ),
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    pieces
+}
+
+pub fn compile_url(template: LitStr) -> TokenStream2 {
+    let pushes = parse(&template).into_iter().map(|piece| match piece {
+        Piece::Text(text) => quote! { __url.push_str(#text); },
+        Piece::Capture(name, true) => quote! { __url.push_str(&#name.to_string()); },
+        Piece::Capture(name, false) => quote! { __encode(&mut __url, &#name.to_string()); },
+    });
+
+    quote! {{
+        fn __encode(url: &mut String, value: &str) {
+            for byte in value.bytes() {
+                match byte {
+                    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                        url.push(byte as char)
+                    }
+                    byte => url.push_str(&format!("%{:02X}", byte)),
+                }
+            }
+        }
+        let mut __url = String::new();
+        #(#pushes)*
+        __url
+    }}
+}
@@ -6,7 +6,11 @@ use hyper::{
     body::{Bytes, Incoming},
     Request, Response,
 };
-use new::server::{error::Error, router::Router, serve, Socket};
+use new::{
+    routes,
+    server::{error::Error, serve},
+    socket,
+};
 
 fn handler(_: Request<Incoming>) -> Result<Response<Full<Bytes>>, Error> {
     Ok(Response::new(Full::new(Bytes::from("Hello, world!"))))
@@ -32,42 +36,14 @@ fn hours(_: Request<Incoming>) -> String {
     }
 }
 
-macro_rules! router {
-    ($(
-        $path: literal : {
-            $init: ident : $init_handler: expr
-        }
-     ),* $(,)?) => {
-        {
-            Router::new()
-                $(
-                    .route($path, new::server::router::$init($init_handler)$(.$method($handler))*)
-                )*
-        }
-    };
-    ($(
-        $path: literal : {
-            $init: ident : $init_handler: expr,
-            $($method: ident : $handler: expr),* $(,)?
-        }),* $(,)?) => {
-        {
-            Router::new()
-                $(
-                    .route($path, new::server::router::$init($init_handler)$(.$method($handler))*)
-                )*
-        }
-    };
-}
-
 #[tokio::main]
 async fn main() {
     let _ = serve(
-        Socket::Local(3000),
-        router! {
-            "/hours": {
-                post: |_| "(POST) Hours Page!!!",
-                get: hours
-            }
+        socket!(3000),
+        routes! {
+            "/hours" => post(|_| "(POST) Hours Page!!!")
+                .post_json(|_| "(POST JSON) Hours Page!!!")
+                .get(hours),
         },
     )
     .await;
@@ -1,6 +1,8 @@
 use http_body_util::Full;
 use hyper::{body::Bytes, Response};
 
+use crate::server::error::Error;
+
 pub trait IntoResponse {
     fn into_response(self) -> Response<Full<Bytes>>;
 }
@@ -28,3 +30,44 @@ impl IntoResponse for String {
             .unwrap()
     }
 }
+
+impl IntoResponse for Bytes {
+    fn into_response(self) -> Response<Full<Bytes>> {
+        Response::builder()
+            .status(200)
+            .header("Content-Type", "application/octet-stream")
+            .body(Full::new(self))
+            .unwrap()
+    }
+}
+
+impl IntoResponse for Vec<u8> {
+    fn into_response(self) -> Response<Full<Bytes>> {
+        Bytes::from(self).into_response()
+    }
+}
+
+impl IntoResponse for &'static [u8] {
+    fn into_response(self) -> Response<Full<Bytes>> {
+        Bytes::from_static(self).into_response()
+    }
+}
+
+impl<T: IntoResponse> IntoResponse for Option<T> {
+    /// `None` responds with a `404 Not Found`; ex: when a lookup by id doesn't find anything
+    fn into_response(self) -> Response<Full<Bytes>> {
+        match self {
+            Some(response) => response.into_response(),
+            None => Error::new(404, "Not found").into_response(),
+        }
+    }
+}
+
+impl<T: IntoResponse, E: IntoResponse> IntoResponse for Result<T, E> {
+    fn into_response(self) -> Response<Full<Bytes>> {
+        match self {
+            Ok(response) => response.into_response(),
+            Err(error) => error.into_response(),
+        }
+    }
+}
@@ -1,22 +1,185 @@
 use std::fmt::Display;
 
+use http_body_util::Full;
+use hyper::{body::Bytes, Response};
+
+use crate::response::IntoResponse;
+
+type Source = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// Error returned from a handler. Has the response status, a message, the error that caused
+/// it, and context that helps with debugging.
+///
+/// Common errors convert into an `Error` so `?` can be used in handlers.
+///
+/// # Example
+/// ```
+/// use new::server::error::Error;
+///
+/// fn read_post(slug: &str) -> Result<String, Error> {
+///     let post = std::fs::read_to_string(format!("posts/{}.md", slug))
+///         .map_err(|error| Error::from(error).context("slug", slug))?;
+///     Ok(post)
+/// }
+/// ```
 #[derive(Debug)]
-pub struct Error(pub u16, pub String);
+pub struct Error {
+    status: u16,
+    message: String,
+    source: Option<Source>,
+    context: Vec<(String, String)>,
+}
+
+impl Error {
+    pub fn new<M: Into<String>>(status: u16, message: M) -> Self {
+        Error {
+            status,
+            message: message.into(),
+            source: None,
+            context: Vec::new(),
+        }
+    }
+
+    /// Error with the status that was caused by another error. The source's message is used.
+    pub fn from_source<E: Into<Source>>(status: u16, source: E) -> Self {
+        let source = source.into();
+        Error {
+            status,
+            message: source.to_string(),
+            source: Some(source),
+            context: Vec::new(),
+        }
+    }
+
+    /// Set the error that caused this one
+    pub fn with_source<E: Into<Source>>(mut self, source: E) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Add a key value pair that explains the state when the error happened
+    pub fn context<K: Display, V: Display>(mut self, key: K, value: V) -> Self {
+        self.context.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn get_context(&self) -> &[(String, String)] {
+        &self.context
+    }
+
+    /// Messages of the errors that caused this one starting with the direct source
+    pub fn chain(&self) -> Vec<String> {
+        let mut chain = Vec::new();
+        let mut source = std::error::Error::source(self);
+        while let Some(error) = source {
+            chain.push(error.to_string());
+            source = error.source();
+        }
+        chain
+    }
+}
 
 impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}: {}", self.0, self.1)
+        write!(f, "{}: {}", self.status, self.message)
     }
 }
 
 impl std::error::Error for Error {
-    fn cause(&self) -> Option<&dyn std::error::Error> {
-        None
-    }
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        None
+        self.source
+            .as_ref()
+            .map(|source| source.as_ref() as &(dyn std::error::Error + 'static))
     }
-    fn description(&self) -> &str {
-        Box::leak(self.to_string().into_boxed_str())
+}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        let status = match value.kind() {
+            std::io::ErrorKind::NotFound => 404,
+            std::io::ErrorKind::PermissionDenied => 403,
+            _ => 500,
+        };
+        Error::from_source(status, value)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    /// Invalid json is the client's fault, failing to read or write it is the server's
+    fn from(value: serde_json::Error) -> Self {
+        let status = match value.is_io() {
+            true => 500,
+            false => 400,
+        };
+        Error::from_source(status, value)
+    }
+}
+
+impl From<hyper::Error> for Error {
+    fn from(value: hyper::Error) -> Self {
+        let status = match value.is_parse() {
+            true => 400,
+            false => 500,
+        };
+        Error::from_source(status, value)
+    }
+}
+
+impl From<hyper::http::Error> for Error {
+    fn from(value: hyper::http::Error) -> Self {
+        Error::from_source(500, value)
+    }
+}
+
+impl From<std::str::Utf8Error> for Error {
+    fn from(value: std::str::Utf8Error) -> Self {
+        Error::from_source(400, value)
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(value: std::string::FromUtf8Error) -> Self {
+        Error::from_source(400, value)
+    }
+}
+
+impl IntoResponse for Error {
+    /// Respond with the error's status and the message in the `Tela-Reason` header. Invalid
+    /// status codes are sent as a 500.
+    ///
+    /// In debug builds the body lists the message, the chain of source errors, and the context.
+    fn into_response(self) -> Response<Full<Bytes>> {
+        let status = hyper::StatusCode::from_u16(self.status)
+            .unwrap_or(hyper::StatusCode::INTERNAL_SERVER_ERROR);
+        let reason = self.message.replace(|c: char| c.is_control(), " ");
+
+        #[cfg(debug_assertions)]
+        let body = {
+            let mut body = format!("{}\n", self);
+            for (i, source) in self.chain().iter().enumerate() {
+                body.push_str(&format!("  {}: caused by: {}\n", i, source));
+            }
+            for (key, value) in &self.context {
+                body.push_str(&format!("  {} = {}\n", key, value));
+            }
+            Bytes::from(body)
+        };
+        #[cfg(not(debug_assertions))]
+        let body = Bytes::new();
+
+        let mut response = Response::new(Full::new(body));
+        *response.status_mut() = status;
+        if let Ok(reason) = hyper::header::HeaderValue::from_str(&reason) {
+            response.headers_mut().insert("Tela-Reason", reason);
+        }
+        response
     }
 }
@@ -1,6 +1,6 @@
 //! https://hyper.rs/guides/1/server/hello-world/
 
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 
 use hyper::server::conn::http1;
 use hyper_util::rt::TokioIo;
@@ -11,10 +11,29 @@ use self::router::IntoRouter;
 pub mod error;
 pub mod router;
 
-/// Defines whether the socket address should be localhost or on the network.
+/// Defines whether the socket address should be localhost, on the network, or a specific ip.
+///
+/// Port `0` binds to any free port; use `serve_on_bind` to get the port that was picked.
 pub enum Socket {
     Local(u16),
     Network(u16),
+    Addr(IpAddr, u16),
+}
+
+impl Socket {
+    /// Use the port from the `PORT` environment variable if it is set, keeping the address;
+    /// ex: when a platform decides the port the app listens on
+    pub fn env(self) -> Self {
+        let port = match std::env::var("PORT").ok().and_then(|port| port.parse().ok()) {
+            Some(port) => port,
+            None => return self,
+        };
+        match self {
+            Socket::Local(_) => Socket::Local(port),
+            Socket::Network(_) => Socket::Network(port),
+            Socket::Addr(ip, _) => Socket::Addr(ip, port),
+        }
+    }
 }
 
 impl Default for Socket {
@@ -23,6 +42,29 @@ impl Default for Socket {
     }
 }
 
+/// Build a `Socket` that uses the `PORT` environment variable's port when it is set.
+///
+/// # Example
+/// ```
+/// use new::socket;
+///
+/// socket!();                    // localhost on `PORT` or 3210
+/// socket!(3000);                // localhost on `PORT` or 3000
+/// socket!([0, 0, 0, 0], 3000);  // any ip address given as `Into<IpAddr>`
+/// ```
+#[macro_export]
+macro_rules! socket {
+    () => {
+        $crate::server::Socket::default().env()
+    };
+    ($port: expr) => {
+        $crate::server::Socket::Local($port).env()
+    };
+    ($ip: expr, $port: expr) => {
+        $crate::server::Socket::Addr(::std::net::IpAddr::from($ip), $port).env()
+    };
+}
+
 /// Convert a tuple of ([], u16) or ([u8; 4], u16) into a SocketAddr;
 /// or convert a Socket into a SocketAddr.
 pub trait IntoSocketAddr {
@@ -46,6 +88,7 @@ impl IntoSocketAddr for Socket {
         match self {
             Socket::Local(port) => SocketAddr::from(([127, 0, 0, 1], port)),
             Socket::Network(port) => SocketAddr::from(([0, 0, 0, 0], port)),
+            Socket::Addr(ip, port) => SocketAddr::new(ip, port),
         }
     }
 }
@@ -76,12 +119,42 @@ where
     Addr: IntoSocketAddr,
     R: IntoRouter,
 {
-    let addr = addr.into_socket_addr();
-    let listener = TcpListener::bind(addr).await?;
-    let router = router.into_router();
+    serve_on_bind(addr, router, |_addr| {
+        #[cfg(debug_assertions)]
+        println!("Serving at {}", _addr);
+    })
+    .await
+}
 
-    #[cfg(debug_assertions)]
-    println!("Serving at {}", addr);
+/// Same as `serve` but calls `on_bind` with the address once it is bound. The address has the
+/// port that was picked when binding to port `0`.
+///
+/// # Example
+/// ```no_run
+/// use new::{
+///     routes,
+///     server::{serve_on_bind, Socket},
+/// };
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+///     let router = routes! { "/" => get(|_| "Hello, world!") };
+///     serve_on_bind(Socket::Local(0), router, |addr| println!("listening on {}", addr.port())).await
+/// }
+/// ```
+pub async fn serve_on_bind<Addr, R, F>(
+    addr: Addr,
+    router: R,
+    on_bind: F,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+    Addr: IntoSocketAddr,
+    R: IntoRouter,
+    F: FnOnce(SocketAddr),
+{
+    let listener = TcpListener::bind(addr.into_socket_addr()).await?;
+    let router = router.into_router();
+    on_bind(listener.local_addr()?);
 
     loop {
         let (stream, _) = listener.accept().await?;
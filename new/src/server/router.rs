@@ -1,10 +1,4 @@
-use std::{
-    collections::HashMap,
-    fmt::Debug,
-    future::Future,
-    pin::Pin,
-    sync::{Arc, RwLock},
-};
+use std::{collections::HashMap, fmt::Debug, future::Future, pin::Pin, sync::Arc};
 
 use http_body_util::Full;
 use hyper::{
@@ -41,14 +35,77 @@ pub enum Endpoint {
     #[default]
     None,
     Route(Arc<dyn Handler + Send + Sync + 'static>),
+    /// The method has handlers but none of them accept the request's content type
+    Unsupported,
 }
 
 #[derive(Clone, Debug)]
 pub struct Route {
     callbacks: RouteMethods,
+    content: ContentTypes,
+}
+
+/// Handlers selected by the request's `Content-Type` after the method matches
+#[derive(Default, Clone)]
+pub struct ContentTypes(Vec<(hyper::Method, String, Endpoint)>);
+
+impl ContentTypes {
+    fn fetch(&self, method: &hyper::Method, content_type: Option<&str>) -> Option<Endpoint> {
+        let mut handlers = self.0.iter().filter(|(m, _, _)| m == method).peekable();
+        handlers.peek()?;
+
+        // Parameters like charset are ignored; ex: `application/json; charset=utf-8`
+        let essence = content_type
+            .and_then(|content_type| content_type.split(';').next())
+            .map(|essence| essence.trim().to_lowercase());
+        Some(
+            handlers
+                .find(|(_, accepts, _)| Some(accepts) == essence.as_ref())
+                .map(|(_, _, endpoint)| endpoint.clone())
+                .unwrap_or(Endpoint::Unsupported),
+        )
+    }
 }
 
-#[derive(Debug)]
+impl Debug for ContentTypes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ContentTypes({})",
+            self.0
+                .iter()
+                .map(|(method, content_type, _)| format!("{} {}", method, content_type))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+impl Route {
+    /// Handler for requests with the method and content type; ex: `(Method::POST, "text/csv")`.
+    ///
+    /// Content type handlers are checked before the method's handler. If none of them accept
+    /// the request the method's handler is used, and if there isn't one the request is
+    /// rejected with a 415.
+    pub fn content<T: Handler + Send + Sync>(
+        mut self,
+        method: hyper::Method,
+        content_type: &str,
+        callback: T,
+    ) -> Self {
+        let content_type = content_type.trim().to_lowercase();
+        self.content
+            .0
+            .retain(|(m, accepts, _)| !(m == &method && accepts == &content_type));
+        self.content
+            .0
+            .push((method, content_type, Endpoint::Route(callback.arced())));
+        self
+    }
+}
+
+/// Route table. It is built before serving and is shared between requests without a lock.
+#[derive(Debug, Clone)]
 pub struct Routes(HashMap<String, Route>);
 
 impl Routes {
@@ -56,13 +113,18 @@ impl Routes {
         self.0.insert(key, value)
     }
 
-    pub fn fetch(&self, uri: &str, method: &hyper::Method) -> Endpoint {
-        if !self.0.contains_key(uri) {
-            return Endpoint::None;
+    pub fn fetch(&self, uri: &str, method: &hyper::Method, content_type: Option<&str>) -> Endpoint {
+        match self.0.get(uri) {
+            Some(route) => match route.content.fetch(method, content_type) {
+                Some(Endpoint::Unsupported) => match route.fetch(method) {
+                    Endpoint::None => Endpoint::Unsupported,
+                    endpoint => endpoint,
+                },
+                Some(endpoint) => endpoint,
+                None => route.fetch(method),
+            },
+            None => Endpoint::None,
         }
-
-        let route = self.0.get(uri).unwrap();
-        route.fetch(method)
     }
 
     pub fn new() -> Self {
@@ -81,7 +143,8 @@ macro_rules! make_methods {
                         callbacks: $crate::server::router::RouteMethods {
                             [<$method:lower>]: $crate::server::router::Endpoint::Route(callback.arced()),
                             ..Default::default()
-                        }
+                        },
+                        content: Default::default(),
                     }
                 }
             )*
@@ -117,6 +180,7 @@ macro_rules! make_methods {
                     let se = |e: &Endpoint| match e {
                         &Endpoint::None => "None",
                         &Endpoint::Route(_) => "Route",
+                        &Endpoint::Unsupported => "Unsupported",
                     };
                     write!(
                         f,
@@ -135,12 +199,114 @@ macro_rules! make_methods {
 
 make_methods! {GET, POST, DELETE, PUT, HEAD, CONNECT, OPTIONS, TRACE, PATCH}
 
+macro_rules! make_content_methods {
+    ($($method: ident),*) => {
+        paste::paste! {
+            $(
+                #[doc="Create a new route with a " $method " handler for `application/json` requests"]
+                pub fn [<$method:lower _json>]<T: Handler + Send + Sync>(callback: T) -> Route {
+                    Route {
+                        callbacks: Default::default(),
+                        content: Default::default(),
+                    }
+                    .[<$method:lower _json>](callback)
+                }
+
+                #[doc="Create a new route with a " $method " handler for `application/x-www-form-urlencoded` requests"]
+                pub fn [<$method:lower _form>]<T: Handler + Send + Sync>(callback: T) -> Route {
+                    Route {
+                        callbacks: Default::default(),
+                        content: Default::default(),
+                    }
+                    .[<$method:lower _form>](callback)
+                }
+            )*
+
+            impl Route {
+                $(
+                    #[doc=$method " handler for `application/json` requests"]
+                    pub fn [<$method:lower _json>]<T: Handler + Send + Sync>(self, callback: T) -> Self {
+                        self.content(hyper::Method::$method, "application/json", callback)
+                    }
+
+                    #[doc=$method " handler for `application/x-www-form-urlencoded` requests"]
+                    pub fn [<$method:lower _form>]<T: Handler + Send + Sync>(self, callback: T) -> Self {
+                        self.content(hyper::Method::$method, "application/x-www-form-urlencoded", callback)
+                    }
+                )*
+            }
+        }
+    };
+}
+
+make_content_methods! {POST, DELETE, PUT, PATCH}
+
+/// Whether two of the paths are the same. Used by `routes!` to reject duplicate paths at
+/// compile time.
+#[doc(hidden)]
+pub const fn has_duplicate(paths: &[&str]) -> bool {
+    let mut i = 0;
+    while i < paths.len() {
+        let mut j = i + 1;
+        while j < paths.len() {
+            let (a, b) = (paths[i].as_bytes(), paths[j].as_bytes());
+            if a.len() == b.len() {
+                let mut k = 0;
+                while k < a.len() && a[k] == b[k] {
+                    k += 1;
+                }
+                if k == a.len() {
+                    return true;
+                }
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Build a `Router` from a table of paths and their routes. A path that is in the table more
+/// than once is a compile error.
+///
+/// # Example
+/// ```
+/// use hyper::{body::Incoming, Request};
+/// use new::routes;
+///
+/// fn home(_: Request<Incoming>) -> String {
+///     "Home".to_string()
+/// }
+///
+/// fn create(_: Request<Incoming>) -> String {
+///     "Created".to_string()
+/// }
+///
+/// let router = routes! {
+///     "/" => get(home).post(create),
+///     "/upload" => post(|_| "Uploaded").post_json(|_| "Uploaded json"),
+/// };
+/// ```
+#[macro_export]
+macro_rules! routes {
+    ($(
+        $path: literal => $init: ident ($init_handler: expr) $(.$method: ident ($handler: expr))*
+    ),* $(,)?) => {{
+        const _: () = assert!(
+            !$crate::server::router::has_duplicate(&[$($path),*]),
+            "routes! has a path more than once"
+        );
+        $crate::server::router::Router::new()
+            $(.route($path, $crate::server::router::$init($init_handler)$(.$method($handler))*))*
+    }};
+}
+
 #[derive(Clone)]
 pub struct Router {
     pub handler: Option<
         Arc<dyn Fn(Request<Incoming>) -> Result<Response<Full<Bytes>>, Error> + Send + Sync>,
     >,
-    pub routes: Arc<RwLock<Routes>>,
+    pub routes: Arc<Routes>,
 }
 
 impl Router {
@@ -149,20 +315,20 @@ impl Router {
             Arc<dyn Fn(Request<Incoming>) -> Result<Response<Full<Bytes>>, Error> + Send + Sync>,
         >,
         request: Request<Incoming>,
-        routes: Arc<RwLock<Routes>>,
+        routes: Arc<Routes>,
     ) -> Result<Response<Full<Bytes>>, Error> {
         if let Some(handler) = handler {
             return handler(request);
         }
 
-        let routes = routes.read().unwrap();
-        match routes.fetch(&request.uri().to_string(), &request.method()) {
+        let content_type = request
+            .headers()
+            .get(hyper::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok());
+        match routes.fetch(&request.uri().to_string(), request.method(), content_type) {
             // TODO: add static file serving
-            Endpoint::None => Ok(Response::builder()
-                .status(404)
-                .header("Tela-Reason", "Page not found")
-                .body(Full::new(Bytes::new()))
-                .unwrap()),
+            Endpoint::None => Err(Error::new(404, "Page not found")),
+            Endpoint::Unsupported => Err(Error::new(415, "Unsupported content type")),
             Endpoint::Route(endpoint) => Ok(endpoint.call(request).into_response()),
         }
     }
@@ -170,7 +336,7 @@ impl Router {
     pub fn new() -> Self {
         Router {
             handler: None,
-            routes: Arc::new(RwLock::new(Routes::new())),
+            routes: Arc::new(Routes::new()),
         }
     }
 
@@ -181,11 +347,9 @@ impl Router {
         }
     }
 
-    pub fn route(self, path: &str, route: Route) -> Self {
-        {
-            let mut routes = self.routes.write().unwrap();
-            routes.insert(path.to_string(), route);
-        }
+    /// Add a route. The table is only copied if the router has already been spawned.
+    pub fn route(mut self, path: &str, route: Route) -> Self {
+        Arc::make_mut(&mut self.routes).insert(path.to_string(), route);
         self
     }
 }
@@ -203,11 +367,15 @@ impl Service<Request<Incoming>> for Router {
     type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
 
     fn call(&self, req: Request<Incoming>) -> Self::Future {
-        Box::pin(Router::handler(
-            self.handler.clone(),
-            req,
-            self.routes.clone(),
-        ))
+        let handler = self.handler.clone();
+        let routes = self.routes.clone();
+        // Errors are sent as responses; returning them to hyper would drop the connection
+        Box::pin(async move {
+            match Router::handler(handler, req, routes).await {
+                Ok(response) => Ok(response),
+                Err(error) => Ok(error.into_response()),
+            }
+        })
     }
 }
 
@@ -223,7 +391,7 @@ where
     fn into_router(self) -> Router {
         Router {
             handler: Some(Arc::new(self)),
-            routes: Arc::new(RwLock::new(Routes::new())),
+            routes: Arc::new(Routes::new()),
         }
     }
 }
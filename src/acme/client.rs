@@ -0,0 +1,250 @@
+use std::{io, time::Duration};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{header::LOCATION, Method};
+use ring::{
+    digest::{digest, SHA256},
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING},
+};
+use serde_json::{json, Value};
+
+/// Times the status of an authorization or order is checked before giving up
+const POLLS: usize = 30;
+
+fn b64<T: AsRef<[u8]>>(value: T) -> String {
+    URL_SAFE_NO_PAD.encode(value)
+}
+
+fn invalid<T: Into<String>>(message: T) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Error from an acme problem document; ex: `urn:ietf:params:acme:error:rateLimited`
+fn problem(body: &[u8]) -> io::Error {
+    match serde_json::from_slice::<Value>(body) {
+        Ok(problem) => io::Error::other(format!(
+            "{}: {}",
+            problem["type"].as_str().unwrap_or("error"),
+            problem["detail"].as_str().unwrap_or("")
+        )),
+        Err(_) => io::Error::other(String::from_utf8_lossy(body).to_string()),
+    }
+}
+
+/// Account with an acme server; ex: Let's Encrypt. Requests are signed with the account key
+/// as JWS (RFC 8555).
+pub(super) struct Account {
+    key: EcdsaKeyPair,
+    rng: SystemRandom,
+    jwk: Value,
+    new_nonce: String,
+    new_account: String,
+    new_order: String,
+    nonce: Option<String>,
+    kid: Option<String>,
+}
+
+impl Account {
+    /// Read the server's directory and create or find the account for the key. `key` is an
+    /// ECDSA P-256 PKCS#8 key.
+    pub(super) async fn open(directory: &str, key: &[u8], contact: &[String]) -> io::Result<Self> {
+        let rng = SystemRandom::new();
+        let key = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, key, &rng)
+            .map_err(|_| invalid("Invalid acme account key"))?;
+        // Uncompressed point; 0x04 then the x and y coordinates
+        let point = key.public_key().as_ref();
+        let jwk = json!({
+            "crv": "P-256",
+            "kty": "EC",
+            "x": b64(&point[1..33]),
+            "y": b64(&point[33..65]),
+        });
+
+        let response = request(Method::GET, directory, None).await?;
+        let directory: Value = serde_json::from_slice(response.body())
+            .map_err(|_| invalid("Invalid acme directory"))?;
+        let url = |name: &str| {
+            directory[name]
+                .as_str()
+                .map(String::from)
+                .ok_or_else(|| invalid(format!("Acme directory is missing {}", name)))
+        };
+
+        let mut account = Account {
+            key,
+            rng,
+            jwk,
+            new_nonce: url("newNonce")?,
+            new_account: url("newAccount")?,
+            new_order: url("newOrder")?,
+            nonce: None,
+            kid: None,
+        };
+        let payload = json!({
+            "termsOfServiceAgreed": true,
+            "contact": contact,
+        });
+        let new_account = account.new_account.clone();
+        let (response, _) = account.post(&new_account, Some(&payload)).await?;
+        account.kid = Some(location(&response)?);
+        Ok(account)
+    }
+
+    /// Thumbprint of the account key (RFC 7638). Members are in lexicographic order.
+    fn thumbprint(&self) -> String {
+        let jwk = format!(
+            r#"{{"crv":"P-256","kty":"EC","x":{},"y":{}}}"#,
+            self.jwk["x"], self.jwk["y"]
+        );
+        b64(digest(&SHA256, jwk.as_bytes()))
+    }
+
+    /// Key authorization served for a challenge's token
+    pub(super) fn key_authorization(&self, token: &str) -> String {
+        format!("{}.{}", token, self.thumbprint())
+    }
+
+    async fn nonce(&mut self) -> io::Result<String> {
+        if let Some(nonce) = self.nonce.take() {
+            return Ok(nonce);
+        }
+        let response = request(Method::HEAD, &self.new_nonce, None).await?;
+        replay_nonce(&response).ok_or_else(|| invalid("Acme server didn't send a nonce"))
+    }
+
+    /// JWS with the account's key id, or its public key before the account exists
+    fn sign(&self, url: &str, nonce: String, payload: Option<&Value>) -> io::Result<Vec<u8>> {
+        let protected = match &self.kid {
+            Some(kid) => json!({"alg": "ES256", "kid": kid, "nonce": nonce, "url": url}),
+            None => json!({"alg": "ES256", "jwk": self.jwk, "nonce": nonce, "url": url}),
+        };
+        let protected = b64(protected.to_string());
+        // An empty payload is a POST-as-GET
+        let payload = payload
+            .map(|payload| b64(payload.to_string()))
+            .unwrap_or_default();
+        let signature = self
+            .key
+            .sign(&self.rng, format!("{}.{}", protected, payload).as_bytes())
+            .map_err(|_| invalid("Failed to sign acme request"))?;
+        Ok(json!({
+            "protected": protected,
+            "payload": payload,
+            "signature": b64(signature),
+        })
+        .to_string()
+        .into_bytes())
+    }
+
+    /// Signed POST. Retried once with a new nonce if the server rejects the nonce.
+    pub(super) async fn post(
+        &mut self,
+        url: &str,
+        payload: Option<&Value>,
+    ) -> io::Result<(hyper::Response<Bytes>, Value)> {
+        for retry in [false, true] {
+            let nonce = self.nonce().await?;
+            let body = self.sign(url, nonce, payload)?;
+            let response = request(Method::POST, url, Some(body)).await?;
+            self.nonce = replay_nonce(&response);
+
+            if response.status().is_success() {
+                let body = serde_json::from_slice(response.body()).unwrap_or(Value::Null);
+                return Ok((response, body));
+            }
+            let bad_nonce = serde_json::from_slice::<Value>(response.body())
+                .map(|problem| problem["type"] == "urn:ietf:params:acme:error:badNonce")
+                .unwrap_or(false);
+            if !bad_nonce || retry {
+                return Err(problem(response.body()));
+            }
+        }
+        unreachable!()
+    }
+
+    /// Create an order for the domains. Returns its url and the order.
+    pub(super) async fn order(&mut self, domains: &[String]) -> io::Result<(String, Value)> {
+        let identifiers: Vec<Value> = domains
+            .iter()
+            .map(|domain| json!({"type": "dns", "value": domain}))
+            .collect();
+        let new_order = self.new_order.clone();
+        let (response, order) = self
+            .post(&new_order, Some(&json!({ "identifiers": identifiers })))
+            .await?;
+        Ok((location(&response)?, order))
+    }
+
+    /// POST-as-GET the object at the url until its status is `valid`, or one of `until`
+    pub(super) async fn poll(&mut self, url: &str, until: &[&str]) -> io::Result<Value> {
+        for _ in 0..POLLS {
+            let (_, object) = self.post(url, None).await?;
+            match object["status"].as_str() {
+                Some("valid") => return Ok(object),
+                Some("invalid") => {
+                    return Err(io::Error::other(format!(
+                        "Acme validation failed: {}",
+                        failure(&object)
+                    )))
+                }
+                Some(status) if until.contains(&status) => return Ok(object),
+                _ => tokio::time::sleep(Duration::from_secs(2)).await,
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("Timed out waiting for {}", url),
+        ))
+    }
+}
+
+/// Why an authorization or order is invalid
+fn failure(object: &Value) -> String {
+    let error = object["challenges"]
+        .as_array()
+        .and_then(|challenges| challenges.iter().find(|c| !c["error"].is_null()))
+        .map(|challenge| &challenge["error"])
+        .unwrap_or(&object["error"]);
+    error["detail"]
+        .as_str()
+        .unwrap_or("no details were given")
+        .to_string()
+}
+
+fn location(response: &hyper::Response<Bytes>) -> io::Result<String> {
+    response
+        .headers()
+        .get(LOCATION)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+        .ok_or_else(|| invalid("Acme server didn't send a location"))
+}
+
+fn replay_nonce(response: &hyper::Response<Bytes>) -> Option<String> {
+    response
+        .headers()
+        .get("Replay-Nonce")
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+async fn request(
+    method: Method,
+    url: &str,
+    body: Option<Vec<u8>>,
+) -> io::Result<hyper::Response<Bytes>> {
+    let mut request = hyper::Request::builder()
+        .method(method)
+        .uri(url)
+        .header("User-Agent", "tela-acme");
+    if body.is_some() {
+        request = request.header("Content-Type", "application/jose+json");
+    }
+    let request = request
+        .body(Full::new(Bytes::from(body.unwrap_or_default())))
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+    crate::client::send(request).await
+}
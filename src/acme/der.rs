@@ -0,0 +1,353 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+/// Object identifiers, already encoded with their tag and length
+const COMMON_NAME: &[u8] = &[0x06, 0x03, 0x55, 0x04, 0x03];
+const EC_PUBLIC_KEY: &[u8] = &[0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const P256: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const ECDSA_SHA256: &[u8] = &[0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+const EXTENSION_REQUEST: &[u8] = &[
+    0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x0e,
+];
+const SUBJECT_ALT_NAME: &[u8] = &[0x06, 0x03, 0x55, 0x1d, 0x11];
+
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+
+/// Encode a value with its tag and length
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        encoded.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|byte| *byte == 0)
+            .collect();
+        encoded.push(0x80 | bytes.len() as u8);
+        encoded.extend(bytes);
+    }
+    encoded.extend_from_slice(content);
+    encoded
+}
+
+fn sequence(items: &[&[u8]]) -> Vec<u8> {
+    der(SEQUENCE, &items.concat())
+}
+
+fn bit_string(bits: &[u8]) -> Vec<u8> {
+    der(0x03, &[&[0], bits].concat())
+}
+
+/// Certificate signing request (PKCS#10) for the domains with an ECDSA P-256 public key.
+/// `sign` signs the request info with the matching private key and returns a DER signature.
+pub(super) fn csr<F>(domains: &[String], public_key: &[u8], sign: F) -> Option<Vec<u8>>
+where
+    F: FnOnce(&[u8]) -> Option<Vec<u8>>,
+{
+    // The common name is limited to 64 characters; the alt names are what is checked
+    let subject = match domains.first() {
+        Some(domain) if domain.len() <= 64 => sequence(&[&der(
+            SET,
+            &sequence(&[COMMON_NAME, &der(0x0c, domain.as_bytes())]),
+        )]),
+        _ => sequence(&[]),
+    };
+    let key = sequence(&[&sequence(&[EC_PUBLIC_KEY, P256]), &bit_string(public_key)]);
+    let names: Vec<u8> = domains
+        .iter()
+        .flat_map(|domain| der(0x82, domain.as_bytes()))
+        .collect();
+    let alt_names = sequence(&[SUBJECT_ALT_NAME, &der(0x04, &der(SEQUENCE, &names))]);
+    let attributes = der(
+        0xa0,
+        &sequence(&[EXTENSION_REQUEST, &der(SET, &sequence(&[&alt_names]))]),
+    );
+    let info = sequence(&[&der(0x02, &[0]), &subject, &key, &attributes]);
+
+    let signature = sign(&info)?;
+    Some(sequence(&[
+        &info,
+        &sequence(&[ECDSA_SHA256]),
+        &bit_string(&signature),
+    ]))
+}
+
+/// Tag, content, and what follows the first value
+fn read(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = match first < 0x80 {
+        true => (first as usize, rest),
+        false => {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > 4 || rest.len() < count {
+                return None;
+            }
+            let len = rest[..count]
+                .iter()
+                .fold(0usize, |len, byte| len << 8 | *byte as usize);
+            (len, &rest[count..])
+        }
+    };
+    match rest.len() >= len {
+        true => Some((tag, &rest[..len], &rest[len..])),
+        false => None,
+    }
+}
+
+/// When a DER certificate expires
+pub(super) fn not_after(certificate: &[u8]) -> Option<SystemTime> {
+    let (_, certificate, _) = read(certificate)?;
+    let (_, tbs, _) = read(certificate)?;
+    // The version is optional; the serial number follows it
+    let (tag, _, mut rest) = read(tbs)?;
+    if tag == 0xa0 {
+        rest = read(rest)?.2;
+    }
+    // Signature algorithm, then issuer
+    let rest = read(read(rest)?.2)?.2;
+    let (_, validity, _) = read(rest)?;
+    let (tag, time, _) = read(read(validity)?.2)?;
+    time_from(tag, time)
+}
+
+/// Parse a `UTCTime`, `YYMMDDHHMMSSZ`, or `GeneralizedTime`, `YYYYMMDDHHMMSSZ`
+fn time_from(tag: u8, time: &[u8]) -> Option<SystemTime> {
+    let time = std::str::from_utf8(time).ok()?.strip_suffix('Z')?;
+    let (year, rest) = match tag {
+        0x17 => {
+            let year: i64 = time.get(..2)?.parse().ok()?;
+            (
+                if year >= 50 { 1900 + year } else { 2000 + year },
+                &time[2..],
+            )
+        }
+        0x18 => (time.get(..4)?.parse().ok()?, &time[4..]),
+        _ => return None,
+    };
+    let field = |index: usize| rest.get(index..index + 2)?.parse::<i64>().ok();
+    let (month, day) = (field(0)?, field(2)?);
+    let (hour, minute, second) = (field(4)?, field(6)?, field(8)?);
+
+    // Days since the unix epoch of a date in the proleptic gregorian calendar
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(seconds.try_into().ok()?))
+}
+
+/// PEM block with the label; ex: `CERTIFICATE`
+pub(super) fn to_pem(label: &str, der: &[u8]) -> String {
+    let encoded = STANDARD.encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap());
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+/// Contents of every PEM block with the label
+pub(super) fn from_pem(label: &str, pem: &str) -> Vec<Vec<u8>> {
+    let (begin, end) = (
+        format!("-----BEGIN {}-----", label),
+        format!("-----END {}-----", label),
+    );
+    pem.split(begin.as_str())
+        .skip(1)
+        .filter_map(|block| {
+            let (block, _) = block.split_once(end.as_str())?;
+            let encoded: String = block.split_whitespace().collect();
+            STANDARD.decode(encoded).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::{
+        rand::SystemRandom,
+        signature::{
+            EcdsaKeyPair, KeyPair, UnparsedPublicKey, ECDSA_P256_SHA256_ASN1,
+            ECDSA_P256_SHA256_ASN1_SIGNING,
+        },
+    };
+
+    use super::*;
+
+    /// Self signed certificate that expires `2027-10-16T21:05:15Z`, as a `UTCTime`
+    const UTC_CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBgTCCASegAwIBAgIUaDAi5jmG0wpwwqX7UpSiiaB+VtwwCgYIKoZIzj0EAwIw
+FjEUMBIGA1UEAwwLZXhhbXBsZS5jb20wHhcNMjYxMDE2MjEwNTE1WhcNMjcxMDE2
+MjEwNTE1WjAWMRQwEgYDVQQDDAtleGFtcGxlLmNvbTBZMBMGByqGSM49AgEGCCqG
+SM49AwEHA0IABA279sed360MC3O+WViAQ7O/SudY+qmEmHm4eU9284/mZ69vSQNe
+AgZCoKiKkwioV0Bx88hp+ti2fKOliYkFLgmjUzBRMB0GA1UdDgQWBBQ4RkTwxkqv
+by9GrHlN/u63rDljxTAfBgNVHSMEGDAWgBQ4RkTwxkqvby9GrHlN/u63rDljxTAP
+BgNVHRMBAf8EBTADAQH/MAoGCCqGSM49BAMCA0gAMEUCIDbMhmg6tpTxdXwVQO1o
+OFrRSo24UusJ5Hm4IYOS4wd3AiEA+ZRpm3ggehJU71V62QzEDz/BZxTAIDLQ3csL
+qRWRXq4=
+-----END CERTIFICATE-----
+";
+
+    /// Self signed certificate that expires `2055-06-15T12:30:00Z`, as a `GeneralizedTime`
+    const GENERALIZED_CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBgzCCASmgAwIBAgIUD3Be23ci/aa7h1Rc+rkfTP6qspkwCgYIKoZIzj0EAwIw
+FjEUMBIGA1UEAwwLZXhhbXBsZS5jb20wIBcNMjYwMTAxMDAwMDAwWhgPMjA1NTA2
+MTUxMjMwMDBaMBYxFDASBgNVBAMMC2V4YW1wbGUuY29tMFkwEwYHKoZIzj0CAQYI
+KoZIzj0DAQcDQgAEDbv2x53frQwLc75ZWIBDs79K51j6qYSYebh5T3bzj+Znr29J
+A14CBkKgqIqTCKhXQHHzyGn62LZ8o6WJiQUuCaNTMFEwHQYDVR0OBBYEFDhGRPDG
+Sq9vL0aseU3+7resOWPFMB8GA1UdIwQYMBaAFDhGRPDGSq9vL0aseU3+7resOWPF
+MA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIgFU2XmbC7cxVXqtlZ
+MxmKbGyrUaWqN4zHyjTVl4mkBMwCIQDr6fJLsq/oPSlUMJW+NBMLKNsxUpoH9u+B
+WKqyuU5lYA==
+-----END CERTIFICATE-----
+";
+
+    fn at(seconds: u64) -> Option<SystemTime> {
+        Some(UNIX_EPOCH + Duration::from_secs(seconds))
+    }
+
+    /// Values of a sequence
+    fn items(mut content: &[u8]) -> Vec<(u8, &[u8])> {
+        let mut items = Vec::new();
+        while !content.is_empty() {
+            let (tag, value, rest) = read(content).unwrap();
+            items.push((tag, value));
+            content = rest;
+        }
+        items
+    }
+
+    #[test]
+    fn certificates_expire_at_not_after() {
+        let certificate = from_pem("CERTIFICATE", UTC_CERTIFICATE).pop().unwrap();
+        assert_eq!(not_after(&certificate), at(1823720715));
+        let certificate = from_pem("CERTIFICATE", GENERALIZED_CERTIFICATE)
+            .pop()
+            .unwrap();
+        assert_eq!(not_after(&certificate), at(2696675400));
+
+        assert_eq!(not_after(&certificate[..100]), None);
+        assert_eq!(not_after(b""), None);
+    }
+
+    #[test]
+    fn times_are_parsed() {
+        let cases: [(u8, &str, Option<SystemTime>); 10] = [
+            (0x17, "700101000000Z", at(0)),
+            (0x17, "991231235959Z", at(946684799)),
+            (0x17, "000101000000Z", at(946684800)),
+            // Two digit years before 50 are 20xx, the rest are 19xx
+            (0x17, "491231235959Z", at(2524607999)),
+            (0x17, "500101000000Z", None),
+            (0x17, "240229120000Z", at(1709208000)),
+            (0x18, "20500101000000Z", at(2524608000)),
+            (0x18, "21000301000000Z", at(4107542400)),
+            (0x17, "240229120000", None),
+            (0x04, "240229120000Z", None),
+        ];
+        for (tag, time, expected) in cases {
+            assert_eq!(time_from(tag, time.as_bytes()), expected, "{}", time);
+        }
+    }
+
+    #[test]
+    fn lengths_round_trip() {
+        for len in [0, 1, 127, 128, 255, 256, 300, 65535, 65536] {
+            let content = vec![0x5a; len];
+            let encoded = der(0x04, &content);
+            let header = match len {
+                0..=127 => vec![0x04, len as u8],
+                128..=255 => vec![0x04, 0x81, len as u8],
+                256..=65535 => vec![0x04, 0x82, (len >> 8) as u8, len as u8],
+                _ => vec![0x04, 0x83, (len >> 16) as u8, (len >> 8) as u8, len as u8],
+            };
+            assert_eq!(encoded[..header.len()], header[..], "{}", len);
+            assert_eq!(encoded.len(), header.len() + len);
+
+            let input = [&encoded[..], b"next"].concat();
+            let (tag, value, rest) = read(&input).unwrap();
+            assert_eq!((tag, value.len(), rest), (0x04, len, &b"next"[..]));
+        }
+
+        // Lengths past the input, or with a length of length 0, aren't values
+        assert_eq!(read(&[0x04, 0x05, 1, 2]), None);
+        assert_eq!(read(&[0x04, 0x82, 0x01]), None);
+        assert_eq!(read(&[0x04, 0x80]), None);
+    }
+
+    #[test]
+    fn pem_round_trips() {
+        let der: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let pem = to_pem("CERTIFICATE", &der);
+        assert!(pem.starts_with("-----BEGIN CERTIFICATE-----\n"));
+        assert!(pem.ends_with("-----END CERTIFICATE-----\n"));
+        assert!(pem.lines().all(|line| line.len() <= 64));
+
+        let chain = format!("{}{}", pem, to_pem("CERTIFICATE", b"second"));
+        assert_eq!(
+            from_pem("CERTIFICATE", &chain),
+            vec![der.clone(), b"second".to_vec()]
+        );
+        assert!(from_pem("PRIVATE KEY", &chain).is_empty());
+    }
+
+    #[test]
+    fn csrs_are_signed_requests_for_the_domains() {
+        let rng = SystemRandom::new();
+        let key = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let pair =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, key.as_ref(), &rng).unwrap();
+        let domains = vec!["example.com".to_string(), "www.example.com".to_string()];
+        let csr = csr(&domains, pair.public_key().as_ref(), |info| {
+            pair.sign(&rng, info).ok().map(|sig| sig.as_ref().to_vec())
+        })
+        .unwrap();
+
+        let (tag, request, rest) = read(&csr).unwrap();
+        assert_eq!((tag, rest.len()), (SEQUENCE, 0));
+        let request = items(request);
+        let [(SEQUENCE, info), (SEQUENCE, algorithm), (0x03, signature)] = request[..] else {
+            panic!("Unexpected request {:?}", request);
+        };
+        assert_eq!(algorithm, ECDSA_SHA256);
+
+        let fields = items(info);
+        assert_eq!(fields[0], (0x02, &[0][..]));
+        assert_eq!(
+            fields[1].1,
+            &sequence(&[&der(
+                SET,
+                &sequence(&[COMMON_NAME, &der(0x0c, b"example.com")])
+            )])[2..]
+        );
+        let key = items(fields[2].1);
+        assert_eq!(key[0].1, &[EC_PUBLIC_KEY, P256].concat()[..]);
+        assert_eq!(key[1].1, &[&[0], pair.public_key().as_ref()].concat()[..]);
+        assert_eq!(fields[3].0, 0xa0);
+        let names: Vec<u8> = [der(0x82, b"example.com"), der(0x82, b"www.example.com")].concat();
+        assert!(fields[3]
+            .1
+            .windows(names.len())
+            .any(|window| window == names));
+
+        // The signature covers the encoded request info, with its tag and length
+        let (_, content, _) = read(&csr).unwrap();
+        let (_, _, after_info) = read(content).unwrap();
+        let info = &content[..content.len() - after_info.len()];
+        assert_eq!(signature[0], 0);
+        UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, pair.public_key().as_ref())
+            .verify(info, &signature[1..])
+            .unwrap();
+    }
+}
@@ -0,0 +1,368 @@
+mod client;
+mod der;
+
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, SystemTime},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bytes::Bytes;
+use http_body_util::Full;
+use ring::{
+    rand::SystemRandom,
+    signature::{
+        EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_ASN1_SIGNING, ECDSA_P256_SHA256_FIXED_SIGNING,
+    },
+};
+use serde_json::json;
+use tokio_rustls::rustls::{
+    server::{ClientHello, ResolvesServerCert},
+    sign::{any_supported_type, CertifiedKey},
+    Certificate, PrivateKey,
+};
+
+use client::Account;
+
+/// Let's Encrypt's production directory
+pub const LETS_ENCRYPT: &str = "https://acme-v02.api.letsencrypt.org/directory";
+
+/// Let's Encrypt's staging directory. Its certificates aren't trusted but its rate limits
+/// are much higher, so use it while testing.
+pub const LETS_ENCRYPT_STAGING: &str = "https://acme-staging-v02.api.letsencrypt.org/directory";
+
+/// Wait before trying again after a certificate couldn't be issued
+const RETRY: Duration = Duration::from_secs(60 * 60);
+
+/// Longest wait between checking whether the certificate needs to be renewed
+const CHECK: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// Certificate and when it expires
+struct Installed {
+    key: Arc<CertifiedKey>,
+    expires: SystemTime,
+}
+
+/// State shared by every clone of an `Acme`
+#[derive(Default)]
+struct Shared {
+    /// Token of each pending http-01 challenge and its key authorization
+    challenges: RwLock<HashMap<String, String>>,
+    certificate: RwLock<Option<Installed>>,
+    started: AtomicBool,
+}
+
+impl Debug for Shared {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Shared")
+            .field("challenges", &self.challenges.read().unwrap().len())
+            .field("certificate", &self.certificate.read().unwrap().is_some())
+            .finish()
+    }
+}
+
+/// Gets and renews a TLS certificate for the domains from an acme server, Let's Encrypt by
+/// default, using http-01 challenges.
+///
+/// Add it to the server listening on port 80 with `Server::acme` so it answers the
+/// challenges, and serve the site with `Server::serve_tls` using it as the certificate. The
+/// certificate is requested when the port 80 server starts and renewed 30 days before it
+/// expires. Certificates and the account key are stored in the cache directory so a restart
+/// reuses them instead of requesting new ones.
+///
+/// # Example
+/// ```no_run
+/// use std::sync::Arc;
+/// use tela::{acme::Acme, prelude::*, Server};
+///
+/// #[get("/")]
+/// fn home() -> &'static str {
+///     "Home"
+/// }
+///
+/// #[tela::main]
+/// async fn main() {
+///     let acme = Acme::new(["example.com", "www.example.com"])
+///         .contact("mailto:admin@example.com")
+///         .cache("/var/lib/example/acme");
+///
+///     let mut http = Server::new().acme(acme.clone());
+///     let mut https = Server::new().route(home);
+///     tokio::try_join!(
+///         http.serve(([0, 0, 0, 0], 80)),
+///         https.serve_tls(([0, 0, 0, 0], 443), Arc::new(acme)),
+///     )?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Acme {
+    domains: Vec<String>,
+    contact: Vec<String>,
+    directory: String,
+    cache: PathBuf,
+    renew_before: Duration,
+    shared: Arc<Shared>,
+}
+
+impl Acme {
+    /// Certificate for the domains. The first domain names the files in the cache.
+    pub fn new<I, D>(domains: I) -> Self
+    where
+        I: IntoIterator<Item = D>,
+        D: Into<String>,
+    {
+        Acme {
+            domains: domains.into_iter().map(Into::into).collect(),
+            contact: Vec::new(),
+            directory: LETS_ENCRYPT.to_string(),
+            cache: PathBuf::from("acme"),
+            renew_before: Duration::from_secs(60 * 60 * 24 * 30),
+            shared: Arc::new(Shared::default()),
+        }
+    }
+
+    /// Contact for the account, ex: `mailto:admin@example.com`, that is told about problems
+    /// with the certificates
+    pub fn contact<C: Into<String>>(mut self, contact: C) -> Self {
+        self.contact.push(contact.into());
+        self
+    }
+
+    /// Directory url of the acme server; ex: `LETS_ENCRYPT_STAGING`
+    pub fn directory<U: Into<String>>(mut self, url: U) -> Self {
+        self.directory = url.into();
+        self
+    }
+
+    /// Directory the certificate, its key, and the account key are stored in. Defaults to
+    /// `acme`.
+    pub fn cache<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.cache = dir.into();
+        self
+    }
+
+    /// How long before the certificate expires it is renewed. Defaults to 30 days.
+    pub fn renew_before(mut self, duration: Duration) -> Self {
+        self.renew_before = duration;
+        self
+    }
+
+    /// Load the cached certificate and keep it renewed in the background. Called when a
+    /// server with `Server::acme` starts; calling it again does nothing.
+    pub fn start(&self) {
+        if self.shared.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        if let Err(error) = self.load() {
+            if error.kind() != io::ErrorKind::NotFound {
+                eprintln!("Failed to load cached certificate: {}", error);
+            }
+        }
+        let acme = self.clone();
+        tokio::spawn(async move { acme.renew().await });
+    }
+
+    /// Respond to an http-01 challenge for the path if one is pending
+    pub(crate) fn respond(&self, path: &str) -> Option<hyper::Response<Full<Bytes>>> {
+        let token = path.strip_prefix("/.well-known/acme-challenge/")?;
+        let key_authorization = self.shared.challenges.read().unwrap().get(token).cloned()?;
+        Some(
+            hyper::Response::builder()
+                .status(200)
+                .header("Content-Type", "application/octet-stream")
+                .header("Cache-Control", "no-store")
+                .body(Full::new(Bytes::from(key_authorization)))
+                .unwrap(),
+        )
+    }
+
+    fn path(&self, extension: &str) -> PathBuf {
+        let name = self.domains.first().map(String::as_str).unwrap_or("cert");
+        self.cache.join(format!("{}.{}", name, extension))
+    }
+
+    /// Install the cached certificate
+    fn load(&self) -> io::Result<()> {
+        let chain = fs::read_to_string(self.path("crt"))?;
+        let key = fs::read_to_string(self.path("key"))?;
+        self.install(&chain, &key)
+    }
+
+    /// Use the PEM certificate chain and private key for new connections
+    fn install(&self, chain: &str, key: &str) -> io::Result<()> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let chain: Vec<Certificate> = der::from_pem("CERTIFICATE", chain)
+            .into_iter()
+            .map(Certificate)
+            .collect();
+        let expires = chain
+            .first()
+            .and_then(|leaf| der::not_after(&leaf.0))
+            .ok_or_else(|| invalid("Invalid certificate"))?;
+        let key = der::from_pem("PRIVATE KEY", key)
+            .pop()
+            .ok_or_else(|| invalid("Invalid certificate key"))?;
+        let key = any_supported_type(&PrivateKey(key))
+            .map_err(|_| invalid("Unsupported certificate key"))?;
+
+        *self.shared.certificate.write().unwrap() = Some(Installed {
+            key: Arc::new(CertifiedKey::new(chain, key)),
+            expires,
+        });
+        Ok(())
+    }
+
+    /// Time until the certificate should be renewed; zero when there isn't one
+    fn renew_in(&self) -> Duration {
+        match &*self.shared.certificate.read().unwrap() {
+            Some(installed) => (installed.expires - self.renew_before)
+                .duration_since(SystemTime::now())
+                .unwrap_or(Duration::ZERO),
+            None => Duration::ZERO,
+        }
+    }
+
+    async fn renew(self) {
+        loop {
+            let wait = match self.renew_in() {
+                Duration::ZERO => match self.issue().await {
+                    Ok(()) => continue,
+                    Err(error) => {
+                        eprintln!(
+                            "Failed to get a certificate for {}: {}",
+                            self.domains.join(", "),
+                            error
+                        );
+                        RETRY
+                    }
+                },
+                wait => wait,
+            };
+            tokio::time::sleep(wait.min(CHECK)).await;
+        }
+    }
+
+    /// Order, validate, and download a new certificate then install and cache it
+    async fn issue(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.cache)?;
+        let rng = SystemRandom::new();
+        let account_key = match fs::read_to_string(self.cache.join("account.key")) {
+            Ok(key) => der::from_pem("PRIVATE KEY", &key)
+                .pop()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid account key"))?,
+            Err(_) => {
+                let key = generate(&rng)?;
+                write_private(
+                    &self.cache.join("account.key"),
+                    &der::to_pem("PRIVATE KEY", &key),
+                )?;
+                key
+            }
+        };
+
+        let mut account = Account::open(&self.directory, &account_key, &self.contact).await?;
+        let (url, order) = account.order(&self.domains).await?;
+
+        let authorizations: Vec<String> = order["authorizations"]
+            .as_array()
+            .map(|urls| {
+                urls.iter()
+                    .filter_map(|url| url.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        for authorization in authorizations {
+            let (_, status) = account.post(&authorization, None).await?;
+            if status["status"] == "valid" {
+                continue;
+            }
+            let challenge = status["challenges"]
+                .as_array()
+                .and_then(|challenges| challenges.iter().find(|c| c["type"] == "http-01"))
+                .ok_or_else(|| io::Error::other("Acme server didn't offer an http-01 challenge"))?;
+            let (Some(token), Some(challenge)) =
+                (challenge["token"].as_str(), challenge["url"].as_str())
+            else {
+                return Err(io::Error::other("Invalid http-01 challenge"));
+            };
+
+            self.shared
+                .challenges
+                .write()
+                .unwrap()
+                .insert(token.to_string(), account.key_authorization(token));
+            let result = match account.post(challenge, Some(&json!({}))).await {
+                Ok(_) => account.poll(&authorization, &[]).await.map(|_| ()),
+                Err(error) => Err(error),
+            };
+            self.shared.challenges.write().unwrap().remove(token);
+            result?;
+        }
+
+        let order = account.poll(&url, &["ready"]).await?;
+        let finalize = order["finalize"]
+            .as_str()
+            .ok_or_else(|| io::Error::other("Acme order is missing finalize"))?;
+        let key = generate(&rng)?;
+        let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &key, &rng)
+            .map_err(|_| io::Error::other("Failed to load certificate key"))?;
+        let csr = der::csr(&self.domains, pair.public_key().as_ref(), |info| {
+            pair.sign(&rng, info)
+                .ok()
+                .map(|signature| signature.as_ref().to_vec())
+        })
+        .ok_or_else(|| io::Error::other("Failed to sign certificate request"))?;
+        let csr = json!({ "csr": URL_SAFE_NO_PAD.encode(csr) });
+        account.post(finalize, Some(&csr)).await?;
+
+        let order = account.poll(&url, &[]).await?;
+        let certificate = order["certificate"]
+            .as_str()
+            .ok_or_else(|| io::Error::other("Acme order is missing its certificate"))?;
+        let (response, _) = account.post(certificate, None).await?;
+        let chain = String::from_utf8_lossy(response.body()).to_string();
+        let key = der::to_pem("PRIVATE KEY", &key);
+
+        self.install(&chain, &key)?;
+        fs::write(self.path("crt"), &chain)?;
+        write_private(&self.path("key"), &key)?;
+        Ok(())
+    }
+}
+
+/// New ECDSA P-256 key as PKCS#8
+fn generate(rng: &SystemRandom) -> io::Result<Vec<u8>> {
+    EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, rng)
+        .map(|key| key.as_ref().to_vec())
+        .map_err(|_| io::Error::other("Failed to generate key"))
+}
+
+/// Write a private key that only the owner can read
+fn write_private(path: &Path, contents: &str) -> io::Result<()> {
+    fs::write(path, contents)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+impl ResolvesServerCert for Acme {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        self.shared
+            .certificate
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|installed| installed.key.clone())
+    }
+}
@@ -0,0 +1,435 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::HeaderMap;
+use sha2::{Digest, Sha256};
+
+/// `Cache-Control` sent with fingerprinted assets. Their content never changes for a given url.
+pub(crate) const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// Fingerprinted names of a router's assets
+#[derive(Debug, Default)]
+pub(crate) struct Manifest {
+    /// Asset path to its fingerprinted url; ex: `css/app.css` -> `/css/app.1f2e3d4c.css`
+    urls: HashMap<String, String>,
+    /// Fingerprinted path to the asset path; ex: `css/app.1f2e3d4c.css` -> `css/app.css`
+    files: HashMap<String, String>,
+}
+
+tokio::task_local! {
+    /// Manifest of the router handling the request
+    pub(crate) static MANIFEST: Arc<Manifest>;
+}
+
+impl Manifest {
+    /// Hash every file in the asset directory and give it a fingerprinted name; ex:
+    /// `app.css` -> `app.1f2e3d4c.css`. The urls are under the mount path.
+    pub(crate) fn new<P: AsRef<Path>>(root: P, mount: &str) -> std::io::Result<Self> {
+        let root = root.as_ref();
+        let mut manifest = Manifest::default();
+        if root.is_dir() {
+            for file in files(root)? {
+                let relative = file
+                    .strip_prefix(root)
+                    .unwrap_or(&file)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                let hash = Sha256::digest(fs::read(&file)?);
+                let hash: String = hash[..4].iter().map(|b| format!("{:02x}", b)).collect();
+
+                let fingerprinted = match relative.rsplit_once('.') {
+                    Some((stem, extension)) if !stem.ends_with('/') && !stem.is_empty() => {
+                        format!("{}.{}.{}", stem, hash, extension)
+                    }
+                    _ => format!("{}.{}", relative, hash),
+                };
+                manifest
+                    .urls
+                    .insert(relative.clone(), format!("{}/{}", mount, fingerprinted));
+                manifest.files.insert(fingerprinted, relative);
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Asset path that a fingerprinted path was created from
+    pub(crate) fn original(&self, path: &str) -> Option<String> {
+        self.files.get(path.trim_start_matches('/')).cloned()
+    }
+
+    /// Fingerprinted paths and the asset paths they were created from
+    pub(crate) fn fingerprinted(&self) -> Vec<(String, String)> {
+        self.files
+            .iter()
+            .map(|(fingerprinted, path)| (fingerprinted.clone(), path.clone()))
+            .collect()
+    }
+}
+
+/// Recursively list the files in a directory
+fn files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        match entry.file_type()?.is_dir() {
+            true => files.extend(self::files(&entry.path())?),
+            false => files.push(entry.path()),
+        }
+    }
+    Ok(files)
+}
+
+/// Url of an asset, fingerprinted if the server handling the request fingerprints its
+/// assets. Use `asset!` in templates. Outside of a request the url isn't fingerprinted.
+///
+/// # Example
+/// ```
+/// // `/css/app.1f2e3d4c.css` once the assets are fingerprinted, otherwise `/css/app.css`
+/// let href = tela::assets::url("css/app.css");
+/// ```
+pub fn url(path: &str) -> String {
+    let path = path.trim_start_matches('/');
+    MANIFEST
+        .try_with(|manifest| manifest.urls.get(path).cloned())
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| format!("/{}", path))
+}
+
+/// Asset paths and their fingerprinted urls for the server handling the request
+pub fn manifest() -> HashMap<String, String> {
+    MANIFEST
+        .try_with(|manifest| manifest.urls.clone())
+        .unwrap_or_default()
+}
+
+/// ETag made from a hash of the contents
+fn etag(contents: &[u8]) -> String {
+    let hash = Sha256::digest(contents);
+    let hash: String = hash[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("\"{}\"", hash)
+}
+
+/// Whether the request's `If-None-Match` has the ETag
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get("If-None-Match")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(',')
+                .any(|tag| tag.trim() == "*" || tag.trim().trim_start_matches("W/") == etag)
+        })
+        .unwrap_or(false)
+}
+
+/// Resolve a request path to a file inside of the root directory.
+///
+/// Paths with `..` or that resolve outside of the root, ex: through a symlink, don't
+/// resolve.
+pub(crate) fn resolve(root: &Path, path: &str) -> Option<PathBuf> {
+    // Decoded before checking the path so encoded `..` segments are still rejected
+    let path = crate::uri::decode(path);
+    let relative = Path::new(path.trim_start_matches('/'));
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    let root = fs::canonicalize(root).ok()?;
+    let file = fs::canonicalize(root.join(relative)).ok()?;
+    match file.starts_with(&root) && file.is_file() {
+        true => Some(file),
+        false => None,
+    }
+}
+
+/// Asset compiled into the binary
+struct EmbeddedFile {
+    contents: &'static [u8],
+    etag: String,
+}
+
+/// Assets compiled into the binary with `include_dir!` and served under a mount path
+#[derive(Clone)]
+pub(crate) struct EmbeddedAssets {
+    mount: String,
+    files: Arc<HashMap<String, EmbeddedFile>>,
+}
+
+impl EmbeddedAssets {
+    #[cfg(feature = "embed")]
+    pub(crate) fn new(mount: &str, dir: &'static include_dir::Dir<'static>) -> Self {
+        fn collect(
+            dir: &'static include_dir::Dir<'static>,
+            files: &mut HashMap<String, EmbeddedFile>,
+        ) {
+            for file in dir.files() {
+                files.insert(
+                    file.path().to_string_lossy().replace('\\', "/"),
+                    EmbeddedFile {
+                        contents: file.contents(),
+                        etag: etag(file.contents()),
+                    },
+                );
+            }
+            for dir in dir.dirs() {
+                collect(dir, files);
+            }
+        }
+
+        let mut files = HashMap::new();
+        collect(dir, &mut files);
+        EmbeddedAssets {
+            mount: mount.trim_end_matches('/').to_string(),
+            files: Arc::new(files),
+        }
+    }
+
+    /// Respond with the embedded file for the path if there is one. Responds with a
+    /// `304 Not Modified` if the request's `If-None-Match` has the file's ETag.
+    pub(crate) fn respond(
+        &self,
+        path: &str,
+        headers: &HeaderMap,
+    ) -> Option<hyper::Response<Full<Bytes>>> {
+        let relative = path.strip_prefix(&self.mount)?.strip_prefix('/')?;
+        let file = self.files.get(&crate::uri::decode(relative))?;
+
+        let builder = hyper::Response::builder().header("ETag", &file.etag);
+        if etag_matches(headers, &file.etag) {
+            return Some(builder.status(304).body(Full::new(Bytes::new())).unwrap());
+        }
+
+        let mut builder = builder.status(200);
+        if let Some(mime) = mime_guess::from_path(relative).first() {
+            builder = builder.header("Content-Type", mime.to_string());
+        }
+        Some(
+            builder
+                .body(Full::new(Bytes::from_static(file.contents)))
+                .unwrap(),
+        )
+    }
+}
+
+/// Icon served at `/favicon.ico`; the icon's bytes, ex: from `include_bytes!`, or the path to
+/// a file that is read when it is added.
+#[derive(Debug, Clone)]
+pub enum Favicon {
+    Bytes(Bytes),
+    File(PathBuf),
+}
+
+impl From<&'static [u8]> for Favicon {
+    fn from(value: &'static [u8]) -> Self {
+        Favicon::Bytes(Bytes::from_static(value))
+    }
+}
+
+impl<const N: usize> From<&'static [u8; N]> for Favicon {
+    fn from(value: &'static [u8; N]) -> Self {
+        Favicon::Bytes(Bytes::from_static(value))
+    }
+}
+
+impl From<Vec<u8>> for Favicon {
+    fn from(value: Vec<u8>) -> Self {
+        Favicon::Bytes(Bytes::from(value))
+    }
+}
+
+impl From<&str> for Favicon {
+    fn from(value: &str) -> Self {
+        Favicon::File(PathBuf::from(value))
+    }
+}
+
+impl From<PathBuf> for Favicon {
+    fn from(value: PathBuf) -> Self {
+        Favicon::File(value)
+    }
+}
+
+/// `Cache-Control` sent with the favicon. Browsers check for a new one after a day.
+const FAVICON_CACHE: &str = "public, max-age=86400";
+
+/// Favicon with its content type and ETag
+#[derive(Debug, Clone)]
+pub(crate) struct Icon {
+    contents: Bytes,
+    content_type: &'static str,
+    etag: String,
+}
+
+impl Icon {
+    pub(crate) fn new(favicon: Favicon) -> std::io::Result<Self> {
+        let (contents, extension) = match favicon {
+            Favicon::Bytes(contents) => (contents, None),
+            Favicon::File(path) => (
+                Bytes::from(fs::read(&path)?),
+                path.extension()
+                    .and_then(|extension| extension.to_str())
+                    .map(str::to_lowercase),
+            ),
+        };
+
+        // Browsers request `/favicon.ico` whatever the format is so it is sent as what it is
+        let content_type = match extension.as_deref() {
+            Some("png") => "image/png",
+            Some("svg") => "image/svg+xml",
+            Some("gif") => "image/gif",
+            Some("ico") => "image/x-icon",
+            _ if contents.starts_with(b"\x89PNG") => "image/png",
+            _ if contents.starts_with(b"GIF8") => "image/gif",
+            _ if contents.starts_with(b"<svg") || contents.starts_with(b"<?xml") => "image/svg+xml",
+            _ => "image/x-icon",
+        };
+
+        Ok(Icon {
+            etag: etag(&contents),
+            contents,
+            content_type,
+        })
+    }
+
+    /// Respond with the icon if the path is `/favicon.ico`
+    pub(crate) fn respond(
+        &self,
+        path: &str,
+        headers: &HeaderMap,
+    ) -> Option<hyper::Response<Full<Bytes>>> {
+        if path != "/favicon.ico" {
+            return None;
+        }
+
+        let builder = hyper::Response::builder()
+            .header("ETag", &self.etag)
+            .header("Cache-Control", FAVICON_CACHE);
+        if etag_matches(headers, &self.etag) {
+            return Some(builder.status(304).body(Full::new(Bytes::new())).unwrap());
+        }
+        Some(
+            builder
+                .status(200)
+                .header("Content-Type", self.content_type)
+                .body(Full::new(self.contents.clone()))
+                .unwrap(),
+        )
+    }
+}
+
+/// Files served under `/.well-known/` from a directory; ex: acme challenges and
+/// `security.txt`. Files are read on every request so new acme challenges are served right
+/// away.
+#[derive(Debug, Clone)]
+pub(crate) struct WellKnown {
+    root: PathBuf,
+}
+
+impl WellKnown {
+    pub(crate) fn new(root: PathBuf) -> Self {
+        WellKnown { root }
+    }
+
+    /// Respond with the file for a `/.well-known/` path if it exists
+    pub(crate) fn respond(&self, path: &str) -> Option<hyper::Response<Full<Bytes>>> {
+        let relative = path.strip_prefix("/.well-known/")?;
+        let contents = fs::read(resolve(&self.root, relative)?).ok()?;
+
+        let (content_type, cache) = match relative {
+            // Challenges are only valid once so they are never cached
+            _ if relative.starts_with("acme-challenge/") => ("text/plain", "no-store"),
+            "security.txt" => ("text/plain; charset=utf-8", "public, max-age=86400"),
+            "apple-app-site-association" => ("application/json", "public, max-age=3600"),
+            _ => (
+                mime_guess::from_path(relative)
+                    .first_raw()
+                    .unwrap_or("application/octet-stream"),
+                "public, max-age=3600",
+            ),
+        };
+        Some(
+            hyper::Response::builder()
+                .status(200)
+                .header("Content-Type", content_type)
+                .header("Cache-Control", cache)
+                .body(Full::new(Bytes::from(contents)))
+                .unwrap(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asset directory with a file outside of it:
+    /// `secret.txt`, `assets/app.css`, `assets/css/site.css`
+    fn fixture(name: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("tela-assets-{}-{}", name, std::process::id()));
+        let root = dir.join("assets");
+        fs::create_dir_all(root.join("css")).unwrap();
+        fs::write(dir.join("secret.txt"), "secret").unwrap();
+        fs::write(root.join("app.css"), "body {}").unwrap();
+        fs::write(root.join("css/site.css"), "main {}").unwrap();
+        (dir, root)
+    }
+
+    #[test]
+    fn resolve_stays_in_root() {
+        let (dir, root) = fixture("traversal");
+        let secret = dir.join("secret.txt");
+        let absolute = secret.to_string_lossy().to_string();
+        let cases = [
+            ("app.css", true),
+            ("/css/site.css", true),
+            ("css%2Fsite.css", true),
+            ("css", false),
+            ("missing.css", false),
+            ("./app.css", false),
+            ("../secret.txt", false),
+            ("/../secret.txt", false),
+            ("css/../../secret.txt", false),
+            ("css/../app.css", false),
+            ("%2e%2e/secret.txt", false),
+            ("%2E%2E/secret.txt", false),
+            ("..%2fsecret.txt", false),
+            ("css/%2e%2e/%2e%2e/secret.txt", false),
+            ("%2e%2e%5csecret.txt", false),
+            (absolute.as_str(), false),
+            (&format!("//{}", absolute), false),
+            (&format!("%2F{}", absolute), false),
+        ];
+        for (path, resolves) in cases {
+            assert_eq!(resolve(&root, path).is_some(), resolves, "{}", path);
+        }
+        assert_eq!(
+            resolve(&root, "css/site.css"),
+            Some(fs::canonicalize(root.join("css/site.css")).unwrap())
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_rejects_symlink_escape() {
+        let (dir, root) = fixture("symlink");
+        std::os::unix::fs::symlink(dir.join("secret.txt"), root.join("escape.txt")).unwrap();
+        std::os::unix::fs::symlink(&dir, root.join("parent")).unwrap();
+        std::os::unix::fs::symlink(root.join("app.css"), root.join("inside.css")).unwrap();
+
+        assert!(resolve(&root, "escape.txt").is_none());
+        assert!(resolve(&root, "parent/secret.txt").is_none());
+        assert!(resolve(&root, "inside.css").is_some());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
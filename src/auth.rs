@@ -0,0 +1,257 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+use crate::{cookie::random_key, uri::query};
+
+/// Time-based one-time passwords (RFC 6238) for two factor logins; the codes shown by
+/// authenticator apps.
+///
+/// Give the user a new secret as a provisioning uri, usually shown as a QR code, and store
+/// the secret with the user. After the password is checked log the user in with
+/// `AuthSession::pending` and finish the login with `AuthSession::complete` once the code is
+/// verified.
+///
+/// # Example
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use tela::{
+///     auth::Totp,
+///     prelude::*,
+///     request::Form,
+///     response::Redirect,
+///     session::AuthSession,
+/// };
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct User {
+///     id: u32,
+///     name: String,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Login {
+///     name: String,
+///     password: String,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Code {
+///     code: String,
+/// }
+///
+/// fn check_password(form: Login) -> Option<User> {
+///     (form.password == "secret").then(|| User { id: 1, name: form.name })
+/// }
+///
+/// fn load_secret(_id: u32) -> String {
+///     "JBSWY3DPEHPK3PXP".to_string()
+/// }
+///
+/// #[post("/login")]
+/// fn login(session: AuthSession<User>, Form(form): Form<Login>) -> (AuthSession<User>, Redirect) {
+///     match check_password(form) {
+///         Some(user) => (session.pending(user), Redirect::to("/login/code")),
+///         None => (session, Redirect::to("/login")),
+///     }
+/// }
+///
+/// #[post("/login/code")]
+/// fn code(session: AuthSession<User>, Form(form): Form<Code>) -> Result<(AuthSession<User>, Redirect)> {
+///     let user = session.pending_user().ok_or((401, "Login expired".to_string()))?;
+///     let totp = Totp::from_base32(&load_secret(user.id)).unwrap();
+///     match totp.verify(&form.code) {
+///         true => response!((session.complete(), Redirect::to("/"))),
+///         false => response!(401, "Invalid code"),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Totp {
+    secret: Vec<u8>,
+    digits: u32,
+    period: u64,
+    skew: u64,
+}
+
+impl Totp {
+    /// Codes for a secret with 6 digits that change every 30 seconds; the defaults used by
+    /// authenticator apps
+    pub fn new<S: Into<Vec<u8>>>(secret: S) -> Self {
+        Totp {
+            secret: secret.into(),
+            digits: 6,
+            period: 30,
+            skew: 1,
+        }
+    }
+
+    /// New random 160 bit secret
+    pub fn generate() -> Self {
+        let mut secret = random_key();
+        secret.truncate(20);
+        Totp::new(secret)
+    }
+
+    /// Secret in the unpadded base32 that authenticator apps use. Spaces and case are ignored.
+    pub fn from_base32(secret: &str) -> Option<Self> {
+        let secret = secret.replace(' ', "").to_uppercase();
+        BASE32_NOPAD
+            .decode(secret.trim_end_matches('=').as_bytes())
+            .ok()
+            .map(Totp::new)
+    }
+
+    /// Number of digits in a code, 6 to 8
+    pub fn digits(mut self, digits: u32) -> Self {
+        self.digits = digits.clamp(6, 8);
+        self
+    }
+
+    /// Seconds each code is valid for
+    pub fn period(mut self, seconds: u64) -> Self {
+        self.period = seconds.max(1);
+        self
+    }
+
+    /// Number of periods before and after the current one whose codes are accepted so slow
+    /// typing and clock drift don't fail a login. Defaults to 1.
+    pub fn skew(mut self, periods: u64) -> Self {
+        self.skew = periods;
+        self
+    }
+
+    pub fn secret(&self) -> &[u8] {
+        &self.secret
+    }
+
+    /// Secret in base32 to store or for the user to type into an authenticator app
+    pub fn secret_base32(&self) -> String {
+        BASE32_NOPAD.encode(&self.secret)
+    }
+
+    /// Code for a time step; the unix time divided by the period
+    fn code_for(&self, step: u64) -> String {
+        let mut mac =
+            Hmac::<Sha1>::new_from_slice(&self.secret).expect("HMAC accepts any key size");
+        mac.update(&step.to_be_bytes());
+        let hash = mac.finalize().into_bytes();
+
+        // Dynamic truncation
+        let offset = (hash[hash.len() - 1] & 0xF) as usize;
+        let binary = u32::from_be_bytes([
+            hash[offset] & 0x7F,
+            hash[offset + 1],
+            hash[offset + 2],
+            hash[offset + 3],
+        ]);
+        format!(
+            "{:0width$}",
+            binary % 10u32.pow(self.digits),
+            width = self.digits as usize
+        )
+    }
+
+    /// Code for a unix time in seconds
+    pub fn code_at(&self, time: u64) -> String {
+        self.code_for(time / self.period)
+    }
+
+    /// Code for the current time
+    pub fn code(&self) -> String {
+        self.code_at(now())
+    }
+
+    /// Whether the code is valid now. See `verify_step` to stop a code from being used twice.
+    pub fn verify(&self, code: &str) -> bool {
+        self.verify_step(code).is_some()
+    }
+
+    /// Time step of the code if it is valid now. Store the step of the last accepted code
+    /// and reject codes whose step isn't newer so a code can't be replayed.
+    pub fn verify_step(&self, code: &str) -> Option<u64> {
+        let code = code.replace([' ', '-'], "");
+        if code.len() != self.digits as usize {
+            return None;
+        }
+        let current = now() / self.period;
+        (current.saturating_sub(self.skew)..=current + self.skew)
+            .find(|step| constant_eq(self.code_for(*step).as_bytes(), code.as_bytes()))
+    }
+
+    /// `otpauth://` uri that authenticator apps read from a QR code; ex:
+    /// `otpauth://totp/Example:ann@example.com?secret=...&issuer=Example`. Encode it as the QR
+    /// code's payload.
+    pub fn provisioning_uri(&self, issuer: &str, account: &str) -> String {
+        let label = crate::uri::encode(&format!("{}:{}", issuer, account));
+        let digits = self.digits.to_string();
+        let period = self.period.to_string();
+        format!(
+            "otpauth://totp/{}?{}",
+            label,
+            query(&[
+                ("secret", &self.secret_base32()),
+                ("issuer", issuer),
+                ("algorithm", "SHA1"),
+                ("digits", &digits),
+                ("period", &period),
+            ])
+        )
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+/// Compare without returning early so the time taken doesn't reveal how much matched
+pub(crate) fn constant_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// SHA1 test vectors from RFC 6238 appendix B
+    #[test]
+    fn rfc_6238_codes() {
+        let totp = Totp::new(*b"12345678901234567890").digits(8);
+        for (time, code) in [
+            (59, "94287082"),
+            (1111111109, "07081804"),
+            (1111111111, "14050471"),
+            (1234567890, "89005924"),
+            (2000000000, "69279037"),
+            (20000000000, "65353130"),
+        ] {
+            assert_eq!(totp.code_at(time), code, "code at {}", time);
+        }
+    }
+
+    #[test]
+    fn codes_verify_within_the_skew() {
+        let totp = Totp::generate();
+        let now = now();
+        assert!(totp.verify(&totp.code_at(now)));
+        assert!(totp.verify(&totp.code_at(now - 30)));
+        assert!(!totp.verify(&totp.code_at(now - 90)));
+        assert!(!totp.verify(&totp.code_at(now + 90)));
+        assert!(!totp.verify("12345"));
+    }
+
+    #[test]
+    fn base32_secrets_round_trip() {
+        let totp = Totp::from_base32("gezd gnbv gy3t qojq gezd gnbv gy3t qojq").unwrap();
+        assert_eq!(totp.secret(), b"12345678901234567890");
+        assert_eq!(totp.secret_base32(), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(feature = "redis")]
+mod redis;
+
+#[cfg(feature = "redis")]
+pub use redis::RedisBackplane;
+
+use std::{collections::HashMap, fmt::Debug, io, sync::Mutex};
+
+use bytes::Bytes;
+use futures::{future::BoxFuture, stream::BoxStream};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Publish and subscribe transport that carries messages between server processes so
+/// realtime features, ex: a `Channel` behind a chat websocket, reach clients connected to any
+/// instance.
+///
+/// `MemoryBackplane` keeps messages in the process and is used when there is a single
+/// instance. `RedisBackplane`, with the `redis` feature, uses redis pub/sub.
+pub trait Backplane: Send + Sync + Debug {
+    /// Send the message to every subscriber of the topic
+    fn publish<'a>(&'a self, topic: &'a str, message: Bytes) -> BoxFuture<'a, io::Result<()>>;
+
+    /// Messages published to the topic from now on. The stream ends if the backplane can't
+    /// deliver any more messages.
+    fn subscribe<'a>(
+        &'a self,
+        topic: &'a str,
+    ) -> BoxFuture<'a, io::Result<BoxStream<'static, Bytes>>>;
+}
+
+/// Backplane that only delivers messages inside of the process
+#[derive(Debug)]
+pub struct MemoryBackplane {
+    capacity: usize,
+    topics: Mutex<HashMap<String, broadcast::Sender<Bytes>>>,
+}
+
+impl MemoryBackplane {
+    /// Keeps up to `capacity` messages per topic for slow subscribers
+    pub fn new(capacity: usize) -> Self {
+        MemoryBackplane {
+            capacity: capacity.max(1),
+            topics: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn sender(&self, topic: &str) -> broadcast::Sender<Bytes> {
+        self.topics
+            .lock()
+            .unwrap()
+            .entry(topic.to_string())
+            .or_insert_with(|| broadcast::channel(self.capacity).0)
+            .clone()
+    }
+}
+
+impl Default for MemoryBackplane {
+    fn default() -> Self {
+        MemoryBackplane::new(256)
+    }
+}
+
+impl Backplane for MemoryBackplane {
+    fn publish<'a>(&'a self, topic: &'a str, message: Bytes) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            // No subscribers isn't an error, the message just isn't delivered
+            let _ = self.sender(topic).send(message);
+            Ok(())
+        })
+    }
+
+    fn subscribe<'a>(
+        &'a self,
+        topic: &'a str,
+    ) -> BoxFuture<'a, io::Result<BoxStream<'static, Bytes>>> {
+        Box::pin(async move {
+            let receiver = self.sender(topic).subscribe();
+            let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(message) => return Some((message, receiver)),
+                        // Messages that were missed are skipped
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            });
+            Ok(Box::pin(stream) as BoxStream<'static, Bytes>)
+        })
+    }
+}
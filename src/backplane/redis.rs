@@ -0,0 +1,143 @@
+use std::{io, time::Duration};
+
+use bytes::Bytes;
+use futures::{future::BoxFuture, stream::BoxStream};
+use tokio::sync::mpsc;
+
+use super::Backplane;
+use crate::redis::{invalid, Address, Client, Connection, Reply};
+
+/// Longest wait between attempts to reconnect a subscription
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Payload of a pub/sub `message` push
+fn message(reply: Reply) -> Option<Bytes> {
+    match reply {
+        Reply::Array(Some(items)) => match <[Reply; 3]>::try_from(items) {
+            Ok([Reply::Bulk(Some(kind)), _, Reply::Bulk(Some(payload))]) if kind == "message" => {
+                Some(payload)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Backplane that uses redis pub/sub so every server process sharing the redis receives
+/// each message.
+///
+/// Publishing reuses one connection. Each subscription has its own connection that is
+/// reconnected with a backoff if it drops; messages published while it is disconnected are
+/// missed.
+///
+/// # Example
+/// ```no_run
+/// use std::sync::Arc;
+/// use serde::{Deserialize, Serialize};
+/// use tela::{backplane::RedisBackplane, sync::Channel, Server};
+///
+/// #[derive(Clone, Serialize, Deserialize)]
+/// struct Message(String);
+///
+/// #[tela::main]
+/// async fn main() {
+///     let backplane = Arc::new(RedisBackplane::new("redis://:secret@cache:6379"));
+///     Server::new()
+///         .manage(Channel::<Message>::with_backplane(100, "chat", backplane))
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+#[derive(Debug)]
+pub struct RedisBackplane {
+    client: Client,
+}
+
+impl RedisBackplane {
+    /// Connect to `host:port` or a `redis://[:password@]host[:port]` url. The port defaults
+    /// to `6379`.
+    pub fn new<A: AsRef<str>>(address: A) -> Self {
+        RedisBackplane {
+            client: Client::new(address.as_ref()),
+        }
+    }
+
+    /// Password sent with `AUTH` when connecting
+    pub fn password<P: Into<String>>(mut self, password: P) -> Self {
+        self.client.address.password = Some(password.into());
+        self
+    }
+}
+
+/// Connection with a confirmed subscription to the topic
+async fn subscription(address: &Address, topic: &str) -> io::Result<Connection> {
+    let mut connection = address.open().await?;
+    connection.send(&[b"SUBSCRIBE", topic.as_bytes()]).await?;
+    match connection.read().await? {
+        Reply::Array(Some(_)) => Ok(connection),
+        Reply::Error(error) => Err(io::Error::other(error)),
+        reply => Err(invalid(format!("Unexpected subscribe reply {:?}", reply))),
+    }
+}
+
+impl Backplane for RedisBackplane {
+    fn publish<'a>(&'a self, topic: &'a str, message: Bytes) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            self.client
+                .call(&[b"PUBLISH", topic.as_bytes(), &message])
+                .await
+                .map(|_| ())
+        })
+    }
+
+    fn subscribe<'a>(
+        &'a self,
+        topic: &'a str,
+    ) -> BoxFuture<'a, io::Result<BoxStream<'static, Bytes>>> {
+        Box::pin(async move {
+            let address = self.client.address.clone();
+            let mut connection = subscription(&address, topic).await?;
+            let (sender, receiver) = mpsc::channel(256);
+            let topic = topic.to_string();
+
+            tokio::spawn(async move {
+                let mut backoff = Duration::from_millis(500);
+                loop {
+                    let reply = tokio::select! {
+                        // Nothing is listening anymore
+                        _ = sender.closed() => return,
+                        reply = connection.read() => reply,
+                    };
+                    match reply {
+                        Ok(reply) => {
+                            if let Some(payload) = message(reply) {
+                                if sender.send(payload).await.is_err() {
+                                    return;
+                                }
+                            }
+                        }
+                        Err(_) => loop {
+                            tokio::time::sleep(backoff).await;
+                            if sender.is_closed() {
+                                return;
+                            }
+                            match subscription(&address, &topic).await {
+                                Ok(reconnected) => {
+                                    connection = reconnected;
+                                    backoff = Duration::from_millis(500);
+                                    break;
+                                }
+                                Err(_) => backoff = (backoff * 2).min(MAX_BACKOFF),
+                            }
+                        },
+                    }
+                }
+            });
+
+            let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+                receiver.recv().await.map(|message| (message, receiver))
+            });
+            Ok(Box::pin(stream) as BoxStream<'static, Bytes>)
+        })
+    }
+}
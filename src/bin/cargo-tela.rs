@@ -0,0 +1,209 @@
+//! `cargo tela`: scaffold projects, run a dev server that restarts on changes, and print the
+//! route table. Built with the `cli` feature.
+//!
+//! ```text
+//! cargo install tela --features cli
+//! cargo tela new blog
+//! cargo tela dev
+//! cargo tela routes
+//! ```
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{exit, Child, Command},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+const HELP: &str = "\
+Usage: cargo tela <command> [args]
+
+Commands:
+  new <name>     Create a new tela project
+  dev [args]     Run the server and restart it when files change; pages reload after a restart
+  routes [args]  Print every route the server has
+
+Arguments after `dev` and `routes` are passed to `cargo run`; ex: `cargo tela dev --example blog`";
+
+/// Directories and files that restart the dev server when they change
+const WATCH: [&str; 5] = ["src", "assets", "templates", "build.rs", "Cargo.toml"];
+
+/// How often the watched files are checked
+const POLL: Duration = Duration::from_millis(500);
+
+const MAIN: &str = r#"use tela::{prelude::*, response::HTML, Server};
+
+#[get("/")]
+fn home() -> HTML<String> {
+    html! {
+        <html>
+            <head>
+                <title>"{name}"</title>
+                <link rel="stylesheet" href="/style.css" />
+            </head>
+            <body>
+                <h1>"Hello, world!"</h1>
+            </body>
+        </html>
+    }
+}
+
+#[tela::main]
+async fn main() {
+    Server::new()
+        .assets("assets/")
+        .route(home)
+        .serve(3000)
+        .await
+}
+"#;
+
+const STYLE: &str = "body {\n    font-family: Arial, sans-serif;\n}\n";
+
+fn main() {
+    // `cargo tela ...` runs `cargo-tela tela ...`
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("tela") {
+        args.remove(0);
+    }
+
+    let result = match args.first().map(String::as_str) {
+        Some("new") => match args.get(1) {
+            Some(name) => scaffold(name),
+            None => Err("Expected a project name; ex: `cargo tela new blog`".to_string()),
+        },
+        Some("dev") => dev(&args[1..]),
+        Some("routes") => routes(&args[1..]),
+        Some("help" | "-h" | "--help") | None => {
+            println!("{}", HELP);
+            Ok(())
+        }
+        Some(command) => Err(format!("Unknown command `{}`\n\n{}", command, HELP)),
+    };
+
+    if let Err(error) = result {
+        eprintln!("error: {}", error);
+        exit(1);
+    }
+}
+
+/// Create a binary crate with tela as a dependency, a home page, and an assets directory
+fn scaffold(name: &str) -> Result<(), String> {
+    let status = Command::new("cargo")
+        .args(["new", "--bin", name])
+        .status()
+        .map_err(|error| format!("Failed to run cargo: {}", error))?;
+    if !status.success() {
+        return Err(format!("Failed to create `{}`", name));
+    }
+
+    let root = PathBuf::from(name);
+    let write = |path: PathBuf, content: &str| {
+        fs::write(&path, content)
+            .map_err(|error| format!("Failed to write {}: {}", path.display(), error))
+    };
+
+    let manifest = fs::read_to_string(root.join("Cargo.toml"))
+        .map_err(|error| format!("Failed to read Cargo.toml: {}", error))?;
+    let manifest = manifest.replacen(
+        "[dependencies]",
+        &format!("[dependencies]\ntela = \"{}\"", env!("CARGO_PKG_VERSION")),
+        1,
+    );
+    write(root.join("Cargo.toml"), &manifest)?;
+
+    let project = Path::new(name)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    write(root.join("src/main.rs"), &MAIN.replace("{name}", &project))?;
+
+    fs::create_dir_all(root.join("assets"))
+        .map_err(|error| format!("Failed to create assets: {}", error))?;
+    write(root.join("assets/style.css"), STYLE)?;
+
+    println!(
+        "Created `{}`; run `cargo tela dev` inside of it to start",
+        name
+    );
+    Ok(())
+}
+
+/// `cargo run` with the args; `env` is set for the server. tela's `dev` feature is enabled so
+/// the server reads it.
+fn cargo_run(args: &[String], env: &str) -> Result<Child, String> {
+    Command::new("cargo")
+        .args(["run", "--features", "tela/dev"])
+        .args(args)
+        .env(env, "1")
+        .spawn()
+        .map_err(|error| format!("Failed to run cargo: {}", error))
+}
+
+/// Run the server and restart it whenever a watched file changes
+fn dev(args: &[String]) -> Result<(), String> {
+    let mut modified = last_modified();
+    let mut server = cargo_run(args, "TELA_RELOAD")?;
+    let mut running = true;
+
+    loop {
+        thread::sleep(POLL);
+
+        // Keep watching after the server exits, ex: on a compile error, so the next
+        // change can start it again
+        if running {
+            if let Ok(Some(status)) = server.try_wait() {
+                eprintln!("Server exited with {}; waiting for changes", status);
+                running = false;
+            }
+        }
+
+        let latest = last_modified();
+        if latest <= modified {
+            continue;
+        }
+        modified = latest;
+
+        println!("Files changed; restarting");
+        let _ = server.kill();
+        let _ = server.wait();
+        server = cargo_run(args, "TELA_RELOAD")?;
+        running = true;
+    }
+}
+
+/// Build the server and print its routes instead of serving them
+fn routes(args: &[String]) -> Result<(), String> {
+    let status = cargo_run(args, "TELA_ROUTES")?
+        .wait()
+        .map_err(|error| format!("Failed to run cargo: {}", error))?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(format!("Server exited with {}", status)),
+    }
+}
+
+/// Latest modified time of the watched files
+fn last_modified() -> SystemTime {
+    WATCH
+        .iter()
+        .map(|path| newest(Path::new(path)))
+        .max()
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+fn newest(path: &Path) -> SystemTime {
+    let Ok(metadata) = fs::metadata(path) else {
+        return SystemTime::UNIX_EPOCH;
+    };
+    let mut latest = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+    if metadata.is_dir() {
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                latest = latest.max(newest(&entry.path()));
+            }
+        }
+    }
+    latest
+}
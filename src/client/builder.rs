@@ -0,0 +1,216 @@
+use std::{io::Error as IoError, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use hyper::{
+    body::Body,
+    header::{HeaderName, HeaderValue, USER_AGENT},
+    HeaderMap,
+};
+
+use super::{dns::Connector, open_with, send_with, Cache, Resolve, Response, Retry};
+
+/// Client that adds default headers, like a user agent or an api token, to every request it
+/// sends. Headers set on a request take precedence over the defaults.
+///
+/// The client is cheap to clone; store one and reuse it for an upstream api. Pass it to
+/// `fetch!` with `client:`, or make it the default with `client::set_default` so every
+/// `fetch!` call sends its headers.
+///
+/// # Example
+/// ```no_run
+/// use bytes::Bytes;
+/// use http_body_util::Full;
+/// use tela::{
+///     client::{Cache, Client, Retry},
+///     fetch,
+/// };
+///
+/// # async fn example(token: &str, repo: &str, sha: &str) -> std::io::Result<()> {
+/// let github = Client::builder()
+///     .user_agent("my-app/1.0")
+///     .default_header("Accept", "application/vnd.github+json")
+///     .default_header("Authorization", format!("Bearer {}", token))
+///     .cache(Cache::new())
+///     .retry(Retry::new(3))
+///     .build();
+///
+/// let request = hyper::Request::get("https://api.github.com/user")
+///     .body(Full::new(Bytes::new()))
+///     .unwrap();
+/// let response = github.send(request).await?;
+///
+/// // The `Accept` header replaces the default one
+/// let diff = fetch!(
+///     "https://api.github.com/repos/{repo}/commits/{sha}",
+///     headers: { "Accept": "application/vnd.github.diff" },
+///     client: github
+/// )
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Client {
+    headers: HeaderMap,
+    cache: Option<Cache>,
+    retry: Option<Retry>,
+    connector: Connector,
+}
+
+impl Client {
+    /// Client with a `tela/<version>` user agent
+    pub fn new() -> Self {
+        Client::builder().build()
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder {
+            headers: HeaderMap::new(),
+            user_agent: HeaderValue::from_static(concat!("tela/", env!("CARGO_PKG_VERSION"))),
+            cache: None,
+            retry: None,
+            connector: Connector::default(),
+        }
+    }
+
+    /// Add the default headers the request doesn't already have
+    fn prepare<B>(&self, mut request: hyper::Request<B>) -> hyper::Request<B> {
+        for name in self.headers.keys() {
+            if !request.headers().contains_key(name) {
+                for value in self.headers.get_all(name) {
+                    request.headers_mut().append(name, value.clone());
+                }
+            }
+        }
+        request
+    }
+
+    /// Send a request and read the whole response, see `client::send`. Uses the client's
+    /// cache if it has one and retries with its `Retry`.
+    pub async fn send<B>(
+        &self,
+        request: hyper::Request<B>,
+    ) -> Result<hyper::Response<Bytes>, IoError>
+    where
+        B: Body + Send + 'static,
+        B::Data: Send,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let request = self.prepare(request);
+        match &self.retry {
+            Some(retry) => retry.run(request, |request| self.send_once(request)).await,
+            None => self.send_once(request).await,
+        }
+    }
+
+    async fn send_once<B>(
+        &self,
+        request: hyper::Request<B>,
+    ) -> Result<hyper::Response<Bytes>, IoError>
+    where
+        B: Body + Send + 'static,
+        B::Data: Send,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        match &self.cache {
+            Some(cache) => cache.send_with(&self.connector, request).await,
+            None => send_with(&self.connector, request).await,
+        }
+    }
+
+    /// Send a request and return once the headers arrive, see `client::open`. The cache
+    /// isn't used since the body isn't read; responses that are retried are dropped unread.
+    pub async fn open<B>(&self, request: hyper::Request<B>) -> Result<Response, IoError>
+    where
+        B: Body + Send + 'static,
+        B::Data: Send,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let request = self.prepare(request);
+        match &self.retry {
+            Some(retry) => {
+                retry
+                    .run(request, |request| open_with(&self.connector, request))
+                    .await
+            }
+            None => open_with(&self.connector, request).await,
+        }
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Client::new()
+    }
+}
+
+/// Builder for a `Client`; see `Client::builder`
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    headers: HeaderMap,
+    user_agent: HeaderValue,
+    cache: Option<Cache>,
+    retry: Option<Retry>,
+    connector: Connector,
+}
+
+impl ClientBuilder {
+    /// Header sent with every request. Adding a header again sends both values. Names and
+    /// values that aren't valid in a header are ignored.
+    pub fn default_header<N: AsRef<str>, V: AsRef<str>>(mut self, name: N, value: V) -> Self {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_ref().as_bytes()),
+            HeaderValue::from_str(value.as_ref()),
+        ) {
+            self.headers.append(name, value);
+        }
+        self
+    }
+
+    /// `User-Agent` sent with every request, `tela/<version>` by default
+    pub fn user_agent<U: AsRef<str>>(mut self, user_agent: U) -> Self {
+        if let Ok(value) = HeaderValue::from_str(user_agent.as_ref()) {
+            self.user_agent = value;
+        }
+        self
+    }
+
+    /// Reuse responses with a cache, see `Cache`
+    pub fn cache(mut self, cache: Cache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Send requests again after rate limits, unavailable servers, and connection errors,
+    /// see `Retry`
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Resolve host names with a custom resolver instead of the system's
+    pub fn resolver<R: Resolve>(mut self, resolver: R) -> Self {
+        self.connector.resolver = Arc::new(resolver);
+        self
+    }
+
+    /// Longest time resolving a host and opening a connection can take, 30 seconds by
+    /// default. `None` waits as long as the system does.
+    pub fn connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connector.timeout = timeout;
+        self
+    }
+
+    pub fn build(self) -> Client {
+        let mut headers = self.headers;
+        if !headers.contains_key(USER_AGENT) {
+            headers.insert(USER_AGENT, self.user_agent);
+        }
+        Client {
+            headers,
+            cache: self.cache,
+            retry: self.retry,
+            connector: self.connector,
+        }
+    }
+}
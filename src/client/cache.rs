@@ -0,0 +1,266 @@
+use std::{
+    collections::HashMap,
+    io::Error as IoError,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use hyper::{
+    body::Body,
+    header::{AGE, CACHE_CONTROL, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    HeaderMap, Method, StatusCode,
+};
+
+use super::{dns::Connector, send_with};
+
+/// Response that was stored along with when it stops being fresh
+#[derive(Debug, Clone)]
+struct Entry {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    stored: Instant,
+    /// How long the response is fresh for; `None` if it must be revalidated before each use
+    fresh: Option<Duration>,
+}
+
+impl Entry {
+    fn is_fresh(&self) -> bool {
+        self.fresh
+            .map(|fresh| self.stored.elapsed() < fresh)
+            .unwrap_or(false)
+    }
+
+    fn has_validator(&self) -> bool {
+        self.headers.contains_key(ETAG) || self.headers.contains_key(LAST_MODIFIED)
+    }
+
+    fn response(&self) -> hyper::Response<Bytes> {
+        let mut response = hyper::Response::new(self.body.clone());
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.clone();
+        response
+    }
+}
+
+/// Directives of a `Cache-Control` header; ex: `max-age=60, no-cache` ->
+/// `[("max-age", Some(60)), ("no-cache", None)]`
+fn directives(headers: &HeaderMap) -> Vec<(String, Option<u64>)> {
+    headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|directive| match directive.split_once('=') {
+            Some((name, value)) => (
+                name.trim().to_ascii_lowercase(),
+                value.trim().trim_matches('"').parse::<u64>().ok(),
+            ),
+            None => (directive.trim().to_ascii_lowercase(), None),
+        })
+        .collect()
+}
+
+fn has(directives: &[(String, Option<u64>)], name: &str) -> bool {
+    directives.iter().any(|(directive, _)| directive == name)
+}
+
+/// How long a response is fresh for from its `Cache-Control` max age less its `Age`
+fn freshness(headers: &HeaderMap) -> Option<Duration> {
+    let directives = directives(headers);
+    if has(&directives, "no-cache") {
+        return None;
+    }
+    let max_age = directives
+        .iter()
+        .find(|(directive, _)| directive == "max-age")
+        .and_then(|(_, value)| *value)?;
+    let age = headers
+        .get(AGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    Some(Duration::from_secs(max_age.saturating_sub(age)))
+}
+
+/// Response cache for the client that follows `Cache-Control` and revalidates with `ETag`
+/// and `Last-Modified`; ex: for polling an upstream api without downloading the same body
+/// each time.
+///
+/// Successful `GET` responses are stored by uri. Fresh responses, within their `max-age`,
+/// are reused without a request. Stale responses with a validator are revalidated by
+/// sending `If-None-Match` or `If-Modified-Since`, and a `304 Not Modified` reuses the
+/// stored body. Responses with `no-store` are never stored and `no-cache` always
+/// revalidates. `Vary` isn't followed so only cache requests to the same uri that send the
+/// same headers.
+///
+/// The cache is cheap to clone and clones share the same responses.
+///
+/// # Example
+/// ```no_run
+/// use bytes::Bytes;
+/// use http_body_util::Full;
+/// use lazy_static::lazy_static;
+/// use tela::client::Cache;
+///
+/// lazy_static! {
+///     static ref CACHE: Cache = Cache::new();
+/// }
+///
+/// # async fn example() -> std::io::Result<()> {
+/// let request = hyper::Request::get("https://api.example.com/status")
+///     .body(Full::new(Bytes::new()))
+///     .unwrap();
+/// let response = CACHE.send(request).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Cache {
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+    capacity: usize,
+}
+
+impl Cache {
+    /// Cache that holds up to 256 responses
+    pub fn new() -> Self {
+        Cache::with_capacity(256)
+    }
+
+    /// Cache that holds up to a number of responses. The oldest response is removed to make
+    /// room for new ones.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Cache {
+            entries: Arc::new(Mutex::new(HashMap::new())),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Remove every stored response
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Send a request, using a stored response when it is fresh or the server says it
+    /// hasn't changed. Requests other than `GET`, or with `Cache-Control: no-store`, are
+    /// sent without the cache.
+    pub async fn send<B>(
+        &self,
+        request: hyper::Request<B>,
+    ) -> Result<hyper::Response<Bytes>, IoError>
+    where
+        B: Body + Send + 'static,
+        B::Data: Send,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        self.send_with(&Connector::default(), request).await
+    }
+
+    pub(super) async fn send_with<B>(
+        &self,
+        connector: &Connector,
+        mut request: hyper::Request<B>,
+    ) -> Result<hyper::Response<Bytes>, IoError>
+    where
+        B: Body + Send + 'static,
+        B::Data: Send,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let request_directives = directives(request.headers());
+        if request.method() != Method::GET || has(&request_directives, "no-store") {
+            return send_with(connector, request).await;
+        }
+
+        // Callers that send their own conditions get the server's 304
+        let conditional = request.headers().contains_key(IF_NONE_MATCH)
+            || request.headers().contains_key(IF_MODIFIED_SINCE);
+        let key = request.uri().to_string();
+        let stored = self.entries.lock().unwrap().get(&key).cloned();
+        if let Some(entry) = &stored {
+            if entry.is_fresh() && !has(&request_directives, "no-cache") {
+                return Ok(entry.response());
+            }
+            if !conditional {
+                for (header, validator) in
+                    [(IF_NONE_MATCH, ETAG), (IF_MODIFIED_SINCE, LAST_MODIFIED)]
+                {
+                    if let Some(value) = entry.headers.get(validator) {
+                        request.headers_mut().insert(header, value.clone());
+                    }
+                }
+            }
+        }
+
+        let response = send_with(connector, request).await?;
+        let not_modified = response.status() == StatusCode::NOT_MODIFIED;
+        match stored {
+            Some(mut entry) if not_modified && !conditional => {
+                // The 304 updates the stored headers, ex: a new max age
+                for name in response.headers().keys() {
+                    entry.headers.remove(name);
+                }
+                for (name, value) in response.headers() {
+                    entry.headers.append(name, value.clone());
+                }
+                entry.stored = Instant::now();
+                entry.fresh = freshness(&entry.headers);
+                let cached = entry.response();
+                self.store(key, entry);
+                Ok(cached)
+            }
+            _ if not_modified => Ok(response),
+            _ => {
+                self.update(key, &response);
+                Ok(response)
+            }
+        }
+    }
+
+    /// Store a response if it can be cached, otherwise forget the old one
+    fn update(&self, key: String, response: &hyper::Response<Bytes>) {
+        let cacheable = response.status() == StatusCode::OK
+            && !has(&directives(response.headers()), "no-store");
+        let entry = Entry {
+            status: response.status(),
+            headers: response.headers().clone(),
+            body: response.body().clone(),
+            stored: Instant::now(),
+            fresh: freshness(response.headers()),
+        };
+        match cacheable && (entry.fresh.is_some() || entry.has_validator()) {
+            true => self.store(key, entry),
+            false => {
+                self.entries.lock().unwrap().remove(&key);
+            }
+        }
+    }
+
+    fn store(&self, key: String, entry: Entry) {
+        let mut entries = self.entries.lock().unwrap();
+        if !entries.contains_key(&key) && entries.len() >= self.capacity {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.stored)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key, entry);
+    }
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Cache::new()
+    }
+}
@@ -0,0 +1,160 @@
+use std::{
+    fmt::Debug,
+    io::{Error as IoError, ErrorKind},
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use futures::{future::BoxFuture, stream::FuturesUnordered, StreamExt};
+use tokio::net::TcpStream;
+
+/// How long an attempt gets before the next address is tried alongside it (RFC 8305)
+const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Resolves host names to the addresses the client connects to; ex: to pin a host to an
+/// address in tests or use a DNS cache. Hosts that are ip addresses aren't resolved.
+///
+/// # Example
+/// ```
+/// use std::{io, net::SocketAddr};
+/// use futures::future::BoxFuture;
+/// use tela::client::{Client, Resolve};
+///
+/// struct Local;
+///
+/// impl Resolve for Local {
+///     fn resolve(&self, _host: &str, port: u16) -> BoxFuture<'static, io::Result<Vec<SocketAddr>>> {
+///         Box::pin(async move { Ok(vec![SocketAddr::from(([127, 0, 0, 1], port))]) })
+///     }
+/// }
+///
+/// let client = Client::builder().resolver(Local).build();
+/// ```
+pub trait Resolve: Send + Sync + 'static {
+    fn resolve(
+        &self,
+        host: &str,
+        port: u16,
+    ) -> BoxFuture<'static, Result<Vec<SocketAddr>, IoError>>;
+}
+
+/// Resolver that uses the system's, `getaddrinfo`, on a blocking thread
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemResolver;
+
+impl Resolve for SystemResolver {
+    fn resolve(
+        &self,
+        host: &str,
+        port: u16,
+    ) -> BoxFuture<'static, Result<Vec<SocketAddr>, IoError>> {
+        let host = host.to_string();
+        Box::pin(async move { Ok(tokio::net::lookup_host((host, port)).await?.collect()) })
+    }
+}
+
+/// Order addresses so ipv6 and ipv4 take turns, starting with the first family the
+/// resolver returned
+fn interleave(addresses: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let first = addresses
+        .first()
+        .map(|address| address.is_ipv6())
+        .unwrap_or(true);
+    let (mut preferred, mut other): (Vec<SocketAddr>, Vec<SocketAddr>) = addresses
+        .into_iter()
+        .partition(|address| address.is_ipv6() == first);
+    let mut ordered = Vec::with_capacity(preferred.len() + other.len());
+    preferred.reverse();
+    other.reverse();
+    loop {
+        match (preferred.pop(), other.pop()) {
+            (None, None) => return ordered,
+            (a, b) => ordered.extend(a.into_iter().chain(b)),
+        }
+    }
+}
+
+/// Connect to the first address that answers. Each attempt gets a head start before the next
+/// address is tried at the same time, and a failed attempt starts the next one right away,
+/// so a broken ipv6 route doesn't stall the connection (happy eyeballs, RFC 8305).
+async fn happy_eyeballs(addresses: Vec<SocketAddr>) -> Result<TcpStream, IoError> {
+    let mut addresses = interleave(addresses).into_iter();
+    let mut attempts = FuturesUnordered::new();
+    let mut error = None;
+    loop {
+        if attempts.is_empty() {
+            match addresses.next() {
+                Some(address) => attempts.push(TcpStream::connect(address)),
+                None => {
+                    return Err(error.unwrap_or_else(|| {
+                        IoError::new(ErrorKind::NotFound, "host has no addresses")
+                    }))
+                }
+            }
+        }
+        tokio::select! {
+            Some(result) = attempts.next() => match result {
+                Ok(stream) => return Ok(stream),
+                Err(failed) => {
+                    error = Some(failed);
+                    if let Some(address) = addresses.next() {
+                        attempts.push(TcpStream::connect(address));
+                    }
+                }
+            },
+            _ = tokio::time::sleep(ATTEMPT_DELAY) => {
+                if let Some(address) = addresses.next() {
+                    attempts.push(TcpStream::connect(address));
+                }
+            }
+        }
+    }
+}
+
+/// How the client opens tcp connections; the resolver and how long a connection can take
+#[derive(Clone)]
+pub(crate) struct Connector {
+    pub(crate) resolver: Arc<dyn Resolve>,
+    pub(crate) timeout: Option<Duration>,
+}
+
+impl Connector {
+    /// Resolve the host and connect to one of its addresses
+    pub(crate) async fn tcp(&self, host: &str, port: u16) -> Result<TcpStream, IoError> {
+        let connect = async {
+            let addresses = match host.parse::<IpAddr>() {
+                Ok(ip) => vec![SocketAddr::new(ip, port)],
+                Err(_) => self.resolver.resolve(host, port).await?,
+            };
+            happy_eyeballs(addresses).await
+        };
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, connect).await.map_err(|_| {
+                IoError::new(
+                    ErrorKind::TimedOut,
+                    format!("connecting to {} timed out", host),
+                )
+            })?,
+            None => connect.await,
+        }
+    }
+}
+
+impl Default for Connector {
+    /// System resolver with a 30 second timeout
+    fn default() -> Self {
+        Connector {
+            resolver: Arc::new(SystemResolver),
+            timeout: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl Debug for Connector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Connector")
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
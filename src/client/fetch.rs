@@ -0,0 +1,398 @@
+//! Requests built with the `fetch!` macro; the server side of `tela_wasm::fetch!`.
+
+use std::io::{Error as IoError, ErrorKind};
+
+use bytes::Bytes;
+use http_body_util::{combinators::UnsyncBoxBody, BodyExt, Full};
+use hyper::{
+    header::{HeaderName, HeaderValue, CONTENT_TYPE},
+    Method, Uri,
+};
+
+use super::{multipart::Form, Client};
+use crate::layer::HmacSignature;
+
+/// Request body along with the content type it implies
+pub struct Body {
+    body: UnsyncBoxBody<Bytes, IoError>,
+    content_type: Option<String>,
+}
+
+impl Body {
+    pub fn new<T: Into<Bytes>>(bytes: T) -> Self {
+        Body {
+            body: Full::new(bytes.into())
+                .map_err(|never| match never {})
+                .boxed_unsync(),
+            content_type: None,
+        }
+    }
+
+    pub fn content_type<T: Into<String>>(mut self, content_type: T) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+}
+
+impl std::fmt::Debug for Body {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Body")
+            .field("content_type", &self.content_type)
+            .finish_non_exhaustive()
+    }
+}
+
+impl From<String> for Body {
+    fn from(value: String) -> Self {
+        Body::new(value).content_type("text/plain")
+    }
+}
+
+impl From<&str> for Body {
+    fn from(value: &str) -> Self {
+        Body::new(value.to_string()).content_type("text/plain")
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(value: Vec<u8>) -> Self {
+        Body::new(value)
+    }
+}
+
+impl From<&[u8]> for Body {
+    fn from(value: &[u8]) -> Self {
+        Body::new(Bytes::copy_from_slice(value))
+    }
+}
+
+impl From<Bytes> for Body {
+    fn from(value: Bytes) -> Self {
+        Body::new(value)
+    }
+}
+
+impl From<serde_json::Value> for Body {
+    fn from(value: serde_json::Value) -> Self {
+        Body::new(value.to_string()).content_type("application/json")
+    }
+}
+
+/// Multipart form whose files are streamed as the request is sent
+impl From<Form> for Body {
+    fn from(value: Form) -> Self {
+        let content_type = value.content_type();
+        Body {
+            body: value.into_body().boxed_unsync(),
+            content_type: Some(content_type),
+        }
+    }
+}
+
+/// Request sent to another server with a `Client`; the default client unless one is given,
+/// see `client::set_default`
+///
+/// Most of the time the `fetch!` macro is used to build and send the request.
+#[derive(Debug)]
+pub struct Request {
+    url: String,
+    method: String,
+    headers: Vec<(String, String)>,
+    body: Option<Body>,
+    signature: Option<HmacSignature>,
+    client: Option<Client>,
+}
+
+impl Request {
+    pub fn new<T: Into<String>>(url: T) -> Self {
+        Request {
+            url: url.into(),
+            method: "GET".to_string(),
+            headers: Vec::new(),
+            body: None,
+            signature: None,
+            client: None,
+        }
+    }
+
+    pub fn method<T: Into<String>>(mut self, method: T) -> Self {
+        self.method = method.into().to_uppercase();
+        self
+    }
+
+    pub fn header<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.headers.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn body<T: Into<Body>>(mut self, body: T) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Sign the body with `HmacSignature::sign_request`; ex: to deliver a webhook to a server
+    /// that checks it with `VerifySignature`. The body is read in full before it is signed.
+    pub fn sign(mut self, signature: &HmacSignature) -> Self {
+        self.signature = Some(signature.clone());
+        self
+    }
+
+    /// Send with a client instead of the default one; ex: one with a `Cache` or api token
+    pub fn client(mut self, client: &Client) -> Self {
+        self.client = Some(client.clone());
+        self
+    }
+
+    /// Build the hyper request that is sent
+    async fn build(&mut self) -> Result<hyper::Request<UnsyncBoxBody<Bytes, IoError>>, IoError> {
+        let invalid = |message: String| IoError::new(ErrorKind::InvalidInput, message);
+
+        let uri = self
+            .url
+            .parse::<Uri>()
+            .map_err(|error| invalid(format!("invalid url {}: {}", self.url, error)))?;
+        let method = Method::from_bytes(self.method.as_bytes())
+            .map_err(|_| invalid(format!("invalid method {}", self.method)))?;
+        let mut request = hyper::Request::builder().method(method).uri(uri);
+        for (name, value) in self.headers.iter() {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| invalid(format!("invalid header name {}", name)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|_| invalid(format!("invalid value for header {}", name)))?;
+            request = request.header(name, value);
+        }
+
+        let (body, content_type) = match self.body.take() {
+            Some(body) => (body.body, body.content_type),
+            None => (Body::new(Bytes::new()).body, None),
+        };
+        let mut request = request
+            .body(body)
+            .map_err(|error| invalid(error.to_string()))?;
+        if let Some(content_type) = content_type {
+            if !request.headers().contains_key(CONTENT_TYPE) {
+                let value = HeaderValue::from_str(&content_type)
+                    .map_err(|_| invalid(format!("invalid content type {}", content_type)))?;
+                request.headers_mut().insert(CONTENT_TYPE, value);
+            }
+        }
+
+        Ok(match &self.signature {
+            Some(signature) => {
+                let (parts, body) = request.into_parts();
+                let body = body.collect().await?.to_bytes();
+                signature
+                    .sign_request(hyper::Request::from_parts(parts, body))
+                    .map(|body| body.map_err(|never| match never {}).boxed_unsync())
+            }
+            None => request,
+        })
+    }
+
+    /// Send the request and read the whole response, see `Client::send`
+    pub async fn send(mut self) -> Result<hyper::Response<Bytes>, IoError> {
+        let request = self.build().await?;
+        match self.client {
+            Some(client) => client.send(request).await,
+            None => super::default_client().send(request).await,
+        }
+    }
+}
+
+/// Build and send a request with a `Client`, returning a future of
+/// `Result<hyper::Response<Bytes>, std::io::Error>`.
+///
+/// `fetch!(url, options...)` has the same syntax as `tela_wasm::fetch!` so shared code can
+/// make requests from the server and the browser. The options are all optional and can be in
+/// any order:
+/// * `method: POST`; `GET` without one
+/// * `headers: { "Accept": "application/json", ... }`
+/// * `body: ...`; anything that converts into a `Body`, which sets the content type
+/// * `sign: ...`; an `HmacSignature` whose headers are added to the request. Server only.
+/// * `client: ...`; the `Client` to send with, so its cache and retries are used. The default
+///   client, see `client::set_default`, is used without one. Server only.
+///
+/// A url that is a string literal can have `{name}` placeholders for variables in scope. The
+/// values are percent-encoded except for a placeholder at the start, the base url. See `url!`.
+///
+/// # Example
+/// ```no_run
+/// use tela::{client::Client, fetch, layer::HmacSignature};
+///
+/// # async fn deploy() -> std::io::Result<()> {
+/// let (base, id) = ("https://hooks.example.com", 42);
+/// let upstream = Client::new();
+/// let webhook = HmacSignature::new("secret").prefix("sha256=");
+/// let response = fetch!(
+///     "{base}/hooks/{id}",
+///     method: POST,
+///     headers: { "Accept": "application/json" },
+///     body: serde_json::json!({ "event": "deployed" }),
+///     sign: webhook,
+///     client: upstream
+/// ).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! fetch {
+    ($($tokens: tt)*) => {
+        $crate::bump::fetch_request!(server $crate::client::fetch::Request; $($tokens)*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+    use crate::client::Cache;
+
+    const OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+
+    /// Server that answers one request and returns what it received
+    async fn server(response: &'static [u8]) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let received = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut received = Vec::new();
+            // Read until the head and the body it has a length for arrive
+            let request = loop {
+                let mut chunk = [0; 1024];
+                let read = stream.read(&mut chunk).await.unwrap();
+                received.extend_from_slice(&chunk[..read]);
+                let text = String::from_utf8_lossy(&received).to_lowercase();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .split("\r\n")
+                        .find_map(|line| line.strip_prefix("content-length: "))
+                        .map(|length| length.parse::<usize>().unwrap())
+                        .unwrap_or(0);
+                    if read == 0 || body.len() >= length {
+                        break text;
+                    }
+                }
+            };
+            stream.write_all(response).await.unwrap();
+            request
+        });
+        (base, received)
+    }
+
+    #[tokio::test]
+    async fn fetch_sends_the_options() {
+        let (base, received) = server(OK).await;
+        let name = "ann lee";
+        let response = crate::fetch!(
+            "{base}/users/{name}",
+            method: post,
+            headers: { "Accept": "application/json" },
+            body: serde_json::json!({ "admin": true })
+        )
+        .await
+        .unwrap();
+        assert_eq!(&response.body()[..], b"ok");
+
+        let request = received.await.unwrap();
+        assert!(request.starts_with("post /users/ann%20lee http/1.1\r\n"));
+        assert!(request.contains("accept: application/json\r\n"));
+        assert!(request.contains("content-type: application/json\r\n"));
+        assert!(request.ends_with("\r\n\r\n{\"admin\":true}"));
+    }
+
+    #[tokio::test]
+    async fn fetch_sends_multipart_forms() {
+        let (base, received) = server(OK).await;
+        let form = Form::new()
+            .text("title", "Notes")
+            .bytes("file", "notes.txt", "hello");
+        let boundary = form.boundary().to_lowercase();
+        crate::fetch!("{base}/upload", method: POST, body: form)
+            .await
+            .unwrap();
+
+        let request = received.await.unwrap();
+        assert!(request.contains(&format!(
+            "content-type: multipart/form-data; boundary={}\r\n",
+            boundary
+        )));
+        assert!(request.contains("name=\"title\"\r\n\r\nnotes\r\n"));
+        assert!(request.contains("filename=\"notes.txt\""));
+        assert!(request.ends_with(&format!("hello\r\n--{}--\r\n", boundary)));
+    }
+
+    #[tokio::test]
+    async fn fetch_headers_override_the_clients() {
+        let (base, received) = server(OK).await;
+        let client = Client::builder()
+            .user_agent("launchpad/1.0")
+            .default_header("Accept", "text/html")
+            .default_header("Authorization", "Bearer token")
+            .build();
+        crate::fetch!(
+            "{base}/users",
+            headers: { "Accept": "application/json" },
+            client: client
+        )
+        .await
+        .unwrap();
+
+        let request = received.await.unwrap();
+        assert!(request.contains("user-agent: launchpad/1.0\r\n"));
+        assert!(request.contains("authorization: bearer token\r\n"));
+        assert!(request.contains("accept: application/json\r\n"));
+        assert!(!request.contains("text/html"));
+    }
+
+    #[tokio::test]
+    async fn fetch_uses_the_clients_cache() {
+        let (base, received) =
+            server(b"HTTP/1.1 200 OK\r\nCache-Control: max-age=60\r\nContent-Length: 2\r\n\r\nok")
+                .await;
+        let client = Client::builder().cache(Cache::new()).build();
+        for _ in 0..2 {
+            // The server only answers once so the second response comes from the cache
+            let response = tokio::time::timeout(
+                std::time::Duration::from_secs(5),
+                crate::fetch!("{base}/users", client: client),
+            )
+            .await
+            .expect("second request wasn't cached")
+            .unwrap();
+            assert_eq!(&response.body()[..], b"ok");
+        }
+        assert!(received.await.unwrap().starts_with("get /users http/1.1"));
+    }
+
+    #[tokio::test]
+    async fn signed_requests_verify() {
+        let signature = HmacSignature::new("secret").prefix("sha256=");
+        let mut request = Request::new("http://localhost/hooks")
+            .method("POST")
+            .body("deployed")
+            .sign(&signature);
+        let request = request.build().await.unwrap();
+        let (parts, body) = request.into_parts();
+        let body = body.collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"deployed");
+        assert_eq!(parts.headers[CONTENT_TYPE], "text/plain");
+        assert!(signature.verify(&parts.headers, &body).is_ok());
+        assert!(signature.verify(&parts.headers, b"changed").is_err());
+    }
+
+    #[tokio::test]
+    async fn invalid_requests_are_errors() {
+        let error = Request::new("not a url").send().await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+        let error = Request::new("http://localhost/")
+            .header("Bad Name", "value")
+            .send()
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,252 @@
+//! Outgoing http requests; ex: calling an upstream api from a handler.
+//!
+//! # Example
+//! ```no_run
+//! use bytes::Bytes;
+//! use http_body_util::Full;
+//! use tela::{client, fetch};
+//!
+//! # async fn example() -> std::io::Result<()> {
+//! let request = hyper::Request::get("https://api.example.com/users")
+//!     .header("Accept", "application/json")
+//!     .body(Full::new(Bytes::new()))
+//!     .unwrap();
+//! let response = client::send(request).await?;
+//!
+//! // Or build and send it with `fetch!`
+//! let response = fetch!(
+//!     "https://api.example.com/users",
+//!     headers: { "Accept": "application/json" }
+//! )
+//! .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod builder;
+mod cache;
+mod dns;
+pub mod fetch;
+pub mod multipart;
+mod response;
+mod retry;
+
+pub use builder::{Client, ClientBuilder};
+pub use cache::Cache;
+pub use dns::{Resolve, SystemResolver};
+pub use response::Response;
+pub use retry::{RateLimit, Retry};
+
+use std::{
+    io::{Error as IoError, ErrorKind},
+    sync::RwLock,
+};
+
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use hyper::{body::Body, header::HOST, Uri};
+use lazy_static::lazy_static;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::support::TokioIo;
+use dns::Connector;
+
+/// Transport of an outgoing connection; a tcp stream or a TLS session over one
+pub(crate) trait Io: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Io for T {}
+
+/// Connect to the host of a uri. `https://` and `wss://` uris start a TLS session which
+/// requires the `tls` feature.
+pub(crate) async fn connect(uri: &Uri) -> Result<Box<dyn Io>, IoError> {
+    connect_with(&Connector::default(), uri).await
+}
+
+async fn connect_with(connector: &Connector, uri: &Uri) -> Result<Box<dyn Io>, IoError> {
+    let host = uri
+        .host()
+        .ok_or_else(|| IoError::new(ErrorKind::InvalidInput, "uri is missing a host"))?;
+    let secure = match uri.scheme_str() {
+        Some("http") | Some("ws") => false,
+        Some("https") | Some("wss") => true,
+        _ => {
+            return Err(IoError::new(
+                ErrorKind::InvalidInput,
+                format!("unsupported uri scheme in {}", uri),
+            ))
+        }
+    };
+    let port = uri.port_u16().unwrap_or(if secure { 443 } else { 80 });
+    // Hosts in uris may be bracketed ipv6 addresses
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    let tcp = connector.tcp(host, port).await?;
+    match secure {
+        true => tls::connect(host, tcp).await,
+        false => Ok(Box::new(tcp)),
+    }
+}
+
+lazy_static! {
+    static ref DEFAULT: RwLock<Client> = RwLock::new(Client::new());
+}
+
+/// Set the client `fetch!` sends with when it isn't given one; ex: to cache responses or send
+/// a user agent with every request
+///
+/// # Example
+/// ```
+/// use tela::client::{self, Cache, Client};
+///
+/// client::set_default(
+///     Client::builder()
+///         .user_agent("my-app/1.0")
+///         .cache(Cache::new())
+///         .build(),
+/// );
+/// ```
+pub fn set_default(client: Client) {
+    *DEFAULT.write().unwrap() = client;
+}
+
+/// Client `fetch!` sends with when it isn't given one, see `set_default`
+pub fn default_client() -> Client {
+    DEFAULT.read().unwrap().clone()
+}
+
+/// Send a request over a new HTTP/1.1 connection and read the whole response. The uri must
+/// be absolute; `https://` uris require the `tls` feature. The body can be any hyper body;
+/// ex: `Full<Bytes>` or a `multipart::Form`. See `Cache` to reuse responses.
+pub async fn send<B>(request: hyper::Request<B>) -> Result<hyper::Response<Bytes>, IoError>
+where
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    send_with(&Connector::default(), request).await
+}
+
+async fn send_with<B>(
+    connector: &Connector,
+    request: hyper::Request<B>,
+) -> Result<hyper::Response<Bytes>, IoError>
+where
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let response = open_with(connector, request).await?.into_inner();
+    let (parts, body) = response.into_parts();
+    let body = body.collect().await.map_err(IoError::other)?.to_bytes();
+    Ok(hyper::Response::from_parts(parts, body))
+}
+
+/// Send a request like `send` but return once the response's headers arrive, leaving the body
+/// to be read; ex: to stream a large download to a file with `Response::stream_to_file`.
+pub async fn open<B>(request: hyper::Request<B>) -> Result<Response, IoError>
+where
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    open_with(&Connector::default(), request).await
+}
+
+async fn open_with<B>(
+    connector: &Connector,
+    mut request: hyper::Request<B>,
+) -> Result<Response, IoError>
+where
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let other = |error: hyper::Error| IoError::other(error);
+
+    let io = connect_with(connector, request.uri()).await?;
+    if let Some(authority) = request.uri().authority() {
+        let host = authority
+            .as_str()
+            .parse()
+            .map_err(|_| IoError::new(ErrorKind::InvalidInput, "invalid host"))?;
+        request.headers_mut().insert(HOST, host);
+    }
+    // The connection is sent the path instead of the full uri
+    let path = request
+        .uri()
+        .path_and_query()
+        .map(|path| path.as_str())
+        .unwrap_or("/")
+        .parse::<Uri>()
+        .map_err(|_| IoError::new(ErrorKind::InvalidInput, "invalid path"))?;
+    *request.uri_mut() = path;
+
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(io))
+        .await
+        .map_err(other)?;
+    tokio::spawn(async move {
+        let _ = connection.await;
+    });
+
+    let response = sender.send_request(request).await.map_err(other)?;
+    Ok(Response::new(response))
+}
+
+#[cfg(feature = "tls")]
+mod tls {
+    use std::{
+        io::{Error as IoError, ErrorKind},
+        sync::Arc,
+    };
+
+    use lazy_static::lazy_static;
+    use tokio::net::TcpStream;
+    use tokio_rustls::{
+        rustls::{ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName},
+        TlsConnector,
+    };
+
+    use super::Io;
+
+    lazy_static! {
+        static ref CONNECTOR: TlsConnector = {
+            let mut roots = RootCertStore::empty();
+            roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+                OwnedTrustAnchor::from_subject_spki_name_constraints(
+                    anchor.subject,
+                    anchor.spki,
+                    anchor.name_constraints,
+                )
+            }));
+            let mut config = ClientConfig::builder()
+                .with_safe_defaults()
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+            // Requests and websocket upgrades are sent over HTTP/1.1
+            config.alpn_protocols = vec![b"http/1.1".to_vec()];
+            TlsConnector::from(Arc::new(config))
+        };
+    }
+
+    /// Start a TLS session with the host as the server name (SNI)
+    pub(super) async fn connect(host: &str, tcp: TcpStream) -> Result<Box<dyn Io>, IoError> {
+        let name = ServerName::try_from(host)
+            .map_err(|error| IoError::new(ErrorKind::InvalidInput, error))?;
+        Ok(Box::new(CONNECTOR.connect(name, tcp).await?))
+    }
+}
+
+#[cfg(not(feature = "tls"))]
+mod tls {
+    use std::io::{Error as IoError, ErrorKind};
+
+    use tokio::net::TcpStream;
+
+    use super::Io;
+
+    pub(super) async fn connect(_host: &str, _tcp: TcpStream) -> Result<Box<dyn Io>, IoError> {
+        Err(IoError::new(
+            ErrorKind::Unsupported,
+            "https:// and wss:// uris require the tls feature",
+        ))
+    }
+}
@@ -0,0 +1,318 @@
+//! `multipart/form-data` request bodies; ex: uploading a file to another service's api.
+
+use std::{
+    io::{Cursor, Error as IoError},
+    path::PathBuf,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures::{stream::BoxStream, StreamExt};
+use hyper::{
+    body::{Frame, SizeHint},
+    header::{CONTENT_LENGTH, CONTENT_TYPE},
+};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::cookie::random_key;
+
+type Reader = Pin<Box<dyn AsyncRead + Send>>;
+
+/// Size of the chunks read from each file part
+const CHUNK: usize = 64 * 1024;
+
+enum Source {
+    Text(String),
+    Bytes(Bytes),
+    File(PathBuf),
+    Reader(Reader),
+}
+
+struct Part {
+    name: String,
+    filename: Option<String>,
+    source: Source,
+}
+
+/// Quote a name for the `Content-Disposition` of a part the way browsers do
+fn quote(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Boundary and headers that start a part
+fn header(boundary: &str, part: &Part) -> Vec<u8> {
+    let mut header = format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+        boundary,
+        quote(&part.name)
+    );
+    if let Some(filename) = &part.filename {
+        header.push_str(&format!(
+            "; filename=\"{}\"\r\nContent-Type: {}",
+            quote(filename),
+            mime_guess::from_path(filename).first_or_octet_stream()
+        ));
+    }
+    header.push_str("\r\n\r\n");
+    header.into_bytes()
+}
+
+/// `multipart/form-data` body made of text fields and files. Files and readers are read in
+/// chunks while the request is sent so large uploads aren't held in memory.
+///
+/// The content type of a file part is guessed from its filename. The request gets a
+/// `Content-Length` when every part's size is known, which is every part except readers,
+/// otherwise the body is sent chunked.
+///
+/// # Example
+/// ```no_run
+/// use tela::{client::{self, multipart::Form}, fetch};
+///
+/// # async fn example() -> std::io::Result<()> {
+/// let request = Form::new()
+///     .text("title", "Holiday")
+///     .file("photo", "photos/beach.jpg")
+///     .into_request(hyper::Request::post("https://api.example.com/photos"))
+///     .unwrap();
+/// let response = client::send(request).await?;
+///
+/// // Or as the body of `fetch!`, which sets the content type
+/// let form = Form::new().file("photo", "photos/beach.jpg");
+/// let response = fetch!("https://api.example.com/photos", method: POST, body: form).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Form {
+    boundary: String,
+    parts: Vec<Part>,
+}
+
+impl Form {
+    /// Empty form with a random boundary
+    pub fn new() -> Self {
+        let boundary: String = random_key()[..12]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        Form {
+            boundary: format!("tela-{}", boundary),
+            parts: Vec::new(),
+        }
+    }
+
+    /// Add a text field
+    pub fn text<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.parts.push(Part {
+            name: name.into(),
+            filename: None,
+            source: Source::Text(value.into()),
+        });
+        self
+    }
+
+    /// Add in-memory data as a file with the filename
+    pub fn bytes<N: Into<String>, F: Into<String>, B: Into<Bytes>>(
+        mut self,
+        name: N,
+        filename: F,
+        data: B,
+    ) -> Self {
+        self.parts.push(Part {
+            name: name.into(),
+            filename: Some(filename.into()),
+            source: Source::Bytes(data.into()),
+        });
+        self
+    }
+
+    /// Add a file from disk. The filename sent is the file's name without its directories.
+    pub fn file<N: Into<String>, P: Into<PathBuf>>(mut self, name: N, path: P) -> Self {
+        let path = path.into();
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.parts.push(Part {
+            name: name.into(),
+            filename: Some(filename),
+            source: Source::File(path),
+        });
+        self
+    }
+
+    /// Add data produced by an async reader as a file with the filename
+    pub fn reader<N: Into<String>, F: Into<String>, R: AsyncRead + Send + 'static>(
+        mut self,
+        name: N,
+        filename: F,
+        reader: R,
+    ) -> Self {
+        self.parts.push(Part {
+            name: name.into(),
+            filename: Some(filename.into()),
+            source: Source::Reader(Box::pin(reader)),
+        });
+        self
+    }
+
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// `multipart/form-data` content type with the boundary
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    fn end(&self) -> Vec<u8> {
+        format!("--{}--\r\n", self.boundary).into_bytes()
+    }
+
+    /// Size of the body if every part's size is known
+    fn len(&self) -> Option<u64> {
+        self.parts
+            .iter()
+            .try_fold(self.end().len() as u64, |len, part| {
+                let data = match &part.source {
+                    Source::Text(text) => text.len() as u64,
+                    Source::Bytes(data) => data.len() as u64,
+                    Source::File(path) => std::fs::metadata(path).ok()?.len(),
+                    Source::Reader(_) => return None,
+                };
+                // Each part ends with a line break before the next boundary
+                Some(len + header(&self.boundary, part).len() as u64 + data + 2)
+            })
+    }
+
+    /// Body to send with a request. Set the content type to `content_type` or use
+    /// `into_request`.
+    pub fn into_body(self) -> FormBody {
+        let len = self.len();
+        let writer = Writer {
+            end: Some(self.end()),
+            boundary: self.boundary,
+            parts: self.parts.into_iter(),
+            current: None,
+        };
+        let chunks = futures::stream::unfold(writer, |mut writer| async move {
+            match writer.next().await {
+                Ok(Some(chunk)) => Some((Ok(Frame::data(chunk)), writer)),
+                Ok(None) => None,
+                Err(error) => Some((Err(error), writer.failed())),
+            }
+        });
+        FormBody {
+            chunks: chunks.boxed(),
+            len,
+        }
+    }
+
+    /// Finish a request with the form as its body and its content type and length
+    pub fn into_request(
+        self,
+        request: hyper::http::request::Builder,
+    ) -> Result<hyper::Request<FormBody>, hyper::http::Error> {
+        let mut request = request.header(CONTENT_TYPE, self.content_type());
+        let body = self.into_body();
+        if let Some(len) = body.len {
+            request = request.header(CONTENT_LENGTH, len);
+        }
+        request.body(body)
+    }
+}
+
+impl Default for Form {
+    fn default() -> Self {
+        Form::new()
+    }
+}
+
+/// Writes the form one chunk at a time
+struct Writer {
+    boundary: String,
+    parts: std::vec::IntoIter<Part>,
+    current: Option<Reader>,
+    /// Closing boundary; `None` once it is written
+    end: Option<Vec<u8>>,
+}
+
+impl Writer {
+    async fn next(&mut self) -> std::io::Result<Option<Bytes>> {
+        if let Some(reader) = self.current.as_mut() {
+            let mut buffer = vec![0; CHUNK];
+            let read = reader.read(&mut buffer).await?;
+            if read == 0 {
+                self.current = None;
+                return Ok(Some(Bytes::from_static(b"\r\n")));
+            }
+            buffer.truncate(read);
+            return Ok(Some(Bytes::from(buffer)));
+        }
+
+        let Some(part) = self.parts.next() else {
+            return Ok(self.end.take().map(Bytes::from));
+        };
+        let mut chunk = header(&self.boundary, &part);
+        match part.source {
+            Source::Text(text) => chunk.extend_from_slice(text.as_bytes()),
+            Source::Bytes(data) if data.len() <= CHUNK => chunk.extend_from_slice(&data),
+            Source::Bytes(data) => {
+                self.current = Some(Box::pin(Cursor::new(data)));
+                return Ok(Some(Bytes::from(chunk)));
+            }
+            Source::File(path) => {
+                self.current = Some(Box::pin(tokio::fs::File::open(path).await?));
+                return Ok(Some(Bytes::from(chunk)));
+            }
+            Source::Reader(reader) => {
+                self.current = Some(reader);
+                return Ok(Some(Bytes::from(chunk)));
+            }
+        }
+        chunk.extend_from_slice(b"\r\n");
+        Ok(Some(Bytes::from(chunk)))
+    }
+
+    /// Stop writing after an error so the request fails instead of sending a partial form
+    fn failed(mut self) -> Self {
+        self.parts = Vec::new().into_iter();
+        self.current = None;
+        self.end = None;
+        self
+    }
+}
+
+/// Body of a `Form` that can be sent with `client::send`
+pub struct FormBody {
+    chunks: BoxStream<'static, Result<Frame<Bytes>, IoError>>,
+    len: Option<u64>,
+}
+
+impl hyper::body::Body for FormBody {
+    type Data = Bytes;
+    type Error = IoError;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        self.chunks.poll_next_unpin(cx)
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match self.len {
+            Some(len) => SizeHint::with_exact(len),
+            None => SizeHint::default(),
+        }
+    }
+}
+
+impl std::fmt::Debug for FormBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FormBody").field("len", &self.len).finish()
+    }
+}
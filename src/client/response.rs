@@ -0,0 +1,133 @@
+use std::{io::Error as IoError, path::Path};
+
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use hyper::{body::Incoming, header::CONTENT_LENGTH, HeaderMap, StatusCode};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use super::RateLimit;
+
+type Progress = Box<dyn FnMut(u64, Option<u64>) + Send>;
+
+/// Response from `client::open` whose body hasn't been read yet. Read it all with `bytes`
+/// or write it as it arrives with `stream_to` and `stream_to_file` so large downloads aren't
+/// held in memory.
+///
+/// # Example
+/// ```no_run
+/// use bytes::Bytes;
+/// use http_body_util::Full;
+/// use tela::client;
+///
+/// # async fn example() -> std::io::Result<()> {
+/// let request = hyper::Request::get("https://example.com/images/disk.iso")
+///     .body(Full::new(Bytes::new()))
+///     .unwrap();
+/// let written = client::open(request)
+///     .await?
+///     .progress(|received, total| println!("{} of {:?} bytes", received, total))
+///     .stream_to_file("disk.iso")
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Response {
+    inner: hyper::Response<Incoming>,
+    progress: Option<Progress>,
+}
+
+impl Response {
+    pub(crate) fn new(inner: hyper::Response<Incoming>) -> Self {
+        Response {
+            inner,
+            progress: None,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.inner.status()
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        self.inner.headers()
+    }
+
+    /// Size of the body from the `Content-Length` header
+    pub fn content_length(&self) -> Option<u64> {
+        self.inner
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+    }
+
+    /// Rate limit the server sent with the response, see `RateLimit`
+    pub fn rate_limit(&self) -> RateLimit {
+        RateLimit::from_headers(self.inner.headers())
+    }
+
+    /// Call a function as the body is streamed with the bytes received so far and the
+    /// content length, if the server sent one
+    pub fn progress<F: FnMut(u64, Option<u64>) + Send + 'static>(mut self, progress: F) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Read the whole body
+    pub async fn bytes(self) -> Result<Bytes, IoError> {
+        let mut body = Vec::new();
+        self.stream_to(&mut body).await?;
+        Ok(Bytes::from(body))
+    }
+
+    /// Write the body to a writer as it arrives and return the number of bytes written
+    pub async fn stream_to<W: AsyncWrite + Unpin>(
+        mut self,
+        writer: &mut W,
+    ) -> Result<u64, IoError> {
+        let total = self.content_length();
+        let mut body = self.inner.into_body();
+        let mut received = 0;
+        while let Some(frame) = body.frame().await {
+            let frame = frame.map_err(IoError::other)?;
+            if let Some(chunk) = frame.data_ref() {
+                writer.write_all(chunk).await?;
+                received += chunk.len() as u64;
+                if let Some(progress) = self.progress.as_mut() {
+                    progress(received, total);
+                }
+            }
+        }
+        writer.flush().await?;
+        Ok(received)
+    }
+
+    /// Write the body to a file as it arrives and return the number of bytes written. The
+    /// file is replaced if it exists and removed if the download fails.
+    pub async fn stream_to_file<P: AsRef<Path>>(self, path: P) -> Result<u64, IoError> {
+        let path = path.as_ref();
+        let mut file = tokio::fs::File::create(path).await?;
+        match self.stream_to(&mut file).await {
+            Ok(written) => Ok(written),
+            Err(error) => {
+                drop(file);
+                let _ = tokio::fs::remove_file(path).await;
+                Err(error)
+            }
+        }
+    }
+
+    /// Response with its unread body
+    pub fn into_inner(self) -> hyper::Response<Incoming> {
+        self.inner
+    }
+}
+
+impl std::fmt::Debug for Response {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Response")
+            .field("status", &self.inner.status())
+            .field("headers", self.inner.headers())
+            .finish()
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    future::Future,
+    io::{Error as IoError, ErrorKind},
+    time::{Duration, SystemTime},
+};
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::{body::Body, header::RETRY_AFTER, HeaderMap, Method, StatusCode};
+
+use super::Response;
+use crate::{cookie::random_key, request::parse_date};
+
+/// Resets larger than this are unix timestamps instead of seconds from now; ex: GitHub's
+/// `X-RateLimit-Reset`
+const TIMESTAMP: u64 = 1_000_000_000;
+
+/// Number at the start of a header; ex: `100` from `100, 100;w=60`
+fn number(headers: &HeaderMap, names: &[&str]) -> Option<u64> {
+    names.iter().find_map(|name| {
+        let value = headers.get(*name)?.to_str().ok()?;
+        value.split([',', ';']).next()?.trim().parse::<u64>().ok()
+    })
+}
+
+/// Time from now until a date
+fn until(date: SystemTime) -> Duration {
+    date.duration_since(SystemTime::now()).unwrap_or_default()
+}
+
+/// Rate limit an api sent with a response from the `RateLimit-*` headers or the common
+/// `X-RateLimit-*` ones, along with `Retry-After`.
+///
+/// # Example
+/// ```no_run
+/// use bytes::Bytes;
+/// use http_body_util::Full;
+/// use tela::client::Client;
+///
+/// # async fn example() -> std::io::Result<()> {
+/// let client = Client::new();
+/// let request = hyper::Request::get("https://api.example.com/users")
+///     .body(Full::new(Bytes::new()))
+///     .unwrap();
+/// let response = client.open(request).await?;
+/// let limit = response.rate_limit();
+/// if limit.is_exhausted() {
+///     println!("rate limited for {:?}", limit.reset);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Requests allowed in the window
+    pub limit: Option<u64>,
+    /// Requests left in the window
+    pub remaining: Option<u64>,
+    /// Time until the window resets
+    pub reset: Option<Duration>,
+    /// Time to wait before sending another request from `Retry-After`, seconds or an http date
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimit {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let reset = number(headers, &["ratelimit-reset", "x-ratelimit-reset"]).map(|reset| {
+            match reset > TIMESTAMP {
+                true => until(SystemTime::UNIX_EPOCH + Duration::from_secs(reset)),
+                false => Duration::from_secs(reset),
+            }
+        });
+        let retry_after = headers
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| match value.trim().parse::<u64>() {
+                Ok(seconds) => Some(Duration::from_secs(seconds)),
+                Err(_) => parse_date(value).map(until),
+            });
+        RateLimit {
+            limit: number(headers, &["ratelimit-limit", "x-ratelimit-limit"]),
+            remaining: number(headers, &["ratelimit-remaining", "x-ratelimit-remaining"]),
+            reset,
+            retry_after,
+        }
+    }
+
+    /// Whether no requests are left in the window
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining == Some(0)
+    }
+}
+
+/// Responses a `Retry` can inspect
+pub(super) trait Outcome {
+    fn status(&self) -> StatusCode;
+    fn headers(&self) -> &HeaderMap;
+}
+
+impl Outcome for hyper::Response<Bytes> {
+    fn status(&self) -> StatusCode {
+        self.status()
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        self.headers()
+    }
+}
+
+impl Outcome for Response {
+    fn status(&self) -> StatusCode {
+        self.status()
+    }
+
+    fn headers(&self) -> &HeaderMap {
+        self.headers()
+    }
+}
+
+/// When a `Client` sends a request again.
+///
+/// `429 Too Many Requests` and `503 Service Unavailable` are retried after the server's
+/// `Retry-After`, or the rate limit's reset once it is exhausted, otherwise after an
+/// exponential backoff with jitter. Requests with idempotent methods are also retried after
+/// `502` and `504` responses and connection errors. A response asking for a longer wait than
+/// `max_delay` is returned instead of waiting.
+///
+/// The request body is read before the first attempt so it can be sent again.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use tela::client::{Client, Retry};
+///
+/// let client = Client::builder()
+///     .retry(Retry::new(3).backoff(Duration::from_millis(200)))
+///     .build();
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Retry {
+    retries: u32,
+    backoff: Duration,
+    max_delay: Duration,
+}
+
+impl Retry {
+    /// Send a request up to `retries` more times, waiting 500ms before the first retry and
+    /// at most a minute before any
+    pub fn new(retries: u32) -> Self {
+        Retry {
+            retries,
+            backoff: Duration::from_millis(500),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+
+    /// Wait before the first retry; each retry after waits twice as long as the last
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Longest wait before a retry
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Exponential backoff for a retry with up to half of it taken off at random so clients
+    /// don't retry in lockstep
+    fn backoff_for(&self, attempt: u32) -> Duration {
+        let delay = self
+            .backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let jitter = u32::from(random_key()[0]);
+        delay / 2 + delay / 2 * jitter / 255
+    }
+
+    /// How long to wait before sending the request again, `None` to return the outcome
+    fn delay<T: Outcome>(
+        &self,
+        attempt: u32,
+        method: &Method,
+        outcome: Result<&T, &IoError>,
+    ) -> Option<Duration> {
+        if attempt >= self.retries {
+            return None;
+        }
+        let idempotent = matches!(
+            *method,
+            Method::GET
+                | Method::HEAD
+                | Method::PUT
+                | Method::DELETE
+                | Method::OPTIONS
+                | Method::TRACE
+        );
+        let delay = match outcome {
+            Ok(response) => match response.status() {
+                StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => {
+                    let limit = RateLimit::from_headers(response.headers());
+                    limit
+                        .retry_after
+                        .or(limit.reset.filter(|_| limit.is_exhausted()))
+                        .unwrap_or_else(|| self.backoff_for(attempt))
+                }
+                StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT if idempotent => {
+                    self.backoff_for(attempt)
+                }
+                _ => return None,
+            },
+            // Invalid requests fail the same way every time
+            Err(error)
+                if idempotent
+                    && !matches!(
+                        error.kind(),
+                        ErrorKind::InvalidInput | ErrorKind::Unsupported
+                    ) =>
+            {
+                self.backoff_for(attempt)
+            }
+            Err(_) => return None,
+        };
+        (delay <= self.max_delay).then_some(delay)
+    }
+
+    /// Send a request with `send` until it succeeds or shouldn't be retried
+    pub(super) async fn run<B, T, F, Fut>(
+        &self,
+        request: hyper::Request<B>,
+        send: F,
+    ) -> Result<T, IoError>
+    where
+        B: Body,
+        B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        T: Outcome,
+        F: Fn(hyper::Request<Full<Bytes>>) -> Fut,
+        Fut: Future<Output = Result<T, IoError>>,
+    {
+        let (parts, body) = request.into_parts();
+        let body = body
+            .collect()
+            .await
+            .map_err(|error| IoError::other(error.into()))?
+            .to_bytes();
+
+        let mut attempt = 0;
+        loop {
+            let mut request = hyper::Request::new(Full::new(body.clone()));
+            *request.method_mut() = parts.method.clone();
+            *request.uri_mut() = parts.uri.clone();
+            *request.version_mut() = parts.version;
+            *request.headers_mut() = parts.headers.clone();
+
+            let outcome = send(request).await;
+            match self.delay(attempt, &parts.method, outcome.as_ref()) {
+                Some(delay) => {
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                None => return outcome,
+            }
+        }
+    }
+}
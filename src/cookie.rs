@@ -0,0 +1,651 @@
+use std::{fmt::Display, sync::Arc};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bytes::Bytes;
+use hmac::{Hmac, Mac};
+use http_body_util::Full;
+use hyper::{
+    header::{HeaderValue, COOKIE, SET_COOKIE},
+    HeaderMap, Method, Uri,
+};
+use sha2::Sha256;
+
+use crate::{
+    request::{RequestData, ToParam},
+    response::{Result, ToResponse},
+};
+
+tokio::task_local! {
+    /// Key of the server handling the request. Defaults to a random key so signed cookies
+    /// don't survive a restart unless a secret is set with `Server::secret`.
+    pub(crate) static SECRET: Arc<[u8]>;
+}
+
+tokio::task_local! {
+    /// Whether the server handling the request is served over TLS
+    pub(crate) static SECURE: bool;
+}
+
+/// Whether cookies should be marked `Secure`, only sent back over https, because the server
+/// handling the request is served over TLS
+pub(crate) fn secure() -> bool {
+    SECURE.try_with(|secure| *secure).unwrap_or(false)
+}
+
+/// Random 32 byte key from the operating system's secure random number generator
+pub(crate) fn random_key() -> Vec<u8> {
+    let mut key = vec![0; 32];
+    getrandom::getrandom(&mut key).expect("the system random number generator is unavailable");
+    key
+}
+
+/// Hmac with the secret of the server handling the request.
+///
+/// # Panics
+/// Outside of a request there is no secret to sign with, ex: in a task started with
+/// `tokio::spawn`. Nothing is signed with a made up key that no server would accept.
+pub(crate) fn mac() -> Hmac<Sha256> {
+    SECRET
+        .try_with(|key| Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key size"))
+        .expect("Signing requires the secret of the server handling the request; sign and verify while handling a request, not in a spawned task or outside of the server")
+}
+
+/// Run `f` with a random secret like a request would
+#[cfg(test)]
+pub(crate) fn with_secret<R>(f: impl FnOnce() -> R) -> R {
+    SECRET.sync_scope(random_key().into(), f)
+}
+
+/// Signature of a cookie's value. The name is signed with it so a value signed for one
+/// cookie can't be sent as another.
+fn signature(name: &str, value: &str) -> Hmac<Sha256> {
+    let mut mac = mac();
+    mac.update(b"cookie:");
+    mac.update(name.as_bytes());
+    mac.update(b"=");
+    mac.update(value.as_bytes());
+    mac
+}
+
+/// Sign the value of the named cookie so it can't be changed by the client; ex:
+/// `value.signature`. Panics outside of a request since there is no server secret to sign with.
+pub fn sign(name: &str, value: &str) -> String {
+    format!(
+        "{}.{}",
+        value,
+        URL_SAFE_NO_PAD.encode(signature(name, value).finalize().into_bytes())
+    )
+}
+
+/// Value of a signed cookie if the signature is valid for the cookie's name. Panics outside of
+/// a request like `sign`.
+pub fn verify<'a>(name: &str, signed: &'a str) -> Option<&'a str> {
+    let (value, signed) = signed.rsplit_once('.')?;
+    let signed = URL_SAFE_NO_PAD.decode(signed).ok()?;
+    signature(name, value)
+        .verify_slice(&signed)
+        .ok()
+        .map(|_| value)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl Display for SameSite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SameSite::Strict => write!(f, "Strict"),
+            SameSite::Lax => write!(f, "Lax"),
+            SameSite::None => write!(f, "None"),
+        }
+    }
+}
+
+/// Name and value of a cookie pair with surrounding whitespace and quotes removed.
+/// Pairs without a `=` or without a name are ignored.
+fn pair(pair: &str) -> Option<(String, String)> {
+    let (name, value) = pair.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let value = value.trim();
+    let value = match value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        true => &value[1..value.len() - 1],
+        false => value,
+    };
+    Some((name.to_string(), value.to_string()))
+}
+
+/// A single cookie along with the attributes used when it is set.
+///
+/// # Example
+/// ```
+/// use tela::cookie::{Cookie, SameSite};
+///
+/// let cookie = Cookie::parse("theme=dark; Path=/; Max-Age=3600; HttpOnly").unwrap();
+/// assert_eq!(cookie.value(), "dark");
+/// assert_eq!(cookie.path(), Some("/"));
+/// assert_eq!(cookie.max_age(), Some(3600));
+///
+/// let cookie = Cookie::new("theme", "light")
+///     .with_path("/")
+///     .with_same_site(SameSite::Lax);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    path: Option<String>,
+    domain: Option<String>,
+    expires: Option<String>,
+    max_age: Option<i64>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+}
+
+impl Cookie {
+    pub fn new<N: Into<String>, V: Into<String>>(name: N, value: V) -> Self {
+        Cookie {
+            name: name.into(),
+            value: value.into(),
+            path: None,
+            domain: None,
+            expires: None,
+            max_age: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        }
+    }
+
+    /// Parse a cookie in the `Set-Cookie` format; ex: `name=value; Path=/; Secure`.
+    ///
+    /// Parsing follows RFC 6265 so unknown or invalid attributes are ignored instead of
+    /// failing the whole cookie. Returns `None` if the cookie doesn't have a name.
+    pub fn parse(cookie: &str) -> Option<Cookie> {
+        let mut parts = cookie.split(';');
+        let (name, value) = pair(parts.next()?)?;
+        let mut cookie = Cookie::new(name, value);
+
+        for attribute in parts {
+            let (key, value) = match attribute.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (attribute.trim(), ""),
+            };
+
+            match key.to_lowercase().as_str() {
+                "path" if value.starts_with('/') => cookie.path = Some(value.to_string()),
+                "domain" if !value.is_empty() => {
+                    cookie.domain = Some(value.trim_start_matches('.').to_lowercase())
+                }
+                "expires" if !value.is_empty() => cookie.expires = Some(value.to_string()),
+                "max-age" => {
+                    if let Ok(max_age) = value.parse::<i64>() {
+                        cookie.max_age = Some(max_age);
+                    }
+                }
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                "samesite" => {
+                    cookie.same_site = match value.to_lowercase().as_str() {
+                        "strict" => Some(SameSite::Strict),
+                        "lax" => Some(SameSite::Lax),
+                        "none" => Some(SameSite::None),
+                        _ => cookie.same_site,
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Some(cookie)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    pub fn domain(&self) -> Option<&str> {
+        self.domain.as_deref()
+    }
+
+    /// Raw `Expires` date; ex: `Wed, 21 Oct 2015 07:28:00 GMT`
+    pub fn expires(&self) -> Option<&str> {
+        self.expires.as_deref()
+    }
+
+    /// Seconds until the cookie expires
+    pub fn max_age(&self) -> Option<i64> {
+        self.max_age
+    }
+
+    pub fn secure(&self) -> bool {
+        self.secure
+    }
+
+    pub fn http_only(&self) -> bool {
+        self.http_only
+    }
+
+    pub fn same_site(&self) -> Option<SameSite> {
+        self.same_site
+    }
+
+    pub fn with_value<T: Into<String>>(mut self, value: T) -> Self {
+        self.value = value.into();
+        self
+    }
+
+    pub fn with_path<T: Into<String>>(mut self, path: T) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn with_domain<T: Into<String>>(mut self, domain: T) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    pub fn with_expires<T: Into<String>>(mut self, expires: T) -> Self {
+        self.expires = Some(expires.into());
+        self
+    }
+
+    pub fn with_max_age(mut self, seconds: i64) -> Self {
+        self.max_age = Some(seconds);
+        self
+    }
+
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    pub fn with_http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+
+    pub fn with_same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+}
+
+/// Formats the cookie as a `Set-Cookie` header value
+impl Display for Cookie {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(path) = &self.path {
+            write!(f, "; Path={}", path)?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={}", domain)?;
+        }
+        if let Some(expires) = &self.expires {
+            write!(f, "; Expires={}", expires)?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age)?;
+        }
+        if self.secure {
+            write!(f, "; Secure")?;
+        }
+        if self.http_only {
+            write!(f, "; HttpOnly")?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={}", same_site)?;
+        }
+        Ok(())
+    }
+}
+
+/// Cookies sent with the request along with the cookies that have been set or deleted.
+///
+/// Return the jar with the response, `(jar, response)`, to send the changes back as
+/// `Set-Cookie` headers.
+///
+/// # Example
+/// ```
+/// use tela::{
+///     cookie::{Cookie, CookieJar},
+///     prelude::*,
+/// };
+///
+/// #[get("/visit")]
+/// fn visit(mut jar: CookieJar) -> (CookieJar, String) {
+///     let visits = jar
+///         .get("visits")
+///         .and_then(|cookie| cookie.value().parse::<u32>().ok())
+///         .unwrap_or(0) + 1;
+///     jar.set(Cookie::new("visits", visits.to_string()).with_path("/"));
+///     (jar, format!("Visit number {}", visits))
+/// }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+    changes: Vec<Cookie>,
+}
+
+impl CookieJar {
+    /// Parse a `Cookie` request header; ex: `theme=dark; visits=2`.
+    ///
+    /// Malformed pairs are skipped and quoted values are unquoted.
+    pub fn new(header: &str) -> Self {
+        CookieJar {
+            cookies: header
+                .split(';')
+                .filter_map(pair)
+                .map(|(name, value)| Cookie::new(name, value))
+                .collect(),
+            changes: Vec::new(),
+        }
+    }
+
+    /// Parse every `Cookie` header in the request
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let header = headers
+            .get_all(COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect::<Vec<&str>>()
+            .join(";");
+        CookieJar::new(&header)
+    }
+
+    /// Cookie with the name. Cookies set in the jar are returned over the ones from the
+    /// request, and deleted cookies are not returned.
+    pub fn get(&self, name: &str) -> Option<&Cookie> {
+        match self.changes.iter().rev().find(|c| c.name == name) {
+            Some(cookie) if cookie.max_age.map(|age| age <= 0).unwrap_or(false) => None,
+            Some(cookie) => Some(cookie),
+            None => self.cookies.iter().find(|c| c.name == name),
+        }
+    }
+
+    /// Value of a cookie set with `set_signed`. Cookies with an invalid signature are ignored.
+    pub fn get_signed(&self, name: &str) -> Option<&str> {
+        self.get(name)
+            .and_then(|cookie| verify(name, cookie.value()))
+    }
+
+    /// Set a cookie whose value is signed so changes made by the client are detected
+    pub fn set_signed(&mut self, cookie: Cookie) {
+        let value = sign(&cookie.name, cookie.value());
+        self.set(cookie.with_value(value));
+    }
+
+    /// Set a cookie. It is sent with the response.
+    pub fn set(&mut self, cookie: Cookie) {
+        self.changes.retain(|c| c.name != cookie.name);
+        self.changes.push(cookie);
+    }
+
+    /// Tell the browser to remove the cookie.
+    ///
+    /// Browsers only remove a cookie if the `Path` and `Domain` match the ones it was set with.
+    /// If the cookie was set in this jar its attributes are reused, otherwise use `delete_with`
+    /// for cookies that were set with a path or domain.
+    pub fn delete(&mut self, name: &str) {
+        let (path, domain) = match self.changes.iter().find(|c| c.name == name) {
+            Some(cookie) => (cookie.path.clone(), cookie.domain.clone()),
+            None => (None, None),
+        };
+        self.delete_with(name, path.as_deref(), domain.as_deref());
+    }
+
+    /// Tell the browser to remove the cookie that was set with the path and domain
+    pub fn delete_with(&mut self, name: &str, path: Option<&str>, domain: Option<&str>) {
+        let mut cookie = Cookie::new(name, "")
+            .with_max_age(0)
+            .with_expires("Thu, 01 Jan 1970 00:00:00 GMT");
+        cookie.path = path.map(str::to_string);
+        cookie.domain = domain.map(str::to_string);
+        self.set(cookie);
+    }
+
+    /// Cookies sent with the request
+    pub fn iter(&self) -> impl Iterator<Item = &Cookie> {
+        self.cookies.iter()
+    }
+
+    /// Cookies that have been set or deleted
+    pub fn changes(&self) -> impl Iterator<Item = &Cookie> {
+        self.changes.iter()
+    }
+}
+
+impl ToParam<CookieJar> for RequestData {
+    fn to_param(&mut self) -> Result<CookieJar> {
+        Ok(CookieJar::from_headers(&self.3))
+    }
+}
+
+/// Send the jar's changes as `Set-Cookie` headers
+impl<T: ToResponse> ToResponse for (CookieJar, T) {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        let mut response = self.1.to_response(method, uri, headers, body)?;
+        for cookie in self.0.changes() {
+            let value = HeaderValue::from_str(&cookie.to_string())
+                .map_err(|_| (500, format!("Invalid cookie {:?}", cookie.name())))?;
+            response.headers_mut().append(SET_COOKIE, value);
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPIRES: &str = "Wed, 21 Oct 2026 07:28:00 GMT";
+
+    #[test]
+    fn set_cookie_pairs_are_parsed() {
+        let cases = [
+            ("theme=dark", "theme", "dark"),
+            ("theme=\"dark\"", "theme", "dark"),
+            (" theme = dark ", "theme", "dark"),
+            ("theme= \"dark\" ; Path=/", "theme", "dark"),
+            ("theme=", "theme", ""),
+            ("theme=\"\"", "theme", ""),
+            ("theme=\"", "theme", "\""),
+            ("token=a=b", "token", "a=b"),
+        ];
+        for (cookie, name, value) in cases {
+            let parsed = Cookie::parse(cookie).unwrap();
+            assert_eq!((parsed.name(), parsed.value()), (name, value), "{}", cookie);
+        }
+
+        for cookie in ["", "theme", "=dark", " =dark; Path=/", ";theme=dark"] {
+            assert_eq!(Cookie::parse(cookie), None, "{}", cookie);
+        }
+    }
+
+    #[test]
+    fn attributes_are_read_in_any_case() {
+        let cases = [
+            format!("id=1; Path=/app; Max-Age=60; Expires={EXPIRES}; SameSite=Lax; Secure; HttpOnly"),
+            format!("id=1; path=/app; max-age=60; expires={EXPIRES}; samesite=lax; secure; httponly"),
+            format!("id=1;PATH=/app;MAX-AGE=60;EXPIRES={EXPIRES};SAMESITE=LAX;SECURE;HTTPONLY"),
+            format!("id=1; pAtH = /app ;; Max-Age=60; ; Expires={EXPIRES}; sameSite=lAx; Secure=; HttpOnly;"),
+        ];
+        for cookie in &cases {
+            let parsed = Cookie::parse(cookie).unwrap();
+            assert_eq!(parsed.value(), "1", "{}", cookie);
+            assert_eq!(parsed.path(), Some("/app"), "{}", cookie);
+            assert_eq!(parsed.max_age(), Some(60), "{}", cookie);
+            assert_eq!(parsed.expires(), Some(EXPIRES), "{}", cookie);
+            assert_eq!(parsed.same_site(), Some(SameSite::Lax), "{}", cookie);
+            assert!(parsed.secure(), "{}", cookie);
+            assert!(parsed.http_only(), "{}", cookie);
+        }
+    }
+
+    #[test]
+    fn invalid_attributes_are_ignored() {
+        let cases = [
+            "id=1; Path=app",
+            "id=1; Path",
+            "id=1; Max-Age=soon",
+            "id=1; Max-Age=",
+            "id=1; Expires=",
+            "id=1; Domain=",
+            "id=1; SameSite=sometimes",
+            "id=1; Priority=High; Partitioned",
+            "id=1; =; ;",
+        ];
+        for cookie in cases {
+            assert_eq!(
+                Cookie::parse(cookie),
+                Some(Cookie::new("id", "1")),
+                "{}",
+                cookie
+            );
+        }
+
+        let cookie = Cookie::parse(
+            "id=1; Max-Age=60; Max-Age=120; SameSite=Strict; SameSite=bogus; Domain=.Example.com",
+        )
+        .unwrap();
+        assert_eq!(cookie.max_age(), Some(120));
+        assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+        assert_eq!(cookie.domain(), Some("example.com"));
+    }
+
+    #[test]
+    fn request_cookies_are_parsed() {
+        let cases = [
+            (
+                "theme=dark; visits=2",
+                vec![("theme", "dark"), ("visits", "2")],
+            ),
+            (
+                "theme=\"dark\";visits=\"2\"",
+                vec![("theme", "dark"), ("visits", "2")],
+            ),
+            (
+                ";theme=dark;; visits=2;",
+                vec![("theme", "dark"), ("visits", "2")],
+            ),
+            ("theme; visits=2", vec![("visits", "2")]),
+            ("theme=; =2; visits", vec![("theme", "")]),
+            (
+                "theme=dark; theme=light",
+                vec![("theme", "dark"), ("theme", "light")],
+            ),
+            ("", vec![]),
+            (" ; ; ", vec![]),
+        ];
+        for (header, cookies) in cases {
+            let jar = CookieJar::new(header);
+            let found: Vec<(&str, &str)> = jar.iter().map(|c| (c.name(), c.value())).collect();
+            assert_eq!(found, cookies, "{}", header);
+        }
+    }
+
+    #[test]
+    fn repeated_names_return_the_first() {
+        let jar = CookieJar::new("theme=dark; theme=light");
+        assert_eq!(jar.get("theme").map(Cookie::value), Some("dark"));
+
+        let mut headers = HeaderMap::new();
+        headers.append(COOKIE, HeaderValue::from_static("theme=dark"));
+        headers.append(COOKIE, HeaderValue::from_static("visits=2; theme=light"));
+        let jar = CookieJar::from_headers(&headers);
+        assert_eq!(jar.get("theme").map(Cookie::value), Some("dark"));
+        assert_eq!(jar.get("visits").map(Cookie::value), Some("2"));
+    }
+
+    #[test]
+    fn random_keys_differ() {
+        let (a, b) = (random_key(), random_key());
+        assert_eq!(a.len(), 32);
+        assert_ne!(a, b);
+        assert!(a.iter().any(|&byte| byte != 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Signing requires the secret")]
+    fn signing_outside_a_request_panics() {
+        sign("_theme", "dark");
+    }
+
+    #[test]
+    fn signed_values_verify() {
+        with_secret(|| {
+            for value in ["", "dark", "a.b.c", "eyJpZCI6MX0"] {
+                let signed = sign("_theme", value);
+                assert_eq!(verify("_theme", &signed), Some(value));
+            }
+        })
+    }
+
+    #[test]
+    fn changed_values_dont_verify() {
+        with_secret(|| {
+            let signed = sign("_theme", "dark");
+            let (_, signature) = signed.rsplit_once('.').unwrap();
+            assert_eq!(verify("_theme", &format!("light.{}", signature)), None);
+            assert_eq!(verify("_theme", "dark"), None);
+            assert_eq!(verify("_theme", "dark."), None);
+            assert_eq!(verify("_theme", "dark.not-base64!"), None);
+
+            let mut tampered = signed.into_bytes();
+            let last = tampered.len() - 1;
+            tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+            assert_eq!(
+                verify("_theme", &String::from_utf8(tampered).unwrap()),
+                None
+            );
+        })
+    }
+
+    #[test]
+    fn signatures_are_bound_to_the_name() {
+        with_secret(|| {
+            let flash = sign("_flash", "admin");
+            assert_eq!(verify("_flash", &flash), Some("admin"));
+            assert_eq!(verify("_auth", &flash), None);
+            assert_eq!(verify("_session", &flash), None);
+
+            let jar = CookieJar::new(&format!("_auth={}; _flash={}", flash, flash));
+            assert_eq!(jar.get_signed("_flash"), Some("admin"));
+            assert_eq!(jar.get_signed("_auth"), None);
+        })
+    }
+
+    #[test]
+    fn jar_signs_with_the_cookie_name() {
+        with_secret(|| {
+            let mut jar = CookieJar::new("");
+            jar.set_signed(Cookie::new("_auth", "user"));
+            let value = jar.get("_auth").unwrap().value().to_string();
+            assert_eq!(verify("_auth", &value), Some("user"));
+            assert_eq!(verify("_flash", &value), None);
+        })
+    }
+}
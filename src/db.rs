@@ -0,0 +1,171 @@
+use std::ops::{Deref, DerefMut};
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{HeaderMap, Method, Uri};
+use sqlx::{pool::PoolConnection, Database, Pool, Transaction};
+
+use crate::{
+    request::{RequestData, ToParam},
+    response::{Result, ToResponse},
+    state,
+    sync::block_on,
+};
+
+fn pool<DB: Database>() -> Result<Pool<DB>> {
+    state::get::<Pool<DB>>()
+        .map(|pool| (*pool).clone())
+        .ok_or_else(|| {
+            (
+                500,
+                "Database pool is not managed by the server; add it with `Server::manage`"
+                    .to_string(),
+            )
+        })
+}
+
+fn unavailable(error: sqlx::Error) -> (u16, String) {
+    match error {
+        sqlx::Error::PoolTimedOut => (
+            503,
+            "Timed out waiting for a database connection".to_string(),
+        ),
+        error => (
+            503,
+            format!("Failed to get a database connection: {}", error),
+        ),
+    }
+}
+
+/// Connection checked out of the managed `sqlx::Pool` for the request. The connection is
+/// returned to the pool when it is dropped.
+///
+/// Waiting for a connection times out after the pool's `acquire_timeout` and responds with
+/// a 503. Queries are async so run them with `tela::sync::block_on`.
+///
+/// # Example
+/// Needs sqlx's `postgres` feature and a database.
+/// ```ignore
+/// use std::time::Duration;
+/// use sqlx::{postgres::PgPoolOptions, Postgres};
+/// use tela::{db::Db, prelude::*, sync::block_on, Server};
+///
+/// #[get("/users/:id")]
+/// fn user(id: i64, mut db: Db<Postgres>) -> Result<String> {
+///     let (name,): (String,) = block_on(
+///         sqlx::query_as("SELECT name FROM users WHERE id = $1")
+///             .bind(id)
+///             .fetch_one(&mut **db),
+///     )
+///     .map_err(|_| (404, "User not found".to_string()))?;
+///     response!(name)
+/// }
+///
+/// #[tela::main]
+/// async fn main() {
+///     let url = std::env::var("DATABASE_URL")?;
+///     Server::new()
+///         .manage(PgPoolOptions::new().acquire_timeout(Duration::from_secs(3)).connect_lazy(&url)?)
+///         .route(user)
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+pub struct Db<DB: Database>(pub PoolConnection<DB>);
+
+impl<DB: Database> Deref for Db<DB> {
+    type Target = PoolConnection<DB>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<DB: Database> DerefMut for Db<DB> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<DB: Database> ToParam<Db<DB>> for RequestData {
+    fn to_param(&mut self) -> Result<Db<DB>> {
+        let pool = pool::<DB>()?;
+        block_on(pool.acquire()).map(Db).map_err(unavailable)
+    }
+}
+
+/// Transaction started for the request. Return it with the response, `(tx, response)`, to
+/// commit it if the response is a success, `2xx`, and roll it back otherwise. A transaction
+/// that isn't returned is rolled back when it is dropped.
+///
+/// # Example
+/// Needs sqlx's `postgres` feature and a database.
+/// ```ignore
+/// use serde::Deserialize;
+/// use sqlx::Postgres;
+/// use tela::{db::Tx, prelude::*, request::Body, sync::block_on};
+///
+/// #[derive(Deserialize)]
+/// struct Transfer {
+///     amount: i64,
+///     from: i64,
+///     to: i64,
+/// }
+///
+/// #[post("/transfer")]
+/// fn transfer(mut tx: Tx<Postgres>, Body(t): Body<Transfer>) -> (Tx<Postgres>, Result<String>) {
+///     let result = block_on(async {
+///         sqlx::query("UPDATE accounts SET balance = balance - $1 WHERE id = $2")
+///             .bind(t.amount).bind(t.from).execute(&mut **tx).await?;
+///         sqlx::query("UPDATE accounts SET balance = balance + $1 WHERE id = $2")
+///             .bind(t.amount).bind(t.to).execute(&mut **tx).await
+///     });
+///     let response = result
+///         .map(|_| "Transferred".to_string())
+///         .map_err(|error| (400, error.to_string()));
+///     (tx, response)
+/// }
+/// ```
+pub struct Tx<DB: Database>(pub Transaction<'static, DB>);
+
+impl<DB: Database> Deref for Tx<DB> {
+    type Target = Transaction<'static, DB>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<DB: Database> DerefMut for Tx<DB> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<DB: Database> ToParam<Tx<DB>> for RequestData {
+    fn to_param(&mut self) -> Result<Tx<DB>> {
+        let pool = pool::<DB>()?;
+        block_on(pool.begin()).map(Tx).map_err(unavailable)
+    }
+}
+
+/// Commit the transaction if the response is a success and roll it back otherwise
+impl<DB: Database, T: ToResponse> ToResponse for (Tx<DB>, T) {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        let (Tx(transaction), response) = self;
+        let response = response.to_response(method, uri, headers, body);
+        match &response {
+            Ok(result) if result.status().is_success() => block_on(transaction.commit())
+                .map_err(|error| (500, format!("Failed to commit transaction: {}", error)))?,
+            _ => block_on(transaction.rollback())
+                .map_err(|error| (500, format!("Failed to roll back transaction: {}", error)))?,
+        }
+        response
+    }
+}
@@ -5,6 +5,9 @@ use http_body_util::Full;
 use hyper::{Method, Uri};
 use phf::phf_map;
 
+#[cfg(debug_assertions)]
+use crate::response::escape;
+
 /// Default http error messages
 pub static MESSAGES: phf::Map<u16, &'static str> = phf_map! {
     100u16 => "Continue",
@@ -76,26 +79,129 @@ pub static MESSAGES: phf::Map<u16, &'static str> = phf_map! {
     511u16 => "Network Authentication Required",
 };
 
-pub fn default_error_page(
-    code: &u16,
-    reason: &String,
-    method: &Method,
-    uri: &Uri,
-    body: String,
-) -> hyper::Response<Full<Bytes>> {
+/// Information about an error that is being rendered into a response.
+///
+/// The request body and backtrace are only available in debug builds so a renderer can't
+/// leak them in production.
+pub struct ErrorContext<'a> {
+    pub code: u16,
+    pub reason: &'a str,
+    pub method: &'a Method,
+    pub uri: &'a Uri,
+    body: &'a str,
+}
+
+impl<'a> ErrorContext<'a> {
+    pub fn new(
+        code: u16,
+        reason: &'a str,
+        method: &'a Method,
+        uri: &'a Uri,
+        body: &'a str,
+    ) -> Self {
+        ErrorContext {
+            code,
+            reason,
+            method,
+            uri,
+            body,
+        }
+    }
+
+    /// Default message for the status code; ex: `Not Found`
+    pub fn message(&self) -> String {
+        StatusCode::from(self.code).message()
+    }
+
+    /// Request body. Always `None` in release builds.
+    pub fn body(&self) -> Option<&str> {
+        match cfg!(debug_assertions) {
+            true => Some(self.body),
+            false => None,
+        }
+    }
+
+    /// Backtrace of where the error was rendered. Always `None` in release builds.
+    pub fn backtrace(&self) -> Option<String> {
+        match cfg!(debug_assertions) {
+            true => Some(Backtrace::force_capture().to_string()),
+            false => None,
+        }
+    }
+}
+
+/// Renders responses for errors that reach the router; ex: missing routes, errors returned
+/// from endpoints without a matching catch, or failing catches.
+///
+/// `HtmlErrorRenderer` is used by default. `JsonErrorRenderer` is a better fit for api servers.
+///
+/// # Example
+/// ```no_run
+/// use tela::{JsonErrorRenderer, Server};
+///
+/// #[tela::main]
+/// async fn main() {
+///     Server::new()
+///         .errors(JsonErrorRenderer)
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+pub trait ErrorRenderer: Send + Sync {
+    fn render(&self, error: &ErrorContext) -> hyper::Response<Full<Bytes>>;
+}
+
+/// Renders errors as json; `{"status": 404, "message": "Not Found", "reason": "..."}`.
+///
+/// In debug builds the method, path, and request body are included as well.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JsonErrorRenderer;
+
+impl ErrorRenderer for JsonErrorRenderer {
+    fn render(&self, error: &ErrorContext) -> hyper::Response<Full<Bytes>> {
+        let mut body = serde_json::json!({
+            "status": error.code,
+            "message": error.message(),
+            "reason": error.reason,
+        });
+        if let Some(request_body) = error.body() {
+            body["method"] = error.method.to_string().into();
+            body["path"] = error.uri.path().into();
+            body["body"] = request_body.into();
+        }
+
+        hyper::Response::builder()
+            .status(error.code)
+            .header("Tela-Reason", error.reason)
+            .header("Content-Type", "application/json")
+            .body(Full::new(Bytes::from(body.to_string())))
+            .unwrap()
+    }
+}
+
+/// Renders errors as html. In debug builds this is a page with the request and backtrace,
+/// in release builds the response has no body.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HtmlErrorRenderer;
+
+impl ErrorRenderer for HtmlErrorRenderer {
+    fn render(&self, error: &ErrorContext) -> hyper::Response<Full<Bytes>> {
+        default_error_page(error)
+    }
+}
+
+fn default_error_page(error: &ErrorContext) -> hyper::Response<Full<Bytes>> {
+    let code = &error.code;
+    let reason = error.reason;
     #[cfg(debug_assertions)]
     let styles = r#"
 *{box-sizing:border-box}body{padding:.5rem;margin:0;min-height:100vh;min-height:100dvh;display:flex;justify-content:center;align-items:center}#overlay{color:#000;border:1px solid #9e9e9e;background:#b8b6b6;display:flex;flex-direction:column;width:97%;min-height:95vh;min-height:95dvh;height:95%;border-radius:.5rem;box-shadow:rgba(0,0,0,0.25) 0 54px 55px,rgba(0,0,0,0.12) 0 -12px 30px,rgba(0,0,0,0.12) 0 4px 6px,rgba(0,0,0,0.17) 0 12px 13px,rgba(0,0,0,0.09) 0 -3px 5px}h1{font-size:2.65rem;text-align:center;margin:.5rem}h2{font-size:2.441rem}h3{font-size:1.953rem}h4{font-size:1.563rem}h5{font-size:1.25rem}small,.text_small{font-size:.8rem}details summary{cursor:pointer}hr{border:1px solid rgba(0,0,0,0.5)}details summary>*{display:inline}summary{background-color:rgba(200,15,50,0.5);padding-block:.25rem;padding-inline:.5rem;font-weight:700}summary::marker{color:rgba(200,15,50,0.50)}details{border:1px solid rgba(200,15,50,0.75);border-radius:.25rem;display:flex;gap:.5rem;width:85%;margin-inline:auto;margin-block:1rem;font-family:Arial,sans-serif;font-size:1.1rem}details>#body{background-color:rgba(200,15,50,0.25);padding:1rem;display:flex;flex-direction:column;gap:.5rem}.path{background-color:rgba(0,0,0,.5);padding:.2rem .35rem;border-radius:.2rem}details>#body>div{width:80%;color:#fff;max-width:95ch;margin-inline:auto;border:1px solid rgba(0,0,0,.5);background-color:rgba(0,0,0,.25);display:flex;flex-wrap:wrap}details>#body>div>span:first-child{display:inline-block;background:#000;padding:.5rem;width:40%;display:flex;align-items:center;justify-content:center}details>#body>div>span:last-child{display:inline-block;text-align:center;padding:.5rem;width:60%;max-height:6rem;overflow:auto}details>#body>div>div:first-child{display:inline-block;text-align:center;background:#000;padding:.5rem;width:100%;max-height:15rem;overflow-y:auto}details>#body>div>pre{padding:1rem;width:100%;overflow:auto;max-height:20rem}table{color:#fff;width:100%;border:1px solid #000;border-collapse:collapse}thead{background:#000}tbody{padding:.5rem;background-color:rgba(0,0,0,.25)}td{padding-block:.5rem;text-align:center}#trace{border:1px solid rgba(200,15,50,0.75);box-sizing:border-box;border-radius:.25rem;height:100%;max-height:27rem;width:85%;margin-inline:auto;overflow:auto;background-color:rgba(200,15,50,0.25)}@media(prefers-color-scheme: dark){#overlay{background:#1c1c1c;border:1px solid #171717;color:#fff}details>#body>div>div:last-child{color:#fff}html{background:#333}}
     "#;
 
     #[cfg(debug_assertions)]
-    std::env::set_var("RUST_BACKTRACE", "1");
-    let bcktrc: String = Backtrace::capture()
-        .to_string()
-        .replace("<", "&lt;")
-        .replace(">", "&gt;");
+    let bcktrc = escape(error.backtrace().unwrap_or_default());
     #[cfg(debug_assertions)]
-    std::env::set_var("RUST_BACKTRACE", "0");
+    let (method, uri, body) = (error.method, error.uri, escape(error.body().unwrap_or("")));
 
     #[cfg(debug_assertions)]
     return hyper::Response::builder()
@@ -122,11 +228,11 @@ pub fn default_error_page(
                         <h4>"Unhandled Error:"</h4>
                     </summary>
                     <div id="body">
-                        <strong>{reason}</strong>
+                        <strong>{escape(reason)}</strong>
                         <div><span>"Method"</span><span>{method}</span></div>
                         <div><span>"Status"</span><span>{code}</span></div>
-                        <div><span>"URI"</span><span><span class="path">{uri.path()}</span></span></div>
-                        <div><span>"Query"</span><span>{uri.query().unwrap_or("")}</span></div>
+                        <div><span>"URI"</span><span><span class="path">{escape(uri.path())}</span></span></div>
+                        <div><span>"Query"</span><span>{escape(uri.query().unwrap_or(""))}</span></div>
                         <div>
                             <div>"Body"</div>
                             <div>{body}</div>
@@ -153,6 +259,26 @@ pub fn default_error_page(
         .unwrap();
 }
 
+/// Error passed to catches that take it as an argument
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, Error};
+///
+/// #[catch(500, 503)]
+/// fn unavailable(error: Error) -> String {
+///     format!("{} {}: {}", error.code, error.message, error.reason)
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub code: u16,
+    /// Default message for the code; ex: `Not Found`
+    pub message: String,
+    /// Why the error happened; ex: `Page not found in router`
+    pub reason: String,
+}
+
 #[derive(Clone, Copy)]
 pub enum StatusCode {
     Unkown = 0,
@@ -0,0 +1,153 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{HeaderMap, Method, Uri};
+
+use crate::{
+    cookie::{Cookie, CookieJar, SameSite},
+    request::{RequestData, ToParam},
+    response::{escape, Result, ToResponse},
+};
+
+/// Name of the signed cookie flash messages are stored in
+pub const FLASH_COOKIE: &str = "_flash";
+
+/// One-shot messages that survive a redirect; ex: "Saved!" after submitting a form.
+///
+/// Messages are stored in a signed cookie. Reading them with the `Flash` parameter and
+/// returning the flash with the response, `(flash, response)`, removes them so they are only
+/// shown once. Messages added to the flash are sent with the response.
+///
+/// # Example
+/// ```
+/// use tela::{
+///     flash::Flash,
+///     prelude::*,
+///     response::{Redirect, HTML},
+/// };
+///
+/// #[post("/settings")]
+/// fn save() -> (Flash, Redirect) {
+///     (Flash::new().success("Saved!"), Redirect::to("/settings"))
+/// }
+///
+/// #[get("/settings")]
+/// fn settings(flash: Flash) -> (Flash, HTML<String>) {
+///     let page = html! {
+///         <div>{flash.render()}</div>
+///         <form method="post"><button>"Save"</button></form>
+///     };
+///     (flash, page)
+/// }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Flash {
+    /// Messages from the request
+    received: Vec<(String, String)>,
+    /// Messages to send with the response
+    pending: Vec<(String, String)>,
+}
+
+impl Flash {
+    pub fn new() -> Self {
+        Flash::default()
+    }
+
+    /// Add a message with a level; ex: `success`, `info`, `warning`, or `error`
+    pub fn message<L: Into<String>, M: Into<String>>(mut self, level: L, message: M) -> Self {
+        self.pending.push((level.into(), message.into()));
+        self
+    }
+
+    pub fn success<M: Into<String>>(self, message: M) -> Self {
+        self.message("success", message)
+    }
+
+    pub fn info<M: Into<String>>(self, message: M) -> Self {
+        self.message("info", message)
+    }
+
+    pub fn warning<M: Into<String>>(self, message: M) -> Self {
+        self.message("warning", message)
+    }
+
+    pub fn error<M: Into<String>>(self, message: M) -> Self {
+        self.message("error", message)
+    }
+
+    /// Messages sent with the request as `(level, message)`
+    pub fn messages(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.received
+            .iter()
+            .map(|(level, message)| (level.as_str(), message.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.received.is_empty()
+    }
+
+    /// Render the messages from the request as a list; ex:
+    /// `<ul class="flash"><li class="flash-success">Saved!</li></ul>`. Renders nothing if
+    /// there aren't any messages.
+    pub fn render(&self) -> String {
+        if self.received.is_empty() {
+            return String::new();
+        }
+        format!(
+            r#"<ul class="flash">{}</ul>"#,
+            self.messages()
+                .map(|(level, message)| format!(
+                    r#"<li class="flash-{}">{}</li>"#,
+                    escape(level),
+                    escape(message)
+                ))
+                .collect::<String>()
+        )
+    }
+
+    fn from_jar(jar: &CookieJar) -> Self {
+        let received = jar
+            .get_signed(FLASH_COOKIE)
+            .and_then(|value| URL_SAFE_NO_PAD.decode(value).ok())
+            .and_then(|value| serde_json::from_slice::<Vec<(String, String)>>(&value).ok())
+            .unwrap_or_default();
+        Flash {
+            received,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl ToParam<Flash> for RequestData {
+    fn to_param(&mut self) -> Result<Flash> {
+        Ok(Flash::from_jar(&CookieJar::from_headers(&self.3)))
+    }
+}
+
+/// Store the new messages in the flash cookie, or remove the cookie if the messages from
+/// the request were read and there aren't any new ones
+impl<T: ToResponse> ToResponse for (Flash, T) {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        let (flash, response) = self;
+        let mut jar = CookieJar::default();
+        if !flash.pending.is_empty() {
+            let messages = serde_json::to_vec(&flash.pending)
+                .map_err(|error| (500, format!("Failed to store flash messages: {}", error)))?;
+            jar.set_signed(
+                Cookie::new(FLASH_COOKIE, URL_SAFE_NO_PAD.encode(messages))
+                    .with_path("/")
+                    .with_http_only(true)
+                    .with_same_site(SameSite::Lax),
+            );
+        } else if !flash.received.is_empty() {
+            jar.delete_with(FLASH_COOKIE, Some("/"), None);
+        }
+        (jar, response).to_response(method, uri, headers, body)
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::future::BoxFuture;
+use hyper::{body::Body, HeaderMap};
+
+use super::{Layer, Next, Request, Response};
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Format of the access log lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `host ident user [time] "request" status bytes`
+    Common,
+    /// Common format followed by `"referer" "user-agent"`
+    Combined,
+}
+
+/// Current time in the log format; ex: `10/Oct/2000:13:55:36 +0000`
+fn timestamp() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, time) = (seconds / 86400, seconds % 86400);
+
+    // Days since the epoch to a civil date; http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{:02}/{}/{}:{:02}:{:02}:{:02} +0000",
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// Escape a value so it can be placed inside of quotes in a log line
+fn quote(value: &str) -> String {
+    let mut escaped = String::new();
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\x{:02x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn header(headers: &HeaderMap, name: &str) -> String {
+    headers
+        .get(name)
+        .map(|value| quote(&String::from_utf8_lossy(value.as_bytes())))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// User name from basic authentication
+fn user(headers: &HeaderMap) -> Option<String> {
+    let credentials = headers
+        .get("Authorization")?
+        .to_str()
+        .ok()?
+        .strip_prefix("Basic ")?;
+    let credentials = String::from_utf8(STANDARD.decode(credentials.trim()).ok()?).ok()?;
+    let (user, _) = credentials.split_once(':')?;
+    match user.is_empty() {
+        true => None,
+        false => Some(quote(user).replace(' ', "%20")),
+    }
+}
+
+/// Log file that is moved aside once it reaches a max size; ex: `access.log` ->
+/// `access.log.1` -> `access.log.2`
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    keep: usize,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_size: u64, keep: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            file,
+            size,
+            max_size,
+            keep,
+        })
+    }
+
+    fn numbered(&self, n: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", n));
+        PathBuf::from(path)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for n in (1..self.keep).rev() {
+                let from = self.numbered(n);
+                if from.exists() {
+                    fs::rename(from, self.numbered(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.numbered(1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Layer that writes a line for every request in the Common or Combined Log Format so the
+/// logs can be read by standard log processing tools.
+///
+/// # Example
+/// ```no_run
+/// use tela::{layer::AccessLog, Server};
+///
+/// #[tela::main]
+/// async fn main() {
+///     Server::new()
+///         // 10 MiB files, keeping the last 5
+///         .layer(AccessLog::rotating("logs/access.log", 10 * 1024 * 1024, 5)?.combined())
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+pub struct AccessLog {
+    format: LogFormat,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl AccessLog {
+    /// Write Common Log Format lines to the writer
+    pub fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        AccessLog {
+            format: LogFormat::Common,
+            writer: Mutex::new(Box::new(writer)),
+        }
+    }
+
+    /// Write to stdout
+    pub fn stdout() -> Self {
+        AccessLog::new(io::stdout())
+    }
+
+    /// Append to a file, creating it if it doesn't exist
+    pub fn file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(AccessLog::new(
+            OpenOptions::new().create(true).append(true).open(path)?,
+        ))
+    }
+
+    /// Append to a file that is rotated once it reaches `max_size` bytes. The last `keep`
+    /// rotated files are kept as `path.1` (newest) through `path.{keep}`.
+    pub fn rotating<P: AsRef<Path>>(path: P, max_size: u64, keep: usize) -> io::Result<Self> {
+        Ok(AccessLog::new(RotatingFile::open(
+            path.as_ref().to_path_buf(),
+            max_size,
+            keep,
+        )?))
+    }
+
+    pub fn format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Use the Combined Log Format
+    pub fn combined(self) -> Self {
+        self.format(LogFormat::Combined)
+    }
+
+    fn line(&self, request: &Request, response: &Response) -> String {
+        let host = request
+            .remote
+            .map(|remote| remote.ip().to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let target = request
+            .uri
+            .path_and_query()
+            .map(|p| p.as_str())
+            .unwrap_or("/");
+        let bytes = match response.body().size_hint().exact() {
+            Some(0) | None => "-".to_string(),
+            Some(bytes) => bytes.to_string(),
+        };
+
+        let mut line = format!(
+            "{} - {} [{}] \"{} {} HTTP/1.1\" {} {}",
+            host,
+            user(&request.headers).unwrap_or_else(|| "-".to_string()),
+            timestamp(),
+            request.method,
+            quote(target),
+            response.status().as_u16(),
+            bytes
+        );
+        if self.format == LogFormat::Combined {
+            line.push_str(&format!(
+                " \"{}\" \"{}\"",
+                header(&request.headers, "Referer"),
+                header(&request.headers, "User-Agent")
+            ));
+        }
+        line.push('\n');
+        line
+    }
+}
+
+impl Layer for AccessLog {
+    fn call<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            // The body is reference counted so the clone is cheap
+            let logged = request.clone();
+            let response = next.run(request).await;
+
+            let line = self.line(&logged, &response);
+            if let Ok(mut writer) = self.writer.lock() {
+                if let Err(error) = writer
+                    .write_all(line.as_bytes())
+                    .and_then(|_| writer.flush())
+                {
+                    eprintln!("Failed to write access log: {}", error);
+                }
+            }
+            response
+        })
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use http_body_util::Full;
+use hyper::HeaderMap;
+
+use super::{Layer, Next, Request, Response};
+use crate::auth::constant_eq;
+
+/// Path segments after decoding and dropping empty and `.` segments so paths match the same
+/// way assets resolve; ex: `//docs/./%61pi` -> `["docs", "api"]`
+pub(super) fn segments(path: &str) -> Vec<String> {
+    crate::uri::decode(path)
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .map(|segment| segment.to_string())
+        .collect()
+}
+
+/// User name and password from the `Authorization` header
+fn credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let encoded = headers
+        .get("Authorization")?
+        .to_str()
+        .ok()?
+        .strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?;
+    let (user, password) = decoded.split_once(':')?;
+    Some((user.to_string(), password.to_string()))
+}
+
+/// Layer that requires HTTP basic authentication for the protected paths. Requests without
+/// valid credentials get a `401 Unauthorized` that asks the browser to log in.
+///
+/// Every path is protected unless paths are given with `protect`.
+///
+/// # Example
+/// ```no_run
+/// use tela::{layer::basic_auth, Server};
+///
+/// #[tela::main]
+/// async fn main() {
+///     Server::new()
+///         .assets("public/")
+///         .layer(basic_auth([("admin", "secret")]).protect("/docs"))
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+pub struct BasicAuth {
+    realm: String,
+    users: HashMap<String, String>,
+    paths: Vec<Vec<String>>,
+}
+
+/// Require basic authentication with one of the user name and password pairs
+pub fn basic_auth<U, P, I>(users: I) -> BasicAuth
+where
+    U: Into<String>,
+    P: Into<String>,
+    I: IntoIterator<Item = (U, P)>,
+{
+    let mut auth = BasicAuth::new("Restricted");
+    for (user, password) in users {
+        auth = auth.user(user, password);
+    }
+    auth
+}
+
+impl BasicAuth {
+    /// Realm shown by the browser when asking for credentials
+    pub fn new<R: Into<String>>(realm: R) -> Self {
+        BasicAuth {
+            realm: realm.into(),
+            users: HashMap::new(),
+            paths: Vec::new(),
+        }
+    }
+
+    pub fn user<U: Into<String>, P: Into<String>>(mut self, user: U, password: P) -> Self {
+        self.users.insert(user.into(), password.into());
+        self
+    }
+
+    pub fn realm<R: Into<String>>(mut self, realm: R) -> Self {
+        self.realm = realm.into();
+        self
+    }
+
+    /// Only require authentication for the path and everything under it; ex: `/docs` protects
+    /// `/docs` and `/docs/index.html` but not `/documents`
+    pub fn protect<P: AsRef<str>>(mut self, path: P) -> Self {
+        self.paths.push(segments(path.as_ref()));
+        self
+    }
+
+    fn is_protected(&self, path: &str) -> bool {
+        if self.paths.is_empty() {
+            return true;
+        }
+        let path = segments(path);
+        self.paths
+            .iter()
+            .any(|protected| path.starts_with(protected))
+    }
+
+    fn is_authorized(&self, headers: &HeaderMap) -> bool {
+        let Some((user, password)) = credentials(headers) else {
+            return false;
+        };
+        // Every user is compared without stopping early so the time taken doesn't reveal
+        // which names exist
+        self.users.iter().fold(false, |found, (name, expected)| {
+            found
+                | (constant_eq(name.as_bytes(), user.as_bytes())
+                    & constant_eq(expected.as_bytes(), password.as_bytes()))
+        })
+    }
+}
+
+impl Layer for BasicAuth {
+    fn call<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            if !self.is_protected(request.uri.path()) || self.is_authorized(&request.headers) {
+                return next.run(request).await;
+            }
+            hyper::Response::builder()
+                .status(401)
+                .header(
+                    "WWW-Authenticate",
+                    format!(
+                        "Basic realm=\"{}\", charset=\"UTF-8\"",
+                        self.realm.replace('"', "'")
+                    ),
+                )
+                .header("Content-Type", "text/plain")
+                .body(Full::new(Bytes::from("Unauthorized")))
+                .unwrap()
+        })
+    }
+}
+
+/// Layer that requires a user logged in with `session::AuthSession` for the protected paths.
+///
+/// Pages redirect to the login url with the requested path in the `next` query so the
+/// login can send the user back; ex: `/login?next=%2Faccount`. Api requests, paths added
+/// with `api` or requests that don't accept html, get a `401 Unauthorized` instead.
+///
+/// Every path except the login url is protected unless paths are given with `protect`.
+///
+/// # Example
+/// ```no_run
+/// use tela::{layer::RequireAuth, prelude::*, session::CurrentUser, Server};
+///
+/// #[get("/login")]
+/// fn login() -> &'static str {
+///     "Log in"
+/// }
+///
+/// #[get("/account")]
+/// fn account(CurrentUser(name): CurrentUser<String>) -> String {
+///     format!("Logged in as {}", name)
+/// }
+///
+/// #[tela::main]
+/// async fn main() {
+///     Server::new()
+///         .route(login)
+///         .route(account)
+///         .layer(RequireAuth::new("/login").protect("/account").protect("/api").api("/api"))
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+pub struct RequireAuth {
+    login: String,
+    paths: Vec<Vec<String>>,
+    api: Vec<Vec<String>>,
+}
+
+impl RequireAuth {
+    /// Url pages redirect to when the user isn't logged in
+    pub fn new<L: Into<String>>(login: L) -> Self {
+        RequireAuth {
+            login: login.into(),
+            paths: Vec::new(),
+            api: Vec::new(),
+        }
+    }
+
+    /// Only require a login for the path and everything under it
+    pub fn protect<P: AsRef<str>>(mut self, path: P) -> Self {
+        self.paths.push(segments(path.as_ref()));
+        self
+    }
+
+    /// Respond with a `401 Unauthorized` instead of redirecting for the path and everything
+    /// under it
+    pub fn api<P: AsRef<str>>(mut self, path: P) -> Self {
+        self.api.push(segments(path.as_ref()));
+        self
+    }
+
+    fn is_protected(&self, path: &[String]) -> bool {
+        let login = segments(self.login.split('?').next().unwrap_or(""));
+        if path == login.as_slice() {
+            return false;
+        }
+        self.paths.is_empty()
+            || self
+                .paths
+                .iter()
+                .any(|protected| path.starts_with(protected))
+    }
+
+    fn is_api(&self, path: &[String], headers: &HeaderMap) -> bool {
+        let html = headers
+            .get("Accept")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.contains("text/html"))
+            .unwrap_or(false);
+        !html || self.api.iter().any(|api| path.starts_with(api))
+    }
+}
+
+impl Layer for RequireAuth {
+    fn call<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            let path = segments(request.uri.path());
+            if !self.is_protected(&path) || crate::session::is_authenticated(&request.headers).await
+            {
+                return next.run(request).await;
+            }
+
+            if self.is_api(&path, &request.headers) {
+                return hyper::Response::builder()
+                    .status(401)
+                    .header("Content-Type", "text/plain")
+                    .body(Full::new(Bytes::from("Unauthorized")))
+                    .unwrap();
+            }
+
+            let requested = request
+                .uri
+                .path_and_query()
+                .map(|path| path.as_str())
+                .unwrap_or("/");
+            let separator = match self.login.contains('?') {
+                true => '&',
+                false => '?',
+            };
+            hyper::Response::builder()
+                .status(303)
+                .header(
+                    "Location",
+                    format!(
+                        "{}{}{}",
+                        self.login,
+                        separator,
+                        crate::uri::query(&[("next", requested)])
+                    ),
+                )
+                .body(Full::new(Bytes::new()))
+                .unwrap()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authorization(credentials: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "Authorization",
+            format!("Basic {}", STANDARD.encode(credentials))
+                .parse()
+                .unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn basic_auth_needs_a_matching_pair() {
+        let auth = basic_auth([("admin", "secret"), ("ann", "hunter2")]);
+        assert!(auth.is_authorized(&authorization("admin:secret")));
+        assert!(auth.is_authorized(&authorization("ann:hunter2")));
+        assert!(!auth.is_authorized(&authorization("admin:hunter2")));
+        assert!(!auth.is_authorized(&authorization("bob:secret")));
+        assert!(!auth.is_authorized(&authorization("admin")));
+        assert!(!auth.is_authorized(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn protect_matches_whole_segments() {
+        let auth = basic_auth([("admin", "secret")]).protect("/docs");
+        assert!(auth.is_protected("/docs"));
+        assert!(auth.is_protected("//docs/./index.html"));
+        assert!(!auth.is_protected("/documents"));
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use http_body_util::{BodyExt, Full};
+use hyper::HeaderMap;
+
+use super::{Layer, Next, Request, Response};
+use crate::{response::escape, RouteInfo};
+
+/// Path the debug panel is served at
+pub const DEBUG_PATH: &str = "/_tela/debug";
+
+/// Max number of bytes kept from each request and response body
+const BODY_LIMIT: usize = 2048;
+
+#[derive(Debug, Clone)]
+struct Record {
+    at: Instant,
+    duration: Duration,
+    method: String,
+    uri: String,
+    status: u16,
+    request_headers: Vec<(String, String)>,
+    request_body: String,
+    response_headers: Vec<(String, String)>,
+    response_body: String,
+}
+
+fn headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).to_string(),
+            )
+        })
+        .collect()
+}
+
+fn truncate(body: &[u8]) -> String {
+    match body.len() > BODY_LIMIT {
+        true => format!(
+            "{}... ({} bytes)",
+            String::from_utf8_lossy(&body[..BODY_LIMIT]),
+            body.len()
+        ),
+        false => String::from_utf8_lossy(body).to_string(),
+    }
+}
+
+/// Developer panel that records the most recent requests and their responses.
+///
+/// Each record has the method, uri, status, timing, headers, and the start of the
+/// request and response bodies. The records are shown at `/_tela/debug`.
+///
+/// The panel is only active in debug builds. In release builds requests are passed
+/// through without being recorded and the panel is not served.
+///
+/// # Example
+/// ```no_run
+/// use tela::{layer::DebugPanel, Server};
+///
+/// #[tela::main]
+/// async fn main() {
+///     Server::new()
+///         .layer(DebugPanel::new(50))
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+pub struct DebugPanel {
+    capacity: usize,
+    records: Mutex<VecDeque<Record>>,
+}
+
+impl DebugPanel {
+    /// Create a panel that keeps the last `capacity` requests
+    pub fn new(capacity: usize) -> Self {
+        DebugPanel {
+            capacity,
+            records: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    fn record(&self, record: Record) {
+        let mut records = self.records.lock().unwrap();
+        if records.len() >= self.capacity {
+            records.pop_front();
+        }
+        if self.capacity > 0 {
+            records.push_back(record);
+        }
+    }
+
+    fn render(&self, routes: &[RouteInfo]) -> String {
+        let routes = routes
+            .iter()
+            .map(|route| {
+                format!(
+                    "<tr><td><b>{}</b></td><td><code>{}</code></td><td>{}</td><td>{}</td></tr>",
+                    route.method,
+                    escape(&route.path),
+                    escape(route.tags.join(", ")),
+                    escape(route.description.as_deref().unwrap_or("")),
+                )
+            })
+            .collect::<String>();
+        let records = self.records.lock().unwrap();
+        let rows = records
+            .iter()
+            .rev()
+            .map(|record| {
+                let list = |headers: &Vec<(String, String)>| {
+                    headers
+                        .iter()
+                        .map(|(name, value)| {
+                            format!("<li><b>{}</b>: {}</li>", escape(name), escape(value))
+                        })
+                        .collect::<String>()
+                };
+                format!(
+                    r#"<details><summary><span class="status s{}">{}</span> {} <code>{}</code> <small>{:.2?} &middot; {}s ago</small></summary><div class="record"><div><h4>Request</h4><ul>{}</ul><pre>{}</pre></div><div><h4>Response</h4><ul>{}</ul><pre>{}</pre></div></div></details>"#,
+                    record.status / 100,
+                    record.status,
+                    escape(&record.method),
+                    escape(&record.uri),
+                    record.duration,
+                    record.at.elapsed().as_secs(),
+                    list(&record.request_headers),
+                    escape(&record.request_body),
+                    list(&record.response_headers),
+                    escape(&record.response_body),
+                )
+            })
+            .collect::<String>();
+
+        format!(
+            r#"<!DOCTYPE html><html lang="en"><head><meta charset="UTF-8"/><meta name="viewport" content="width=device-width, initial-scale=1"/><title>Tela Debug</title><style>body{{font-family:Arial,sans-serif;margin:1rem}}summary{{cursor:pointer;padding:.25rem}}.record{{display:flex;gap:1rem;padding:.5rem}}.record>div{{flex:1;min-width:0}}pre{{background:rgba(0,0,0,.1);padding:.5rem;overflow:auto;max-height:15rem}}.status{{font-weight:700}}.s2{{color:green}}.s3{{color:purple}}.s4{{color:crimson}}.s5{{color:darkorange}}table{{border-collapse:collapse}}td,th{{text-align:left;padding:.25rem .75rem .25rem 0}}</style></head><body><h1>Routes</h1><table><tr><th>Method</th><th>Path</th><th>Tags</th><th>Description</th></tr>{}</table><h1>Recent Requests</h1>{}</body></html>"#,
+            routes,
+            match rows.is_empty() {
+                true => "<p>No requests recorded yet</p>".to_string(),
+                false => rows,
+            }
+        )
+    }
+}
+
+impl Layer for DebugPanel {
+    fn call<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            if !cfg!(debug_assertions) {
+                return next.run(request).await;
+            }
+
+            if request.uri.path() == DEBUG_PATH {
+                return hyper::Response::builder()
+                    .status(200)
+                    .header("Content-Type", "text/html")
+                    .header("Cache-Control", "no-store")
+                    .body(Full::new(Bytes::from(self.render(&next.router.routes()))))
+                    .unwrap();
+            }
+
+            let at = Instant::now();
+            let method = request.method.to_string();
+            let uri = request.uri.to_string();
+            let request_headers = headers(&request.headers);
+            let request_body = truncate(&request.body);
+
+            let response = next.run(request).await;
+            let duration = at.elapsed();
+
+            let (parts, body) = response.into_parts();
+            let body = body
+                .collect()
+                .await
+                .map(|body| body.to_bytes())
+                .unwrap_or_default();
+
+            self.record(Record {
+                at,
+                duration,
+                method,
+                uri,
+                status: parts.status.as_u16(),
+                request_headers,
+                request_body,
+                response_headers: headers(&parts.headers),
+                response_body: truncate(&body),
+            });
+
+            Response::from_parts(parts, Full::new(body))
+        })
+    }
+}
@@ -0,0 +1,199 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use http_body_util::{BodyExt, Full};
+use hyper::{header::SET_COOKIE, HeaderMap, Method, StatusCode};
+use tokio::sync::broadcast;
+
+use super::{auth::segments, Layer, Next, Request, Response};
+use crate::websocket::WebSocketRoute;
+
+/// Response shared with the requests waiting on a flight. `None` when it can't be shared.
+type Shared = Option<Snapshot>;
+
+#[derive(Debug, Clone)]
+struct Snapshot {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl Snapshot {
+    fn response(self) -> Response {
+        let mut response = hyper::Response::new(Full::new(self.body));
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers;
+        response
+    }
+}
+
+type Flights = Mutex<HashMap<String, broadcast::Sender<Shared>>>;
+
+/// Ends the flight for the request that is running the handler. If it is dropped before
+/// finishing, ex: the client went away, the waiting requests run the handler themselves.
+struct Leader<'a> {
+    flights: &'a Flights,
+    key: &'a str,
+}
+
+impl Leader<'_> {
+    fn finish(self, shared: Shared) {
+        if let Some(sender) = self.flights.lock().unwrap().remove(self.key) {
+            let _ = sender.send(shared);
+        }
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        self.flights.lock().unwrap().remove(self.key);
+    }
+}
+
+/// Run the handler once for identical `GET` and `HEAD` requests that arrive while it is
+/// already running and send every one of them its response. Protects expensive pages from a
+/// stampede when a cache expires or a link goes viral.
+///
+/// Requests are identical when they have the same method, path, and query, in any order, and
+/// the same values for the key headers. `Accept`, `Accept-Language`, `Authorization`, and
+/// `Cookie` are always part of the key so users never get each other's responses. Responses
+/// that set a cookie aren't shared; the waiting requests run the handler themselves.
+///
+/// # Example
+/// ```
+/// use tela::{layer::SingleFlight, Server};
+///
+/// Server::new().layer(SingleFlight::new().path("/reports").key_header("X-Tenant"));
+/// ```
+#[derive(Debug)]
+pub struct SingleFlight {
+    headers: Vec<String>,
+    paths: Vec<Vec<String>>,
+    flights: Flights,
+}
+
+impl SingleFlight {
+    pub fn new() -> Self {
+        SingleFlight {
+            headers: ["Accept", "Accept-Language", "Authorization", "Cookie"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            paths: Vec::new(),
+            flights: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Add a header whose value must match for requests to share a response
+    pub fn key_header<H: Into<String>>(mut self, header: H) -> Self {
+        self.headers.push(header.into());
+        self
+    }
+
+    /// Only coalesce requests for the path and everything under it. All paths are coalesced
+    /// if none are given.
+    pub fn path<P: AsRef<str>>(mut self, path: P) -> Self {
+        self.paths.push(segments(path.as_ref()));
+        self
+    }
+
+    fn applies(&self, request: &Request) -> bool {
+        if request.method != Method::GET && request.method != Method::HEAD {
+            return false;
+        }
+        // Every upgrade is its own connection so it can't be shared
+        if WebSocketRoute::is_upgrade(&request.headers) {
+            return false;
+        }
+        if self.paths.is_empty() {
+            return true;
+        }
+        let path = segments(request.uri.path());
+        self.paths.iter().any(|prefix| path.starts_with(prefix))
+    }
+
+    /// Method, path, sorted query, and key header values
+    fn key(&self, request: &Request) -> String {
+        let mut query: Vec<&str> = request
+            .uri
+            .query()
+            .unwrap_or("")
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .collect();
+        query.sort_unstable();
+
+        let mut key = format!(
+            "{} {}?{}",
+            request.method,
+            request.uri.path(),
+            query.join("&")
+        );
+        for header in self.headers.iter() {
+            key.push('\n');
+            for value in request.headers.get_all(header.as_str()) {
+                key.push_str(&String::from_utf8_lossy(value.as_bytes()));
+                key.push(',');
+            }
+        }
+        key
+    }
+}
+
+impl Default for SingleFlight {
+    fn default() -> Self {
+        SingleFlight::new()
+    }
+}
+
+impl Layer for SingleFlight {
+    fn call<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            if !self.applies(&request) {
+                return next.run(request).await;
+            }
+
+            let key = self.key(&request);
+            let waiting = {
+                let mut flights = self.flights.lock().unwrap();
+                match flights.get(&key) {
+                    Some(sender) => Some(sender.subscribe()),
+                    None => {
+                        flights.insert(key.clone(), broadcast::channel(1).0);
+                        None
+                    }
+                }
+            };
+
+            if let Some(mut receiver) = waiting {
+                return match receiver.recv().await {
+                    Ok(Some(snapshot)) => snapshot.response(),
+                    // The response couldn't be shared or the flight was dropped
+                    _ => next.run(request).await,
+                };
+            }
+
+            let leader = Leader {
+                flights: &self.flights,
+                key: &key,
+            };
+            let (parts, body) = next.run(request).await.into_parts();
+            let body = match body.collect().await {
+                Ok(body) => body.to_bytes(),
+                Err(never) => match never {},
+            };
+            let shared = match parts.headers.contains_key(SET_COOKIE) {
+                true => None,
+                false => Some(Snapshot {
+                    status: parts.status,
+                    headers: parts.headers.clone(),
+                    body: body.clone(),
+                }),
+            };
+            leader.finish(shared);
+            hyper::Response::from_parts(parts, Full::new(body))
+        })
+    }
+}
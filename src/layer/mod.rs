@@ -0,0 +1,136 @@
+mod access;
+mod auth;
+mod debug;
+mod flight;
+mod reload;
+mod shed;
+mod signature;
+mod slow;
+mod timing;
+
+pub use access::{AccessLog, LogFormat};
+pub use auth::{basic_auth, BasicAuth, RequireAuth};
+pub use debug::{DebugPanel, DEBUG_PATH};
+pub use flight::SingleFlight;
+pub use reload::{LiveReload, RELOAD_PATH};
+pub use shed::LoadShed;
+pub use signature::{HmacAlgorithm, HmacSignature, SignatureEncoding, VerifySignature};
+pub use slow::SlowRequests;
+pub use timing::ResponseTime;
+
+use std::{net::SocketAddr, sync::Arc};
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use http_body_util::Full;
+use hyper::{HeaderMap, Method, Uri};
+
+use crate::{request::BodyStream, Router};
+
+pub type Response = hyper::Response<Full<Bytes>>;
+
+/// Request that has been fully read and is passed through each layer
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+    /// Trailers sent after a chunked body. Empty if the client didn't send any.
+    pub trailers: HeaderMap,
+    /// Unread body for endpoints that stream it; `body` and `trailers` are empty when it is used
+    pub stream: BodyStream,
+    /// Address of the client. `None` if the request didn't come from a connection, ex: when
+    /// exporting a static site.
+    pub remote: Option<SocketAddr>,
+}
+
+/// The rest of the layers along with the router. Call `run` to get the response
+/// from the next layer.
+pub struct Next<'a> {
+    router: &'a Router,
+    layers: &'a [Arc<dyn Layer>],
+    upgrade: bool,
+}
+
+/// Marks the response to a websocket upgrade that made it past every layer; see `Next::upgrade`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Upgrade;
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(router: &'a Router, layers: &'a [Arc<dyn Layer>]) -> Self {
+        Next {
+            router,
+            layers,
+            upgrade: false,
+        }
+    }
+
+    /// Run a websocket upgrade through the layers. Instead of dispatching it, the end of the
+    /// layers responds with a `101 Switching Protocols` marked with `Upgrade` so the router
+    /// knows to finish the handshake. A layer that responds early refuses the upgrade.
+    pub(crate) fn upgrade(router: &'a Router, layers: &'a [Arc<dyn Layer>]) -> Self {
+        Next {
+            router,
+            layers,
+            upgrade: true,
+        }
+    }
+
+    pub async fn run(self, request: Request) -> Response {
+        match self.layers.split_first() {
+            Some((layer, layers)) => {
+                layer
+                    .call(
+                        request,
+                        Next {
+                            router: self.router,
+                            layers,
+                            upgrade: self.upgrade,
+                        },
+                    )
+                    .await
+            }
+            None if self.upgrade => {
+                let mut response = hyper::Response::builder()
+                    .status(101)
+                    .body(Full::new(Bytes::new()))
+                    .unwrap();
+                response.extensions_mut().insert(Upgrade);
+                response
+            }
+            None => self.router.dispatch(request).await,
+        }
+    }
+}
+
+/// Code that runs around every request. A layer can change the request, respond early,
+/// or change the response returned from `next.run(request)`.
+///
+/// Layers run in the order they are added to the server with the first layer being
+/// the outer most.
+///
+/// # Example
+/// ```
+/// use futures::future::BoxFuture;
+/// use tela::{
+///     layer::{Layer, Next, Request, Response},
+///     Server,
+/// };
+///
+/// struct Powered;
+/// impl Layer for Powered {
+///     fn call<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Response> {
+///         Box::pin(async move {
+///             let mut response = next.run(request).await;
+///             response.headers_mut().insert("X-Powered-By", "tela".parse().unwrap());
+///             response
+///         })
+///     }
+/// }
+///
+/// Server::new().layer(Powered);
+/// ```
+pub trait Layer: Send + Sync {
+    fn call<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Response>;
+}
@@ -0,0 +1,91 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use http_body_util::{BodyExt, Full};
+use hyper::header::{HeaderValue, CONTENT_LENGTH, CONTENT_TYPE};
+
+use super::{Layer, Next, Request, Response};
+
+/// Path the reload script polls for the server's id
+pub const RELOAD_PATH: &str = "/__tela/reload";
+
+/// Reload open pages when the server restarts; used by `cargo tela dev`.
+///
+/// A script is added to the end of every html page that polls `RELOAD_PATH` for an id made when
+/// the server started. The page reloads once a new id is returned.
+///
+/// With the `dev` feature, which `cargo tela dev` enables, `Server::serve` adds this layer when
+/// the server is run by `cargo tela dev`.
+///
+/// # Example
+/// ```
+/// use tela::{layer::LiveReload, Server};
+///
+/// Server::new().layer(LiveReload::new());
+/// ```
+#[derive(Debug, Clone)]
+pub struct LiveReload {
+    id: String,
+}
+
+impl LiveReload {
+    pub fn new() -> Self {
+        let id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_nanos())
+            .unwrap_or_default();
+        LiveReload { id: id.to_string() }
+    }
+
+    fn script(&self) -> String {
+        format!(
+            r#"<script>(()=>{{const id="{}";setInterval(async()=>{{try{{const r=await fetch("{}");if(r.ok&&(await r.text())!==id)location.reload()}}catch(_){{}}}},1000)}})()</script>"#,
+            self.id, RELOAD_PATH
+        )
+    }
+}
+
+impl Default for LiveReload {
+    fn default() -> Self {
+        LiveReload::new()
+    }
+}
+
+impl Layer for LiveReload {
+    fn call<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            if request.uri.path() == RELOAD_PATH {
+                return hyper::Response::builder()
+                    .header(CONTENT_TYPE, "text/plain")
+                    .header("Cache-Control", "no-store")
+                    .body(Full::new(Bytes::from(self.id.clone())))
+                    .unwrap();
+            }
+
+            let response = next.run(request).await;
+            let html = response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.starts_with("text/html"))
+                .unwrap_or(false);
+            if !html {
+                return response;
+            }
+
+            let (mut parts, body) = response.into_parts();
+            let body = body.collect().await.unwrap().to_bytes();
+            let mut page = String::from_utf8_lossy(&body).to_string();
+            match page.rfind("</body>") {
+                Some(index) => page.insert_str(index, &self.script()),
+                None => page.push_str(&self.script()),
+            }
+            parts.headers.remove(CONTENT_LENGTH);
+            if let Ok(length) = HeaderValue::from_str(&page.len().to_string()) {
+                parts.headers.insert(CONTENT_LENGTH, length);
+            }
+            hyper::Response::from_parts(parts, Full::new(Bytes::from(page)))
+        })
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use http_body_util::Full;
+
+use super::{Layer, Next, Request, Response};
+
+/// Latency seen over the current interval; see `LoadShed::target`
+#[derive(Debug)]
+struct Window {
+    start: Instant,
+    /// Fastest response in the interval; `None` until one finishes
+    fastest: Option<Duration>,
+    overloaded: bool,
+}
+
+/// Counts a request as in flight until it is dropped, even if the client goes away
+struct InFlight<'a>(&'a AtomicUsize);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Reject requests with `503 Service Unavailable` while the server is overloaded so the
+/// requests it does accept stay fast.
+///
+/// The server is overloaded when more than `max_in_flight` requests are being handled at
+/// once, or, like CoDel, when even the fastest response in an interval took longer than the
+/// `target` latency. Latency based shedding stops once an interval has a fast response, or
+/// after an interval with no responses so the server is probed again.
+///
+/// Rejected requests get a `Retry-After` header.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use tela::{layer::LoadShed, Server};
+///
+/// Server::new().layer(
+///     LoadShed::new()
+///         .max_in_flight(512)
+///         .target(Duration::from_millis(50)),
+/// );
+/// ```
+#[derive(Debug)]
+pub struct LoadShed {
+    max_in_flight: Option<usize>,
+    target: Option<Duration>,
+    interval: Duration,
+    retry_after: u64,
+    in_flight: AtomicUsize,
+    window: Mutex<Window>,
+}
+
+impl LoadShed {
+    pub fn new() -> Self {
+        LoadShed {
+            max_in_flight: None,
+            target: None,
+            interval: Duration::from_millis(100),
+            retry_after: 1,
+            in_flight: AtomicUsize::new(0),
+            window: Mutex::new(Window {
+                start: Instant::now(),
+                fastest: None,
+                overloaded: false,
+            }),
+        }
+    }
+
+    /// Most requests handled at once; requests past this are rejected
+    pub fn max_in_flight(mut self, max: usize) -> Self {
+        self.max_in_flight = Some(max);
+        self
+    }
+
+    /// Latency the fastest response in each interval should stay under; intervals are
+    /// 100ms by default
+    pub fn target(mut self, target: Duration) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// How long latency is measured over before deciding whether the server is overloaded
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Seconds clients are told to wait before retrying, 1 by default
+    pub fn retry_after(mut self, seconds: u64) -> Self {
+        self.retry_after = seconds;
+        self
+    }
+
+    /// Whether the latency target has been missed for the last interval
+    fn is_overloaded(&self) -> bool {
+        if self.target.is_none() {
+            return false;
+        }
+        let mut window = self.window.lock().unwrap();
+        if window.overloaded && window.start.elapsed() >= self.interval {
+            // Nothing finished in the interval since everything was rejected; try again
+            if window.fastest.is_none() {
+                window.overloaded = false;
+            }
+            window.start = Instant::now();
+            window.fastest = None;
+        }
+        window.overloaded
+    }
+
+    fn record(&self, latency: Duration) {
+        let Some(target) = self.target else {
+            return;
+        };
+        let mut window = self.window.lock().unwrap();
+        let fastest = window
+            .fastest
+            .map_or(latency, |fastest| fastest.min(latency));
+        if window.start.elapsed() >= self.interval {
+            window.overloaded = fastest > target;
+            window.start = Instant::now();
+            window.fastest = None;
+        } else {
+            window.fastest = Some(fastest);
+        }
+    }
+
+    fn reject(&self) -> Response {
+        hyper::Response::builder()
+            .status(503)
+            .header("Retry-After", self.retry_after.to_string())
+            .header("Content-Type", "text/plain")
+            .body(Full::new(Bytes::from("Service Unavailable")))
+            .unwrap()
+    }
+}
+
+impl Default for LoadShed {
+    fn default() -> Self {
+        LoadShed::new()
+    }
+}
+
+impl Layer for LoadShed {
+    fn call<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            let _guard = InFlight(&self.in_flight);
+
+            let full = self.max_in_flight.is_some_and(|max| in_flight > max);
+            if full || self.is_overloaded() {
+                return self.reject();
+            }
+
+            let start = Instant::now();
+            let response = next.run(request).await;
+            self.record(start.elapsed());
+            response
+        })
+    }
+}
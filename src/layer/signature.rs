@@ -0,0 +1,444 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use http_body_util::Full;
+use hyper::{header::HeaderName, HeaderMap};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+
+use super::{auth::segments, Layer, Next, Request, Response};
+use crate::auth::constant_eq;
+
+/// Hash used for an HMAC signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HmacAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// How the signature bytes are written in the header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureEncoding {
+    /// Lowercase hex, ex: GitHub. Uppercase hex is accepted when verifying.
+    Hex,
+    /// Standard base64 with padding, ex: Shopify
+    Base64,
+}
+
+/// How webhook bodies are signed; the secret, the header the signature is sent in, and the
+/// hash. Used by `VerifySignature` to check incoming requests and `sign` for outgoing ones.
+///
+/// The signature is the hex HMAC of the body; ex: `X-Signature: sha256=4f2a...` with the
+/// `sha256=` prefix. Services that send base64 are configured with `encoding`. With a
+/// timestamp header the signed payload is `{timestamp}.{body}` and requests outside the
+/// tolerance are rejected so a captured request can't be replayed.
+///
+/// # Example
+/// ```no_run
+/// use tela::{
+///     layer::{HmacSignature, VerifySignature},
+///     prelude::*,
+///     request::Body,
+///     Server,
+/// };
+///
+/// #[post("/webhooks/github")]
+/// fn deliveries(Body(payload): Body<String>) -> String {
+///     format!("Received {} bytes", payload.len())
+/// }
+///
+/// #[tela::main]
+/// async fn main() {
+///     // GitHub webhooks
+///     let secret = std::env::var("WEBHOOK_SECRET")?;
+///     let github = HmacSignature::new(secret)
+///         .header("X-Hub-Signature-256")
+///         .prefix("sha256=");
+///
+///     Server::new()
+///         .route(deliveries)
+///         .layer(VerifySignature::new(github).protect("/webhooks/github"))
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct HmacSignature {
+    secret: Vec<u8>,
+    header: String,
+    algorithm: HmacAlgorithm,
+    encoding: SignatureEncoding,
+    prefix: String,
+    timestamp: Option<String>,
+    tolerance: Duration,
+}
+
+impl HmacSignature {
+    /// Sha256 signature in the `X-Signature` header
+    pub fn new<S: AsRef<[u8]>>(secret: S) -> Self {
+        HmacSignature {
+            secret: secret.as_ref().to_vec(),
+            header: "X-Signature".to_string(),
+            algorithm: HmacAlgorithm::Sha256,
+            encoding: SignatureEncoding::Hex,
+            prefix: String::new(),
+            timestamp: None,
+            tolerance: Duration::from_secs(300),
+        }
+    }
+
+    /// Header the signature is sent in
+    pub fn header<H: Into<String>>(mut self, header: H) -> Self {
+        self.header = header.into();
+        self
+    }
+
+    pub fn algorithm(mut self, algorithm: HmacAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// How the signature is written in the header. Defaults to hex.
+    pub fn encoding(mut self, encoding: SignatureEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Text before the signature in the header; ex: `sha256=`
+    pub fn prefix<P: Into<String>>(mut self, prefix: P) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Sign the unix timestamp in the header along with the body
+    pub fn timestamp<H: Into<String>>(mut self, header: H) -> Self {
+        self.timestamp = Some(header.into());
+        self
+    }
+
+    /// How far the timestamp can be from now. Defaults to 5 minutes.
+    pub fn tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    fn mac(&self, timestamp: Option<&str>, body: &[u8]) -> Vec<u8> {
+        macro_rules! mac {
+            ($hash: ty) => {{
+                let mut mac =
+                    Hmac::<$hash>::new_from_slice(&self.secret).expect("HMAC accepts any key size");
+                if let Some(timestamp) = timestamp {
+                    mac.update(timestamp.as_bytes());
+                    mac.update(b".");
+                }
+                mac.update(body);
+                mac.finalize().into_bytes().to_vec()
+            }};
+        }
+        match self.algorithm {
+            HmacAlgorithm::Sha1 => mac!(Sha1),
+            HmacAlgorithm::Sha256 => mac!(Sha256),
+            HmacAlgorithm::Sha512 => mac!(Sha512),
+        }
+    }
+
+    /// Headers to send with an outgoing body; the signature and the timestamp if one is used
+    pub fn sign(&self, body: &[u8]) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        let timestamp = self.timestamp.as_ref().map(|header| {
+            let now = now().to_string();
+            headers.push((header.clone(), now.clone()));
+            now
+        });
+        let mac = self.mac(timestamp.as_deref(), body);
+        let signature = match self.encoding {
+            SignatureEncoding::Hex => mac.iter().map(|b| format!("{:02x}", b)).collect(),
+            SignatureEncoding::Base64 => STANDARD.encode(mac),
+        };
+        headers.push((self.header.clone(), format!("{}{}", self.prefix, signature)));
+        headers
+    }
+
+    /// Add the signature headers to an outgoing request and give it a body that can be sent
+    /// with hyper; ex: for a webhook delivery.
+    pub fn sign_request(&self, request: hyper::Request<Bytes>) -> hyper::Request<Full<Bytes>> {
+        let (mut parts, body) = request.into_parts();
+        for (name, value) in self.sign(&body) {
+            if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), value.parse())
+            {
+                parts.headers.insert(name, value);
+            }
+        }
+        hyper::Request::from_parts(parts, Full::new(body))
+    }
+
+    /// Check the signature of a request's body
+    pub fn verify(&self, headers: &HeaderMap, body: &[u8]) -> Result<(), &'static str> {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+        let timestamp = match &self.timestamp {
+            Some(name) => {
+                let timestamp = header(name).ok_or("Missing signature timestamp")?;
+                let time = timestamp
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|_| "Invalid signature timestamp")?;
+                if now().abs_diff(time) > self.tolerance.as_secs() {
+                    return Err("Signature timestamp is outside the tolerance");
+                }
+                Some(timestamp.trim())
+            }
+            None => None,
+        };
+
+        let signature = header(&self.header)
+            .and_then(|value| value.trim().strip_prefix(self.prefix.as_str()))
+            .ok_or("Missing signature")?;
+        let expected = self.mac(timestamp, body);
+        match decode(signature.trim(), self.encoding) {
+            Some(signature) if constant_eq(&signature, &expected) => Ok(()),
+            _ => Err("Invalid signature"),
+        }
+    }
+}
+
+/// Signature bytes in the configured encoding
+fn decode(signature: &str, encoding: SignatureEncoding) -> Option<Vec<u8>> {
+    match encoding {
+        SignatureEncoding::Hex => signature
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| {
+                let digit = |c: u8| (c as char).to_digit(16);
+                match pair {
+                    [high, low] => Some((digit(*high)? * 16 + digit(*low)?) as u8),
+                    _ => None,
+                }
+            })
+            .collect(),
+        SignatureEncoding::Base64 => STANDARD.decode(signature).ok(),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+/// Layer that checks the HMAC signature of request bodies before they reach the endpoint,
+/// see `HmacSignature`. Requests with a missing or invalid signature get a
+/// `401 Unauthorized`.
+///
+/// Every path is checked unless paths are given with `protect`. Endpoints that stream
+/// their body with `BodyStream` can't be verified by the layer.
+pub struct VerifySignature {
+    signature: HmacSignature,
+    paths: Vec<Vec<String>>,
+}
+
+impl VerifySignature {
+    pub fn new(signature: HmacSignature) -> Self {
+        VerifySignature {
+            signature,
+            paths: Vec::new(),
+        }
+    }
+
+    /// Only check the signature for the path and everything under it
+    pub fn protect<P: AsRef<str>>(mut self, path: P) -> Self {
+        self.paths.push(segments(path.as_ref()));
+        self
+    }
+
+    fn is_protected(&self, path: &str) -> bool {
+        if self.paths.is_empty() {
+            return true;
+        }
+        let path = segments(path);
+        self.paths
+            .iter()
+            .any(|protected| path.starts_with(protected))
+    }
+}
+
+impl Layer for VerifySignature {
+    fn call<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            if !self.is_protected(request.uri.path()) {
+                return next.run(request).await;
+            }
+            match self.signature.verify(&request.headers, &request.body) {
+                Ok(()) => next.run(request).await,
+                Err(reason) => hyper::Response::builder()
+                    .status(401)
+                    .header("Content-Type", "text/plain")
+                    .body(Full::new(Bytes::from(reason)))
+                    .unwrap(),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = b"The quick brown fox jumps over the lazy dog";
+    /// HMAC-SHA256 of `BODY` with the key `key`
+    const HEX: &str = "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8";
+    const BASE64: &str = "97yD9DBThCSxMpjmqm+xQ+9NWaFJRhdZl0edvC0aPNg=";
+
+    fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(
+                HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        headers
+    }
+
+    #[test]
+    fn signed_bodies_verify() {
+        let github = HmacSignature::new("key")
+            .header("X-Hub-Signature-256")
+            .prefix("sha256=");
+        let signed = github.sign(BODY);
+        assert_eq!(
+            signed,
+            vec![("X-Hub-Signature-256".to_string(), format!("sha256={}", HEX))]
+        );
+
+        let header = format!("sha256={}", HEX);
+        assert!(github
+            .verify(&headers(&[("X-Hub-Signature-256", &header)]), BODY)
+            .is_ok());
+        let upper = format!("sha256={}", HEX.to_uppercase());
+        assert!(github
+            .verify(&headers(&[("X-Hub-Signature-256", &upper)]), BODY)
+            .is_ok());
+
+        // The prefix is required, and only the body that was signed verifies
+        let unprefixed = headers(&[("X-Hub-Signature-256", HEX)]);
+        assert_eq!(github.verify(&unprefixed, BODY), Err("Missing signature"));
+        let signed = headers(&[("X-Hub-Signature-256", &header)]);
+        assert_eq!(github.verify(&signed, b"{}"), Err("Invalid signature"));
+        assert_eq!(
+            github.verify(&HeaderMap::new(), BODY),
+            Err("Missing signature")
+        );
+    }
+
+    #[test]
+    fn wrong_secret_is_rejected() {
+        let signed = headers(&[("X-Signature", HEX)]);
+        assert!(HmacSignature::new("key").verify(&signed, BODY).is_ok());
+        assert_eq!(
+            HmacSignature::new("other").verify(&signed, BODY),
+            Err("Invalid signature")
+        );
+        // Another hash of the same secret isn't the same signature
+        assert_eq!(
+            HmacSignature::new("key")
+                .algorithm(HmacAlgorithm::Sha512)
+                .verify(&signed, BODY),
+            Err("Invalid signature")
+        );
+    }
+
+    #[test]
+    fn signatures_are_read_in_the_configured_encoding() {
+        let hex = HmacSignature::new("key");
+        let base64 = HmacSignature::new("key").encoding(SignatureEncoding::Base64);
+        assert_eq!(base64.sign(BODY)[0].1, BASE64);
+
+        let (hex_header, base64_header) = (
+            headers(&[("X-Signature", HEX)]),
+            headers(&[("X-Signature", BASE64)]),
+        );
+        assert!(hex.verify(&hex_header, BODY).is_ok());
+        assert!(base64.verify(&base64_header, BODY).is_ok());
+        assert_eq!(hex.verify(&base64_header, BODY), Err("Invalid signature"));
+        assert_eq!(base64.verify(&hex_header, BODY), Err("Invalid signature"));
+
+        // Hex that is also valid base64 is only read as the configured encoding
+        assert_eq!(
+            decode("deadbeef", SignatureEncoding::Hex),
+            Some(vec![0xde, 0xad, 0xbe, 0xef])
+        );
+        assert_eq!(
+            decode("deadbeef", SignatureEncoding::Base64),
+            Some(vec![0x75, 0xe6, 0x9d, 0x6d, 0xe7, 0x9f])
+        );
+    }
+
+    #[test]
+    fn malformed_hex_is_rejected() {
+        assert_eq!(decode("abc", SignatureEncoding::Hex), None);
+        assert_eq!(decode("zz", SignatureEncoding::Hex), None);
+        assert_eq!(decode("+f", SignatureEncoding::Hex), None);
+        assert_eq!(decode("", SignatureEncoding::Hex), Some(Vec::new()));
+
+        // A signature missing its last digit doesn't verify
+        let odd = headers(&[("X-Signature", &HEX[..HEX.len() - 1])]);
+        assert_eq!(
+            HmacSignature::new("key").verify(&odd, BODY),
+            Err("Invalid signature")
+        );
+    }
+
+    #[test]
+    fn timestamps_outside_the_tolerance_are_rejected() {
+        let signature = HmacSignature::new("key")
+            .timestamp("X-Timestamp")
+            .tolerance(Duration::from_secs(60));
+        let signed = signature.sign(b"{}");
+        assert_eq!(signed[0].0, "X-Timestamp");
+        let pairs: Vec<(&str, &str)> = signed
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        assert!(signature.verify(&headers(&pairs), b"{}").is_ok());
+
+        // The timestamp is signed so it can't be changed
+        let now = now();
+        let hex = |time: u64| -> String {
+            let mac = signature.mac(Some(&time.to_string()), b"{}");
+            mac.iter().map(|b| format!("{:02x}", b)).collect()
+        };
+        let moved = headers(&[
+            ("X-Timestamp", &now.to_string()),
+            ("X-Signature", &hex(now - 10)),
+        ]);
+        assert_eq!(signature.verify(&moved, b"{}"), Err("Invalid signature"));
+
+        for time in [now - 61, now + 61] {
+            let old = headers(&[
+                ("X-Timestamp", &time.to_string()),
+                ("X-Signature", &hex(time)),
+            ]);
+            assert_eq!(
+                signature.verify(&old, b"{}"),
+                Err("Signature timestamp is outside the tolerance")
+            );
+        }
+
+        let missing = headers(&[pairs[1]]);
+        assert_eq!(
+            signature.verify(&missing, b"{}"),
+            Err("Missing signature timestamp")
+        );
+        let invalid = headers(&[("X-Timestamp", "yesterday"), pairs[1]]);
+        assert_eq!(
+            signature.verify(&invalid, b"{}"),
+            Err("Invalid signature timestamp")
+        );
+    }
+}
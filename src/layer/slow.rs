@@ -0,0 +1,104 @@
+use std::{
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use futures::future::BoxFuture;
+
+use super::{Layer, Next, Request, Response};
+
+/// Log requests that take longer than a threshold to respond; ex:
+/// `Slow request: GET /users/42 (/users/:id) 200 took 1.204s`.
+///
+/// The time includes the layers added after this one, so add it last to time only the
+/// handler.
+///
+/// Handlers run to completion before anything else on their thread can run, so a stuck
+/// handler isn't logged until it returns. With `watch`, debug builds also log a request the
+/// moment it passes the threshold while it is still running, along with the route that is
+/// handling it.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use tela::{layer::SlowRequests, Server};
+///
+/// Server::new().layer(SlowRequests::new(Duration::from_millis(500)).watch(true));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SlowRequests {
+    threshold: Duration,
+    watch: bool,
+}
+
+impl SlowRequests {
+    pub fn new(threshold: Duration) -> Self {
+        SlowRequests {
+            threshold,
+            watch: false,
+        }
+    }
+
+    /// Log requests that are still running once they pass the threshold. Only used in debug
+    /// builds; each request is watched from its own thread.
+    pub fn watch(mut self, watch: bool) -> Self {
+        self.watch = watch;
+        self
+    }
+}
+
+impl Default for SlowRequests {
+    /// Requests slower than a second
+    fn default() -> Self {
+        SlowRequests::new(Duration::from_secs(1))
+    }
+}
+
+impl Layer for SlowRequests {
+    fn call<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            let method = request.method.clone();
+            let path = request.uri.path().to_string();
+            let route = next
+                .router
+                .route_path(&method, &path)
+                .filter(|route| *route != path)
+                .map(|route| format!(" ({})", route))
+                .unwrap_or_default();
+
+            // Runs on its own thread so it can log while the handler blocks this one. Dropping
+            // the sender when the request finishes wakes it up early.
+            let watcher = (cfg!(debug_assertions) && self.watch).then(|| {
+                let (sender, receiver) = mpsc::channel::<()>();
+                let (threshold, line) = (self.threshold, format!("{} {}{}", method, path, route));
+                thread::spawn(move || {
+                    if let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(threshold) {
+                        eprintln!(
+                            "Slow request: {} is still running after {:.3?}",
+                            line, threshold
+                        );
+                    }
+                });
+                sender
+            });
+
+            let start = Instant::now();
+            let response = next.run(request).await;
+            let elapsed = start.elapsed();
+            drop(watcher);
+
+            if elapsed >= self.threshold {
+                eprintln!(
+                    "Slow request: {} {}{} {} took {:.3?}",
+                    method,
+                    path,
+                    route,
+                    response.status().as_u16(),
+                    elapsed
+                );
+            }
+            response
+        })
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::Instant;
+
+use futures::future::BoxFuture;
+use hyper::header::{HeaderName, HeaderValue};
+
+use super::{Layer, Next, Request, Response};
+
+/// Set a header to how long the rest of the request took to respond; ex:
+/// `X-Response-Time: 12.345ms`.
+///
+/// The time includes the layers added after this one, so add it last to time only the
+/// handler or first to time every layer too.
+///
+/// # Example
+/// ```
+/// use tela::{
+///     layer::{AccessLog, ResponseTime},
+///     Server,
+/// };
+///
+/// Server::new()
+///     .layer(AccessLog::stdout())
+///     .layer(ResponseTime::new());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ResponseTime {
+    header: HeaderName,
+}
+
+impl ResponseTime {
+    pub fn new() -> Self {
+        ResponseTime {
+            header: HeaderName::from_static("x-response-time"),
+        }
+    }
+
+    /// Name of the header to set, `X-Response-Time` by default. Names that aren't valid in a
+    /// header are ignored.
+    pub fn header<T: AsRef<str>>(mut self, name: T) -> Self {
+        if let Ok(name) = HeaderName::from_bytes(name.as_ref().as_bytes()) {
+            self.header = name;
+        }
+        self
+    }
+}
+
+impl Default for ResponseTime {
+    fn default() -> Self {
+        ResponseTime::new()
+    }
+}
+
+impl Layer for ResponseTime {
+    fn call<'a>(&'a self, request: Request, next: Next<'a>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            let start = Instant::now();
+            let mut response = next.run(request).await;
+            let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+            if let Ok(value) = HeaderValue::from_str(&format!("{:.3}ms", elapsed)) {
+                response.headers_mut().insert(self.header.clone(), value);
+            }
+            response
+        })
+    }
+}
@@ -1,16 +1,46 @@
+#[cfg(feature = "acme")]
+pub mod acme;
+pub mod client;
+#[cfg(feature = "sqlx")]
+pub mod db;
 pub(crate) mod errors;
 mod router;
+mod runtime;
 mod server;
 
+pub mod assets;
+pub mod auth;
+pub mod backplane;
+pub mod cookie;
+pub mod flash;
+pub mod layer;
+#[cfg(feature = "oauth")]
+pub mod oauth;
+#[cfg(feature = "otel")]
+pub mod otel;
 pub mod prelude;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+#[cfg(feature = "redis")]
+mod redis;
 pub mod request;
 pub mod response;
+pub mod session;
+pub mod shutdown;
+pub mod sign;
+pub mod ssg;
+pub mod state;
 pub mod support;
+pub mod sync;
 pub mod uri;
+pub mod websocket;
 
-pub use errors::StatusCode;
-pub use router::Router;
-pub use server::Server;
+pub use errors::{
+    Error, ErrorContext, ErrorRenderer, HtmlErrorRenderer, JsonErrorRenderer, StatusCode,
+};
+pub use router::{DateHeader, MapRequest, MapResponse, RouteInfo, Router};
+pub use runtime::{runtime_entry, Configure, Flavor, RuntimeConfig};
+pub use server::{Server, Socket};
 
 /// Re-export needed dependencies for macros
 pub mod bump {
@@ -20,9 +50,11 @@ pub mod bump {
     pub use serde;
     pub use serde_json;
     pub use tokio;
+
+    pub use tela_macros::fetch_request;
 }
 
-pub use tela_macros::main;
+pub use tela_macros::{handler, main, memo};
 
 pub trait StripPath {
     fn norm_strip_slashes(self) -> Self;
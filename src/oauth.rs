@@ -0,0 +1,431 @@
+use std::{collections::HashMap, fmt::Debug};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{HeaderMap, Method, Uri};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    auth::constant_eq,
+    client,
+    cookie::{random_key, Cookie, CookieJar, SameSite},
+    request::{BodyStream, Endpoint},
+    response::{Result, ToResponse},
+    sync::block_on,
+    uri::query,
+};
+
+/// Name of the signed cookie that holds the state and PKCE verifier of a login in progress
+pub const OAUTH_COOKIE: &str = "_oauth";
+
+/// OAuth2 authorization code flow with PKCE for a provider; ex: "Login with GitHub".
+///
+/// `login` creates the route that sends the user to the provider and `callback` creates the
+/// route the provider sends them back to. The callback checks the state, exchanges the code
+/// for a token, and passes the token to the handler. The state and PKCE verifier are kept in
+/// a signed cookie between the two requests.
+///
+/// # Example
+/// ```no_run
+/// use serde::{Deserialize, Serialize};
+/// use tela::{
+///     fetch,
+///     oauth::{OAuth2, Token},
+///     prelude::*,
+///     response::Redirect,
+///     session::AuthSession,
+///     sync::block_on,
+///     Server,
+/// };
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct User {
+///     login: String,
+/// }
+///
+/// fn fetch_user(access_token: &str) -> Result<User> {
+///     let authorization = format!("Bearer {}", access_token);
+///     let response = block_on(fetch!(
+///         "https://api.github.com/user",
+///         headers: { "Authorization": authorization, "User-Agent": "example" }
+///     ))
+///     .map_err(|error| (502, error.to_string()))?;
+///     serde_json::from_slice(response.body()).map_err(|error| (502, error.to_string()))
+/// }
+///
+/// #[tela::main]
+/// async fn main() {
+///     let github = OAuth2::github(
+///         std::env::var("GITHUB_CLIENT_ID")?,
+///         std::env::var("GITHUB_CLIENT_SECRET")?,
+///         "https://example.com/auth/github/callback",
+///     );
+///
+///     Server::new()
+///         .route(github.login("/auth/github"))
+///         .route(github.callback(
+///             |token: Result<Token>| -> Result<(AuthSession<User>, Redirect)> {
+///                 let user = fetch_user(&token?.access_token)?;
+///                 response!((AuthSession::new().login(user), Redirect::to("/")))
+///             },
+///         ))
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OAuth2 {
+    client_id: String,
+    client_secret: String,
+    authorize_url: String,
+    token_url: String,
+    redirect_url: String,
+    scopes: Vec<String>,
+}
+
+impl OAuth2 {
+    /// Provider with its authorization and token urls. The redirect url is the full url of
+    /// the callback route that is registered with the provider.
+    pub fn new<I, S, A, T, R>(
+        client_id: I,
+        client_secret: S,
+        authorize_url: A,
+        token_url: T,
+        redirect_url: R,
+    ) -> Self
+    where
+        I: Into<String>,
+        S: Into<String>,
+        A: Into<String>,
+        T: Into<String>,
+        R: Into<String>,
+    {
+        OAuth2 {
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            authorize_url: authorize_url.into(),
+            token_url: token_url.into(),
+            redirect_url: redirect_url.into(),
+            scopes: Vec::new(),
+        }
+    }
+
+    /// GitHub with the `read:user` scope
+    pub fn github<I: Into<String>, S: Into<String>, R: Into<String>>(
+        client_id: I,
+        client_secret: S,
+        redirect_url: R,
+    ) -> Self {
+        OAuth2::new(
+            client_id,
+            client_secret,
+            "https://github.com/login/oauth/authorize",
+            "https://github.com/login/oauth/access_token",
+            redirect_url,
+        )
+        .scope("read:user")
+    }
+
+    /// Google with the `openid`, `email`, and `profile` scopes
+    pub fn google<I: Into<String>, S: Into<String>, R: Into<String>>(
+        client_id: I,
+        client_secret: S,
+        redirect_url: R,
+    ) -> Self {
+        OAuth2::new(
+            client_id,
+            client_secret,
+            "https://accounts.google.com/o/oauth2/v2/auth",
+            "https://oauth2.googleapis.com/token",
+            redirect_url,
+        )
+        .scope("openid")
+        .scope("email")
+        .scope("profile")
+    }
+
+    /// Request a scope from the provider
+    pub fn scope<S: Into<String>>(mut self, scope: S) -> Self {
+        self.scopes.push(scope.into());
+        self
+    }
+
+    /// Redirect to the provider's authorization page. Use it in a handler when the login
+    /// route needs more than `login` gives; ex: remembering where the user came from.
+    pub fn authorize(&self) -> Authorize {
+        let state = URL_SAFE_NO_PAD.encode(random_key());
+        let verifier = URL_SAFE_NO_PAD.encode(random_key());
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+        let scope = self.scopes.join(" ");
+        let mut params = vec![
+            ("response_type", "code"),
+            ("client_id", self.client_id.as_str()),
+            ("redirect_uri", self.redirect_url.as_str()),
+            ("state", state.as_str()),
+            ("code_challenge", challenge.as_str()),
+            ("code_challenge_method", "S256"),
+        ];
+        if !scope.is_empty() {
+            params.push(("scope", scope.as_str()));
+        }
+        let separator = match self.authorize_url.contains('?') {
+            true => '&',
+            false => '?',
+        };
+
+        Authorize {
+            location: format!("{}{}{}", self.authorize_url, separator, query(&params)),
+            cookie: format!("{}.{}", state, verifier),
+        }
+    }
+
+    /// Exchange an authorization code from the callback for a token
+    pub async fn exchange(&self, code: &str, verifier: &str) -> Result<Token> {
+        let failed = |error: String| (502, format!("Failed to exchange oauth code: {}", error));
+
+        let body = query(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.redirect_url),
+            ("client_id", &self.client_id),
+            ("client_secret", &self.client_secret),
+            ("code_verifier", verifier),
+        ]);
+        let request = hyper::Request::builder()
+            .method(Method::POST)
+            .uri(&self.token_url)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("Accept", "application/json")
+            .body(Full::new(Bytes::from(body)))
+            .map_err(|error| failed(error.to_string()))?;
+        let response = client::send(request)
+            .await
+            .map_err(|error| failed(error.to_string()))?;
+
+        // Some providers respond to errors with a 200 and an error in the body
+        let json: serde_json::Value =
+            serde_json::from_slice(response.body()).map_err(|error| failed(error.to_string()))?;
+        if let Some(error) = json.get("error") {
+            let description = json
+                .get("error_description")
+                .and_then(|description| description.as_str())
+                .or_else(|| error.as_str())
+                .unwrap_or("unknown error");
+            return Err(failed(description.to_string()));
+        }
+        if !response.status().is_success() {
+            return Err(failed(format!(
+                "token endpoint responded with {}",
+                response.status()
+            )));
+        }
+        serde_json::from_value(json).map_err(|error| failed(error.to_string()))
+    }
+
+    /// Route that redirects to the provider's authorization page
+    pub fn login<P: Into<String>>(&self, path: P) -> OAuthLogin {
+        OAuthLogin {
+            path: path.into(),
+            oauth: self.clone(),
+        }
+    }
+
+    /// Route for the redirect url that finishes the login and calls the handler with the
+    /// token. A denied login, a state that doesn't match, or a failed exchange is passed to
+    /// the handler as an error.
+    pub fn callback<F, R>(&self, handler: F) -> OAuthCallback<F>
+    where
+        F: Fn(Result<Token>) -> R + Send + Sync,
+        R: ToResponse,
+    {
+        let path = self
+            .redirect_url
+            .parse::<Uri>()
+            .map(|uri| uri.path().to_string())
+            .unwrap_or_else(|_| "/".to_string());
+        OAuthCallback {
+            path,
+            oauth: self.clone(),
+            handler,
+        }
+    }
+
+    /// Check the callback's query against the login cookie and exchange the code
+    fn finish(&self, uri: &Uri, headers: &HeaderMap) -> Result<Token> {
+        let params: HashMap<String, String> =
+            serde_qs::from_str(uri.query().unwrap_or("")).unwrap_or_default();
+        if let Some(error) = params.get("error") {
+            let description = params.get("error_description").unwrap_or(error);
+            return Err((401, format!("Login was not completed: {}", description)));
+        }
+
+        let jar = CookieJar::from_headers(headers);
+        let (state, verifier) = jar
+            .get_signed(OAUTH_COOKIE)
+            .and_then(|value| value.split_once('.'))
+            .ok_or_else(|| (400, "Login expired or was not started".to_string()))?;
+        let matches = params
+            .get("state")
+            .map(|given| constant_eq(given.as_bytes(), state.as_bytes()))
+            .unwrap_or(false);
+        if !matches {
+            return Err((400, "Login state does not match".to_string()));
+        }
+        let code = params
+            .get("code")
+            .ok_or_else(|| (400, "Missing authorization code".to_string()))?;
+
+        block_on(self.exchange(code, verifier))
+    }
+}
+
+/// Access token from the provider's token endpoint. Other fields the provider sent are in
+/// `extra`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Token {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: Option<u64>,
+    pub refresh_token: Option<String>,
+    pub scope: Option<String>,
+    /// OpenID Connect id token
+    pub id_token: Option<String>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// Redirect to a provider's authorization page that stores the login's state in a cookie
+#[derive(Debug, Clone)]
+pub struct Authorize {
+    location: String,
+    cookie: String,
+}
+
+impl Authorize {
+    /// Url of the provider's authorization page
+    pub fn url(&self) -> &str {
+        &self.location
+    }
+}
+
+impl ToResponse for Authorize {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        let mut jar = CookieJar::default();
+        // Lax so the cookie is sent when the provider redirects back
+        jar.set_signed(
+            Cookie::new(OAUTH_COOKIE, self.cookie)
+                .with_path("/")
+                .with_max_age(600)
+                .with_http_only(true)
+                .with_same_site(SameSite::Lax),
+        );
+        let redirect = hyper::Response::builder()
+            .status(303)
+            .header("Location", self.location)
+            .body(Full::new(Bytes::new()))
+            .map_err(|_| (500, "Invalid oauth authorization url".to_string()))?;
+        (jar, Raw(redirect)).to_response(method, uri, headers, body)
+    }
+}
+
+/// Response that is already built
+struct Raw(hyper::Response<Full<Bytes>>);
+
+impl ToResponse for Raw {
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        _headers: &HeaderMap,
+        _body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        Ok(self.0)
+    }
+}
+
+/// Route created with `OAuth2::login`
+#[derive(Debug)]
+pub struct OAuthLogin {
+    path: String,
+    oauth: OAuth2,
+}
+
+impl Endpoint for OAuthLogin {
+    fn methods(&self) -> Vec<Method> {
+        vec![Method::GET]
+    }
+
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    fn execute(
+        &self,
+        method: &Method,
+        uri: &mut Uri,
+        headers: &HeaderMap,
+        body: &Bytes,
+        _trailers: &HeaderMap,
+        _stream: &BodyStream,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        self.oauth
+            .authorize()
+            .to_response(method, uri, headers, body)
+    }
+}
+
+/// Route created with `OAuth2::callback`
+pub struct OAuthCallback<F> {
+    path: String,
+    oauth: OAuth2,
+    handler: F,
+}
+
+impl<F> Debug for OAuthCallback<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OAuthCallback")
+            .field("path", &self.path)
+            .field("oauth", &self.oauth)
+            .finish()
+    }
+}
+
+impl<F, R> Endpoint for OAuthCallback<F>
+where
+    F: Fn(Result<Token>) -> R + Send + Sync,
+    R: ToResponse,
+{
+    fn methods(&self) -> Vec<Method> {
+        vec![Method::GET]
+    }
+
+    fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    fn execute(
+        &self,
+        method: &Method,
+        uri: &mut Uri,
+        headers: &HeaderMap,
+        body: &Bytes,
+        _trailers: &HeaderMap,
+        _stream: &BodyStream,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        let token = self.oauth.finish(uri, headers);
+
+        // The state can only be used once
+        let mut jar = CookieJar::default();
+        jar.delete_with(OAUTH_COOKIE, Some("/"), None);
+        (jar, (self.handler)(token)).to_response(method, uri, headers, body)
+    }
+}
@@ -0,0 +1,302 @@
+use std::{
+    env,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use http_body_util::Full;
+use hyper::header::{HeaderValue, CONTENT_TYPE, USER_AGENT};
+use serde_json::{json, Value};
+
+use crate::{
+    client,
+    cookie::random_key,
+    layer::{Layer, Next, Request, Response},
+    shutdown::Shutdown,
+};
+
+/// Most spans kept while waiting to be exported; older spans are dropped past this
+const MAX_QUEUE: usize = 2048;
+
+/// Export a span for every request to an OpenTelemetry collector over OTLP/HTTP with json.
+///
+/// Spans continue the trace from a `traceparent` header and the request's `traceparent` is
+/// replaced with the new span so handlers can pass it on to other services. Spans are sent in
+/// batches every few seconds and once more when the server shuts down.
+///
+/// The endpoint, `service.name`, and resource attributes default to the
+/// `OTEL_EXPORTER_OTLP_ENDPOINT`, `OTEL_SERVICE_NAME`, and `OTEL_RESOURCE_ATTRIBUTES`
+/// environment variables. `https://` endpoints require the `tls` feature.
+///
+/// # Example
+/// ```no_run
+/// use tela::{otel::Otel, prelude::*, Server};
+///
+/// #[get("/")]
+/// fn home() -> &'static str {
+///     "Home"
+/// }
+///
+/// #[tela::main]
+/// async fn main() {
+///     Server::new()
+///         .otel(
+///             Otel::new()
+///                 .endpoint("http://collector:4318")
+///                 .service_name("blog")
+///                 .resource("deployment.environment", "production"),
+///         )
+///         .route(home)
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Otel {
+    endpoint: String,
+    resource: Vec<(String, String)>,
+    interval: Duration,
+    queue: Arc<Mutex<Vec<Value>>>,
+    exporting: Arc<OnceLock<()>>,
+}
+
+impl Otel {
+    pub fn new() -> Self {
+        let mut otel = Otel {
+            endpoint: env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+                .unwrap_or_else(|_| "http://localhost:4318".to_string()),
+            resource: Vec::new(),
+            interval: Duration::from_secs(5),
+            queue: Arc::new(Mutex::new(Vec::new())),
+            exporting: Arc::new(OnceLock::new()),
+        };
+
+        // `OTEL_RESOURCE_ATTRIBUTES` is a comma separated list of `key=value`
+        if let Ok(attributes) = env::var("OTEL_RESOURCE_ATTRIBUTES") {
+            for (key, value) in attributes
+                .split(',')
+                .filter_map(|pair| pair.split_once('='))
+            {
+                otel = otel.resource(key.trim(), value.trim());
+            }
+        }
+        match env::var("OTEL_SERVICE_NAME") {
+            Ok(name) => otel.service_name(name),
+            Err(_) if !otel.has_resource("service.name") => otel.service_name("tela"),
+            Err(_) => otel,
+        }
+    }
+
+    /// Base url of the collector; spans are sent to `{endpoint}/v1/traces`
+    pub fn endpoint<T: Into<String>>(mut self, endpoint: T) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Name of the service the spans are from; the `service.name` resource attribute
+    pub fn service_name<T: Into<String>>(self, name: T) -> Self {
+        self.resource("service.name", name)
+    }
+
+    /// Attribute of the service the spans are from; ex: `service.version` or
+    /// `deployment.environment`. Setting a key again replaces its value.
+    pub fn resource<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        let key = key.into();
+        self.resource.retain(|(k, _)| *k != key);
+        self.resource.push((key, value.into()));
+        self
+    }
+
+    /// How often spans are exported, 5 seconds by default
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    fn has_resource(&self, key: &str) -> bool {
+        self.resource.iter().any(|(k, _)| k == key)
+    }
+
+    fn record(&self, span: Value) {
+        let mut queue = self.queue.lock().unwrap();
+        if queue.len() >= MAX_QUEUE {
+            queue.remove(0);
+        }
+        queue.push(span);
+    }
+
+    /// Start exporting in the background the first time a request is traced
+    fn start(&self) {
+        self.exporting.get_or_init(|| {
+            let otel = self.clone();
+            // Held so a graceful shutdown waits for the last export
+            let guard = Shutdown::current().guard();
+            tokio::spawn(async move {
+                let mut shutdown = guard.signal();
+                loop {
+                    tokio::select! {
+                        _ = tokio::time::sleep(otel.interval) => otel.export().await,
+                        _ = &mut shutdown => {
+                            otel.export().await;
+                            break;
+                        }
+                    }
+                }
+            });
+        });
+    }
+
+    async fn export(&self) {
+        let spans = std::mem::take(&mut *self.queue.lock().unwrap());
+        if spans.is_empty() {
+            return;
+        }
+
+        let body = json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": self
+                        .resource
+                        .iter()
+                        .map(|(key, value)| attribute(key, json!({ "stringValue": value })))
+                        .collect::<Vec<_>>(),
+                },
+                "scopeSpans": [{
+                    "scope": { "name": "tela", "version": env!("CARGO_PKG_VERSION") },
+                    "spans": spans,
+                }],
+            }],
+        });
+
+        let request =
+            hyper::Request::post(format!("{}/v1/traces", self.endpoint.trim_end_matches('/')))
+                .header(CONTENT_TYPE, "application/json")
+                .body(Full::new(Bytes::from(body.to_string())));
+        let result = match request {
+            Ok(request) => client::send(request)
+                .await
+                .map(|response| response.status()),
+            Err(error) => {
+                eprintln!("Failed to export spans: {}", error);
+                return;
+            }
+        };
+        match result {
+            Ok(status) if status.is_success() => {}
+            Ok(status) => eprintln!("Failed to export spans: collector responded {}", status),
+            Err(error) => eprintln!("Failed to export spans: {}", error),
+        }
+    }
+}
+
+impl Default for Otel {
+    fn default() -> Self {
+        Otel::new()
+    }
+}
+
+fn attribute(key: &str, value: Value) -> Value {
+    json!({ "key": key, "value": value })
+}
+
+fn now() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_nanos())
+        .unwrap_or_default()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Trace and parent span ids from a `traceparent` header; ex:
+/// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`
+fn parent(value: &str) -> Option<(String, String)> {
+    let mut parts = value.trim().split('-');
+    let (_version, trace, span) = (parts.next()?, parts.next()?, parts.next()?);
+    let valid = |id: &str, len: usize| {
+        id.len() == len && id.chars().all(|c| c.is_ascii_hexdigit()) && id.chars().any(|c| c != '0')
+    };
+    match valid(trace, 32) && valid(span, 16) {
+        true => Some((trace.to_ascii_lowercase(), span.to_ascii_lowercase())),
+        false => None,
+    }
+}
+
+impl Layer for Otel {
+    fn call<'a>(&'a self, mut request: Request, next: Next<'a>) -> BoxFuture<'a, Response> {
+        Box::pin(async move {
+            self.start();
+
+            let key = random_key();
+            let parent = request
+                .headers
+                .get("traceparent")
+                .and_then(|value| value.to_str().ok())
+                .and_then(parent);
+            let (trace_id, parent_id) = match parent {
+                Some((trace, span)) => (trace, Some(span)),
+                None => (hex(&key[..16]), None),
+            };
+            let span_id = hex(&key[16..24]);
+            if let Ok(value) = HeaderValue::from_str(&format!("00-{}-{}-01", trace_id, span_id)) {
+                request.headers.insert("traceparent", value);
+            }
+
+            let method = request.method.to_string();
+            let mut attributes = vec![
+                attribute("http.request.method", json!({ "stringValue": method })),
+                attribute("url.path", json!({ "stringValue": request.uri.path() })),
+            ];
+            if let Some(query) = request.uri.query() {
+                attributes.push(attribute("url.query", json!({ "stringValue": query })));
+            }
+            if let Some(agent) = request
+                .headers
+                .get(USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+            {
+                attributes.push(attribute(
+                    "user_agent.original",
+                    json!({ "stringValue": agent }),
+                ));
+            }
+            if let Some(remote) = request.remote {
+                attributes.push(attribute(
+                    "client.address",
+                    json!({ "stringValue": remote.ip().to_string() }),
+                ));
+            }
+
+            let start = now();
+            let response = next.run(request).await;
+            let status = response.status().as_u16();
+            attributes.push(attribute(
+                "http.response.status_code",
+                json!({ "intValue": status.to_string() }),
+            ));
+
+            let mut span = json!({
+                "traceId": trace_id,
+                "spanId": span_id,
+                "name": method,
+                // SPAN_KIND_SERVER
+                "kind": 2,
+                "startTimeUnixNano": start.to_string(),
+                "endTimeUnixNano": now().to_string(),
+                "attributes": attributes,
+                // STATUS_CODE_ERROR for server errors, otherwise unset
+                "status": { "code": if status >= 500 { 2 } else { 0 } },
+            });
+            if let Some(parent_id) = parent_id {
+                span["parentSpanId"] = json!(parent_id);
+            }
+            self.record(span);
+
+            response
+        })
+    }
+}
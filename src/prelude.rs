@@ -1,10 +1,11 @@
-pub use crate::request::{Catch, Endpoint, ToParam};
+pub use crate::request::{Catch, DefaultHeader, Describe, Endpoint, ToParam};
 pub use crate::response::{template::TemplateEngine, Result, ToErrorResponse, ToResponse};
-pub use crate::{context, group, response, template};
+pub use crate::{asset, context, fetch, group, hydrate, response, routes, socket, template};
 pub use html_to_string_macro::html as html_raw;
 pub use serde_json::json;
 pub use tela_macros::{
-    catch, connect, delete, get, head, html, options, patch, post, put, request, trace,
+    catch, connect, delete, get, handler, head, html, memo, options, patch, post, put, request,
+    trace, url, HtmlForm,
 };
 
 #[macro_export]
@@ -27,6 +28,141 @@ macro_rules! group {
     };
 }
 
+/// Build a `Server` from a table of paths. Each path has the handlers for its methods, or the
+/// directory of static files served under it.
+///
+/// The table is checked when it is compiled. A path can only be in it once, and each handler
+/// must be for the path and method it is listed under.
+///
+/// # Example
+/// ```no_run
+/// use tela::prelude::*;
+///
+/// #[get("/")]
+/// fn home() -> String {
+///     "Home".to_string()
+/// }
+///
+/// #[post("/")]
+/// fn create() -> String {
+///     "Created".to_string()
+/// }
+///
+/// #[tela::main]
+/// async fn main() {
+///     routes! {
+///         "/" => get(home).post(create),
+///         "/static" => assets("web"),
+///     }
+///     .serve(3000)
+///     .await
+/// }
+/// ```
+#[macro_export]
+macro_rules! routes {
+    (@entry $server: ident, $path: literal => assets($dir: expr)) => {
+        $server.assets_at($path, $dir)
+    };
+    (@entry $server: ident, $path: literal => $($method: ident($handler: path)).+) => {{
+        $(
+            const _: () = assert!(
+                $crate::uri::same(<$handler>::PATH, $path, false),
+                concat!("routes! ", stringify!($handler), " isn't a route for ", $path)
+            );
+            const _: () = assert!(
+                $crate::uri::has_method(<$handler>::METHODS, stringify!($method)),
+                concat!("routes! ", stringify!($handler), " doesn't handle ", stringify!($method))
+            );
+        )+
+        $server$(.route($handler))+
+    }};
+    ($($path: literal => $kind: ident $args: tt $(.$method: ident $handler: tt)*),* $(,)?) => {{
+        const _: () = assert!(
+            !$crate::uri::has_duplicate(&[$($path),*]),
+            "routes! has a path more than once"
+        );
+        let server = $crate::Server::new();
+        $(let server = $crate::routes!(@entry server, $path => $kind $args $(.$method $handler)*);)*
+        server
+    }};
+}
+
+/// Build a `Socket` that uses the `PORT` environment variable's port when it is set.
+///
+/// # Example
+/// ```no_run
+/// use tela::{prelude::*, Server};
+///
+/// #[tela::main]
+/// async fn main() {
+///     let _ = socket!(); // localhost on `PORT` or 3000
+///     let _ = socket!([0, 0, 0, 0], 8080); // any ip given as `Into<IpAddr>` on `PORT` or 8080
+///     Server::new()
+///         .serve(socket!(3000)) // localhost on `PORT` or 3000
+///         .await
+/// }
+/// ```
+#[macro_export]
+macro_rules! socket {
+    () => {
+        $crate::Socket::default().env()
+    };
+    ($port: expr) => {
+        $crate::Socket::Local($port).env()
+    };
+    ($ip: expr, $port: expr) => {
+        $crate::Socket::Addr(::std::net::IpAddr::from($ip), $port).env()
+    };
+}
+
+/// Url of an asset that is fingerprinted when the server uses `fingerprint_assets`
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, response::HTML};
+///
+/// #[get("/")]
+/// fn home() -> HTML<String> {
+///     html! {
+///         <link rel="stylesheet" href={asset!("css/app.css")} />
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! asset {
+    ($path: expr) => {
+        $crate::assets::url($path)
+    };
+}
+
+/// Create a `tela::response::Island` for a client component. The component name
+/// is the identifier passed in.
+///
+/// # Example
+/// ```
+/// # use tela::prelude::*;
+/// #[derive(serde::Serialize)]
+/// struct Counter {
+///     count: u32,
+/// }
+///
+/// # fn main() -> Result<()> {
+/// let props = Counter { count: 0 };
+/// let island = hydrate!(Counter, props)?;
+/// let island = hydrate!(Counter, props, html!(<button>"0"</button>))?;
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! hydrate {
+    ($component: ident, $props: expr $(,)?) => {
+        $crate::response::Island::new(stringify!($component), &$props, String::new())
+    };
+    ($component: ident, $props: expr, $markup: expr $(,)?) => {
+        $crate::response::Island::new(stringify!($component), &$props, $markup)
+    };
+}
+
 #[cfg(feature = "tera")]
 #[macro_export]
 macro_rules! tera {
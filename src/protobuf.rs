@@ -0,0 +1,124 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{HeaderMap, Method, Uri};
+use prost::Message;
+
+use crate::{
+    request::{RequestData, ToParam},
+    response::{Result, ToResponse},
+};
+
+/// Content type of protobuf requests and responses
+pub const CONTENT_TYPE: &str = "application/x-protobuf";
+
+/// Largest protobuf body that is decoded, 4 MiB by default
+static LIMIT: AtomicUsize = AtomicUsize::new(4 * 1024 * 1024);
+
+/// Set the largest protobuf body, in bytes, that `Protobuf` will decode. Larger requests
+/// get a `413 Payload Too Large`.
+pub fn limit(bytes: usize) {
+    LIMIT.store(bytes, Ordering::Relaxed);
+}
+
+/// Protocol buffer message encoded with prost; `application/x-protobuf`
+///
+/// As a parameter the request body is decoded into the message. The request must have a
+/// protobuf content type and a body no larger than the limit, see `protobuf::limit`. As a
+/// response the message is encoded as the body.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, protobuf::Protobuf};
+///
+/// #[derive(Clone, PartialEq, prost::Message)]
+/// struct User {
+///     #[prost(string, tag = "1")]
+///     name: String,
+/// }
+///
+/// #[post("/users")]
+/// fn create(Protobuf(user): Protobuf<User>) -> Protobuf<User> {
+///     Protobuf(user)
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Protobuf<T: Message>(pub T);
+
+/// Whether the content type is a protobuf content type, ignoring parameters
+fn is_protobuf(headers: &HeaderMap) -> bool {
+    headers
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            let mime = value.split(';').next().unwrap_or("").trim();
+            mime.eq_ignore_ascii_case(CONTENT_TYPE)
+                || mime.eq_ignore_ascii_case("application/protobuf")
+        })
+        .unwrap_or(false)
+}
+
+fn decode<T: Message + Default>(data: &RequestData) -> Result<Protobuf<T>> {
+    if !is_protobuf(&data.3) {
+        return Err((
+            415,
+            format!("Expected a request with content type {}", CONTENT_TYPE),
+        ));
+    }
+
+    let limit = LIMIT.load(Ordering::Relaxed);
+    let length = data
+        .3
+        .get(hyper::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    if length > limit || data.2.len() > limit {
+        return Err((
+            413,
+            format!("Protobuf body is larger than the limit of {} bytes", limit),
+        ));
+    }
+
+    T::decode(data.2.clone()).map(Protobuf).map_err(|err| {
+        (
+            400,
+            format!("Failed to decode protobuf from request: {}", err),
+        )
+    })
+}
+
+impl<T: Message + Default> ToParam<Protobuf<T>> for RequestData {
+    fn to_param(&mut self) -> Result<Protobuf<T>> {
+        decode(self)
+    }
+}
+
+impl<T: Message + Default> ToParam<Option<Protobuf<T>>> for RequestData {
+    fn to_param(&mut self) -> Result<Option<Protobuf<T>>> {
+        Ok(decode(self).ok())
+    }
+}
+
+impl<T: Message + Default> ToParam<Result<Protobuf<T>>> for RequestData {
+    fn to_param(&mut self) -> Result<Result<Protobuf<T>>> {
+        Ok(decode(self))
+    }
+}
+
+impl<T: Message> ToResponse for Protobuf<T> {
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        _headers: &HeaderMap,
+        _body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        Ok(hyper::Response::builder()
+            .status(200)
+            .header("Content-Type", CONTENT_TYPE)
+            .body(Full::new(Bytes::from(self.0.encode_to_vec())))
+            .unwrap())
+    }
+}
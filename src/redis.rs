@@ -0,0 +1,189 @@
+//! Minimal redis client, just the RESP protocol over a tcp stream, shared by the redis
+//! backplane and session store
+
+use std::io;
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    sync::Mutex,
+};
+
+/// Reply to a redis command in the RESP protocol
+#[derive(Debug)]
+pub(crate) enum Reply {
+    Simple,
+    Error(String),
+    Integer,
+    Bulk(Option<Bytes>),
+    Array(Option<Vec<Reply>>),
+}
+
+pub(crate) fn invalid<T: Into<String>>(message: T) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Encode a command as an array of bulk strings
+fn command(args: &[&[u8]]) -> Vec<u8> {
+    let mut encoded = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        encoded.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        encoded.extend_from_slice(arg);
+        encoded.extend_from_slice(b"\r\n");
+    }
+    encoded
+}
+
+pub(crate) struct Connection(BufReader<TcpStream>);
+
+impl std::fmt::Debug for Connection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Connection")
+    }
+}
+
+impl Connection {
+    pub(crate) async fn open(address: &str, password: Option<&str>) -> io::Result<Self> {
+        let mut connection = Connection(BufReader::new(TcpStream::connect(address).await?));
+        if let Some(password) = password {
+            connection.call(&[b"AUTH", password.as_bytes()]).await?;
+        }
+        Ok(connection)
+    }
+
+    pub(crate) async fn send(&mut self, args: &[&[u8]]) -> io::Result<()> {
+        self.0.get_mut().write_all(&command(args)).await
+    }
+
+    /// Send a command and wait for its reply. Error replies are returned as errors.
+    pub(crate) async fn call(&mut self, args: &[&[u8]]) -> io::Result<Reply> {
+        self.send(args).await?;
+        match self.read().await? {
+            Reply::Error(error) => Err(io::Error::other(error)),
+            reply => Ok(reply),
+        }
+    }
+
+    pub(crate) fn read(&mut self) -> BoxFuture<'_, io::Result<Reply>> {
+        Box::pin(async move {
+            let mut line = String::new();
+            if self.0.read_line(&mut line).await? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                return Err(invalid("Empty redis reply"));
+            }
+            let (kind, value) = line.split_at(1);
+            let number = || {
+                value
+                    .parse::<i64>()
+                    .map_err(|_| invalid(format!("Invalid redis reply {:?}", line)))
+            };
+            Ok(match kind {
+                "+" => Reply::Simple,
+                "-" => Reply::Error(value.to_string()),
+                ":" => {
+                    number()?;
+                    Reply::Integer
+                }
+                "$" => match usize::try_from(number()?) {
+                    Ok(len) => {
+                        let mut bulk = vec![0; len + 2];
+                        self.0.read_exact(&mut bulk).await?;
+                        bulk.truncate(len);
+                        Reply::Bulk(Some(Bytes::from(bulk)))
+                    }
+                    Err(_) => Reply::Bulk(None),
+                },
+                "*" => match usize::try_from(number()?) {
+                    Ok(len) => {
+                        let mut items = Vec::with_capacity(len);
+                        for _ in 0..len {
+                            items.push(self.read().await?);
+                        }
+                        Reply::Array(Some(items))
+                    }
+                    Err(_) => Reply::Array(None),
+                },
+                _ => return Err(invalid(format!("Unknown redis reply {:?}", line))),
+            })
+        })
+    }
+}
+
+/// Where to connect to redis
+#[derive(Debug, Clone)]
+pub(crate) struct Address {
+    host: String,
+    pub(crate) password: Option<String>,
+}
+
+impl Address {
+    /// Parse `host:port` or a `redis://[:password@]host[:port]` url. The port defaults to
+    /// `6379`.
+    pub(crate) fn parse(address: &str) -> Self {
+        let address = address.strip_prefix("redis://").unwrap_or(address);
+        let address = address.split('/').next().unwrap_or(address);
+        let (password, host) = match address.rsplit_once('@') {
+            Some((user, host)) => {
+                let password = user.rsplit(':').next().unwrap_or(user);
+                (Some(password.to_string()), host)
+            }
+            None => (None, address),
+        };
+        Address {
+            host: match host.contains(':') {
+                true => host.to_string(),
+                false => format!("{}:6379", host),
+            },
+            password: password.filter(|password| !password.is_empty()),
+        }
+    }
+
+    pub(crate) async fn open(&self) -> io::Result<Connection> {
+        Connection::open(&self.host, self.password.as_deref()).await
+    }
+}
+
+/// Address with a connection that is reused for commands
+#[derive(Debug)]
+pub(crate) struct Client {
+    pub(crate) address: Address,
+    connection: Mutex<Option<Connection>>,
+}
+
+impl Client {
+    pub(crate) fn new(address: &str) -> Self {
+        Client {
+            address: Address::parse(address),
+            connection: Mutex::new(None),
+        }
+    }
+
+    /// Send a command on the shared connection and wait for its reply
+    pub(crate) async fn call(&self, args: &[&[u8]]) -> io::Result<Reply> {
+        let mut connection = self.connection.lock().await;
+        // A connection that was closed by redis is only noticed when it is used so retry
+        // once with a new one
+        for retry in [false, true] {
+            if connection.is_none() {
+                *connection = Some(self.address.open().await?);
+            }
+            match connection.as_mut().unwrap().call(args).await {
+                Ok(reply) => return Ok(reply),
+                // Redis answered so the connection is still good
+                Err(error) if error.kind() == io::ErrorKind::Other => return Err(error),
+                Err(error) => {
+                    *connection = None;
+                    if retry {
+                        return Err(error);
+                    }
+                }
+            }
+        }
+        unreachable!()
+    }
+}
@@ -0,0 +1,103 @@
+use bytes::Bytes;
+use http_body_util::Full;
+
+use super::{BodyStream, Endpoint};
+use crate::response::Result;
+
+/// Documentation attached to a route with `describe` and `tag`. Listed by `Router::routes`
+/// and the debug panel.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Attach documentation to an endpoint. Implemented for every endpoint.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, request::Body, Server};
+///
+/// #[derive(serde::Deserialize)]
+/// struct User { name: String }
+///
+/// #[post("/users")]
+/// fn create_user(Body(user): Body<User>) -> Result<String> {
+///     response!(format!("Created {}", user.name))
+/// }
+///
+/// Server::new().route(create_user.describe("Creates a user").tag("users"));
+/// ```
+pub trait Describe: Endpoint + Sized {
+    fn describe<T: Into<String>>(self, description: T) -> Described<Self> {
+        Described::new(self).describe(description)
+    }
+
+    fn tag<T: Into<String>>(self, tag: T) -> Described<Self> {
+        Described::new(self).tag(tag)
+    }
+}
+
+impl<E: Endpoint> Describe for E {}
+
+/// Endpoint with documentation; see `Describe`
+#[derive(Debug)]
+pub struct Described<E> {
+    endpoint: E,
+    metadata: Metadata,
+}
+
+impl<E: Endpoint> Described<E> {
+    fn new(endpoint: E) -> Self {
+        Described {
+            endpoint,
+            metadata: Metadata::default(),
+        }
+    }
+
+    /// What the route does
+    pub fn describe<T: Into<String>>(mut self, description: T) -> Self {
+        self.metadata.description = Some(description.into());
+        self
+    }
+
+    /// Group the route with others; a route can have many tags
+    pub fn tag<T: Into<String>>(mut self, tag: T) -> Self {
+        let tag = tag.into();
+        if !self.metadata.tags.contains(&tag) {
+            self.metadata.tags.push(tag);
+        }
+        self
+    }
+}
+
+impl<E: Endpoint> Endpoint for Described<E> {
+    fn methods(&self) -> Vec<hyper::Method> {
+        self.endpoint.methods()
+    }
+
+    fn path(&self) -> String {
+        self.endpoint.path()
+    }
+
+    fn execute(
+        &self,
+        method: &hyper::Method,
+        uri: &mut hyper::Uri,
+        headers: &hyper::HeaderMap,
+        body: &Bytes,
+        trailers: &hyper::HeaderMap,
+        stream: &BodyStream,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        self.endpoint
+            .execute(method, uri, headers, body, trailers, stream)
+    }
+
+    fn streams(&self) -> bool {
+        self.endpoint.streams()
+    }
+
+    fn metadata(&self) -> Metadata {
+        self.metadata.clone()
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use serde::de::DeserializeOwned;
+
+use crate::response::{escape, Result};
+
+pub trait IntoForm {
+    fn into_form(body: &str) -> Result<Form<Self>>
+    where
+        Self: Sized;
+}
+
+/// Data sent from a html form; `application/x-www-form-urlencoded`
+///
+/// The data is parsed with serde_qs into the generic type. For `GET` and `HEAD` requests,
+/// `<form method="get">`, the data is read from the uri query. Otherwise it is read from
+/// the body, which is decoded with the `charset` of the content type. Utf-8 and latin-1
+/// charsets are supported.
+#[derive(Debug, Clone, Copy)]
+pub struct Form<T: IntoForm>(pub T);
+
+impl<T: DeserializeOwned> IntoForm for T {
+    fn into_form(body: &str) -> Result<Form<Self>>
+    where
+        Self: Sized,
+    {
+        match serde_qs::from_str::<T>(body) {
+            Ok(result) => Ok(Form(result)),
+            Err(err) => Err((400, format!("Failed to parse form from request: {}", err))),
+        }
+    }
+}
+
+/// Error messages for form fields, keyed by the field's input name
+#[derive(Debug, Default, Clone)]
+pub struct FormErrors(HashMap<String, String>);
+
+impl FormErrors {
+    pub fn new() -> Self {
+        FormErrors(HashMap::new())
+    }
+
+    pub fn insert<N: Into<String>, M: Into<String>>(&mut self, name: N, message: M) {
+        self.0.insert(name.into(), message.into());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(|m| m.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Value of a field that can be rendered as a form input
+pub trait FormValue {
+    /// Input type used for the field; ex: `text`, `number`, or `checkbox`
+    const INPUT: &'static str = "text";
+
+    fn form_value(&self) -> Option<String>;
+}
+
+macro_rules! form_value {
+    ($input: literal: $($type: ty),*) => {
+        $(
+            impl FormValue for $type {
+                const INPUT: &'static str = $input;
+                fn form_value(&self) -> Option<String> {
+                    Some(self.to_string())
+                }
+            }
+        )*
+    };
+}
+
+form_value!("text": String, &str, char);
+form_value!("number": u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+form_value!("checkbox": bool);
+
+impl<T: FormValue> FormValue for Option<T> {
+    const INPUT: &'static str = T::INPUT;
+    fn form_value(&self) -> Option<String> {
+        self.as_ref().and_then(|value| value.form_value())
+    }
+}
+
+/// Render a labeled input along with it's error message
+pub fn render_input(
+    name: &str,
+    label: &str,
+    input: &str,
+    value: Option<String>,
+    error: Option<&str>,
+) -> String {
+    let name = escape(name);
+    let mut html = format!(
+        r#"<label for="{name}">{}</label><input id="{name}" name="{name}" type="{}""#,
+        escape(label),
+        escape(input)
+    );
+
+    match (input, value) {
+        ("checkbox", value) => {
+            html.push_str(r#" value="true""#);
+            if value.as_deref() == Some("true") {
+                html.push_str(" checked");
+            }
+        }
+        (_, Some(value)) => html.push_str(&format!(r#" value="{}""#, escape(value))),
+        _ => {}
+    }
+
+    match error {
+        Some(error) => html.push_str(&format!(
+            r#" aria-invalid="true"/><small class="error">{}</small>"#,
+            escape(error)
+        )),
+        None => html.push_str("/>"),
+    }
+    html
+}
+
+/// Render a struct as html form inputs. Use `#[derive(HtmlForm)]` to implement this
+/// for a struct that is also parsed with `Form<T>`, so the input names always match the
+/// parsed fields.
+///
+/// Fields can be customized with `#[form(label = "...", input = "...")]`, and the
+/// `#[serde(rename = "...")]` name is used as the input name.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, request::{Form, FormErrors, HtmlForm}, response::HTML};
+///
+/// #[derive(Default, serde::Deserialize, HtmlForm)]
+/// struct Login {
+///     username: String,
+///     #[form(input = "password")]
+///     password: String,
+/// }
+///
+/// #[get("/login")]
+/// fn login() -> HTML<String> {
+///     HTML(Login::default().render("/login", &FormErrors::new()))
+/// }
+///
+/// #[post("/login")]
+/// fn submit(Form(form): Form<Login>) -> HTML<String> {
+///     HTML(format!("<p>Welcome back, {}</p>", form.username))
+/// }
+/// ```
+pub trait HtmlForm {
+    /// Render the inputs for each field
+    fn inputs(&self, errors: &FormErrors) -> String;
+
+    /// Render a `post` form with the inputs and a submit button
+    fn render(&self, action: &str, errors: &FormErrors) -> String {
+        format!(
+            r#"<form method="post" action="{}">{}<button type="submit">Submit</button></form>"#,
+            escape(action),
+            self.inputs(errors)
+        )
+    }
+}
@@ -0,0 +1,20 @@
+use hyper::{HeaderMap, Method, Uri};
+
+/// Method, uri, and headers of the request that caused an error. Passed to catches that
+/// take it as an argument.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, request::Head, response::HTML};
+///
+/// #[catch(404)]
+/// async fn not_found(head: Head) -> HTML<String> {
+///     html!(<h1>{head.uri.path()}" was not found"</h1>)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Head {
+    pub method: Method,
+    pub uri: Uri,
+    pub headers: HeaderMap,
+}
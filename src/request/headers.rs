@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{
+    header::{HeaderName, HeaderValue},
+    HeaderMap,
+};
+
+use super::{BodyStream, Endpoint, Metadata};
+use crate::response::Result;
+
+/// Headers added to a route's responses when the handler doesn't set them. Implemented for
+/// every endpoint and for groups of routes made with `group!`. Names or values that aren't
+/// valid in a header are ignored.
+///
+/// Route defaults are used before the ones from `Server::default_header`, and only for
+/// responses from the handler; not for error pages.
+///
+/// A group needs its type written out so the routes are collected as endpoints first.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use tela::{prelude::*, Server};
+///
+/// #[get("/")]
+/// fn home() -> &'static str {
+///     "Home"
+/// }
+///
+/// #[get("/api/users")]
+/// fn list_users() -> &'static str {
+///     "[]"
+/// }
+///
+/// #[post("/api/users")]
+/// fn create_user() -> &'static str {
+///     "{}"
+/// }
+///
+/// let api: Vec<Arc<dyn Endpoint>> = group![list_users, create_user];
+///
+/// Server::new()
+///     .default_header("X-Content-Type-Options", "nosniff")
+///     .route(home.default_header("Cache-Control", "public, max-age=60"))
+///     .routes(api.default_header("Cache-Control", "no-store"));
+/// ```
+pub trait DefaultHeader: Sized {
+    type Output;
+
+    fn default_header<N: AsRef<str>, V: AsRef<str>>(self, name: N, value: V) -> Self::Output;
+}
+
+/// Parse a header, or `None` if the name or value is invalid
+fn header<N: AsRef<str>, V: AsRef<str>>(name: N, value: V) -> Option<(HeaderName, HeaderValue)> {
+    Some((
+        HeaderName::from_bytes(name.as_ref().as_bytes()).ok()?,
+        HeaderValue::from_str(value.as_ref()).ok()?,
+    ))
+}
+
+impl<E: Endpoint> DefaultHeader for E {
+    type Output = WithHeaders<E>;
+
+    fn default_header<N: AsRef<str>, V: AsRef<str>>(self, name: N, value: V) -> WithHeaders<E> {
+        WithHeaders {
+            endpoint: self,
+            headers: HeaderMap::new(),
+        }
+        .default_header(name, value)
+    }
+}
+
+impl DefaultHeader for Vec<Arc<dyn Endpoint>> {
+    type Output = Vec<Arc<dyn Endpoint>>;
+
+    fn default_header<N: AsRef<str>, V: AsRef<str>>(self, name: N, value: V) -> Self::Output {
+        let Some((name, value)) = header(name, value) else {
+            return self;
+        };
+        self.into_iter()
+            .map(|endpoint| {
+                let mut headers = HeaderMap::new();
+                headers.insert(name.clone(), value.clone());
+                Arc::new(WithHeaders { endpoint, headers }) as Arc<dyn Endpoint>
+            })
+            .collect()
+    }
+}
+
+/// Endpoint with default response headers; see `DefaultHeader`
+#[derive(Debug)]
+pub struct WithHeaders<E> {
+    endpoint: E,
+    headers: HeaderMap,
+}
+
+impl<E: Endpoint> WithHeaders<E> {
+    /// Add another default header; a header with the same name replaces it
+    pub fn default_header<N: AsRef<str>, V: AsRef<str>>(mut self, name: N, value: V) -> Self {
+        if let Some((name, value)) = header(name, value) {
+            self.headers.insert(name, value);
+        }
+        self
+    }
+}
+
+impl<E: Endpoint> Endpoint for WithHeaders<E> {
+    fn methods(&self) -> Vec<hyper::Method> {
+        self.endpoint.methods()
+    }
+
+    fn path(&self) -> String {
+        self.endpoint.path()
+    }
+
+    fn execute(
+        &self,
+        method: &hyper::Method,
+        uri: &mut hyper::Uri,
+        headers: &hyper::HeaderMap,
+        body: &Bytes,
+        trailers: &hyper::HeaderMap,
+        stream: &BodyStream,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        let mut response = self
+            .endpoint
+            .execute(method, uri, headers, body, trailers, stream)?;
+        for (name, value) in self.headers.iter() {
+            response
+                .headers_mut()
+                .entry(name)
+                .or_insert_with(|| value.clone());
+        }
+        Ok(response)
+    }
+
+    fn streams(&self) -> bool {
+        self.endpoint.streams()
+    }
+
+    fn metadata(&self) -> Metadata {
+        self.endpoint.metadata()
+    }
+}
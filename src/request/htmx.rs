@@ -0,0 +1,98 @@
+use hyper::HeaderMap;
+
+use super::{RequestData, ToParam};
+use crate::response::Result;
+
+/// Value of a header, `None` if it is missing or isn't valid text
+fn header(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Headers htmx sends with its requests. Every field is empty for requests htmx didn't send.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, request::HxRequest, response::HTML};
+///
+/// fn list() -> HTML<String> {
+///     html!(<ul id="todos"><li>"Write docs"</li></ul>)
+/// }
+///
+/// #[get("/todos")]
+/// fn todos(hx: HxRequest) -> HTML<String> {
+///     match hx.is_partial() {
+///         true => list(),
+///         false => html!(<html><body><h1>"Todos"</h1>{list().0}</body></html>),
+///     }
+/// }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HxRequest {
+    /// `HX-Request`; the request was sent by htmx
+    pub htmx: bool,
+    /// `HX-Boosted`; the request is from an element using `hx-boost`
+    pub boosted: bool,
+    /// `HX-History-Restore-Request`; the page wasn't in htmx's history cache
+    pub history_restore: bool,
+    /// `HX-Current-URL`; url of the page that sent the request
+    pub current_url: Option<String>,
+    /// `HX-Prompt`; the user's answer to an `hx-prompt`
+    pub prompt: Option<String>,
+}
+
+impl HxRequest {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let flag = |name| header(headers, name).as_deref() == Some("true");
+        HxRequest {
+            htmx: flag("HX-Request"),
+            boosted: flag("HX-Boosted"),
+            history_restore: flag("HX-History-Restore-Request"),
+            current_url: header(headers, "HX-Current-URL"),
+            prompt: header(headers, "HX-Prompt"),
+        }
+    }
+
+    /// Whether htmx will swap the response into part of the page. Boosted requests and
+    /// history restores replace the whole body so they need the whole page.
+    pub fn is_partial(&self) -> bool {
+        self.htmx && !self.boosted && !self.history_restore
+    }
+}
+
+impl ToParam<HxRequest> for RequestData {
+    fn to_param(&mut self) -> Result<HxRequest> {
+        Ok(HxRequest::from_headers(&self.3))
+    }
+}
+
+/// Element that triggered an htmx request; from `HX-Trigger` and `HX-Trigger-Name`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HxTrigger {
+    /// Id of the element, if it has one
+    pub id: Option<String>,
+    /// Name of the element, if it has one
+    pub name: Option<String>,
+}
+
+impl ToParam<HxTrigger> for RequestData {
+    fn to_param(&mut self) -> Result<HxTrigger> {
+        Ok(HxTrigger {
+            id: header(&self.3, "HX-Trigger"),
+            name: header(&self.3, "HX-Trigger-Name"),
+        })
+    }
+}
+
+/// Id of the element an htmx response will be swapped into; from `HX-Target`.
+/// `None` if the target doesn't have an id or htmx didn't send the request.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HxTarget(pub Option<String>);
+
+impl ToParam<HxTarget> for RequestData {
+    fn to_param(&mut self) -> Result<HxTarget> {
+        Ok(HxTarget(header(&self.3, "HX-Target")))
+    }
+}
@@ -0,0 +1,255 @@
+use std::{fmt::Display, marker::PhantomData, ops::Deref};
+
+use serde::de::DeserializeOwned;
+
+use super::{RequestData, ToParam};
+use crate::response::Result;
+
+/// How strictly `Json` parses a body
+pub trait JsonMode {
+    /// Whether fields the type doesn't have are an error
+    const DENY_UNKNOWN_FIELDS: bool;
+}
+
+/// Fields the type doesn't have are ignored, the same as serde's default. This is the
+/// default mode of `Json`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lenient;
+
+impl JsonMode for Lenient {
+    const DENY_UNKNOWN_FIELDS: bool = false;
+}
+
+/// Fields the type doesn't have are an error, like `#[serde(deny_unknown_fields)]` but
+/// without changing the type. Clients find out about misspelled or removed fields instead of
+/// them being dropped.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Strict;
+
+impl JsonMode for Strict {
+    const DENY_UNKNOWN_FIELDS: bool = true;
+}
+
+/// Json request body; `application/json` or a `+json` content type.
+///
+/// When the body can't be parsed the response says where; ex:
+/// `Invalid json at line 3 column 12 (items[0].count): invalid type: string "2", expected u32`.
+/// Malformed json is a `400 Bad Request` and json that doesn't match the type is a
+/// `422 Unprocessable Entity`. Take a `std::result::Result<Json<T>, JsonError>` to handle
+/// the error instead.
+///
+/// Use `Json<T, Strict>` to reject fields the type doesn't have.
+///
+/// # Example
+/// ```
+/// use serde::Deserialize;
+/// use tela::{prelude::*, request::{Json, Strict}};
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// #[post("/users")]
+/// fn create(Json(user, ..): Json<User>) -> Result<String> {
+///     Ok(format!("Created {}", user.name))
+/// }
+///
+/// #[put("/users/:id")]
+/// fn update(id: u32, user: Json<User, Strict>) -> Result<String> {
+///     let user = user.into_inner();
+///     Ok(format!("Updated {} to {}", id, user.name))
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Json<T, M: JsonMode = Lenient>(pub T, pub PhantomData<M>);
+
+impl<T, M: JsonMode> Json<T, M> {
+    pub fn new(value: T) -> Self {
+        Json(value, PhantomData)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T, M: JsonMode> Deref for Json<T, M> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Error from parsing a json request body
+#[derive(Debug, Clone)]
+pub struct JsonError {
+    /// Path to the value that failed to parse; ex: `items[0].count`. `None` when it is the
+    /// whole body.
+    pub path: Option<String>,
+    /// Line of the error, starting at 1; `0` when it isn't at a position in the body, ex: the
+    /// content type is wrong
+    pub line: usize,
+    /// Column of the error, starting at 1
+    pub column: usize,
+    /// Why the body failed to parse
+    pub message: String,
+    status: u16,
+}
+
+impl JsonError {
+    /// Status code of the error response; `400 Bad Request` for malformed json,
+    /// `415 Unsupported Media Type` when the body isn't json, and
+    /// `422 Unprocessable Entity` when the json doesn't match the type
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    fn new(status: u16, message: String) -> Self {
+        JsonError {
+            path: None,
+            line: 0,
+            column: 0,
+            message,
+            status,
+        }
+    }
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid json")?;
+        if self.line > 0 {
+            write!(f, " at line {} column {}", self.line, self.column)?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, " ({})", path)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl From<JsonError> for (u16, String) {
+    fn from(value: JsonError) -> Self {
+        (value.status, value.to_string())
+    }
+}
+
+/// Whether the content type is json; ex: `application/json` or `application/problem+json`
+fn is_json(data: &RequestData) -> bool {
+    let mime = data
+        .3
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(';')
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_ascii_lowercase()
+        })
+        .unwrap_or_default();
+    mime == "application/json" || (mime.starts_with("application/") && mime.ends_with("+json"))
+}
+
+fn parse<T: DeserializeOwned, M: JsonMode>(
+    data: &RequestData,
+) -> std::result::Result<Json<T, M>, JsonError> {
+    if !is_json(data) {
+        return Err(JsonError::new(
+            415,
+            "Expected a request with content type application/json".to_string(),
+        ));
+    }
+
+    let mut deserializer = serde_json::Deserializer::from_slice(&data.2);
+    let mut unknown = Vec::new();
+    let mut ignored = |path: serde_ignored::Path| unknown.push(field_path(&path));
+    let value = serde_path_to_error::deserialize(serde_ignored::Deserializer::new(
+        &mut deserializer,
+        &mut ignored,
+    ))
+    .map_err(|error| {
+        let path = error.path().to_string();
+        JsonError {
+            // `?` is a value the error happened before the start of, ex: the body ended
+            path: (path != "." && path != "?").then_some(path),
+            ..error.into_inner().into()
+        }
+    })?;
+    // Only whitespace may follow the value
+    deserializer.end().map_err(JsonError::from)?;
+
+    match unknown.into_iter().next() {
+        Some(field) if M::DENY_UNKNOWN_FIELDS => Err(JsonError {
+            path: Some(field),
+            ..JsonError::new(422, "unknown field".to_string())
+        }),
+        _ => Ok(Json::new(value)),
+    }
+}
+
+/// Path of an unknown field in the same format as the other errors; ex: `items[0].colour`
+fn field_path(path: &serde_ignored::Path) -> String {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => String::new(),
+        Path::Seq { parent, index } => format!("{}[{}]", field_path(parent), index),
+        Path::Map { parent, key } => match field_path(parent) {
+            parent if parent.is_empty() => key.clone(),
+            parent => format!("{}.{}", parent, key),
+        },
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => field_path(parent),
+    }
+}
+
+/// Malformed json is a bad request and json that doesn't match the type can't be processed
+impl From<serde_json::Error> for JsonError {
+    fn from(error: serde_json::Error) -> Self {
+        // The message ends with the position, which is reported separately
+        let message = error.to_string();
+        let message = match message.rfind(" at line ") {
+            Some(index) => message[..index].to_string(),
+            None => message,
+        };
+        JsonError {
+            path: None,
+            line: error.line(),
+            column: error.column(),
+            message,
+            status: if error.is_data() { 422 } else { 400 },
+        }
+    }
+}
+
+impl<T: DeserializeOwned, M: JsonMode> ToParam<Json<T, M>> for RequestData {
+    fn to_param(&mut self) -> Result<Json<T, M>> {
+        parse(self).map_err(Into::into)
+    }
+}
+
+impl<T: DeserializeOwned, M: JsonMode> ToParam<Option<Json<T, M>>> for RequestData {
+    fn to_param(&mut self) -> Result<Option<Json<T, M>>> {
+        Ok(parse(self).ok())
+    }
+}
+
+impl<T: DeserializeOwned, M: JsonMode> ToParam<Result<Json<T, M>>> for RequestData {
+    fn to_param(&mut self) -> Result<Result<Json<T, M>>> {
+        Ok(parse(self).map_err(Into::into))
+    }
+}
+
+impl<T: DeserializeOwned, M: JsonMode> ToParam<std::result::Result<Json<T, M>, JsonError>>
+    for RequestData
+{
+    fn to_param(&mut self) -> Result<std::result::Result<Json<T, M>, JsonError>> {
+        Ok(parse(self))
+    }
+}
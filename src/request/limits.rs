@@ -0,0 +1,283 @@
+use std::{fmt::Display, sync::Arc, time::Duration};
+
+use bytes::{Bytes, BytesMut};
+use http_body_util::BodyExt;
+use hyper::{body::Body, header::CONTENT_LENGTH, HeaderMap, Method, Uri};
+
+use super::{RequestData, ToParam};
+use crate::response::Result;
+
+/// How much of a request body has been read. Passed to the hook set with
+/// `Server::body_progress` after each chunk and once the whole body is read.
+#[derive(Debug)]
+pub struct BodyProgress<'a> {
+    pub method: &'a Method,
+    pub uri: &'a Uri,
+    /// Bytes read so far
+    pub received: usize,
+    /// Length from the `Content-Length` header, if it was sent
+    pub expected: Option<u64>,
+    /// Whether the whole body has been read
+    pub done: bool,
+}
+
+/// Function told about the progress of reading every request body. Returning an error stops
+/// reading the body and responds with the error; see `Server::body_progress`.
+pub type BodyHook = Arc<dyn Fn(&BodyProgress) -> Result<()> + Send + Sync>;
+
+/// Limits on reading request bodies before they are passed to a handler. Bodies of routes
+/// that stream them with `BodyStream` aren't read so they aren't limited.
+#[derive(Clone, Default)]
+pub(crate) struct BodyLimits {
+    pub(crate) limit: Option<usize>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) progress: Option<BodyHook>,
+}
+
+impl BodyLimits {
+    fn too_large(limit: usize) -> (u16, String) {
+        (
+            413,
+            format!("Request body is larger than the limit of {} bytes", limit),
+        )
+    }
+
+    /// Read the body and its trailers. Bodies with a `Content-Length` over the limit are
+    /// rejected without reading them.
+    pub(crate) async fn read<B>(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        mut incoming: B,
+    ) -> Result<(Bytes, HeaderMap)>
+    where
+        B: Body<Data = Bytes> + Unpin,
+        B::Error: Display,
+    {
+        let expected = headers
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        if let (Some(limit), Some(expected)) = (self.limit, expected) {
+            if expected > limit as u64 {
+                return Err(Self::too_large(limit));
+            }
+        }
+
+        let read = async {
+            let mut progress = BodyProgress {
+                method,
+                uri,
+                received: 0,
+                expected,
+                done: false,
+            };
+            let mut body = BytesMut::new();
+            let mut trailers = HeaderMap::new();
+            while let Some(frame) = incoming.frame().await {
+                let frame = frame
+                    .map_err(|error| (400, format!("Failed to read request body: {}", error)))?;
+                let data = match frame.into_data() {
+                    Ok(data) => data,
+                    Err(frame) => {
+                        if let Ok(frame) = frame.into_trailers() {
+                            trailers.extend(frame);
+                        }
+                        continue;
+                    }
+                };
+
+                if let Some(limit) = self.limit {
+                    if body.len() + data.len() > limit {
+                        return Err(Self::too_large(limit));
+                    }
+                }
+                body.extend_from_slice(&data);
+                if let Some(hook) = &self.progress {
+                    progress.received = body.len();
+                    hook(&progress)?;
+                }
+            }
+
+            if let Some(hook) = &self.progress {
+                progress.received = body.len();
+                progress.done = true;
+                hook(&progress)?;
+            }
+            Ok((body.freeze(), trailers))
+        };
+
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, read)
+                .await
+                .unwrap_or_else(|_| Err((408, "Request body was not sent in time".to_string()))),
+            None => read.await,
+        }
+    }
+}
+
+/// Length of the request body from the `Content-Length` header. Requests without one, ex: a
+/// chunked body, get a `411 Length Required`; use `Option<ContentLength>` to allow them.
+///
+/// It doesn't read the body, so it's a quick check for routes that stream it.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, request::{BodyStream, ContentLength}};
+///
+/// #[post("/upload")]
+/// fn upload(ContentLength(length): ContentLength, mut stream: BodyStream) -> Result<String> {
+///     if length > 100 * 1024 * 1024 {
+///         return Err((413, "Uploads are limited to 100 MiB".to_string()));
+///     }
+///     let mut size = 0;
+///     while let Some(chunk) = stream.blocking_next() {
+///         size += chunk?.len();
+///     }
+///     response!(format!("Received {} bytes", size))
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ContentLength(pub u64);
+
+fn content_length(data: &RequestData) -> Option<Result<ContentLength>> {
+    let value = data.3.get(CONTENT_LENGTH)?;
+    Some(
+        value
+            .to_str()
+            .ok()
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(ContentLength)
+            .ok_or_else(|| (400, "Invalid Content-Length header".to_string())),
+    )
+}
+
+impl ToParam<ContentLength> for RequestData {
+    fn to_param(&mut self) -> Result<ContentLength> {
+        content_length(self)
+            .unwrap_or_else(|| Err((411, "Request is missing a Content-Length".to_string())))
+    }
+}
+
+impl ToParam<Option<ContentLength>> for RequestData {
+    fn to_param(&mut self) -> Result<Option<ContentLength>> {
+        content_length(self).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, sync::Mutex};
+
+    use futures::stream::{self, StreamExt};
+    use http_body_util::StreamBody;
+    use hyper::body::Frame;
+
+    use super::*;
+
+    type Chunks =
+        StreamBody<stream::Iter<std::vec::IntoIter<std::result::Result<Frame<Bytes>, Infallible>>>>;
+
+    fn chunks(chunks: &[&'static [u8]]) -> Chunks {
+        let frames: Vec<_> = chunks
+            .iter()
+            .map(|chunk| Ok(Frame::data(Bytes::from_static(chunk))))
+            .collect();
+        StreamBody::new(stream::iter(frames))
+    }
+
+    /// Body that sends a chunk and then never finishes
+    fn stalled() -> impl Body<Data = Bytes, Error = Infallible> + Unpin {
+        let chunk = Ok(Frame::data(Bytes::from_static(b"start")));
+        StreamBody::new(stream::iter([chunk]).chain(stream::pending()))
+    }
+
+    async fn read<B>(limits: &BodyLimits, headers: &[(&str, &str)], body: B) -> Result<Bytes>
+    where
+        B: Body<Data = Bytes> + Unpin,
+        B::Error: Display,
+    {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.insert(
+                hyper::header::HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        let uri = Uri::from_static("/upload");
+        limits
+            .read(&Method::POST, &uri, &map, body)
+            .await
+            .map(|(body, _)| body)
+    }
+
+    #[tokio::test]
+    async fn bodies_over_the_limit_are_too_large() {
+        let limits = BodyLimits {
+            limit: Some(8),
+            ..Default::default()
+        };
+        let body = read(&limits, &[], chunks(&[b"1234", b"5678"])).await;
+        assert_eq!(&body.unwrap()[..], b"12345678");
+
+        let error = read(&limits, &[], chunks(&[b"1234", b"56789"])).await;
+        assert_eq!(error.unwrap_err().0, 413);
+
+        // Rejected from the header without waiting for the body
+        let error = read(&limits, &[("Content-Length", "9")], stalled()).await;
+        assert_eq!(error.unwrap_err().0, 413);
+
+        // A short Content-Length doesn't get past the limit
+        let error = read(&limits, &[("Content-Length", "1")], chunks(&[b"123456789"])).await;
+        assert_eq!(error.unwrap_err().0, 413);
+    }
+
+    #[tokio::test]
+    async fn slow_bodies_time_out() {
+        let limits = BodyLimits {
+            timeout: Some(Duration::from_millis(20)),
+            ..Default::default()
+        };
+        let error = read(&limits, &[], stalled()).await;
+        assert_eq!(error.unwrap_err().0, 408);
+
+        let body = read(&limits, &[], chunks(&[b"quick"])).await;
+        assert_eq!(&body.unwrap()[..], b"quick");
+    }
+
+    #[tokio::test]
+    async fn progress_is_reported() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let hook = seen.clone();
+        let limits = BodyLimits {
+            progress: Some(Arc::new(move |progress: &BodyProgress| {
+                hook.lock()
+                    .unwrap()
+                    .push((progress.received, progress.expected, progress.done));
+                match progress.received > 6 {
+                    true => Err((429, "Slow down".to_string())),
+                    false => Ok(()),
+                }
+            })),
+            ..Default::default()
+        };
+        let body = read(
+            &limits,
+            &[("Content-Length", "6")],
+            chunks(&[b"abc", b"def"]),
+        )
+        .await;
+        assert_eq!(&body.unwrap()[..], b"abcdef");
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![(3, Some(6), false), (6, Some(6), false), (6, Some(6), true)]
+        );
+
+        // An error from the hook stops reading the body
+        seen.lock().unwrap().clear();
+        let error = read(&limits, &[], chunks(&[b"abcdefg", b"h"])).await;
+        assert_eq!(error.unwrap_err().0, 429);
+        assert_eq!(*seen.lock().unwrap(), vec![(7, None, false)]);
+    }
+}
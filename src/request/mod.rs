@@ -1,14 +1,47 @@
 mod body;
+mod describe;
+mod form;
+mod head;
+mod headers;
+mod htmx;
+mod json;
+mod limits;
+mod multipart;
+mod pagination;
+mod patch;
+mod precondition;
 mod query;
+mod query_map;
 mod request_data;
+mod stream;
+mod trailers;
+mod upload;
 
 pub use body::Body;
-pub use query::Query;
+pub use describe::{Describe, Described, Metadata};
+pub use form::{render_input, Form, FormErrors, FormValue, HtmlForm};
+pub use head::Head;
+pub use headers::{DefaultHeader, WithHeaders};
+pub use htmx::{HxRequest, HxTarget, HxTrigger};
+pub use json::{Json, JsonError, JsonMode, Lenient, Strict};
+pub(crate) use limits::BodyLimits;
+pub use limits::{BodyHook, BodyProgress, ContentLength};
+pub use multipart::{Multipart, Part};
+pub use pagination::Pagination;
+pub use patch::{JsonPatch, MergePatch, PatchOperation};
+pub(crate) use precondition::parse_date;
+pub use precondition::{IfMatch, IfUnmodifiedSince, Precondition};
+pub use query::{query_raw, Query, QueryError};
+pub use query_map::QueryMap;
 pub use request_data::{RequestData, ToParam};
+pub use stream::BodyStream;
+pub use trailers::Trailers;
+pub use upload::{sniff, FileType, UploadError, UploadRules};
 
 use bytes::Bytes;
+use futures::future::BoxFuture;
 use http_body_util::Full;
-use std::fmt::Debug;
+use std::{fmt::Debug, sync::Arc};
 
 use crate::response::Result;
 
@@ -19,16 +52,63 @@ pub trait Endpoint: Sync + Send + Debug {
         &self,
         method: &hyper::Method,
         uri: &mut hyper::Uri,
-        body: &mut Vec<u8>,
+        headers: &hyper::HeaderMap,
+        body: &Bytes,
+        trailers: &hyper::HeaderMap,
+        stream: &BodyStream,
     ) -> Result<hyper::Response<Full<Bytes>>>;
+    /// Whether the body is passed to the endpoint as a `BodyStream` instead of being read first
+    fn streams(&self) -> bool {
+        false
+    }
+    /// Documentation for the route; see `Describe`
+    fn metadata(&self) -> Metadata {
+        Metadata::default()
+    }
 }
 
-pub trait Catch: Send + Sync + Debug {
+/// Routes in a `group!` can be wrapped like any other endpoint; ex: with `Describe`
+impl<E: Endpoint + ?Sized> Endpoint for Arc<E> {
+    fn methods(&self) -> Vec<hyper::Method> {
+        (**self).methods()
+    }
+
+    fn path(&self) -> String {
+        (**self).path()
+    }
+
     fn execute(
         &self,
+        method: &hyper::Method,
+        uri: &mut hyper::Uri,
+        headers: &hyper::HeaderMap,
+        body: &Bytes,
+        trailers: &hyper::HeaderMap,
+        stream: &BodyStream,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        (**self).execute(method, uri, headers, body, trailers, stream)
+    }
+
+    fn streams(&self) -> bool {
+        (**self).streams()
+    }
+
+    fn metadata(&self) -> Metadata {
+        (**self).metadata()
+    }
+}
+
+pub trait Catch: Send + Sync + Debug {
+    fn execute<'a>(
+        &'a self,
         code: u16,
         message: String,
         reason: String,
-    ) -> Result<hyper::Response<Full<Bytes>>>;
+        head: &'a Head,
+    ) -> BoxFuture<'a, Result<hyper::Response<Full<Bytes>>>>;
     fn code(&self) -> u16;
+    /// Every code the catch handles; `0` handles codes without their own catch
+    fn codes(&self) -> Vec<u16> {
+        vec![self.code()]
+    }
 }
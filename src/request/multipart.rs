@@ -0,0 +1,281 @@
+use bytes::Bytes;
+use hyper::{header::CONTENT_TYPE, HeaderMap};
+
+use super::{FileType, RequestData, ToParam, UploadError, UploadRules};
+use crate::response::Result;
+
+/// Field of a `multipart/form-data` body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    /// Name of the form field
+    pub name: String,
+    /// Name of the file the client sent, `None` for fields that aren't files
+    pub filename: Option<String>,
+    /// Content type the client sent. Don't trust it for files; check them with `UploadRules`.
+    pub content_type: Option<String>,
+    pub data: Bytes,
+}
+
+impl Part {
+    pub fn is_file(&self) -> bool {
+        self.filename.is_some()
+    }
+
+    /// Data of the part as text, `None` if it isn't utf-8
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.data).ok()
+    }
+}
+
+/// Form sent as `multipart/form-data`; ex: a `<form enctype="multipart/form-data">` with a
+/// file input. The parts reference the request body so no copy is made.
+///
+/// A request with another content type is a `415 Unsupported Media Type` and a body that
+/// can't be parsed is a `400 Bad Request`.
+///
+/// # Example
+/// ```
+/// use tela::{
+///     prelude::*,
+///     request::{FileType, Multipart, UploadRules},
+///     response::Problem,
+/// };
+///
+/// #[post("/photos")]
+/// fn upload(form: Multipart) -> std::result::Result<String, Problem> {
+///     let rules = UploadRules::new().allow(FileType::Jpeg).max_size(10 * 1024 * 1024);
+///     let kinds = form.check_files(&rules)?;
+///     Ok(format!("Saved {} photos", kinds.len()))
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Multipart(pub Vec<Part>);
+
+impl Multipart {
+    /// Parse a body with the boundary from its content type
+    pub fn parse(headers: &HeaderMap, body: &Bytes) -> Result<Self> {
+        let boundary = boundary(headers).ok_or((
+            415,
+            "Expected a request with content type multipart/form-data".to_string(),
+        ))?;
+        parse(&boundary, body).map(Multipart).ok_or((
+            400,
+            "Failed to parse multipart form from request".to_string(),
+        ))
+    }
+
+    /// First part with the name
+    pub fn get(&self, name: &str) -> Option<&Part> {
+        self.0.iter().find(|part| part.name == name)
+    }
+
+    /// Parts that are files
+    pub fn files(&self) -> impl Iterator<Item = &Part> {
+        self.0.iter().filter(|part| part.is_file())
+    }
+
+    /// Check every file part against the rules, in the order they were sent. The error is for
+    /// the first file that breaks a rule.
+    pub fn check_files(
+        &self,
+        rules: &UploadRules,
+    ) -> std::result::Result<Vec<FileType>, UploadError> {
+        self.files().map(|part| rules.check_part(part)).collect()
+    }
+}
+
+/// Boundary from a `multipart/form-data` content type
+fn boundary(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(CONTENT_TYPE)?.to_str().ok()?;
+    let mut params = value.split(';');
+    if !params
+        .next()?
+        .trim()
+        .eq_ignore_ascii_case("multipart/form-data")
+    {
+        return None;
+    }
+    params
+        .filter_map(|param| param.split_once('='))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        .filter(|boundary| !boundary.is_empty())
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|at| at + from)
+}
+
+/// Value of a `Content-Disposition` parameter; ex: `name` in `form-data; name="avatar"`
+fn disposition_param(disposition: &str, name: &str) -> Option<String> {
+    disposition
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| {
+            let value = value.trim();
+            value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value)
+                .replace("\\\"", "\"")
+        })
+}
+
+fn parse(boundary: &str, body: &Bytes) -> Option<Vec<Part>> {
+    let delimiter = format!("--{}", boundary);
+    let next = format!("\r\n--{}", boundary);
+
+    // Anything before the first boundary is a preamble that is ignored
+    let mut at = find(body, delimiter.as_bytes(), 0)? + delimiter.len();
+    let mut parts = Vec::new();
+    loop {
+        if body[at..].starts_with(b"--") {
+            return Some(parts);
+        }
+        // Whitespace is allowed after a boundary before its line ends
+        while matches!(body.get(at), Some(b' ' | b'\t')) {
+            at += 1;
+        }
+        if !body[at..].starts_with(b"\r\n") {
+            return None;
+        }
+        at += 2;
+
+        let end_of_headers = find(body, b"\r\n\r\n", at)?;
+        let headers = std::str::from_utf8(&body[at..end_of_headers]).ok()?;
+        let mut disposition = None;
+        let mut content_type = None;
+        for line in headers.split("\r\n").filter(|line| !line.is_empty()) {
+            let (name, value) = line.split_once(':')?;
+            if name.trim().eq_ignore_ascii_case("content-disposition") {
+                disposition = Some(value.trim());
+            } else if name.trim().eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.trim().to_string());
+            }
+        }
+        let disposition = disposition?;
+
+        let start = end_of_headers + 4;
+        let end = find(body, next.as_bytes(), start)?;
+        parts.push(Part {
+            name: disposition_param(disposition, "name")?,
+            filename: disposition_param(disposition, "filename"),
+            content_type,
+            data: body.slice(start..end),
+        });
+        at = end + next.len();
+    }
+}
+
+impl ToParam<Multipart> for RequestData {
+    fn to_param(&mut self) -> Result<Multipart> {
+        Multipart::parse(&self.3, &self.2)
+    }
+}
+
+impl ToParam<Option<Multipart>> for RequestData {
+    fn to_param(&mut self) -> Result<Option<Multipart>> {
+        Ok(Multipart::parse(&self.3, &self.2).ok())
+    }
+}
+
+impl ToParam<Result<Multipart>> for RequestData {
+    fn to_param(&mut self) -> Result<Result<Multipart>> {
+        Ok(Multipart::parse(&self.3, &self.2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x10\0\0\0\x08";
+
+    fn multipart(parts: &[(&str, Option<&str>, &[u8])]) -> (HeaderMap, Bytes) {
+        let mut body = b"preamble\r\n".to_vec();
+        for (name, filename, data) in parts {
+            body.extend_from_slice(b"--XyZ\r\nContent-Disposition: form-data; name=\"");
+            body.extend_from_slice(name.as_bytes());
+            body.push(b'"');
+            if let Some(filename) = filename {
+                body.extend_from_slice(format!("; filename=\"{}\"", filename).as_bytes());
+                body.extend_from_slice(b"\r\nContent-Type: image/png");
+            }
+            body.extend_from_slice(b"\r\n\r\n");
+            body.extend_from_slice(data);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(b"--XyZ--\r\n");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CONTENT_TYPE,
+            "multipart/form-data; boundary=\"XyZ\"".parse().unwrap(),
+        );
+        (headers, Bytes::from(body))
+    }
+
+    #[test]
+    fn parts_are_parsed() {
+        let (headers, body) = multipart(&[
+            ("title", None, b"Holiday"),
+            ("photo", Some("beach.png"), PNG),
+            ("empty", None, b""),
+        ]);
+        let form = Multipart::parse(&headers, &body).unwrap();
+        assert_eq!(form.0.len(), 3);
+        assert_eq!(form.get("title").and_then(Part::text), Some("Holiday"));
+        let photo = form.get("photo").unwrap();
+        assert_eq!(photo.filename.as_deref(), Some("beach.png"));
+        assert_eq!(photo.content_type.as_deref(), Some("image/png"));
+        assert_eq!(&photo.data[..], PNG);
+        assert_eq!(form.get("empty").unwrap().data.len(), 0);
+        assert_eq!(form.files().count(), 1);
+    }
+
+    #[test]
+    fn file_parts_are_sniffed() {
+        let rules = UploadRules::new()
+            .allow(FileType::Png)
+            .max_dimensions(16, 16);
+        let (headers, body) =
+            multipart(&[("title", None, b"not a file"), ("a", Some("a.png"), PNG)]);
+        let form = Multipart::parse(&headers, &body).unwrap();
+        assert_eq!(form.check_files(&rules).unwrap(), vec![FileType::Png]);
+
+        // The client's content type and file name don't change the sniffed type
+        let (headers, body) =
+            multipart(&[("a", Some("a.png"), PNG), ("b", Some("b.png"), b"%PDF-1.7")]);
+        let form = Multipart::parse(&headers, &body).unwrap();
+        let error = form.check_files(&rules).unwrap_err();
+        assert_eq!(error.field, "b");
+        assert_eq!(error.code, "type");
+    }
+
+    #[test]
+    fn malformed_bodies_are_rejected() {
+        let (headers, body) = multipart(&[("title", None, b"Holiday")]);
+        let cut = body.slice(..body.len() - 9);
+        assert_eq!(Multipart::parse(&headers, &cut).unwrap_err().0, 400);
+
+        let missing_name =
+            Bytes::from_static(b"--XyZ\r\nContent-Disposition: form-data\r\n\r\nvalue\r\n--XyZ--");
+        assert_eq!(
+            Multipart::parse(&headers, &missing_name).unwrap_err().0,
+            400
+        );
+
+        let mut urlencoded = HeaderMap::new();
+        urlencoded.insert(
+            CONTENT_TYPE,
+            "application/x-www-form-urlencoded".parse().unwrap(),
+        );
+        assert_eq!(Multipart::parse(&urlencoded, &body).unwrap_err().0, 415);
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use super::{QueryMap, RequestData, ToParam};
+use crate::response::Result;
+
+/// Items per page when the request doesn't give one
+static PER_PAGE: AtomicU32 = AtomicU32::new(20);
+/// Most items a request can ask for
+static MAX_PER_PAGE: AtomicU32 = AtomicU32::new(100);
+
+/// Page of a list requested with the `page` and `per_page`, or `cursor` and `per_page`,
+/// query parameters; ex: `/posts?page=2&per_page=50`.
+///
+/// Pages start at 1. `per_page` defaults to 20 and is capped at 100, see
+/// `Pagination::limits` to change them. A page or per page that isn't a positive number is
+/// a `400 Bad Request`. Respond with `Paginated` to send the page with its links.
+///
+/// # Example
+/// ```
+/// use tela::{
+///     prelude::*,
+///     request::Pagination,
+///     response::Paginated,
+/// };
+///
+/// #[derive(serde::Serialize)]
+/// struct Post { id: u64 }
+///
+/// mod db {
+///     # use super::Post;
+///     pub fn posts(offset: u64, limit: u32) -> Vec<Post> {
+///         (offset..(offset + limit as u64).min(95)).map(|id| Post { id }).collect()
+///     }
+///
+///     pub fn count_posts() -> u64 {
+///         95
+///     }
+/// }
+///
+/// #[get("/posts")]
+/// fn posts(page: Pagination) -> Paginated<Post> {
+///     let items = db::posts(page.offset(), page.limit());
+///     Paginated::new(items, &page).total(db::count_posts())
+/// }
+/// #
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pagination {
+    pub page: u32,
+    pub per_page: u32,
+    /// Opaque position to continue from for cursor pagination; ex: the id of the last item
+    pub cursor: Option<String>,
+}
+
+impl Pagination {
+    /// Set the default and largest number of items per page for every request
+    pub fn limits(default: u32, max: u32) {
+        let max = max.max(1);
+        MAX_PER_PAGE.store(max, Ordering::Relaxed);
+        PER_PAGE.store(default.clamp(1, max), Ordering::Relaxed);
+    }
+
+    /// Number of items before the page
+    pub fn offset(&self) -> u64 {
+        (self.page as u64 - 1) * self.per_page as u64
+    }
+
+    /// Number of items in the page
+    pub fn limit(&self) -> u32 {
+        self.per_page
+    }
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Pagination {
+            page: 1,
+            per_page: PER_PAGE.load(Ordering::Relaxed),
+            cursor: None,
+        }
+    }
+}
+
+impl ToParam<Pagination> for RequestData {
+    fn to_param(&mut self) -> Result<Pagination> {
+        let query = QueryMap::new(self.0.query().unwrap_or(""));
+        let positive = |key: &str| match query.parse::<u32>(key)? {
+            Some(0) => Err((400, format!("Query parameter {:?} must be at least 1", key))),
+            value => Ok(value),
+        };
+
+        let max = MAX_PER_PAGE.load(Ordering::Relaxed);
+        let default = Pagination::default();
+        Ok(Pagination {
+            page: positive("page")?.unwrap_or(default.page),
+            per_page: positive("per_page")?
+                .map(|per_page| per_page.min(max))
+                .unwrap_or(default.per_page),
+            cursor: query
+                .get("cursor")
+                .filter(|cursor| !cursor.is_empty())
+                .map(|cursor| cursor.to_string()),
+        })
+    }
+}
@@ -0,0 +1,373 @@
+use std::marker::PhantomData;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{RequestData, ToParam};
+use crate::response::Result;
+
+/// Content type of JSON Patch requests
+const JSON_PATCH: &str = "application/json-patch+json";
+/// Content type of JSON Merge Patch requests
+const MERGE_PATCH: &str = "application/merge-patch+json";
+
+/// Check the request is json, or the patch content type, and parse the body
+fn parse<T: DeserializeOwned>(data: &RequestData, content_type: &str) -> Result<T> {
+    let mime = data
+        .3
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(';').next().unwrap_or("").trim().to_string())
+        .unwrap_or_default();
+    if !mime.eq_ignore_ascii_case(content_type) && !mime.eq_ignore_ascii_case("application/json") {
+        return Err((
+            415,
+            format!("Expected a request with content type {}", content_type),
+        ));
+    }
+    serde_json::from_slice(&data.2).map_err(|error| (400, format!("Invalid patch: {}", error)))
+}
+
+/// Serialize a value, apply a change to it, and deserialize the result
+fn patch_value<T, F>(target: &T, change: F) -> Result<T>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce(&mut Value) -> Result<()>,
+{
+    let mut value = serde_json::to_value(target)
+        .map_err(|error| (500, format!("Failed to serialize patch target: {}", error)))?;
+    change(&mut value)?;
+    serde_json::from_value(value)
+        .map_err(|error| (422, format!("Patched value is invalid: {}", error)))
+}
+
+/// One operation of a JSON Patch (RFC 6902). Paths are JSON Pointers (RFC 6901); ex:
+/// `/tags/0`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+/// JSON Patch (RFC 6902) request body; a list of operations sent with the
+/// `application/json-patch+json` or `application/json` content type.
+///
+/// Operations are applied in order and either all of them are applied or none are. A path
+/// that doesn't exist is a `422 Unprocessable Entity` and a `test` that fails is a
+/// `409 Conflict`. A body that isn't a patch is a `400 Bad Request`.
+///
+/// # Example
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use tela::{prelude::*, request::JsonPatch, response::JSON};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Post { title: String }
+///
+/// mod db {
+///     # use super::Post;
+///     # use tela::response::Result;
+///     pub fn post(_id: u32) -> Result<Post> {
+///         Ok(Post { title: "Hello".to_string() })
+///     }
+///
+///     pub fn save(_id: u32, _post: &Post) {}
+/// }
+///
+/// #[patch("/posts/:id")]
+/// fn update(id: u32, patch: JsonPatch) -> Result<JSON<Post>> {
+///     let post = patch.apply_to(&db::post(id)?)?;
+///     db::save(id, &post);
+///     response!(JSON(post))
+/// }
+/// #
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonPatch(pub Vec<PatchOperation>);
+
+impl JsonPatch {
+    /// Apply the operations to a json value
+    pub fn apply(&self, target: &mut Value) -> Result<()> {
+        let mut patched = target.clone();
+        for operation in self.0.iter() {
+            match operation {
+                PatchOperation::Add { path, value } => add(&mut patched, path, value.clone())?,
+                PatchOperation::Remove { path } => {
+                    remove(&mut patched, path)?;
+                }
+                PatchOperation::Replace { path, value } => {
+                    *pointer(&mut patched, path)? = value.clone();
+                }
+                PatchOperation::Move { from, path } => {
+                    if path.starts_with(&format!("{}/", from)) {
+                        return Err((
+                            422,
+                            format!("Can't move {:?} into one of its children", from),
+                        ));
+                    }
+                    let value = remove(&mut patched, from)?;
+                    add(&mut patched, path, value)?;
+                }
+                PatchOperation::Copy { from, path } => {
+                    let value = pointer(&mut patched, from)?.clone();
+                    add(&mut patched, path, value)?;
+                }
+                PatchOperation::Test { path, value } => {
+                    if pointer(&mut patched, path)? != value {
+                        return Err((409, format!("Patch test failed for {:?}", path)));
+                    }
+                }
+            }
+        }
+        *target = patched;
+        Ok(())
+    }
+
+    /// Apply the operations to a value through its json form
+    pub fn apply_to<T: Serialize + DeserializeOwned>(&self, target: &T) -> Result<T> {
+        patch_value(target, |value| self.apply(value))
+    }
+}
+
+impl ToParam<JsonPatch> for RequestData {
+    fn to_param(&mut self) -> Result<JsonPatch> {
+        parse(self, JSON_PATCH).map(JsonPatch)
+    }
+}
+
+/// Split a JSON Pointer into its unescaped tokens
+fn tokens(path: &str) -> Result<Vec<String>> {
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    match path.strip_prefix('/') {
+        Some(path) => Ok(path
+            .split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect()),
+        None => Err((422, format!("Invalid json pointer {:?}", path))),
+    }
+}
+
+/// Index into an array; `-` is the end for adding. Only `0` or digits without a leading
+/// zero are indexes (RFC 6901), so `+1` and `01` aren't.
+fn index(token: &str, len: usize, end: bool) -> Option<usize> {
+    if end && token == "-" {
+        return Some(len);
+    }
+    if token.is_empty()
+        || !token.bytes().all(|b| b.is_ascii_digit())
+        || (token.len() > 1 && token.starts_with('0'))
+    {
+        return None;
+    }
+    token
+        .parse::<usize>()
+        .ok()
+        .filter(|index| *index < len + end as usize)
+}
+
+fn missing(path: &str) -> (u16, String) {
+    (422, format!("Path {:?} does not exist", path))
+}
+
+/// Value a path points to
+fn pointer<'a>(target: &'a mut Value, path: &str) -> Result<&'a mut Value> {
+    let mut current = target;
+    for token in tokens(path)? {
+        current = match current {
+            Value::Object(map) => map.get_mut(&token),
+            Value::Array(list) => match index(&token, list.len(), false) {
+                Some(index) => list.get_mut(index),
+                None => None,
+            },
+            _ => None,
+        }
+        .ok_or_else(|| missing(path))?;
+    }
+    Ok(current)
+}
+
+/// Parent of the value a path points to and the last token
+fn parent<'a>(target: &'a mut Value, path: &str) -> Result<(&'a mut Value, String)> {
+    let mut tokens = tokens(path)?;
+    let last = tokens.pop().ok_or_else(|| missing(path))?;
+    let parent = tokens
+        .iter()
+        .map(|token| format!("/{}", token.replace('~', "~0").replace('/', "~1")))
+        .collect::<String>();
+    Ok((pointer(target, &parent).map_err(|_| missing(path))?, last))
+}
+
+fn add(target: &mut Value, path: &str, value: Value) -> Result<()> {
+    if path.is_empty() {
+        *target = value;
+        return Ok(());
+    }
+    let (parent, last) = parent(target, path)?;
+    match parent {
+        Value::Object(map) => {
+            map.insert(last, value);
+        }
+        Value::Array(list) => {
+            let index = index(&last, list.len(), true).ok_or_else(|| missing(path))?;
+            list.insert(index, value);
+        }
+        _ => return Err(missing(path)),
+    }
+    Ok(())
+}
+
+fn remove(target: &mut Value, path: &str) -> Result<Value> {
+    let (parent, last) = parent(target, path)?;
+    match parent {
+        Value::Object(map) => map.remove(&last),
+        Value::Array(list) => index(&last, list.len(), false).map(|index| list.remove(index)),
+        _ => None,
+    }
+    .ok_or_else(|| missing(path))
+}
+
+/// JSON Merge Patch (RFC 7386) request body for a `T`; a partial json object sent with the
+/// `application/merge-patch+json` or `application/json` content type. Fields in the patch
+/// replace the target's, objects are merged, and `null` removes a field.
+///
+/// # Example
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use tela::{prelude::*, request::MergePatch, response::JSON};
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct User { name: String, email: Option<String> }
+///
+/// mod db {
+///     # use super::User;
+///     # use tela::response::Result;
+///     pub fn user(_id: u32) -> Result<User> {
+///         Ok(User { name: "tela".to_string(), email: None })
+///     }
+///
+///     pub fn save(_id: u32, _user: &User) {}
+/// }
+///
+/// #[patch("/users/:id")]
+/// fn update(id: u32, patch: MergePatch<User>) -> Result<JSON<User>> {
+///     let user = patch.apply_to(&db::user(id)?)?;
+///     db::save(id, &user);
+///     response!(JSON(user))
+/// }
+/// #
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergePatch<T = Value> {
+    pub patch: Value,
+    target: PhantomData<T>,
+}
+
+impl<T> MergePatch<T> {
+    pub fn new(patch: Value) -> Self {
+        MergePatch {
+            patch,
+            target: PhantomData,
+        }
+    }
+
+    /// Merge the patch into a json value
+    pub fn apply(&self, target: &mut Value) {
+        merge(target, &self.patch)
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> MergePatch<T> {
+    /// Merge the patch into a value through its json form. A result that isn't a valid `T`
+    /// is a `422 Unprocessable Entity`.
+    pub fn apply_to(&self, target: &T) -> Result<T> {
+        patch_value(target, |value| {
+            self.apply(value);
+            Ok(())
+        })
+    }
+}
+
+fn merge(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    if let Value::Object(map) = target {
+        for (key, value) in patch {
+            match value {
+                Value::Null => {
+                    map.remove(key);
+                }
+                value => merge(map.entry(key.as_str()).or_insert(Value::Null), value),
+            }
+        }
+    }
+}
+
+impl<T> ToParam<MergePatch<T>> for RequestData {
+    fn to_param(&mut self) -> Result<MergePatch<T>> {
+        parse(self, MERGE_PATCH).map(MergePatch::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn array_indexes_follow_rfc_6901() {
+        let cases = [
+            ("0", false, Some(0)),
+            ("2", false, Some(2)),
+            ("3", false, None),
+            ("3", true, Some(3)),
+            ("-", true, Some(3)),
+            ("-", false, None),
+            ("+1", false, None),
+            ("01", false, None),
+            ("00", false, None),
+            ("-1", false, None),
+            (" 1", false, None),
+            ("1e0", false, None),
+            ("", false, None),
+        ];
+        for (token, end, expected) in cases {
+            assert_eq!(index(token, 3, end), expected, "{:?}", token);
+        }
+    }
+
+    #[test]
+    fn patches_apply_all_or_nothing() {
+        let mut target = json!({ "tags": ["a", "b"], "title": "Post" });
+        let patch: JsonPatch = serde_json::from_value(json!([
+            { "op": "add", "path": "/tags/-", "value": "c" },
+            { "op": "replace", "path": "/title", "value": "Edited" },
+            { "op": "remove", "path": "/tags/0" },
+        ]))
+        .unwrap();
+        patch.apply(&mut target).unwrap();
+        assert_eq!(target, json!({ "tags": ["b", "c"], "title": "Edited" }));
+
+        let patch: JsonPatch = serde_json::from_value(json!([
+            { "op": "remove", "path": "/tags/0" },
+            { "op": "remove", "path": "/tags/+1" },
+        ]))
+        .unwrap();
+        assert_eq!(patch.apply(&mut target).unwrap_err().0, 422);
+        assert_eq!(target, json!({ "tags": ["b", "c"], "title": "Edited" }));
+    }
+}
@@ -0,0 +1,230 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hyper::header::{IF_MATCH, IF_UNMODIFIED_SINCE};
+
+use super::{RequestData, ToParam};
+use crate::response::Result;
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Parse an http date; ex: `Sun, 06 Nov 1994 08:49:37 GMT`
+pub(crate) fn parse_date(date: &str) -> Option<SystemTime> {
+    let (_, date) = date.trim().split_once(", ")?;
+    let parts: Vec<&str> = date.split(' ').collect();
+    if parts.len() != 5 || parts[4] != "GMT" {
+        return None;
+    }
+    let day = parts[0].parse::<i64>().ok()?;
+    let month = MONTHS.iter().position(|month| *month == parts[1])? as i64 + 1;
+    let year = parts[2].parse::<i64>().ok()?;
+    let time: Vec<u64> = parts[3]
+        .split(':')
+        .map(|part| part.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    if time.len() != 3 || !(1..=31).contains(&day) || time[0] > 23 || time[1] > 59 {
+        return None;
+    }
+
+    // Civil date to days since the epoch; http://howardhinnant.github.io/date_algorithms.html
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = u64::try_from(era * 146097 + doe - 719468).ok()?;
+
+    Some(UNIX_EPOCH + Duration::from_secs(days * 86400 + time[0] * 3600 + time[1] * 60 + time[2]))
+}
+
+/// ETag with quotes; versions can be given without them, ex: `3` -> `"3"`
+fn quote(etag: &str) -> String {
+    match etag.starts_with('"') || etag.starts_with("W/") {
+        true => etag.to_string(),
+        false => format!("\"{}\"", etag),
+    }
+}
+
+/// ETags in the `If-Match` header; the versions of a resource the client expects to change.
+/// Empty if the request doesn't have the header.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IfMatch(pub Vec<String>);
+
+impl IfMatch {
+    pub fn is_present(&self) -> bool {
+        !self.0.is_empty()
+    }
+
+    /// Whether the resource's ETag is one of the tags. Weak tags never match since a change
+    /// needs the exact version. Always true without the header.
+    pub fn matches(&self, etag: &str) -> bool {
+        if !self.is_present() {
+            return true;
+        }
+        let etag = quote(etag);
+        !etag.starts_with("W/")
+            && self
+                .0
+                .iter()
+                .any(|tag| tag == "*" || (!tag.starts_with("W/") && *tag == etag))
+    }
+}
+
+impl ToParam<IfMatch> for RequestData {
+    fn to_param(&mut self) -> Result<IfMatch> {
+        Ok(IfMatch(
+            self.3
+                .get_all(IF_MATCH)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .map(|tag| tag.trim().to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+        ))
+    }
+}
+
+/// Time in the `If-Unmodified-Since` header; when the client's copy of a resource was last
+/// modified. `None` if the request doesn't have the header or the date is invalid.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IfUnmodifiedSince(pub Option<SystemTime>);
+
+impl IfUnmodifiedSince {
+    pub fn is_present(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Whether the resource hasn't been modified since the time. Compared in whole seconds
+    /// since that is all http dates have. Always true without the header.
+    pub fn matches(&self, modified: SystemTime) -> bool {
+        let seconds = |time: SystemTime| {
+            time.duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs())
+                .unwrap_or(0)
+        };
+        match self.0 {
+            Some(since) => seconds(modified) <= seconds(since),
+            None => true,
+        }
+    }
+}
+
+impl ToParam<IfUnmodifiedSince> for RequestData {
+    fn to_param(&mut self) -> Result<IfUnmodifiedSince> {
+        Ok(IfUnmodifiedSince(
+            self.3
+                .get(IF_UNMODIFIED_SINCE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(parse_date),
+        ))
+    }
+}
+
+/// Conditional headers of a request for optimistic concurrency; check them against the
+/// current version of a resource before changing it so one client doesn't overwrite
+/// another's change.
+///
+/// A check that fails is a `412 Precondition Failed`. `If-Unmodified-Since` is only used
+/// when the request doesn't have `If-Match`. Requests without either header pass unless
+/// `required` is used, then they get a `428 Precondition Required`.
+///
+/// # Example
+/// ```
+/// use std::time::SystemTime;
+/// use tela::{
+///     prelude::*,
+///     request::{Body, Precondition},
+/// };
+///
+/// #[derive(serde::Deserialize)]
+/// struct Post { title: String }
+///
+/// mod db {
+///     # use super::Post;
+///     # use std::time::SystemTime;
+///     # use tela::response::Result;
+///     pub struct Current {
+///         pub version: u32,
+///         pub updated: SystemTime,
+///     }
+///
+///     pub fn post(_id: u32) -> Result<Current> {
+///         Ok(Current { version: 3, updated: SystemTime::UNIX_EPOCH })
+///     }
+///
+///     pub fn save(_id: u32, _post: Post) {}
+/// }
+///
+/// #[put("/posts/:id")]
+/// fn update(id: u32, precondition: Precondition, Body(post): Body<Post>) -> Result<String> {
+///     let current = db::post(id)?;
+///     precondition.required().check(&current.version.to_string(), Some(current.updated))?;
+///     db::save(id, post);
+///     response!("Updated".to_string())
+/// }
+/// #
+/// # fn main() {}
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Precondition {
+    pub if_match: IfMatch,
+    pub if_unmodified_since: IfUnmodifiedSince,
+    required: bool,
+}
+
+impl Precondition {
+    /// Reject requests that don't have `If-Match` or `If-Unmodified-Since`
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Check the headers against the resource's ETag and when it was last modified. ETags can
+    /// be given with or without quotes. `If-Unmodified-Since` is skipped without a modified
+    /// time.
+    pub fn check(&self, etag: &str, modified: Option<SystemTime>) -> Result<()> {
+        if self.if_match.is_present() {
+            return match self.if_match.matches(etag) {
+                true => Ok(()),
+                false => Err((
+                    412,
+                    "Resource has changed; If-Match does not match".to_string(),
+                )),
+            };
+        }
+        if self.if_unmodified_since.is_present() {
+            return match modified {
+                Some(modified) if !self.if_unmodified_since.matches(modified) => Err((
+                    412,
+                    "Resource has changed since If-Unmodified-Since".to_string(),
+                )),
+                _ => Ok(()),
+            };
+        }
+        match self.required {
+            true => Err((
+                428,
+                "Request must have an If-Match or If-Unmodified-Since header".to_string(),
+            )),
+            false => Ok(()),
+        }
+    }
+
+    /// Check the headers against the resource's ETag
+    pub fn check_etag(&self, etag: &str) -> Result<()> {
+        self.check(etag, None)
+    }
+}
+
+impl ToParam<Precondition> for RequestData {
+    fn to_param(&mut self) -> Result<Precondition> {
+        Ok(Precondition {
+            if_match: self.to_param()?,
+            if_unmodified_since: self.to_param()?,
+            required: false,
+        })
+    }
+}
@@ -1,8 +1,9 @@
-use crate::response::Result;
-use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+use serde::de::DeserializeOwned;
 
 pub trait IntoQuery {
-    fn into_query(query: &str) -> Result<Query<Self>>
+    fn into_query(query: &str) -> Result<Query<Self>, QueryError>
     where
         Self: Sized;
 }
@@ -16,23 +17,57 @@ impl<T: IntoQuery> From<String> for Query<T> {
     }
 }
 
-impl<'a, T: Deserialize<'a> + Default + Serialize> IntoQuery for T {
-    fn into_query(query: &str) -> Result<Query<Self>>
+/// Error from parsing a uri query into a type
+#[derive(Debug, Clone)]
+pub struct QueryError {
+    /// The query that failed to parse
+    pub query: String,
+    /// Why the query failed to parse
+    pub message: String,
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed to parse query {:?}: {}",
+            self.query, self.message
+        )
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// A query that can't be parsed is a bad request
+impl From<QueryError> for (u16, String) {
+    fn from(value: QueryError) -> Self {
+        (400, value.to_string())
+    }
+}
+
+/// Split a query into key value pairs without decoding them; ex: `a=1&b` -> `[("a", "1"), ("b", "")]`
+pub fn query_raw(query: &str) -> Vec<(&str, &str)> {
+    query
+        .trim_start_matches('?')
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| pair.split_once('=').unwrap_or((pair, "")))
+        .collect()
+}
+
+impl<T: DeserializeOwned> IntoQuery for T {
+    fn into_query(query: &str) -> Result<Query<Self>, QueryError>
     where
         Self: Sized,
     {
-        let query = query.to_string();
-        match serde_qs::from_str::<T>(Box::leak(query.clone().into_boxed_str())) {
+        match serde_qs::from_str::<T>(query) {
             Ok(result) => Ok(Query(result)),
-            Err(_) => match serde_plain::from_str::<T>(Box::leak(query.clone().into_boxed_str())) {
+            Err(error) => match serde_plain::from_str::<T>(query) {
                 Ok(result) => Ok(Query(result)),
-                Err(_) => Err((
-                    500,
-                    format!(
-                        "Failed to parse query from request; expected <span class=path>?{}</span>",
-                        serde_qs::to_string(&T::default()).unwrap()
-                    ),
-                )),
+                Err(_) => Err(QueryError {
+                    query: query.to_string(),
+                    message: error.to_string(),
+                }),
             },
         }
     }
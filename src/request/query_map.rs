@@ -0,0 +1,125 @@
+use std::str::FromStr;
+
+use percent_encoding::percent_decode_str;
+
+use super::query::query_raw;
+use crate::response::Result;
+
+/// Decode a `application/x-www-form-urlencoded` key or value
+fn decode(value: &str) -> String {
+    percent_decode_str(&value.replace('+', " "))
+        .decode_utf8_lossy()
+        .to_string()
+}
+
+/// Uri query parsed into key value pairs where a key can be repeated; ex: `?tag=a&tag=b`.
+///
+/// Pairs keep the order they are in the uri and keys and values are percent decoded.
+/// If the request doesn't have a query the map is empty.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, request::QueryMap, response::JSON};
+///
+/// #[derive(serde::Serialize)]
+/// struct Post {
+///     tag: String,
+///     page: u32,
+/// }
+///
+/// #[get("/posts")]
+/// fn posts(query: QueryMap) -> Result<JSON<Vec<Post>>> {
+///     let tags = query.get_all("tag");
+///     let page = query.parse::<u32>("page")?.unwrap_or(1);
+///     Ok(JSON(
+///         tags.into_iter()
+///             .map(|tag| Post { tag: tag.to_string(), page })
+///             .collect(),
+///     ))
+/// }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct QueryMap(Vec<(String, String)>);
+
+impl QueryMap {
+    pub fn new(query: &str) -> Self {
+        QueryMap(
+            query_raw(query)
+                .into_iter()
+                .map(|(key, value)| (decode(key), decode(value)))
+                .collect(),
+        )
+    }
+
+    /// First value for the key
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Every value for the key
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.0
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    /// Parse the first value for the key. Responds with a 400 if the value can't be parsed.
+    pub fn parse<T: FromStr>(&self, key: &str) -> Result<Option<T>> {
+        match self.get(key) {
+            Some(value) => value.parse::<T>().map(Some).map_err(|_| {
+                (
+                    400,
+                    format!("Invalid value {:?} for query parameter {:?}", value, key),
+                )
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Parse every value for the key. Responds with a 400 if any value can't be parsed.
+    pub fn parse_all<T: FromStr>(&self, key: &str) -> Result<Vec<T>> {
+        self.get_all(key)
+            .into_iter()
+            .map(|value| {
+                value.parse::<T>().map_err(|_| {
+                    (
+                        400,
+                        format!("Invalid value {:?} for query parameter {:?}", value, key),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.0.iter().any(|(k, _)| k == key)
+    }
+
+    /// Unique keys in the order they first appear
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = Vec::new();
+        for (key, _) in self.0.iter() {
+            if !keys.contains(&key.as_str()) {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
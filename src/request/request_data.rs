@@ -1,18 +1,110 @@
+use std::borrow::Cow;
+
+use bytes::Bytes;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
 use crate::response::Result;
 
-use super::{body::IntoBody, query::IntoQuery, Body, Query};
+use super::{
+    body::IntoBody, form::IntoForm, query::query_raw, query::IntoQuery, Body, BodyStream, Form,
+    Query, QueryError, QueryMap, Trailers,
+};
 
 pub trait ToParam<T> {
     fn to_param(&mut self) -> Result<T>;
 }
-pub struct RequestData(pub hyper::Uri, pub hyper::Method, pub Vec<u8>);
+pub struct RequestData(
+    pub hyper::Uri,
+    pub hyper::Method,
+    pub Bytes,
+    pub hyper::HeaderMap,
+    pub hyper::HeaderMap,
+    pub BodyStream,
+);
+
+impl RequestData {
+    /// Uri query split into key value pairs without decoding or copying them.
+    /// See `QueryMap` for decoded pairs.
+    pub fn query_raw(&self) -> Vec<(&str, &str)> {
+        query_raw(self.0.query().unwrap_or(""))
+    }
+
+    /// Request body as text
+    fn body(&self) -> Result<&str> {
+        std::str::from_utf8(&self.2[..])
+            .map_err(|_| (400, "Request body is not valid utf-8".to_string()))
+    }
+
+    /// Encoded form data. Browsers send `GET` and `HEAD` forms in the query and every other
+    /// method in the body.
+    fn form(&self) -> Result<Cow<'_, str>> {
+        if self.1 == hyper::Method::GET || self.1 == hyper::Method::HEAD {
+            return Ok(Cow::Borrowed(self.0.query().unwrap_or("")));
+        }
+
+        let charset = self
+            .3
+            .get(hyper::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(charset);
+        match charset {
+            None => self.body().map(Cow::Borrowed),
+            Some(charset) if charset == "utf-8" || charset == "us-ascii" => {
+                self.body().map(Cow::Borrowed)
+            }
+            Some(charset) if LATIN1.contains(&charset.as_str()) => {
+                Ok(Cow::Owned(latin1_form(self.body()?)))
+            }
+            Some(charset) => Err((415, format!("Unsupported form charset {:?}", charset))),
+        }
+    }
+}
+
+/// Charsets that are decoded as latin-1
+const LATIN1: [&str; 4] = ["iso-8859-1", "latin1", "windows-1252", "cp1252"];
+
+/// Characters encoded when re-encoding form data. Brackets are kept so nested keys still parse.
+const FORM: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'[')
+    .remove(b']')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Lowercase charset parameter of a content type; ex: `text/plain; charset=UTF-8` -> `utf-8`
+fn charset(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        match key.trim().eq_ignore_ascii_case("charset") {
+            true => Some(value.trim().trim_matches('"').to_lowercase()),
+            false => None,
+        }
+    })
+}
+
+/// Re-encode latin-1 form data as utf-8 form data
+fn latin1_form(form: &str) -> String {
+    let encode = |part: &str| {
+        let bytes = percent_decode_str(&part.replace('+', " ")).collect::<Vec<u8>>();
+        let text = bytes.into_iter().map(char::from).collect::<String>();
+        utf8_percent_encode(&text, FORM).to_string()
+    };
+
+    form.split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => format!("{}={}", encode(key), encode(value)),
+            None => encode(pair),
+        })
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+// A missing query is parsed as an empty query so types where every field is optional still parse
 
 impl<T: IntoQuery> ToParam<Query<T>> for RequestData {
     fn to_param(&mut self) -> Result<Query<T>> {
-        match self.0.query() {
-            Some(query) => T::into_query(query),
-            _ => Err((500, "No query to parse".to_string())),
-        }
+        Ok(T::into_query(self.0.query().unwrap_or(""))?)
     }
 }
 
@@ -27,30 +119,74 @@ impl<T: IntoQuery> ToParam<Option<Query<T>>> for RequestData {
 
 impl<T: IntoQuery> ToParam<Result<Query<T>>> for RequestData {
     fn to_param(&mut self) -> Result<Result<Query<T>>> {
-        match self.0.query() {
-            Some(query) => Ok(T::into_query(query)),
-            _ => Ok(Err((500, "No query to parse".to_string()))),
-        }
+        Ok(T::into_query(self.0.query().unwrap_or("")).map_err(|error| error.into()))
+    }
+}
+
+impl<T: IntoQuery> ToParam<std::result::Result<Query<T>, QueryError>> for RequestData {
+    fn to_param(&mut self) -> Result<std::result::Result<Query<T>, QueryError>> {
+        Ok(T::into_query(self.0.query().unwrap_or("")))
+    }
+}
+
+/// Never fails; a request without a query gives an empty map
+impl ToParam<QueryMap> for RequestData {
+    fn to_param(&mut self) -> Result<QueryMap> {
+        Ok(QueryMap::new(self.0.query().unwrap_or("")))
     }
 }
 
 impl<T: IntoBody> ToParam<Body<T>> for RequestData {
     fn to_param(&mut self) -> Result<Body<T>> {
-        let body = std::str::from_utf8(&self.2[..]).unwrap();
-        T::into_body(body)
+        T::into_body(self.body()?)
     }
 }
 
 impl<T: IntoBody> ToParam<Option<Body<T>>> for RequestData {
     fn to_param(&mut self) -> Result<Option<Body<T>>> {
-        let body = std::str::from_utf8(&self.2[..]).unwrap();
-        Ok(T::into_body(body).ok())
+        Ok(self.body().and_then(T::into_body).ok())
     }
 }
 
 impl<T: IntoBody> ToParam<Result<Body<T>>> for RequestData {
     fn to_param(&mut self) -> Result<Result<Body<T>>> {
-        let body = std::str::from_utf8(&self.2[..]).unwrap();
-        Ok(T::into_body(body))
+        Ok(self.body().and_then(T::into_body))
+    }
+}
+
+impl<T: IntoForm> ToParam<Form<T>> for RequestData {
+    fn to_param(&mut self) -> Result<Form<T>> {
+        T::into_form(&self.form()?)
+    }
+}
+
+impl<T: IntoForm> ToParam<Option<Form<T>>> for RequestData {
+    fn to_param(&mut self) -> Result<Option<Form<T>>> {
+        Ok(self.form().and_then(|form| T::into_form(&form)).ok())
+    }
+}
+
+impl<T: IntoForm> ToParam<Result<Form<T>>> for RequestData {
+    fn to_param(&mut self) -> Result<Result<Form<T>>> {
+        Ok(self.form().and_then(|form| T::into_form(&form)))
+    }
+}
+
+/// The raw request body. This is a reference to the body that was read so no copy is made.
+impl ToParam<Bytes> for RequestData {
+    fn to_param(&mut self) -> Result<Bytes> {
+        Ok(self.2.clone())
+    }
+}
+
+impl ToParam<Trailers> for RequestData {
+    fn to_param(&mut self) -> Result<Trailers> {
+        Ok(Trailers(self.4.clone()))
+    }
+}
+
+impl ToParam<BodyStream> for RequestData {
+    fn to_param(&mut self) -> Result<BodyStream> {
+        Ok(self.5.clone())
     }
 }
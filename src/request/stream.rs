@@ -0,0 +1,84 @@
+use std::{
+    fmt::Debug,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use hyper::body::Incoming;
+
+use crate::response::Result;
+
+type Frames = Pin<Box<dyn Stream<Item = Result<Bytes>> + Send>>;
+
+/// Request body read a chunk at a time as it arrives instead of being read in full before
+/// the handler is called; ex: hashing an upload or piping it to storage.
+///
+/// The body is only streamed for endpoints that take a `BodyStream`. Every other endpoint
+/// gets an empty stream. The stream can only be read once, and a chunk that fails to be read
+/// is a 400 error.
+///
+/// Handlers are not async so the stream can either be read with `blocking_next` or
+/// moved into a task with `tokio::spawn`.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, request::BodyStream};
+///
+/// #[post("/upload")]
+/// fn upload(mut stream: BodyStream) -> Result<String> {
+///     let mut size = 0;
+///     while let Some(chunk) = stream.blocking_next() {
+///         size += chunk?.len();
+///     }
+///     response!(format!("Received {} bytes", size))
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct BodyStream(Arc<Mutex<Option<Frames>>>);
+
+impl BodyStream {
+    pub(crate) fn new(body: Incoming) -> Self {
+        let frames = http_body_util::BodyStream::new(body).filter_map(|frame| async move {
+            match frame {
+                // Trailers are skipped
+                Ok(frame) => frame.into_data().ok().map(Ok),
+                Err(error) => Some(Err((
+                    400,
+                    format!("Failed to read request body: {}", error),
+                ))),
+            }
+        });
+        BodyStream(Arc::new(Mutex::new(Some(Box::pin(frames)))))
+    }
+
+    /// Wait for the next chunk of the body. Endpoints that take a `BodyStream` are run on a
+    /// blocking thread so this doesn't hold up other requests.
+    pub fn blocking_next(&mut self) -> Option<Result<Bytes>> {
+        if self.0.lock().unwrap().is_none() {
+            return None;
+        }
+        tokio::runtime::Handle::current().block_on(self.next())
+    }
+}
+
+impl Stream for BodyStream {
+    type Item = Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.0.lock().unwrap().as_mut() {
+            Some(frames) => frames.as_mut().poll_next(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+impl Debug for BodyStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BodyStream")
+            .field("streaming", &self.0.lock().unwrap().is_some())
+            .finish()
+    }
+}
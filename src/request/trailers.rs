@@ -0,0 +1,22 @@
+use hyper::HeaderMap;
+
+/// Trailers sent after a chunked request body; ex: a checksum of an upload.
+///
+/// Trailers are only available once the whole body has been read. If the client didn't send
+/// any the map is empty.
+///
+/// # Example
+/// ```
+/// use bytes::Bytes;
+/// use tela::{prelude::*, request::Trailers};
+///
+/// #[post("/upload")]
+/// fn upload(body: Bytes, Trailers(trailers): Trailers) -> Result<String> {
+///     match trailers.get("Digest") {
+///         Some(digest) => response!(format!("Received {} bytes with {:?}", body.len(), digest)),
+///         None => response!(400, "Missing upload digest"),
+///     }
+/// }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Trailers(pub HeaderMap);
@@ -0,0 +1,424 @@
+use serde::Serialize;
+
+use super::Part;
+use crate::response::Problem;
+
+/// Type of an uploaded file found from its magic bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileType {
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+    Pdf,
+    Zip,
+}
+
+impl FileType {
+    pub fn mime(&self) -> &'static str {
+        match self {
+            FileType::Png => "image/png",
+            FileType::Jpeg => "image/jpeg",
+            FileType::Gif => "image/gif",
+            FileType::Webp => "image/webp",
+            FileType::Pdf => "application/pdf",
+            FileType::Zip => "application/zip",
+        }
+    }
+
+    pub fn is_image(&self) -> bool {
+        matches!(
+            self,
+            FileType::Png | FileType::Jpeg | FileType::Gif | FileType::Webp
+        )
+    }
+}
+
+/// Find the type of a file from its first bytes instead of trusting the content type the
+/// client sent
+pub fn sniff(data: &[u8]) -> Option<FileType> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(FileType::Png)
+    } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some(FileType::Jpeg)
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some(FileType::Gif)
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some(FileType::Webp)
+    } else if data.starts_with(b"%PDF-") {
+        Some(FileType::Pdf)
+    } else if data.starts_with(b"PK\x03\x04") || data.starts_with(b"PK\x05\x06") {
+        Some(FileType::Zip)
+    } else {
+        None
+    }
+}
+
+/// Width and height of an image read from its header
+fn dimensions(kind: FileType, data: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |at: usize| Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?) as u32);
+    let le16 = |at: usize| Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?) as u32);
+    let be32 = |at: usize| Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?));
+    let le24 = |at: usize| {
+        let bytes = data.get(at..at + 3)?;
+        Some(bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16)
+    };
+
+    match kind {
+        FileType::Png => Some((be32(16)?, be32(20)?)),
+        FileType::Gif => Some((le16(6)?, le16(8)?)),
+        FileType::Jpeg => {
+            // Walk the segments until a start of frame
+            let mut at = 2;
+            while at + 4 <= data.len() {
+                if data[at] != 0xFF {
+                    return None;
+                }
+                let marker = data[at + 1];
+                match marker {
+                    0xFF => at += 1,
+                    0xD0..=0xD9 | 0x01 => at += 2,
+                    0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                        return Some((be16(at + 7)?, be16(at + 5)?));
+                    }
+                    _ => at += 2 + be16(at + 2)? as usize,
+                }
+            }
+            None
+        }
+        FileType::Webp => match data.get(12..16)? {
+            b"VP8 " => Some((le16(26)? & 0x3FFF, le16(28)? & 0x3FFF)),
+            b"VP8L" => {
+                let bits = data.get(21..25)?;
+                let width = 1 + (bits[0] as u32 | (bits[1] as u32 & 0x3F) << 8);
+                let height = 1
+                    + (bits[1] as u32 >> 6 | (bits[2] as u32) << 2 | (bits[3] as u32 & 0xF) << 10);
+                Some((width, height))
+            }
+            b"VP8X" => Some((1 + le24(24)?, 1 + le24(27)?)),
+            _ => None,
+        },
+        FileType::Pdf | FileType::Zip => None,
+    }
+}
+
+/// Rules an uploaded file is checked against. The file's type comes from its magic bytes
+/// so a client can't get a file accepted by changing its content type.
+///
+/// Files sent in a `multipart/form-data` form are checked a part at a time with
+/// `check_part`, or all at once with `Multipart::check_files`.
+///
+/// Failures are `UploadError`s which convert into a `422 Unprocessable Entity` `Problem`, or
+/// an endpoint error, with `?`.
+///
+/// # Example
+/// ```
+/// use tela::{
+///     prelude::*,
+///     request::{FileType, Multipart, UploadRules},
+///     response::Problem,
+/// };
+///
+/// #[post("/avatar")]
+/// fn upload_avatar(form: Multipart) -> std::result::Result<String, Problem> {
+///     let avatar = form.get("avatar").ok_or(Problem::new(400).detail("Missing avatar"))?;
+///     let kind = UploadRules::new()
+///         .allow(FileType::Png)
+///         .allow(FileType::Jpeg)
+///         .max_size(2 * 1024 * 1024)
+///         .max_dimensions(1024, 1024)
+///         .check_part(avatar)?;
+///     Ok(format!("Saved {}", kind.mime()))
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct UploadRules {
+    allowed: Vec<FileType>,
+    max_size: Option<usize>,
+    max_dimensions: Option<(u32, u32)>,
+}
+
+impl UploadRules {
+    pub fn new() -> Self {
+        UploadRules::default()
+    }
+
+    /// Allow a type of file. Every type is rejected until one is allowed.
+    pub fn allow(mut self, kind: FileType) -> Self {
+        if !self.allowed.contains(&kind) {
+            self.allowed.push(kind);
+        }
+        self
+    }
+
+    /// Largest file in bytes
+    pub fn max_size(mut self, bytes: usize) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Largest width and height of an image. Images whose size can't be read are rejected.
+    pub fn max_dimensions(mut self, width: u32, height: u32) -> Self {
+        self.max_dimensions = Some((width, height));
+        self
+    }
+
+    /// Check a file against the rules. The field is the name of the form field or upload
+    /// it came from and is included in the error.
+    pub fn check(&self, field: &str, data: &[u8]) -> std::result::Result<FileType, UploadError> {
+        let error = |code, message: String| UploadError {
+            field: field.to_string(),
+            code,
+            message,
+        };
+
+        if let Some(max) = self.max_size {
+            if data.len() > max {
+                return Err(error(
+                    "size",
+                    format!("File is {} bytes; the limit is {} bytes", data.len(), max),
+                ));
+            }
+        }
+
+        let kind = match sniff(data) {
+            Some(kind) if self.allowed.contains(&kind) => kind,
+            _ => {
+                let allowed = self
+                    .allowed
+                    .iter()
+                    .map(FileType::mime)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                return Err(error(
+                    "type",
+                    format!("File type is not allowed; expected one of: {}", allowed),
+                ));
+            }
+        };
+
+        if let (Some((max_width, max_height)), true) = (self.max_dimensions, kind.is_image()) {
+            match dimensions(kind, data) {
+                Some((width, height)) if width <= max_width && height <= max_height => {}
+                Some((width, height)) => {
+                    return Err(error(
+                        "dimensions",
+                        format!(
+                            "Image is {}x{}; the limit is {}x{}",
+                            width, height, max_width, max_height
+                        ),
+                    ))
+                }
+                None => {
+                    return Err(error(
+                        "dimensions",
+                        "Image dimensions could not be read".to_string(),
+                    ))
+                }
+            }
+        }
+
+        Ok(kind)
+    }
+
+    /// Check a file part of a multipart form against the rules. The type comes from the
+    /// part's data, not the content type the client sent with it.
+    pub fn check_part(&self, part: &Part) -> std::result::Result<FileType, UploadError> {
+        self.check(&part.name, &part.data)
+    }
+}
+
+/// Uploaded file that broke an `UploadRules` rule
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadError {
+    /// Form field or upload the file came from
+    pub field: String,
+    /// Rule that was broken; `size`, `type`, or `dimensions`
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl std::fmt::Display for UploadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+impl std::error::Error for UploadError {}
+
+impl From<UploadError> for Problem {
+    fn from(error: UploadError) -> Self {
+        Problem::new(422)
+            .title("Invalid upload")
+            .detail(error.to_string())
+            .extension("errors", [error])
+    }
+}
+
+impl From<UploadError> for (u16, String) {
+    fn from(error: UploadError) -> Self {
+        (422, error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+
+    /// Signature and `IHDR` chunk of a png
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        data.extend(width.to_be_bytes());
+        data.extend(height.to_be_bytes());
+        data.extend([8, 6, 0, 0, 0]);
+        data
+    }
+
+    /// Start of image, a `JFIF` segment, and a baseline start of frame
+    fn jpeg(width: u16, height: u16) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+        data.extend(b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0");
+        data.extend([0xFF, 0xC0, 0x00, 0x11, 0x08]);
+        data.extend(height.to_be_bytes());
+        data.extend(width.to_be_bytes());
+        data.extend([0x03, 0x01, 0x22, 0x00]);
+        data
+    }
+
+    fn gif(width: u16, height: u16) -> Vec<u8> {
+        let mut data = b"GIF89a".to_vec();
+        data.extend(width.to_le_bytes());
+        data.extend(height.to_le_bytes());
+        data.extend([0xF7, 0x00, 0x00]);
+        data
+    }
+
+    /// `RIFF` header and the start of a `chunk`
+    fn webp(chunk: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut webp = b"RIFF\x24\0\0\0WEBP".to_vec();
+        webp.extend(chunk);
+        webp.extend(b"\x10\0\0\0");
+        webp.extend(data);
+        webp
+    }
+
+    fn part(content_type: &str, data: Vec<u8>) -> Part {
+        Part {
+            name: "avatar".to_string(),
+            filename: Some("avatar.png".to_string()),
+            content_type: Some(content_type.to_string()),
+            data: Bytes::from(data),
+        }
+    }
+
+    #[test]
+    fn sniff_file_types() {
+        assert_eq!(sniff(&png(1, 1)), Some(FileType::Png));
+        assert_eq!(sniff(&jpeg(1, 1)), Some(FileType::Jpeg));
+        assert_eq!(sniff(&gif(1, 1)), Some(FileType::Gif));
+        assert_eq!(sniff(b"GIF87a\x01\0\x01\0"), Some(FileType::Gif));
+        assert_eq!(sniff(&webp(b"VP8 ", &[])), Some(FileType::Webp));
+        assert_eq!(sniff(b"%PDF-1.7\n%\xE2\xE3\xCF\xD3"), Some(FileType::Pdf));
+        assert_eq!(sniff(b"PK\x03\x04\x14\0\0\0"), Some(FileType::Zip));
+        assert_eq!(sniff(b"PK\x05\x06\0\0\0\0"), Some(FileType::Zip));
+
+        assert_eq!(sniff(b""), None);
+        assert_eq!(sniff(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"), None);
+        assert_eq!(sniff(b"\x89PNG"), None);
+        assert_eq!(sniff(b"RIFF\x24\0\0\0WAVEfmt "), None);
+        assert_eq!(sniff(b"RIFF\x24\0\0\0WEB"), None);
+    }
+
+    #[test]
+    fn image_dimensions() {
+        assert_eq!(dimensions(FileType::Png, &png(640, 480)), Some((640, 480)));
+        assert_eq!(
+            dimensions(FileType::Jpeg, &jpeg(800, 600)),
+            Some((800, 600))
+        );
+        assert_eq!(dimensions(FileType::Gif, &gif(320, 200)), Some((320, 200)));
+
+        // Lossy frame: tag, start code, then 14 bit width and height
+        let lossy = webp(b"VP8 ", b"\x30\x01\0\x9d\x01\x2a\x90\x01\x2c\x01");
+        assert_eq!(dimensions(FileType::Webp, &lossy), Some((400, 300)));
+        // Lossless: signature then the width and height less one in 14 bits each
+        let lossless = webp(b"VP8L", b"\x2f\x8f\xc1\x4a\x00");
+        assert_eq!(dimensions(FileType::Webp, &lossless), Some((400, 300)));
+        // Extended: flags then the canvas width and height less one in 24 bits each
+        let extended = webp(b"VP8X", b"\x10\0\0\0\x8f\x01\0\x2b\x01\0");
+        assert_eq!(dimensions(FileType::Webp, &extended), Some((400, 300)));
+
+        assert_eq!(dimensions(FileType::Pdf, b"%PDF-1.7"), None);
+        assert_eq!(dimensions(FileType::Zip, b"PK\x03\x04"), None);
+    }
+
+    #[test]
+    fn truncated_images_have_no_dimensions() {
+        let png = png(640, 480);
+        assert_eq!(sniff(&png[..18]), Some(FileType::Png));
+        assert_eq!(dimensions(FileType::Png, &png[..18]), None);
+
+        // Cut off before and inside of the start of frame
+        let jpeg = jpeg(800, 600);
+        assert_eq!(dimensions(FileType::Jpeg, &jpeg[..20]), None);
+        assert_eq!(dimensions(FileType::Jpeg, &jpeg[..27]), None);
+        assert_eq!(dimensions(FileType::Gif, &gif(1, 1)[..8]), None);
+        assert_eq!(dimensions(FileType::Webp, &webp(b"VP8X", b"\x10\0")), None);
+        assert_eq!(dimensions(FileType::Webp, &webp(b"ALPH", &[0; 10])), None);
+
+        let rules = UploadRules::new()
+            .allow(FileType::Png)
+            .max_dimensions(1024, 1024);
+        let error = rules.check("avatar", &png[..18]).unwrap_err();
+        assert_eq!(error.code, "dimensions");
+        assert_eq!(rules.check("avatar", &png).unwrap(), FileType::Png);
+    }
+
+    #[test]
+    fn content_type_is_not_trusted() {
+        let rules = UploadRules::new()
+            .allow(FileType::Png)
+            .allow(FileType::Jpeg);
+
+        // A pdf or script sent as a png is rejected
+        for data in [
+            b"%PDF-1.7\n".to_vec(),
+            b"<script>alert(1)</script>".to_vec(),
+        ] {
+            let error = rules.check_part(&part("image/png", data)).unwrap_err();
+            assert_eq!(error.field, "avatar");
+            assert_eq!(error.code, "type");
+            assert!(error.message.contains("image/png, image/jpeg"));
+        }
+        // A jpeg sent as something else is still a jpeg
+        assert_eq!(
+            rules
+                .check_part(&part("application/octet-stream", jpeg(1, 1)))
+                .unwrap(),
+            FileType::Jpeg
+        );
+    }
+
+    #[test]
+    fn rules_limit_size_and_dimensions() {
+        let rules = UploadRules::new()
+            .allow(FileType::Gif)
+            .max_size(64)
+            .max_dimensions(100, 100);
+        assert_eq!(rules.check("logo", &gif(100, 100)).unwrap(), FileType::Gif);
+        assert_eq!(
+            rules.check("logo", &gif(101, 10)).unwrap_err().code,
+            "dimensions"
+        );
+        assert_eq!(rules.check("logo", &[b'G'; 65]).unwrap_err().code, "size");
+        assert_eq!(
+            UploadRules::new()
+                .check("logo", &gif(1, 1))
+                .unwrap_err()
+                .code,
+            "type"
+        );
+    }
+}
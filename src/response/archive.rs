@@ -0,0 +1,452 @@
+use std::{
+    io::Cursor,
+    path::PathBuf,
+    pin::Pin,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{HeaderMap, Method, Uri};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::{stream::streaming, Result, ToResponse};
+
+type Reader = Pin<Box<dyn AsyncRead + Send>>;
+
+/// Size of the chunks read from each entry
+const CHUNK: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Zip,
+    Tar,
+}
+
+enum Source {
+    File(PathBuf),
+    Bytes(Bytes),
+    Reader(Reader),
+}
+
+struct Entry {
+    name: String,
+    source: Source,
+}
+
+/// Zip or tar archive that is written as it is sent; ex: a "download all" endpoint.
+///
+/// Entries are read one at a time while the response streams so the archive is never built
+/// in memory or in a temp file. Zip entries are stored without compression. Tar needs the size
+/// of each entry before its contents, so `reader` entries in a tar are read into memory first.
+///
+/// Headers are sent before the entries are read. If an entry fails to open or read the
+/// archive ends early and the client gets a truncated download.
+///
+/// # Example
+/// ```
+/// use std::path::PathBuf;
+/// use tela::{prelude::*, response::Archive};
+///
+/// struct Photo {
+///     name: String,
+///     path: PathBuf,
+/// }
+///
+/// fn photos(album: u32) -> Vec<Photo> {
+///     (1..=3)
+///         .map(|i| Photo {
+///             name: format!("{}.jpg", i),
+///             path: PathBuf::from(format!("albums/{}/{}.jpg", album, i)),
+///         })
+///         .collect()
+/// }
+///
+/// #[get("/albums/:id/download")]
+/// fn download(id: u32) -> Archive {
+///     photos(id)
+///         .into_iter()
+///         .fold(Archive::zip().filename(format!("album-{}.zip", id)), |archive, photo| {
+///             archive.file(&photo.name, &photo.path)
+///         })
+///         .bytes("README.txt", "Downloaded from tela")
+/// }
+/// ```
+pub struct Archive {
+    format: Format,
+    entries: Vec<Entry>,
+    filename: Option<String>,
+}
+
+impl Archive {
+    /// `application/zip` archive
+    pub fn zip() -> Self {
+        Archive {
+            format: Format::Zip,
+            entries: Vec::new(),
+            filename: None,
+        }
+    }
+
+    /// `application/x-tar` archive
+    pub fn tar() -> Self {
+        Archive {
+            format: Format::Tar,
+            entries: Vec::new(),
+            filename: None,
+        }
+    }
+
+    /// Name the archive is saved as
+    pub fn filename<T: Into<String>>(mut self, filename: T) -> Self {
+        self.filename = Some(filename.into());
+        self
+    }
+
+    /// Add a file from disk under the name in the archive
+    pub fn file<N: Into<String>, P: Into<PathBuf>>(mut self, name: N, path: P) -> Self {
+        self.entries.push(Entry {
+            name: name.into(),
+            source: Source::File(path.into()),
+        });
+        self
+    }
+
+    /// Add in-memory data under the name in the archive
+    pub fn bytes<N: Into<String>, B: Into<Bytes>>(mut self, name: N, data: B) -> Self {
+        self.entries.push(Entry {
+            name: name.into(),
+            source: Source::Bytes(data.into()),
+        });
+        self
+    }
+
+    /// Add data produced by an async reader under the name in the archive
+    pub fn reader<N: Into<String>, R: AsyncRead + Send + 'static>(
+        mut self,
+        name: N,
+        reader: R,
+    ) -> Self {
+        self.entries.push(Entry {
+            name: name.into(),
+            source: Source::Reader(Box::pin(reader)),
+        });
+        self
+    }
+}
+
+impl ToResponse for Archive {
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        _headers: &HeaderMap,
+        _body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        let content_type = match self.format {
+            Format::Zip => "application/zip",
+            Format::Tar => "application/x-tar",
+        };
+        let writer = Writer {
+            format: self.format,
+            entries: self.entries.into_iter(),
+            current: None,
+            offset: 0,
+            central: Vec::new(),
+            count: 0,
+            done: false,
+            modified: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs())
+                .unwrap_or(0),
+        };
+        let chunks = futures::stream::unfold(writer, |mut writer| async move {
+            match writer.next().await {
+                Ok(Some(chunk)) => Some((chunk, writer)),
+                Ok(None) => None,
+                Err(error) => {
+                    // Headers are already sent so the stream can only be ended early
+                    eprintln!("Failed to write archive: {}", error);
+                    None
+                }
+            }
+        });
+
+        let mut response = streaming(content_type, chunks);
+        if let Some(filename) = self.filename {
+            let filename = filename.replace(['"', '\\'], "_");
+            if let Ok(value) = format!("attachment; filename=\"{}\"", filename).parse() {
+                response
+                    .headers_mut()
+                    .insert(hyper::header::CONTENT_DISPOSITION, value);
+            }
+        }
+        Ok(response)
+    }
+}
+
+/// Entry that is currently being written
+struct Current {
+    name: String,
+    reader: Reader,
+    /// Offset of the zip local header
+    header: u64,
+    crc: crc32fast::Hasher,
+    size: u64,
+    /// Size written in the tar header
+    expected: u64,
+}
+
+/// Writes the archive one chunk at a time
+struct Writer {
+    format: Format,
+    entries: std::vec::IntoIter<Entry>,
+    current: Option<Current>,
+    /// Bytes written so far
+    offset: u64,
+    /// Zip central directory records
+    central: Vec<u8>,
+    count: u16,
+    done: bool,
+    modified: u64,
+}
+
+impl Writer {
+    async fn next(&mut self) -> std::io::Result<Option<Bytes>> {
+        let read = match self.current.as_mut() {
+            Some(current) => {
+                let mut buffer = vec![0; CHUNK];
+                let read = current.reader.read(&mut buffer).await?;
+                buffer.truncate(read);
+                if self.format == Format::Tar {
+                    // A tar entry can't be longer than the size in its header
+                    let remaining = current.expected.saturating_sub(current.size);
+                    buffer.truncate(remaining.min(read as u64) as usize);
+                }
+                current.crc.update(&buffer);
+                current.size += buffer.len() as u64;
+                Some((read, buffer))
+            }
+            None => None,
+        };
+
+        let chunk = match read {
+            Some((0, _)) => self.finish_entry(),
+            Some((_, buffer)) => buffer,
+            None => match self.entries.next() {
+                Some(entry) => self.start_entry(entry).await?,
+                None if !self.done => {
+                    self.done = true;
+                    self.end()
+                }
+                None => return Ok(None),
+            },
+        };
+        self.offset += chunk.len() as u64;
+        Ok(Some(Bytes::from(chunk)))
+    }
+
+    async fn start_entry(&mut self, entry: Entry) -> std::io::Result<Vec<u8>> {
+        let name = entry
+            .name
+            .replace('\\', "/")
+            .trim_start_matches('/')
+            .to_string();
+        let (reader, expected): (Reader, u64) = match entry.source {
+            Source::File(path) => {
+                let file = tokio::fs::File::open(&path).await?;
+                let len = file.metadata().await?.len();
+                (Box::pin(file), len)
+            }
+            Source::Bytes(data) => {
+                let len = data.len() as u64;
+                (Box::pin(Cursor::new(data)), len)
+            }
+            Source::Reader(mut reader) if self.format == Format::Tar => {
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data).await?;
+                let len = data.len() as u64;
+                (Box::pin(Cursor::new(data)), len)
+            }
+            Source::Reader(reader) => (reader, 0),
+        };
+
+        let header = match self.format {
+            Format::Zip => self.zip_local_header(&name),
+            Format::Tar => self.tar_header(&name, expected),
+        };
+        self.current = Some(Current {
+            name,
+            reader,
+            header: self.offset,
+            crc: crc32fast::Hasher::new(),
+            size: 0,
+            expected,
+        });
+        Ok(header)
+    }
+
+    fn finish_entry(&mut self) -> Vec<u8> {
+        let current = self.current.take().unwrap();
+        match self.format {
+            Format::Zip => {
+                let crc = current.crc.finalize();
+                let mut descriptor = Vec::with_capacity(16);
+                descriptor.extend_from_slice(&0x08074b50u32.to_le_bytes());
+                descriptor.extend_from_slice(&crc.to_le_bytes());
+                descriptor.extend_from_slice(&(current.size as u32).to_le_bytes());
+                descriptor.extend_from_slice(&(current.size as u32).to_le_bytes());
+
+                let (time, date) = dos_time(self.modified);
+                let central = &mut self.central;
+                central.extend_from_slice(&0x02014b50u32.to_le_bytes());
+                central.extend_from_slice(&20u16.to_le_bytes());
+                central.extend_from_slice(&20u16.to_le_bytes());
+                central.extend_from_slice(&ZIP_FLAGS.to_le_bytes());
+                central.extend_from_slice(&0u16.to_le_bytes());
+                central.extend_from_slice(&time.to_le_bytes());
+                central.extend_from_slice(&date.to_le_bytes());
+                central.extend_from_slice(&crc.to_le_bytes());
+                central.extend_from_slice(&(current.size as u32).to_le_bytes());
+                central.extend_from_slice(&(current.size as u32).to_le_bytes());
+                central.extend_from_slice(&(current.name.len() as u16).to_le_bytes());
+                // Extra field, comment, disk, and internal attribute lengths
+                central.extend_from_slice(&[0; 8]);
+                central.extend_from_slice(&0u32.to_le_bytes());
+                central.extend_from_slice(&(current.header as u32).to_le_bytes());
+                central.extend_from_slice(current.name.as_bytes());
+                self.count = self.count.wrapping_add(1);
+                descriptor
+            }
+            Format::Tar => {
+                // Fill a short read with zeros so the entry matches its header, then pad to
+                // the end of the block
+                let missing = current.expected.saturating_sub(current.size);
+                vec![0; (missing + padding(current.expected)) as usize]
+            }
+        }
+    }
+
+    fn end(&mut self) -> Vec<u8> {
+        match self.format {
+            Format::Zip => {
+                let mut end = std::mem::take(&mut self.central);
+                let size = end.len() as u32;
+                end.extend_from_slice(&0x06054b50u32.to_le_bytes());
+                end.extend_from_slice(&[0; 4]);
+                end.extend_from_slice(&self.count.to_le_bytes());
+                end.extend_from_slice(&self.count.to_le_bytes());
+                end.extend_from_slice(&size.to_le_bytes());
+                end.extend_from_slice(&(self.offset as u32).to_le_bytes());
+                end.extend_from_slice(&0u16.to_le_bytes());
+                end
+            }
+            // Two empty blocks end a tar
+            Format::Tar => vec![0; 1024],
+        }
+    }
+
+    fn zip_local_header(&self, name: &str) -> Vec<u8> {
+        let (time, date) = dos_time(self.modified);
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        header.extend_from_slice(&20u16.to_le_bytes());
+        header.extend_from_slice(&ZIP_FLAGS.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&time.to_le_bytes());
+        header.extend_from_slice(&date.to_le_bytes());
+        // The crc and sizes follow the data in a data descriptor
+        header.extend_from_slice(&[0; 12]);
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        header
+    }
+
+    fn tar_header(&self, name: &str, size: u64) -> Vec<u8> {
+        let mut header = Vec::new();
+        if name.len() > 100 {
+            // Names that don't fit in the header are sent in a pax extended header
+            let record = pax_record("path", name);
+            header.extend(ustar("PaxHeader", record.len() as u64, self.modified, b'x'));
+            header.extend_from_slice(record.as_bytes());
+            header.resize(header.len() + padding(record.len() as u64) as usize, 0);
+        }
+        let short = match name.char_indices().nth(100) {
+            Some((index, _)) => &name[..index],
+            None => name,
+        };
+        header.extend(ustar(short, size, self.modified, b'0'));
+        header
+    }
+}
+
+/// General purpose flags of zip entries; sizes are in a data descriptor and names are utf-8
+const ZIP_FLAGS: u16 = 0x0808;
+
+/// Zeros needed to pad data to the end of a 512 byte tar block
+fn padding(len: u64) -> u64 {
+    (512 - len % 512) % 512
+}
+
+/// Ustar header block
+fn ustar(name: &str, size: u64, modified: u64, kind: u8) -> Vec<u8> {
+    let mut block = vec![0u8; 512];
+    let name = name.as_bytes();
+    let name = &name[..name.len().min(100)];
+    block[..name.len()].copy_from_slice(name);
+
+    let octal = |block: &mut [u8], value: u64, width: usize| {
+        let text = format!("{:0width$o}", value, width = width - 1);
+        block[..width - 1].copy_from_slice(&text.as_bytes()[text.len() - (width - 1)..]);
+    };
+    octal(&mut block[100..108], 0o644, 8);
+    octal(&mut block[108..116], 0, 8);
+    octal(&mut block[116..124], 0, 8);
+    octal(&mut block[124..136], size, 12);
+    octal(&mut block[136..148], modified, 12);
+    block[156] = kind;
+    block[257..263].copy_from_slice(b"ustar\0");
+    block[263..265].copy_from_slice(b"00");
+
+    // The checksum is calculated with its own field filled with spaces
+    block[148..156].copy_from_slice(b"        ");
+    let checksum: u64 = block.iter().map(|b| *b as u64).sum();
+    let text = format!("{:06o}\0 ", checksum);
+    block[148..156].copy_from_slice(text.as_bytes());
+    block
+}
+
+/// Pax record; ex: `30 path=some/very/long/name\n`. The length includes itself.
+fn pax_record(key: &str, value: &str) -> String {
+    let content = key.len() + value.len() + 3;
+    let mut len = content + 1;
+    while len != content + len.to_string().len() {
+        len = content + len.to_string().len();
+    }
+    format!("{} {}={}\n", len, key, value)
+}
+
+/// Unix seconds as an ms-dos time and date in UTC
+fn dos_time(seconds: u64) -> (u16, u16) {
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+
+    // Civil date from days since the unix epoch
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    if year < 1980 {
+        return (0, (1 << 5) | 1);
+    }
+    let time = ((time / 3600) << 11) | (((time % 3600) / 60) << 5) | ((time % 60) / 2);
+    let date = (((year - 1980) as u16) << 9) | ((month as u16) << 5) | day as u16;
+    (time as u16, date)
+}
@@ -0,0 +1,107 @@
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{
+    header::{ACCEPT_RANGES, CONTENT_RANGE, CONTENT_TYPE, ETAG},
+    HeaderMap, Method, Uri,
+};
+use sha2::{Digest, Sha256};
+
+use super::{
+    file::{conditional, unsatisfiable, Conditional},
+    Result, ToResponse,
+};
+
+/// In-memory data sent with the same caching and range support as files; ex: a generated
+/// image or zip archive.
+///
+/// `If-None-Match`, `Range`, and `If-Range` are handled against the blob's ETag. The ETag
+/// defaults to a hash of the data and the content type defaults to
+/// `application/octet-stream`.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, response::Blob};
+///
+/// fn render_avatar(id: u32) -> Vec<u8> {
+///     id.to_be_bytes().to_vec()
+/// }
+///
+/// #[get("/avatars/:id")]
+/// fn avatar(id: u32) -> Blob {
+///     Blob::new(render_avatar(id))
+///         .content_type("image/png")
+///         .etag(format!("avatar-{}", id))
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Blob {
+    data: Bytes,
+    content_type: Option<String>,
+    etag: Option<String>,
+}
+
+impl Blob {
+    pub fn new<B: Into<Bytes>>(data: B) -> Self {
+        Blob {
+            data: data.into(),
+            content_type: None,
+            etag: None,
+        }
+    }
+
+    pub fn content_type<T: Into<String>>(mut self, content_type: T) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Entity tag for the data. Quotes are added if it isn't already quoted.
+    pub fn etag<T: Into<String>>(mut self, etag: T) -> Self {
+        let etag = etag.into();
+        self.etag = Some(match etag.starts_with('"') || etag.starts_with("W/\"") {
+            true => etag,
+            false => format!("\"{}\"", etag),
+        });
+        self
+    }
+}
+
+impl ToResponse for Blob {
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        headers: &HeaderMap,
+        _body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        let etag = self.etag.unwrap_or_else(|| {
+            let hash = Sha256::digest(&self.data);
+            let hash: String = hash[..8].iter().map(|b| format!("{:02x}", b)).collect();
+            format!("\"{}\"", hash)
+        });
+        let len = self.data.len() as u64;
+        let builder = hyper::Response::builder()
+            .header(ETAG, &etag)
+            .header(ACCEPT_RANGES, "bytes");
+
+        let condition = conditional(headers, etag.trim_start_matches("W/"), len);
+        if let Conditional::NotModified = condition {
+            return Ok(builder.status(304).body(Full::new(Bytes::new())).unwrap());
+        }
+
+        let builder = builder.header(
+            CONTENT_TYPE,
+            self.content_type
+                .as_deref()
+                .unwrap_or("application/octet-stream"),
+        );
+        Ok(match condition {
+            Conditional::Range(start, end) => builder
+                .status(206)
+                .header(CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, len))
+                .body(Full::new(self.data.slice(start as usize..=end as usize)))
+                .unwrap(),
+            Conditional::Unsatisfiable => unsatisfiable(builder, len),
+            _ => builder.status(200).body(Full::new(self.data)).unwrap(),
+        })
+    }
+}
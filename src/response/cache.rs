@@ -0,0 +1,67 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+
+use super::Result;
+
+/// Number of renders kept before expired renders are removed
+const PRUNE_AT: usize = 1024;
+
+lazy_static! {
+    static ref RENDERS: RwLock<HashMap<u64, (Instant, String)>> = RwLock::new(HashMap::new());
+}
+
+/// Reuse the markup rendered for the key until `ttl` has passed. Failed renders aren't cached.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use tela::response::{cache, Result};
+///
+/// fn render_posts(posts: &[&str]) -> String {
+///     posts.iter().map(|post| format!("<h2>{}</h2>", post)).collect()
+/// }
+///
+/// # fn main() -> Result<()> {
+/// let posts = ["Hello", "World"];
+/// let page = cache::render(("/blog", &posts), Duration::from_secs(60), || {
+///     Ok(render_posts(&posts))
+/// })?;
+/// assert_eq!(page, "<h2>Hello</h2><h2>World</h2>");
+/// # Ok(())
+/// # }
+/// ```
+pub fn render<K, F>(key: K, ttl: Duration, render: F) -> Result<String>
+where
+    K: Hash,
+    F: FnOnce() -> Result<String>,
+{
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    let key = hasher.finish();
+
+    if let Some((expires, markup)) = RENDERS.read().unwrap().get(&key) {
+        if *expires > Instant::now() {
+            return Ok(markup.clone());
+        }
+    }
+
+    let markup = render()?;
+    let mut renders = RENDERS.write().unwrap();
+    if renders.len() >= PRUNE_AT {
+        let now = Instant::now();
+        renders.retain(|_, (expires, _)| *expires > now);
+    }
+    renders.insert(key, (Instant::now() + ttl, markup.clone()));
+    Ok(markup)
+}
+
+/// Remove every cached render; ex: after the content used by cached pages changes
+pub fn clear() {
+    RENDERS.write().unwrap().clear();
+}
@@ -1,8 +1,21 @@
-use std::{ffi::OsStr, fs, path::Path};
+use std::{
+    ffi::OsStr,
+    fs,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
 
 use bytes::Bytes;
 use http_body_util::Full;
-use hyper::{Method, Uri};
+use hyper::{
+    header::{
+        HeaderName, ACCEPT_RANGES, CONTENT_DISPOSITION, CONTENT_RANGE, CONTENT_TYPE, ETAG,
+        IF_NONE_MATCH, IF_RANGE, RANGE,
+    },
+    HeaderMap, Method, Uri,
+};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 
 use super::{Result, ToErrorResponse, ToResponse};
 
@@ -22,7 +35,8 @@ impl<T: Into<String> + Clone> ToResponse for File<T> {
         self,
         _method: &Method,
         _uri: &Uri,
-        _body: String,
+        _headers: &HeaderMap,
+        _body: &Bytes,
     ) -> Result<hyper::Response<Full<Bytes>>> {
         let ct = match Path::new(&Into::<String>::into(self.0.clone()))
             .extension()
@@ -65,3 +79,318 @@ impl<T: Into<String> + Clone> ToErrorResponse for File<T> {
             .unwrap())
     }
 }
+
+/// Send a file from anywhere on disk as a download; ex: a generated report or a user upload.
+///
+/// The content type is guessed from the extension and `ETag`, `If-None-Match`, `Range`, and
+/// `If-Range` are handled so downloads can be cached and resumed. Only a single byte range is
+/// served; requests for multiple ranges get the whole file.
+///
+/// The file, or the requested range, is read into memory before it is sent since responses
+/// have a single buffered body. Keep large downloads to ranges, or serve them from a static
+/// file server in front of the app.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, response::FileResponse};
+///
+/// #[get("/reports/:id")]
+/// fn report(id: u32) -> FileResponse {
+///     FileResponse::new(format!("reports/{}.pdf", id))
+///         .attachment()
+///         .filename(format!("report-{}.pdf", id))
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FileResponse {
+    path: PathBuf,
+    content_type: Option<String>,
+    disposition: Option<&'static str>,
+    filename: Option<String>,
+}
+
+impl FileResponse {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        FileResponse {
+            path: path.as_ref().to_path_buf(),
+            content_type: None,
+            disposition: None,
+            filename: None,
+        }
+    }
+
+    /// Override the content type guessed from the file extension
+    pub fn content_type<T: Into<String>>(mut self, content_type: T) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Ask the browser to display the file
+    pub fn inline(mut self) -> Self {
+        self.disposition = Some("inline");
+        self
+    }
+
+    /// Ask the browser to save the file instead of displaying it
+    pub fn attachment(mut self) -> Self {
+        self.disposition = Some("attachment");
+        self
+    }
+
+    /// Name the file is saved as. Defaults to the name of the file on disk.
+    pub fn filename<T: Into<String>>(mut self, filename: T) -> Self {
+        self.filename = Some(filename.into());
+        self
+    }
+
+    fn content_disposition(&self) -> Option<String> {
+        let disposition = match (self.disposition, &self.filename) {
+            (Some(disposition), _) => disposition,
+            (None, Some(_)) => "attachment",
+            (None, None) => return None,
+        };
+        let filename = self.filename.clone().or_else(|| {
+            self.path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        });
+
+        Some(match filename {
+            Some(filename) => {
+                let fallback = filename
+                    .chars()
+                    .map(
+                        |c| match c.is_ascii() && !c.is_ascii_control() && c != '"' && c != '\\' {
+                            true => c,
+                            false => '_',
+                        },
+                    )
+                    .collect::<String>();
+                match filename.is_ascii() {
+                    true => format!("{}; filename=\"{}\"", disposition, fallback),
+                    false => format!(
+                        "{}; filename=\"{}\"; filename*=UTF-8''{}",
+                        disposition,
+                        fallback,
+                        utf8_percent_encode(&filename, NON_ALPHANUMERIC)
+                    ),
+                }
+            }
+            None => disposition.to_string(),
+        })
+    }
+}
+
+/// Parse a single range against the file length; ex: `bytes=0-99`, `bytes=100-`, `bytes=-100`.
+///
+/// `None` means the range should be ignored and `Some(Err)` means it can't be satisfied.
+fn byte_range(range: &str, len: u64) -> Option<std::result::Result<(u64, u64), ()>> {
+    let range = range.trim().strip_prefix("bytes=")?;
+    if range.contains(',') {
+        return None;
+    }
+    let (start, end) = range.split_once('-')?;
+    let (start, end) = (start.trim(), end.trim());
+
+    let range = match (start.is_empty(), end.is_empty()) {
+        (true, true) => return None,
+        // Suffix range of the last n bytes
+        (true, false) => {
+            let suffix = end.parse::<u64>().ok()?;
+            (len.saturating_sub(suffix), len.saturating_sub(1))
+        }
+        (false, true) => (start.parse::<u64>().ok()?, len.saturating_sub(1)),
+        (false, false) => {
+            let (start, end) = (start.parse::<u64>().ok()?, end.parse::<u64>().ok()?);
+            if end < start {
+                return None;
+            }
+            (start, end.min(len.saturating_sub(1)))
+        }
+    };
+
+    match len == 0 || range.0 >= len {
+        true => Some(Err(())),
+        false => Some(Ok(range)),
+    }
+}
+
+/// Outcome of a request's conditional and range headers for a resource
+#[derive(Debug, PartialEq)]
+pub(super) enum Conditional {
+    /// `If-None-Match` has the resource's ETag; respond with a `304 Not Modified`
+    NotModified,
+    /// Serve the inclusive byte range with a `206 Partial Content`
+    Range(u64, u64),
+    /// The range is outside the resource; respond with a `416 Range Not Satisfiable`
+    Unsatisfiable,
+    /// Serve the whole resource
+    Full,
+}
+
+/// Check `If-None-Match`, `If-Range`, and `Range` against a resource's ETag and length.
+/// Shared by the file and blob responders.
+pub(super) fn conditional(headers: &HeaderMap, etag: &str, len: u64) -> Conditional {
+    let header = |name: HeaderName| headers.get(name).and_then(|value| value.to_str().ok());
+
+    if let Some(matches) = header(IF_NONE_MATCH) {
+        if matches
+            .split(',')
+            .any(|tag| tag.trim() == "*" || tag.trim().trim_start_matches("W/") == etag)
+        {
+            return Conditional::NotModified;
+        }
+    }
+
+    // A range is only served if the resource hasn't changed since the client's copy
+    let range = match header(IF_RANGE) {
+        Some(tag) if tag.trim() != etag => None,
+        _ => header(RANGE).and_then(|range| byte_range(range, len)),
+    };
+    match range {
+        Some(Ok((start, end))) => Conditional::Range(start, end),
+        Some(Err(())) => Conditional::Unsatisfiable,
+        None => Conditional::Full,
+    }
+}
+
+/// `416 Range Not Satisfiable` response for a resource of the given length
+pub(super) fn unsatisfiable(
+    builder: hyper::http::response::Builder,
+    len: u64,
+) -> hyper::Response<Full<Bytes>> {
+    builder
+        .status(416)
+        .header(CONTENT_RANGE, format!("bytes */{}", len))
+        .body(Full::new(Bytes::new()))
+        .unwrap()
+}
+
+impl ToResponse for FileResponse {
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        headers: &HeaderMap,
+        _body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        let not_found = |_| (404, "File not found".to_string());
+        let metadata = fs::metadata(&self.path).map_err(not_found)?;
+        if !metadata.is_file() {
+            return Err((404, "File not found".to_string()));
+        }
+
+        let len = metadata.len();
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+        let etag = format!("\"{:x}-{:x}\"", len, modified);
+        let mut builder = hyper::Response::builder()
+            .header(ETAG, &etag)
+            .header(ACCEPT_RANGES, "bytes");
+
+        let condition = conditional(headers, &etag, len);
+        if let Conditional::NotModified = condition {
+            return Ok(builder.status(304).body(Full::new(Bytes::new())).unwrap());
+        }
+
+        let content_type = self.content_type.clone().unwrap_or_else(|| {
+            mime_guess::from_path(&self.path)
+                .first_or_octet_stream()
+                .to_string()
+        });
+        builder = builder.header(CONTENT_TYPE, content_type);
+        if let Some(disposition) = self.content_disposition() {
+            builder = builder.header(CONTENT_DISPOSITION, disposition);
+        }
+
+        let mut file = fs::File::open(&self.path).map_err(not_found)?;
+        let read_error = |error: std::io::Error| (500, format!("Failed to read file: {}", error));
+        match condition {
+            Conditional::Range(start, end) => {
+                let mut contents = vec![0; (end - start + 1) as usize];
+                file.seek(SeekFrom::Start(start)).map_err(read_error)?;
+                file.read_exact(&mut contents).map_err(read_error)?;
+                Ok(builder
+                    .status(206)
+                    .header(CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, len))
+                    .body(Full::new(Bytes::from(contents)))
+                    .unwrap())
+            }
+            Conditional::Unsatisfiable => Ok(unsatisfiable(builder, len)),
+            _ => {
+                let mut contents = Vec::with_capacity(len as usize);
+                file.read_to_end(&mut contents).map_err(read_error)?;
+                Ok(builder
+                    .status(200)
+                    .body(Full::new(Bytes::from(contents)))
+                    .unwrap())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(HeaderName, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.clone(), value.parse().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn byte_ranges() {
+        assert_eq!(byte_range("bytes=0-99", 1000), Some(Ok((0, 99))));
+        assert_eq!(byte_range("bytes=-100", 1000), Some(Ok((900, 999))));
+        assert_eq!(byte_range("bytes=100-", 1000), Some(Ok((100, 999))));
+        // Ends past the file are cut to its length
+        assert_eq!(byte_range("bytes=900-5000", 1000), Some(Ok((900, 999))));
+        assert_eq!(byte_range("bytes=-5000", 1000), Some(Ok((0, 999))));
+
+        assert_eq!(byte_range("bytes=1000-", 1000), Some(Err(())));
+        assert_eq!(byte_range("bytes=2000-2100", 1000), Some(Err(())));
+        assert_eq!(byte_range("bytes=0-0", 0), Some(Err(())));
+
+        // Multiple ranges and malformed ranges fall back to the whole file
+        assert_eq!(byte_range("bytes=0-9,20-29", 1000), None);
+        assert_eq!(byte_range("bytes=-", 1000), None);
+        assert_eq!(byte_range("bytes=9-0", 1000), None);
+        assert_eq!(byte_range("bytes=a-9", 1000), None);
+        assert_eq!(byte_range("items=0-9", 1000), None);
+    }
+
+    #[test]
+    fn conditional_requests() {
+        let etag = "\"3e8-1\"";
+        let check = |pairs: &[(HeaderName, &str)]| conditional(&headers(pairs), etag, 1000);
+
+        assert_eq!(check(&[]), Conditional::Full);
+        assert_eq!(check(&[(RANGE, "bytes=0-99")]), Conditional::Range(0, 99));
+        assert_eq!(check(&[(RANGE, "bytes=1000-")]), Conditional::Unsatisfiable);
+        assert_eq!(check(&[(RANGE, "bytes=0-9,20-29")]), Conditional::Full);
+
+        assert_eq!(check(&[(IF_NONE_MATCH, etag)]), Conditional::NotModified);
+        assert_eq!(
+            check(&[(IF_NONE_MATCH, "\"other\", W/\"3e8-1\"")]),
+            Conditional::NotModified
+        );
+        assert_eq!(check(&[(IF_NONE_MATCH, "*")]), Conditional::NotModified);
+        assert_eq!(check(&[(IF_NONE_MATCH, "\"other\"")]), Conditional::Full);
+
+        // The range is only used if the client's copy is still current
+        assert_eq!(
+            check(&[(RANGE, "bytes=100-"), (IF_RANGE, etag)]),
+            Conditional::Range(100, 999)
+        );
+        assert_eq!(
+            check(&[(RANGE, "bytes=100-"), (IF_RANGE, "\"old\"")]),
+            Conditional::Full
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{HeaderMap, Method, Uri};
+
+use super::{Blocks, Layout, Result, ToResponse};
+use crate::request::HxRequest;
+
+/// Request headers that decide whether a block or the whole page is sent
+const VARY: &str = "HX-Request, HX-Target, HX-Boosted, HX-History-Restore-Request, Turbo-Frame";
+
+/// Page that only sends one of its blocks when HTMX or a Turbo frame asks for part of it.
+///
+/// Which markup is sent:
+/// * `Turbo-Frame: <id>`; the block named after the frame, or the target block. The block
+///   should contain the `<turbo-frame id="...">` so the full page has it too.
+/// * `HX-Request: true`; the block named by `HX-Target`, or the target block. Boosted links
+///   and history restores get the whole page since htmx swaps the body.
+/// * Anything else; the blocks rendered into the layout.
+///
+/// `Vary` is set to the headers above so caches keep the page and fragments apart.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, response::{Blocks, Fragment, HTML}};
+///
+/// fn base(blocks: &Blocks) -> HTML<String> {
+///     html!(<html><body>{blocks.get("content")}</body></html>)
+/// }
+///
+/// #[get("/todos")]
+/// fn todos() -> Fragment<fn(&Blocks) -> HTML<String>> {
+///     let blocks = Blocks::new()
+///         .block("content", html!(<ul id="todos"><li>"Write docs"</li></ul>));
+///     Fragment::new(base, blocks, "content")
+/// }
+/// ```
+pub struct Fragment<L: Layout> {
+    layout: L,
+    blocks: Blocks,
+    target: String,
+}
+
+impl<L: Layout> Fragment<L> {
+    /// Page made from the layout and blocks where `target` is the block sent when a
+    /// request doesn't name one that exists
+    pub fn new<T: Into<String>>(layout: L, blocks: Blocks, target: T) -> Self {
+        Fragment {
+            layout,
+            blocks,
+            target: target.into(),
+        }
+    }
+
+    /// Name of the block the request asks for, or `None` for the whole page
+    fn requested<'a>(&'a self, headers: &'a HeaderMap) -> Option<&'a str> {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        let block = |name: Option<&'a str>| match name {
+            Some(name) if self.blocks.contains(name) => name,
+            _ => self.target.as_str(),
+        };
+
+        if let Some(frame) = header("Turbo-Frame") {
+            return Some(block(Some(frame)));
+        }
+        match HxRequest::from_headers(headers).is_partial() {
+            // `HX-Target` is the target element's id
+            true => Some(block(header("HX-Target"))),
+            false => None,
+        }
+    }
+
+    /// Markup for the request; the requested block or the whole page
+    pub fn render(self, headers: &HeaderMap) -> String {
+        match self.requested(headers) {
+            Some(block) => self.blocks.get(block),
+            None => self.blocks.extend(&self.layout),
+        }
+    }
+}
+
+impl<L: Layout> ToResponse for Fragment<L> {
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        headers: &HeaderMap,
+        _body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        Ok(hyper::Response::builder()
+            .status(200)
+            .header("Content-Type", "text/html")
+            .header("Vary", VARY)
+            .body(Full::new(Bytes::from(self.render(headers))))
+            .unwrap())
+    }
+}
@@ -0,0 +1,202 @@
+use bytes::Bytes;
+use hyper::{
+    header::{HeaderValue, LINK},
+    HeaderMap, Method, Uri,
+};
+
+use super::{Result, ToResponse, HTML};
+
+/// Wrap a response with `Link` headers that let the browser start loading resources the page
+/// needs; ex: `</style.css>; rel=preload; as=style`.
+///
+/// The hyper server doesn't expose a way to write a `103 Early Hints` interim response before
+/// the final response, so the links are sent as headers on the final response. Browsers and
+/// proxies that support early hints can still act on them from there.
+///
+/// # Example
+/// ```
+/// use tela::{
+///     prelude::*,
+///     response::{EarlyHints, HTML},
+/// };
+///
+/// #[get("/")]
+/// fn home() -> EarlyHints<HTML<String>> {
+///     EarlyHints::new(html!(<link rel="stylesheet" href="/style.css" />))
+///         .preload("/style.css", "style")
+///         .preload("/app.js", "script")
+/// }
+/// ```
+pub struct EarlyHints<T: ToResponse> {
+    response: T,
+    links: Vec<String>,
+}
+
+impl<T: ToResponse> EarlyHints<T> {
+    pub fn new(response: T) -> Self {
+        EarlyHints {
+            response,
+            links: Vec::new(),
+        }
+    }
+
+    /// Preload a resource; `kind` is the `as` value, ex: `style`, `script`, `font`, or `image`
+    pub fn preload<P: Into<String>, K: Into<String>>(mut self, path: P, kind: K) -> Self {
+        self.links.push(format!(
+            "<{}>; rel=preload; as={}",
+            path.into(),
+            kind.into()
+        ));
+        self
+    }
+
+    /// Add a full `Link` header value; ex: `<https://cdn.example.com>; rel=preconnect`
+    pub fn link<L: Into<String>>(mut self, link: L) -> Self {
+        self.links.push(link.into());
+        self
+    }
+}
+
+impl EarlyHints<HTML<String>> {
+    /// Respond with the markup and preload the stylesheets and scripts it references.
+    ///
+    /// `<link rel="stylesheet">` and `<script src>` tags become `rel=preload` links and module
+    /// scripts become `rel=modulepreload` links. Existing `<link rel="preload">` and
+    /// `<link rel="modulepreload">` tags are repeated as headers.
+    ///
+    /// # Example
+    /// ```
+    /// use tela::{
+    ///     prelude::*,
+    ///     response::{EarlyHints, HTML},
+    /// };
+    ///
+    /// #[get("/")]
+    /// fn home() -> EarlyHints<HTML<String>> {
+    ///     EarlyHints::scan(html! {
+    ///         <head>
+    ///             <link rel="stylesheet" href="/style.css" />
+    ///             <script type="module" src="/app.js"></script>
+    ///         </head>
+    ///     })
+    /// }
+    /// ```
+    pub fn scan<T: Into<String>>(markup: T) -> Self {
+        let markup: String = markup.into();
+        let mut links = Vec::new();
+        for (tag, attributes) in tags(&markup) {
+            let get = |name: &str| {
+                attributes
+                    .iter()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.as_str())
+            };
+            let rel = get("rel").unwrap_or("").to_ascii_lowercase();
+            let link = match (tag.as_str(), get("href"), get("src")) {
+                ("link", Some(href), _) if rel == "stylesheet" => {
+                    format!("<{}>; rel=preload; as=style", href)
+                }
+                ("link", Some(href), _) if rel == "preload" => match get("as") {
+                    Some(kind) => format!("<{}>; rel=preload; as={}", href, kind),
+                    None => continue,
+                },
+                ("link", Some(href), _) if rel == "modulepreload" => {
+                    format!("<{}>; rel=modulepreload", href)
+                }
+                ("script", _, Some(src)) if get("type") == Some("module") => {
+                    format!("<{}>; rel=modulepreload", src)
+                }
+                ("script", _, Some(src)) => format!("<{}>; rel=preload; as=script", src),
+                _ => continue,
+            };
+            if !link.starts_with("<data:") && !links.contains(&link) {
+                links.push(link);
+            }
+        }
+        EarlyHints {
+            response: HTML(markup),
+            links,
+        }
+    }
+}
+
+/// Names and attributes of the `link` and `script` start tags in the markup
+fn tags(markup: &str) -> Vec<(String, Vec<(String, String)>)> {
+    let mut tags = Vec::new();
+    let mut rest = markup;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].to_ascii_lowercase();
+        if name != "link" && name != "script" {
+            continue;
+        }
+        rest = &rest[name_end..];
+
+        let mut attributes = Vec::new();
+        loop {
+            rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+            if rest.is_empty() || rest.starts_with('>') {
+                break;
+            }
+            let key_end = rest
+                .find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/')
+                .unwrap_or(rest.len());
+            let key = rest[..key_end].to_ascii_lowercase();
+            rest = rest[key_end..].trim_start();
+
+            let mut value = String::new();
+            if let Some(after) = rest.strip_prefix('=') {
+                let after = after.trim_start();
+                let (raw, remaining) = match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let end = after[1..].find(quote).map(|i| i + 1).unwrap_or(after.len());
+                        (&after[1..end], after.get(end + 1..).unwrap_or(""))
+                    }
+                    _ => {
+                        let end = after
+                            .find(|c: char| c.is_whitespace() || c == '>')
+                            .unwrap_or(after.len());
+                        (&after[..end], &after[end..])
+                    }
+                };
+                value = raw.replace("&amp;", "&");
+                rest = remaining;
+            }
+            if key.is_empty() {
+                // Skip a stray character so the scan always moves forward
+                rest = rest.get(1..).unwrap_or("");
+                continue;
+            }
+            attributes.push((key, value));
+        }
+        if name == "script" {
+            // Script contents aren't markup
+            if let Some(end) = rest.to_ascii_lowercase().find("</script") {
+                rest = &rest[end..];
+            }
+        }
+        tags.push((name, attributes));
+    }
+    tags
+}
+
+impl<T: ToResponse> ToResponse for EarlyHints<T> {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+        let mut response = self.response.to_response(method, uri, headers, body)?;
+        for link in self.links {
+            let value = HeaderValue::from_str(&link)
+                .map_err(|_| (500, format!("Invalid early hint link {:?}", link)))?;
+            response.headers_mut().append(LINK, value);
+        }
+        Ok(response)
+    }
+}
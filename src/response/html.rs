@@ -1,17 +1,59 @@
+use std::{hash::Hash, time::Duration};
+
 use bytes::Bytes;
 use http_body_util::Full;
-use hyper::{Method, Uri};
+use hyper::{HeaderMap, Method, Uri};
+
+use super::{cache, Result, ToErrorResponse, ToResponse};
 
-use super::{Result, ToErrorResponse, ToResponse};
+/// Escape text so it is safe to place in html content or attribute values
+pub fn escape<T: AsRef<str>>(value: T) -> String {
+    value
+        .as_ref()
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
 
 pub struct HTML<T: Into<String>>(pub T);
 
+impl HTML<String> {
+    /// Reuse the markup rendered for the key until `ttl` has passed
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use tela::{prelude::*, response::HTML};
+    ///
+    /// fn render_post(slug: &str) -> String {
+    ///     format!("<h1>{}</h1>", slug)
+    /// }
+    ///
+    /// #[get("/blog/:slug")]
+    /// fn post(slug: String) -> HTML<String> {
+    ///     HTML::cached(("post", &slug), Duration::from_secs(300), || render_post(&slug))
+    /// }
+    /// ```
+    pub fn cached<K: Hash, F: FnOnce() -> String>(key: K, ttl: Duration, render: F) -> Self {
+        HTML(cache::render(("html", key), ttl, || Ok(render())).unwrap_or_default())
+    }
+}
+
+impl<T: Into<String>> From<HTML<T>> for String {
+    fn from(value: HTML<T>) -> Self {
+        value.0.into()
+    }
+}
+
 impl<T: Into<String>> ToResponse for HTML<T> {
     fn to_response(
         self,
         _method: &Method,
         _uri: &Uri,
-        _body: String,
+        _headers: &HeaderMap,
+        _body: &Bytes,
     ) -> Result<hyper::Response<Full<Bytes>>> {
         Ok(hyper::Response::builder()
             .status(200)
@@ -0,0 +1,189 @@
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{header::HeaderValue, HeaderMap, Method, Uri};
+use serde_json::{Map, Value};
+
+use super::{Result, ToResponse};
+
+/// Events for one of the `HX-Trigger` headers
+#[derive(Debug, Default, Clone)]
+struct Events(Vec<(String, Option<Value>)>);
+
+impl Events {
+    fn push(&mut self, event: String, detail: Option<Value>) {
+        self.0.retain(|(name, _)| *name != event);
+        self.0.push((event, detail));
+    }
+
+    /// Comma separated names, or a json object when an event has details
+    fn encode(&self) -> Option<String> {
+        if self.0.is_empty() {
+            return None;
+        }
+        if self.0.iter().all(|(_, detail)| detail.is_none()) {
+            return Some(
+                self.0
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", "),
+            );
+        }
+        let events: Map<String, Value> = self
+            .0
+            .iter()
+            .map(|(name, detail)| (name.clone(), detail.clone().unwrap_or(Value::Null)))
+            .collect();
+        Some(Value::Object(events).to_string())
+    }
+}
+
+/// Response headers that tell htmx what to do with a response.
+///
+/// Send them with a response as a tuple, `(HxResponseHeaders, T)`.
+///
+/// # Example
+/// ```
+/// use tela::{
+///     prelude::*,
+///     request::Form,
+///     response::{HxResponseHeaders, HTML},
+/// };
+///
+/// #[derive(serde::Deserialize)]
+/// struct Todo {
+///     id: u32,
+///     title: String,
+/// }
+///
+/// #[post("/todos")]
+/// fn create(Form(todo): Form<Todo>) -> (HxResponseHeaders, HTML<String>) {
+///     (
+///         HxResponseHeaders::new()
+///             .retarget("#todos")
+///             .reswap("beforeend")
+///             .trigger_with("todo-added", json!({"id": todo.id})),
+///         html!(<li>{todo.title}</li>),
+///     )
+/// }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct HxResponseHeaders {
+    headers: Vec<(&'static str, String)>,
+    trigger: Events,
+    after_settle: Events,
+    after_swap: Events,
+}
+
+impl HxResponseHeaders {
+    pub fn new() -> Self {
+        HxResponseHeaders::default()
+    }
+
+    fn set(mut self, name: &'static str, value: String) -> Self {
+        self.headers.retain(|(header, _)| *header != name);
+        self.headers.push((name, value));
+        self
+    }
+
+    /// Load the url with an ajax request instead of a full page load; `HX-Location`
+    pub fn location<U: Into<String>>(self, url: U) -> Self {
+        self.set("HX-Location", url.into())
+    }
+
+    /// Full page redirect to the url; `HX-Redirect`
+    pub fn redirect<U: Into<String>>(self, url: U) -> Self {
+        self.set("HX-Redirect", url.into())
+    }
+
+    /// Reload the whole page; `HX-Refresh`
+    pub fn refresh(self) -> Self {
+        self.set("HX-Refresh", "true".to_string())
+    }
+
+    /// Push the url onto the browser's history; `HX-Push-Url`
+    pub fn push_url<U: Into<String>>(self, url: U) -> Self {
+        self.set("HX-Push-Url", url.into())
+    }
+
+    /// Replace the current url in the browser's history; `HX-Replace-Url`
+    pub fn replace_url<U: Into<String>>(self, url: U) -> Self {
+        self.set("HX-Replace-Url", url.into())
+    }
+
+    /// Swap the response into the element matching the css selector instead of the request's
+    /// target; `HX-Retarget`
+    pub fn retarget<S: Into<String>>(self, selector: S) -> Self {
+        self.set("HX-Retarget", selector.into())
+    }
+
+    /// How the response is swapped in, ex: `outerHTML` or `beforeend`; `HX-Reswap`
+    pub fn reswap<S: Into<String>>(self, swap: S) -> Self {
+        self.set("HX-Reswap", swap.into())
+    }
+
+    /// Only swap in the part of the response matching the css selector; `HX-Reselect`
+    pub fn reselect<S: Into<String>>(self, selector: S) -> Self {
+        self.set("HX-Reselect", selector.into())
+    }
+
+    /// Trigger an event as soon as the response is received; `HX-Trigger`
+    pub fn trigger<E: Into<String>>(mut self, event: E) -> Self {
+        self.trigger.push(event.into(), None);
+        self
+    }
+
+    /// Trigger an event with details, `event.detail` in javascript, as soon as the response
+    /// is received
+    pub fn trigger_with<E: Into<String>>(mut self, event: E, detail: Value) -> Self {
+        self.trigger.push(event.into(), Some(detail));
+        self
+    }
+
+    /// Trigger an event after the response is swapped in; `HX-Trigger-After-Swap`
+    pub fn trigger_after_swap<E: Into<String>>(mut self, event: E) -> Self {
+        self.after_swap.push(event.into(), None);
+        self
+    }
+
+    /// Trigger an event after the swapped in content settles; `HX-Trigger-After-Settle`
+    pub fn trigger_after_settle<E: Into<String>>(mut self, event: E) -> Self {
+        self.after_settle.push(event.into(), None);
+        self
+    }
+
+    /// Header names and values in the order they are sent
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = self.headers.clone();
+        let events = [
+            ("HX-Trigger", &self.trigger),
+            ("HX-Trigger-After-Swap", &self.after_swap),
+            ("HX-Trigger-After-Settle", &self.after_settle),
+        ];
+        for (name, events) in events {
+            if let Some(value) = events.encode() {
+                headers.push((name, value));
+            }
+        }
+        headers
+    }
+}
+
+impl<T: ToResponse> ToResponse for (HxResponseHeaders, T) {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        let (hx, response) = self;
+        let mut response = response.to_response(method, uri, headers, body)?;
+        for (name, value) in hx.headers() {
+            let value = HeaderValue::from_str(&value)
+                .map_err(|_| (500, format!("Invalid {} header {:?}", name, value)))?;
+            response.headers_mut().insert(name, value);
+        }
+        Ok(response)
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use super::{escape, Result};
+
+/// Server rendered markup that a client side (wasm) component mounts onto.
+///
+/// The props are serialized to json and embedded next to the markup so the client
+/// component starts with the same state the server rendered with. Use `Island::loader`
+/// once per page to mount every island, and `tela_wasm::island!` in the client crate to
+/// export the component that hydrates it.
+///
+/// Renders as:
+/// ```html
+/// <tela-island data-component="Counter">
+///     <script type="application/json" data-props>{"count":0}</script>
+///     ...server rendered markup
+/// </tela-island>
+/// ```
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, response::{Island, HTML}};
+///
+/// #[derive(serde::Serialize)]
+/// struct Counter { count: u32 }
+///
+/// #[get("/")]
+/// fn home() -> Result<HTML<String>> {
+///     let counter = hydrate!(Counter, Counter { count: 0 }, html!(<button>"0"</button>))?;
+///     response!(html! {
+///         {counter}
+///         {Island::loader("/pkg/client.js")}
+///     })
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Island {
+    component: String,
+    props: String,
+    markup: String,
+}
+
+impl Island {
+    /// Create an island for the named client component with it's props and the
+    /// server rendered markup.
+    pub fn new<N, P, M>(component: N, props: &P, markup: M) -> Result<Self>
+    where
+        N: Into<String>,
+        P: Serialize,
+        M: Into<String>,
+    {
+        let props = serde_json::to_string(props).map_err(|err| {
+            (
+                500,
+                format!("Failed to serialize island props to json: {}", err),
+            )
+        })?;
+
+        Ok(Island {
+            component: component.into(),
+            // `<` is only found in json strings so it can be escaped to prevent `</script>`
+            props: props.replace('<', "\\u003c"),
+            markup: markup.into(),
+        })
+    }
+
+    pub fn component(&self) -> &str {
+        &self.component
+    }
+
+    /// The json encoded props
+    pub fn props(&self) -> &str {
+        &self.props
+    }
+
+    /// Module script that mounts every island on the page.
+    ///
+    /// `module` is the url to the javascript generated by `wasm-bindgen --target web`.
+    /// For each island the exported function matching the component name is called with the
+    /// island element and the parsed props; ex: `Counter(element, props)`. Export it with
+    /// `tela_wasm::island!(Counter, hydrate: |island, props: Counter| { ... })`.
+    pub fn loader(module: &str) -> String {
+        format!(
+            r#"<script type="module">
+import init, * as client from {};
+await init();
+for (const island of document.querySelectorAll("tela-island")) {{
+    const mount = client[island.dataset.component];
+    const props = island.querySelector(":scope > script[data-props]");
+    if (typeof mount === "function") {{
+        mount(island, props ? JSON.parse(props.textContent) : null);
+    }}
+}}
+</script>"#,
+            // A json string is a valid javascript string
+            serde_json::Value::from(module)
+                .to_string()
+                .replace('<', "\\u003c")
+        )
+    }
+}
+
+impl Display for Island {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            r#"<tela-island data-component="{}"><script type="application/json" data-props>{}</script>{}</tela-island>"#,
+            escape(&self.component),
+            self.props,
+            self.markup
+        )
+    }
+}
+
+impl From<Island> for String {
+    fn from(value: Island) -> Self {
+        value.to_string()
+    }
+}
@@ -1,14 +1,199 @@
 use bytes::Bytes;
 use http_body_util::Full;
-use hyper::{Method, Uri};
+use hyper::{HeaderMap, Method, Uri};
 use serde::{Deserialize, Serialize};
 
-use crate::errors::default_error_page;
-
 use super::{File, Result, ToErrorResponse, ToResponse};
 
 pub type Raw = serde_json::Value;
 
+tokio::task_local! {
+    /// Format of the router handling the request; see `Router::json_format`
+    pub(crate) static FORMAT: JsonFormat;
+}
+
+/// Casing of the keys of serialized objects
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyCase {
+    /// Keys are kept as they are serialized
+    #[default]
+    Unchanged,
+    /// ex: `user_id` -> `userId`
+    Camel,
+    /// ex: `userId` -> `user_id`
+    Snake,
+}
+
+impl KeyCase {
+    fn convert(&self, key: &str) -> String {
+        // Leading underscores are kept; ex: `_links`
+        let name = key.trim_start_matches('_');
+        let prefix = &key[..key.len() - name.len()];
+        match self {
+            KeyCase::Unchanged => key.to_string(),
+            KeyCase::Snake => format!("{}{}", prefix, snake_case(name)),
+            KeyCase::Camel => {
+                let mut camel = String::from(prefix);
+                for (i, word) in snake_case(name)
+                    .split('_')
+                    .filter(|w| !w.is_empty())
+                    .enumerate()
+                {
+                    let mut chars = word.chars();
+                    if let (Some(first), true) = (chars.next(), i > 0) {
+                        camel.extend(first.to_uppercase());
+                        camel.push_str(chars.as_str());
+                    } else {
+                        camel.push_str(word);
+                    }
+                }
+                camel
+            }
+        }
+    }
+
+    /// Convert the keys of every object in serialized json. The json is rewritten in place of
+    /// going through a `serde_json::Value` so the order of the keys is kept.
+    fn apply(&self, json: &str) -> String {
+        let mut converted = String::with_capacity(json.len());
+        // Whether each open container is an object
+        let mut objects = Vec::new();
+        let mut key = false;
+        let mut chars = json.char_indices();
+        while let Some((start, c)) = chars.next() {
+            match c {
+                '"' => {
+                    let mut escaped = false;
+                    let mut end = json.len();
+                    for (i, c) in chars.by_ref() {
+                        match c {
+                            '\\' => escaped = !escaped,
+                            '"' if !escaped => {
+                                end = i;
+                                break;
+                            }
+                            _ => escaped = false,
+                        }
+                    }
+                    let string = &json[start + 1..end];
+                    converted.push('"');
+                    match key && !string.contains('\\') {
+                        true => converted.push_str(&self.convert(string)),
+                        false => converted.push_str(string),
+                    }
+                    converted.push('"');
+                    key = false;
+                    continue;
+                }
+                '{' => {
+                    objects.push(true);
+                    key = true;
+                }
+                '[' => objects.push(false),
+                '}' | ']' => {
+                    objects.pop();
+                }
+                ',' => key = objects.last() == Some(&true),
+                _ => {}
+            }
+            converted.push(c);
+        }
+        converted
+    }
+}
+
+/// Words of a key joined with underscores. A word starts at an uppercase letter after a
+/// lowercase letter or digit, or at the last capital of an acronym; ex: `HTTPServer` ->
+/// `http_server`.
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::with_capacity(name.len());
+    for (i, c) in chars.iter().enumerate() {
+        if *c == '-' {
+            snake.push('_');
+            continue;
+        }
+        if c.is_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next = chars.get(i + 1);
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+            {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+/// How `JSON` responses are serialized. Set it for every response of a server with
+/// `Server::json_format`, or for one response by returning it with the json;
+/// ex: `(JsonFormat::new().pretty(true), JSON(user))`.
+///
+/// # Example
+/// ```
+/// use tela::{response::{JsonFormat, KeyCase}, Server};
+///
+/// Server::new()
+///     // Readable responses while developing, compact ones in release builds
+///     .json_format(
+///         JsonFormat::new()
+///             .pretty(cfg!(debug_assertions))
+///             .keys(KeyCase::Camel),
+///     );
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonFormat {
+    pretty: bool,
+    keys: KeyCase,
+}
+
+impl JsonFormat {
+    /// Compact json with keys as they are serialized
+    pub const fn new() -> Self {
+        JsonFormat {
+            pretty: false,
+            keys: KeyCase::Unchanged,
+        }
+    }
+
+    /// Indent the json so it's easier to read
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Convert the key of every object, including nested objects and maps, to the case.
+    /// Keys renamed with serde attributes are converted too.
+    pub fn keys(mut self, keys: KeyCase) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Serialize the value
+    pub fn to_string<T: Serialize + ?Sized>(&self, value: &T) -> serde_json::Result<String> {
+        let json = match self.pretty {
+            true => serde_json::to_string_pretty(value)?,
+            false => serde_json::to_string(value)?,
+        };
+        Ok(match self.keys {
+            KeyCase::Unchanged => json,
+            keys => keys.apply(&json),
+        })
+    }
+}
+
+/// Serialize a value with the format of the router handling the request. Outside of a
+/// request the default format is used.
+pub(crate) fn to_json<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+    FORMAT
+        .try_with(|format| *format)
+        .unwrap_or_default()
+        .to_string(value)
+}
+
 pub struct JSON<T: Serialize>(pub T);
 
 impl<T: Deserialize<'static> + Serialize> JSON<T> {
@@ -34,30 +219,25 @@ impl<T: Deserialize<'static> + Serialize> JSON<T> {
 impl<T: serde::Serialize> ToResponse for JSON<T> {
     fn to_response(
         self,
-        method: &Method,
-        uri: &Uri,
-        body: String,
+        _method: &Method,
+        _uri: &Uri,
+        _headers: &HeaderMap,
+        _body: &Bytes,
     ) -> Result<hyper::Response<Full<Bytes>>> {
-        match serde_json::to_string(&self.0) {
+        match to_json(&self.0) {
             Ok(result) => Ok(hyper::Response::builder()
                 .status(200)
                 .header("Content-Type", "application/json")
                 .body(Full::new(Bytes::from(result)))
                 .unwrap()),
-            Err(_) => Ok(default_error_page(
-                &500,
-                &"Failed to parse json in response".to_string(),
-                method,
-                uri,
-                body,
-            )),
+            Err(_) => Err((500, "Failed to parse json in response".to_string())),
         }
     }
 }
 
 impl<T: serde::Serialize> ToErrorResponse for JSON<T> {
     fn to_error_response(self, code: u16, reason: String) -> Result<hyper::Response<Full<Bytes>>> {
-        match serde_json::to_string(&self.0) {
+        match to_json(&self.0) {
             Ok(result) => Ok(hyper::Response::builder()
                 .status(code)
                 .header("Content-Type", "application/json")
@@ -76,3 +256,23 @@ impl<T: serde::Serialize> ToErrorResponse for JSON<T> {
         }
     }
 }
+
+/// Json serialized with the format instead of the one set with `Server::json_format`
+impl<T: serde::Serialize> ToResponse for (JsonFormat, JSON<T>) {
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        _headers: &HeaderMap,
+        _body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        match self.0.to_string(&self.1 .0) {
+            Ok(result) => Ok(hyper::Response::builder()
+                .status(200)
+                .header("Content-Type", "application/json")
+                .body(Full::new(Bytes::from(result)))
+                .unwrap()),
+            Err(_) => Err((500, "Failed to parse json in response".to_string())),
+        }
+    }
+}
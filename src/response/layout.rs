@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+/// Named blocks of markup that are placed into a layout.
+///
+/// Created by the `html!` macro when the markup starts with `<extends layout={...} />`.
+/// Each `<block name="...">` that follows becomes an entry.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, response::{Blocks, HTML}};
+///
+/// fn base(blocks: &Blocks) -> HTML<String> {
+///     html! {
+///         <html>
+///             <head><title>{blocks.get_or("title", "Tela")}</title></head>
+///             <body>{blocks.get("content")}</body>
+///         </html>
+///     }
+/// }
+///
+/// #[get("/")]
+/// fn home() -> HTML<String> {
+///     html! {
+///         <extends layout={base} />
+///         <block name="content"><h1>"Hello, world!"</h1></block>
+///     }
+/// }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Blocks(HashMap<String, String>);
+
+impl Blocks {
+    pub fn new() -> Self {
+        Blocks(HashMap::new())
+    }
+
+    /// Add or override a named block
+    pub fn block<N: Into<String>, C: Into<String>>(mut self, name: N, content: C) -> Self {
+        self.0.insert(name.into(), content.into());
+        self
+    }
+
+    /// Get the markup for a block; empty if it wasn't provided
+    pub fn get(&self, name: &str) -> String {
+        self.0.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Get the markup for a block or fallback to the given default markup
+    pub fn get_or<T: Into<String>>(&self, name: &str, default: T) -> String {
+        match self.0.get(name) {
+            Some(block) => block.clone(),
+            None => default.into(),
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// Render the blocks into the given layout
+    pub fn extend<L: Layout>(self, layout: &L) -> String {
+        layout.render(&self)
+    }
+}
+
+/// Markup that other pages can extend by providing blocks.
+///
+/// Implemented for any `Fn(&Blocks) -> T` where `T` can be turned into a String.
+/// This includes functions that return the `html!` macro.
+pub trait Layout {
+    fn render(&self, blocks: &Blocks) -> String;
+}
+
+impl<F, T> Layout for F
+where
+    F: Fn(&Blocks) -> T,
+    T: Into<String>,
+{
+    fn render(&self, blocks: &Blocks) -> String {
+        self(blocks).into()
+    }
+}
@@ -0,0 +1,82 @@
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+
+use super::HTML;
+
+/// Url schemes links and images may use; relative urls are always allowed
+const SCHEMES: [&str; 4] = ["http", "https", "mailto", "tel"];
+
+/// Replace urls with a scheme that isn't allowed, ex: `javascript:`, with `#`
+fn safe_url(url: CowStr) -> CowStr {
+    let scheme = url
+        .split(['/', '?', '#'])
+        .next()
+        .and_then(|start| start.split_once(':'))
+        .map(|(scheme, _)| {
+            // Browsers ignore whitespace and control characters in a scheme; ex: `java\tscript:`
+            scheme
+                .chars()
+                .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+                .collect::<String>()
+                .to_ascii_lowercase()
+        });
+    match scheme {
+        Some(scheme) if !SCHEMES.contains(&scheme.as_str()) => CowStr::Borrowed("#"),
+        _ => url,
+    }
+}
+
+impl HTML<String> {
+    /// Render CommonMark, along with tables, strikethrough, and task lists, to html.
+    ///
+    /// The output is sanitized: html written in the markdown is escaped and shown as text,
+    /// and links and images can only use `http`, `https`, `mailto`, `tel`, or relative urls.
+    ///
+    /// The `html!` macro renders `<markdown>{source}</markdown>` with this. String literals,
+    /// `<markdown>"# Title"</markdown>`, are rendered when compiling instead.
+    ///
+    /// # Example
+    /// ```
+    /// use tela::{prelude::*, response::HTML};
+    ///
+    /// #[get("/blog/:slug")]
+    /// fn post(slug: String) -> Result<HTML<String>> {
+    ///     let source = std::fs::read_to_string(format!("posts/{}.md", slug))
+    ///         .map_err(|_| (404, "Post not found".to_string()))?;
+    ///     response!(HTML::markdown(source))
+    /// }
+    /// ```
+    pub fn markdown<T: AsRef<str>>(source: T) -> Self {
+        let options =
+            Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+        let events = Parser::new_ext(source.as_ref(), options).map(|event| match event {
+            Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => Event::Start(Tag::Link {
+                link_type,
+                dest_url: safe_url(dest_url),
+                title,
+                id,
+            }),
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => Event::Start(Tag::Image {
+                link_type,
+                dest_url: safe_url(dest_url),
+                title,
+                id,
+            }),
+            event => event,
+        });
+
+        let mut output = String::new();
+        html::push_html(&mut output, events);
+        HTML(output)
+    }
+}
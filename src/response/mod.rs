@@ -1,18 +1,43 @@
+mod archive;
+mod blob;
+pub mod cache;
 mod file;
+mod fragment;
+mod hints;
 mod html;
+mod htmx;
+mod island;
 mod json;
+mod layout;
+#[cfg(feature = "markdown")]
+mod markdown;
+mod paginated;
+mod problem;
 mod redirect;
+pub(crate) mod stream;
 
 pub mod template;
 
 use bytes::Bytes;
 use http_body_util::Full;
 
-pub use file::File;
-pub use html::HTML;
-use hyper::{Method, Uri};
-pub use json::{Raw, JSON};
+pub use archive::Archive;
+pub use blob::Blob;
+pub use file::{File, FileResponse};
+pub use fragment::Fragment;
+pub use hints::EarlyHints;
+pub use html::{escape, HTML};
+pub use htmx::HxResponseHeaders;
+use hyper::{HeaderMap, Method, Uri};
+pub use island::Island;
+pub(crate) use json::{to_json, FORMAT as JSON_FORMAT};
+pub use json::{JsonFormat, KeyCase, Raw, JSON};
+pub use layout::{Blocks, Layout};
+pub use paginated::Paginated;
+pub use problem::{Problem, ProblemErrorRenderer};
 pub use redirect::Redirect;
+pub use stream::JsonLines;
+pub use tela_macros::ToResponse;
 pub use template::Template;
 
 use crate::StatusCode;
@@ -24,7 +49,8 @@ pub trait ToResponse {
         self,
         method: &Method,
         uri: &Uri,
-        body: String,
+        headers: &HeaderMap,
+        body: &Bytes,
     ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>>;
 }
 
@@ -41,20 +67,19 @@ impl<T: ToResponse> ToResponse for (u16, T) {
         self,
         method: &Method,
         uri: &Uri,
-        body: String,
+        headers: &HeaderMap,
+        body: &Bytes,
     ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
         let code = self.0;
-        self.1.to_response(method, uri, body).map(|result| {
-            let mut response = hyper::Response::builder()
-                .status(code)
-                .body(result.body().clone())
-                .unwrap();
-
-            // Copy over all headers
-            response.headers_mut().extend(result.headers().clone());
-
-            response
-        })
+        self.1
+            .to_response(method, uri, headers, body)
+            .and_then(|result| {
+                // Keep the headers and body, only the status changes
+                let (mut parts, body) = result.into_parts();
+                parts.status = hyper::StatusCode::from_u16(code)
+                    .map_err(|_| (500, format!("Invalid response status code {}", code)))?;
+                Ok(hyper::Response::from_parts(parts, body))
+            })
     }
 }
 
@@ -63,20 +88,19 @@ impl<T: ToResponse> ToResponse for (StatusCode, T) {
         self,
         method: &Method,
         uri: &Uri,
-        body: String,
+        headers: &HeaderMap,
+        body: &Bytes,
     ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
         let code: u16 = self.0 as u16;
-        self.1.to_response(method, uri, body).map(|result| {
-            let mut response = hyper::Response::builder()
-                .status(code)
-                .body(result.body().clone())
-                .unwrap();
-
-            // Copy over all headers
-            response.headers_mut().extend(result.headers().clone());
-
-            response
-        })
+        self.1
+            .to_response(method, uri, headers, body)
+            .and_then(|result| {
+                // Keep the headers and body, only the status changes
+                let (mut parts, body) = result.into_parts();
+                parts.status = hyper::StatusCode::from_u16(code)
+                    .map_err(|_| (500, format!("Invalid response status code {}", code)))?;
+                Ok(hyper::Response::from_parts(parts, body))
+            })
     }
 }
 
@@ -85,21 +109,72 @@ impl<T: ToResponse> ToResponse for Result<T> {
         self,
         method: &Method,
         uri: &Uri,
-        body: String,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+        match self {
+            Ok(response) => response.to_response(method, uri, headers, body),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+impl<T: ToErrorResponse> ToErrorResponse for Result<T> {
+    fn to_error_response(
+        self,
+        code: u16,
+        reason: String,
     ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
         match self {
-            Ok(response) => response.to_response(method, uri, body),
+            Ok(response) => response.to_error_response(code, reason),
             Err(error) => Err(error),
         }
     }
 }
 
+/// Error type that is sent as its own response when a handler returns it in a
+/// `std::result::Result<T, E>`; ex: an application error enum with `#[derive(ToResponse)]`
+pub trait ResponseError: ToResponse {}
+
+impl<T: ToResponse, E: ResponseError> ToResponse for std::result::Result<T, E> {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+        match self {
+            Ok(response) => response.to_response(method, uri, headers, body),
+            Err(error) => error.to_response(method, uri, headers, body),
+        }
+    }
+}
+
+impl<T: ToResponse> ToResponse for Option<T> {
+    /// `None` is a `404 Not Found` that goes through the catches; ex: when a lookup by id
+    /// doesn't find anything
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+        match self {
+            Some(response) => response.to_response(method, uri, headers, body),
+            None => Err((404, "Not found".to_string())),
+        }
+    }
+}
+
 impl ToResponse for String {
     fn to_response(
         self,
         _method: &Method,
         _uri: &Uri,
-        _body: String,
+        _headers: &HeaderMap,
+        _body: &Bytes,
     ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
         Ok(hyper::Response::builder()
             .status(200)
@@ -129,7 +204,8 @@ impl ToResponse for &str {
         self,
         _method: &Method,
         _uri: &Uri,
-        _body: String,
+        _headers: &HeaderMap,
+        _body: &Bytes,
     ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
         Ok(hyper::Response::builder()
             .status(200)
@@ -139,6 +215,47 @@ impl ToResponse for &str {
     }
 }
 
+/// Raw bytes are sent as `application/octet-stream`
+impl ToResponse for Bytes {
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        _headers: &HeaderMap,
+        _body: &Bytes,
+    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+        Ok(hyper::Response::builder()
+            .status(200)
+            .header("Content-Type", "application/octet-stream")
+            .body(Full::new(self))
+            .unwrap())
+    }
+}
+
+impl ToResponse for Vec<u8> {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+        Bytes::from(self).to_response(method, uri, headers, body)
+    }
+}
+
+impl ToResponse for &'static [u8] {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
+        Bytes::from_static(self).to_response(method, uri, headers, body)
+    }
+}
+
 impl ToErrorResponse for &str {
     fn to_error_response(
         self,
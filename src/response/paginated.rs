@@ -0,0 +1,185 @@
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{HeaderMap, Method, Uri};
+use serde::Serialize;
+use serde_json::json;
+
+use super::{Result, ToResponse};
+use crate::{
+    request::{Pagination, QueryMap},
+    uri::query,
+};
+
+/// Page of a list as json with the page in an envelope and links to the other pages in the
+/// `Link` header (RFC 5988); ex:
+///
+/// ```text
+/// Link: </posts?page=3&per_page=20>; rel="next", </posts?page=1&per_page=20>; rel="prev", ...
+/// X-Total-Count: 95
+///
+/// {"data": [...], "page": 2, "per_page": 20, "total": 95, "total_pages": 5}
+/// ```
+///
+/// Without a total there isn't a `last` link and `next` is given while pages are full. With
+/// a next cursor the envelope has `next_cursor` and the `next` link uses it instead of the
+/// page. Links keep the request's other query parameters.
+///
+/// # Example
+/// ```
+/// use tela::{
+///     prelude::*,
+///     request::Pagination,
+///     response::Paginated,
+/// };
+///
+/// #[derive(serde::Serialize)]
+/// struct Event { id: u64 }
+///
+/// mod db {
+///     # use super::Event;
+///     pub fn events_after(cursor: Option<&str>, limit: u32) -> Vec<Event> {
+///         let start = cursor.and_then(|cursor| cursor.parse::<u64>().ok()).unwrap_or(0) + 1;
+///         (start..start + limit as u64).map(|id| Event { id }).collect()
+///     }
+/// }
+///
+/// #[get("/events")]
+/// fn events(page: Pagination) -> Paginated<Event> {
+///     let items = db::events_after(page.cursor.as_deref(), page.limit());
+///     let next = items.last().map(|event| event.id.to_string());
+///     Paginated::new(items, &page).next_cursor(next)
+/// }
+/// #
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone)]
+pub struct Paginated<T: Serialize> {
+    items: Vec<T>,
+    page: u32,
+    per_page: u32,
+    cursor: bool,
+    total: Option<u64>,
+    next_cursor: Option<String>,
+}
+
+impl<T: Serialize> Paginated<T> {
+    pub fn new(items: Vec<T>, pagination: &Pagination) -> Self {
+        Paginated {
+            items,
+            page: pagination.page,
+            per_page: pagination.per_page,
+            cursor: pagination.cursor.is_some(),
+            total: None,
+            next_cursor: None,
+        }
+    }
+
+    /// Number of items across every page
+    pub fn total(mut self, total: u64) -> Self {
+        self.total = Some(total);
+        self
+    }
+
+    /// Cursor of the next page, `None` if this is the last page. Switches the links to
+    /// cursor pagination.
+    pub fn next_cursor<C: Into<String>>(mut self, cursor: Option<C>) -> Self {
+        self.next_cursor = cursor.map(Into::into);
+        self.cursor = true;
+        self
+    }
+
+    fn total_pages(&self) -> Option<u64> {
+        self.total
+            .map(|total| total.div_ceil(self.per_page.max(1) as u64).max(1))
+    }
+
+    /// Links to other pages as `rel` and query pairs to replace
+    fn links(&self) -> Vec<(&'static str, Vec<(&'static str, String)>)> {
+        let per_page = self.per_page.to_string();
+        let page = |page: u64| vec![("page", page.to_string()), ("per_page", per_page.clone())];
+
+        if self.cursor {
+            let mut links = vec![("first", vec![("per_page", per_page.clone())])];
+            if let Some(cursor) = &self.next_cursor {
+                links.push((
+                    "next",
+                    vec![("cursor", cursor.clone()), ("per_page", per_page.clone())],
+                ));
+            }
+            return links;
+        }
+
+        let current = self.page as u64;
+        let mut links = vec![("first", page(1))];
+        if current > 1 {
+            links.push(("prev", page(current - 1)));
+        }
+        match self.total_pages() {
+            Some(last) => {
+                if current < last {
+                    links.push(("next", page(current + 1)));
+                }
+                links.push(("last", page(last)));
+            }
+            None if self.items.len() >= self.per_page as usize => {
+                links.push(("next", page(current + 1)))
+            }
+            None => {}
+        }
+        links
+    }
+}
+
+/// Uri with the pagination parameters replaced
+fn link(uri: &Uri, params: &[(&str, String)]) -> String {
+    let existing = QueryMap::new(uri.query().unwrap_or(""));
+    let mut pairs: Vec<(&str, &str)> = existing
+        .iter()
+        .filter(|(key, _)| !matches!(*key, "page" | "per_page" | "cursor"))
+        .collect();
+    pairs.extend(params.iter().map(|(key, value)| (*key, value.as_str())));
+    format!("{}?{}", uri.path(), query(&pairs))
+}
+
+impl<T: Serialize> ToResponse for Paginated<T> {
+    fn to_response(
+        self,
+        _method: &Method,
+        uri: &Uri,
+        _headers: &HeaderMap,
+        _body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        let links = self
+            .links()
+            .iter()
+            .map(|(rel, params)| format!("<{}>; rel=\"{}\"", link(uri, params), rel))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let pages = self.total_pages();
+
+        let mut envelope = json!({
+            "data": self.items,
+            "per_page": self.per_page,
+        });
+        if self.cursor {
+            envelope["next_cursor"] = json!(self.next_cursor);
+        } else {
+            envelope["page"] = json!(self.page);
+            if let (Some(total), Some(pages)) = (self.total, pages) {
+                envelope["total"] = json!(total);
+                envelope["total_pages"] = json!(pages);
+            }
+        }
+        let body = super::to_json(&envelope)
+            .map_err(|_| (500, "Failed to parse json in response".to_string()))?;
+
+        let mut response = hyper::Response::builder()
+            .status(200)
+            .header("Content-Type", "application/json")
+            .header("Link", links);
+        if let Some(total) = self.total {
+            response = response.header("X-Total-Count", total);
+        }
+        Ok(response.body(Full::new(Bytes::from(body))).unwrap())
+    }
+}
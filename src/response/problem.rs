@@ -0,0 +1,207 @@
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{HeaderMap, Method, Uri};
+use serde::Serialize;
+
+use super::{Result, ToErrorResponse, ToResponse};
+use crate::{ErrorContext, ErrorRenderer, StatusCode};
+
+/// RFC 7807 problem details; an `application/problem+json` response that describes an error
+/// in a way api clients can read.
+///
+/// Errors returned from endpoints, `(u16, String)`, convert into a problem with the reason as
+/// the detail.
+///
+/// # Example
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use tela::{
+///     prelude::*,
+///     request::Json,
+///     response::{Problem, JSON},
+/// };
+///
+/// #[derive(Deserialize)]
+/// struct Transfer { amount: u32 }
+///
+/// #[derive(Serialize)]
+/// struct Receipt { amount: u32 }
+///
+/// #[post("/transfer")]
+/// fn transfer(Json(request, ..): Json<Transfer>) -> std::result::Result<JSON<Receipt>, Problem> {
+///     let balance = 30;
+///     if request.amount > balance {
+///         return Err(Problem::new(403)
+///             .with_type("https://example.com/probs/out-of-credit")
+///             .title("You do not have enough credit")
+///             .detail(format!("Your current balance is {}, but that costs {}", balance, request.amount))
+///             .extension("balance", balance));
+///     }
+///     Ok(JSON(Receipt { amount: request.amount }))
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    kind: String,
+    title: String,
+    status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instance: Option<String>,
+    #[serde(flatten)]
+    extensions: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Problem {
+    /// Problem for the status with the default message as the title
+    pub fn new(status: u16) -> Self {
+        Problem {
+            kind: "about:blank".to_string(),
+            title: StatusCode::from(status).message(),
+            status,
+            detail: None,
+            instance: None,
+            extensions: serde_json::Map::new(),
+        }
+    }
+
+    /// Uri that identifies the type of problem. Defaults to `about:blank`.
+    pub fn with_type<T: Into<String>>(mut self, kind: T) -> Self {
+        self.kind = kind.into();
+        self
+    }
+
+    /// Short summary of the type of problem
+    pub fn title<T: Into<String>>(mut self, title: T) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Explanation specific to this occurrence of the problem
+    pub fn detail<T: Into<String>>(mut self, detail: T) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Uri that identifies this occurrence of the problem; ex: the request path
+    pub fn instance<T: Into<String>>(mut self, instance: T) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Add an extra member to the problem. Members named after the standard members are
+    /// ignored.
+    pub fn extension<K: Into<String>, V: Serialize>(mut self, key: K, value: V) -> Self {
+        let key = key.into();
+        if !["type", "title", "status", "detail", "instance"].contains(&key.as_str()) {
+            self.extensions.insert(
+                key,
+                serde_json::to_value(value).unwrap_or(serde_json::Value::Null),
+            );
+        }
+        self
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    fn response(&self) -> hyper::Response<Full<Bytes>> {
+        let mut builder = hyper::Response::builder()
+            .status(self.status)
+            .header("Content-Type", "application/problem+json");
+        if let Some(detail) = &self.detail {
+            builder = builder.header("Tela-Reason", crate::router::header_safe(detail));
+        }
+        builder
+            .body(Full::new(Bytes::from(
+                serde_json::to_string(self).unwrap_or_default(),
+            )))
+            .unwrap()
+    }
+}
+
+impl From<(u16, String)> for Problem {
+    fn from((status, reason): (u16, String)) -> Self {
+        Problem::new(status).detail(reason)
+    }
+}
+
+impl From<&ErrorContext<'_>> for Problem {
+    fn from(error: &ErrorContext<'_>) -> Self {
+        Problem::new(error.code)
+            .detail(error.reason)
+            .instance(error.uri.path())
+    }
+}
+
+impl ToResponse for Problem {
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        _headers: &HeaderMap,
+        _body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        Ok(self.response())
+    }
+}
+
+impl ToErrorResponse for Problem {
+    /// The catch's status is used and the reason becomes the detail if there isn't one
+    fn to_error_response(
+        mut self,
+        code: u16,
+        reason: String,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        if self.status != code {
+            self.title = StatusCode::from(code).message();
+            self.status = code;
+        }
+        if self.detail.is_none() {
+            self.detail = Some(reason);
+        }
+        Ok(self.response())
+    }
+}
+
+impl<T: ToResponse> ToResponse for std::result::Result<T, Problem> {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        match self {
+            Ok(response) => response.to_response(method, uri, headers, body),
+            Err(problem) => Ok(problem.response()),
+        }
+    }
+}
+
+/// Renders errors that reach the router as problem details with the request path as the
+/// instance
+///
+/// # Example
+/// ```no_run
+/// use tela::{response::ProblemErrorRenderer, Server};
+///
+/// #[tela::main]
+/// async fn main() {
+///     Server::new()
+///         .errors(ProblemErrorRenderer)
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ProblemErrorRenderer;
+
+impl ErrorRenderer for ProblemErrorRenderer {
+    fn render(&self, error: &ErrorContext) -> hyper::Response<Full<Bytes>> {
+        Problem::from(error).response()
+    }
+}
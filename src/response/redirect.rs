@@ -1,6 +1,6 @@
 use bytes::Bytes;
 use http_body_util::Full;
-use hyper::{Method, Uri};
+use hyper::{HeaderMap, Method, Uri};
 
 use super::{Result, ToErrorResponse, ToResponse};
 
@@ -43,7 +43,8 @@ impl<const CODE: u16> ToResponse for Redirect<CODE> {
         self,
         _method: &Method,
         _uri: &Uri,
-        _body: String,
+        _headers: &HeaderMap,
+        _body: &Bytes,
     ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
         if ![301, 302, 303, 307, 308].contains(&CODE) {
             Ok(hyper::Response::builder()
@@ -0,0 +1,115 @@
+use std::{
+    convert::Infallible,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use http_body_util::{combinators::UnsyncBoxBody, BodyExt, Full, StreamBody};
+use hyper::{body::Frame, HeaderMap, Method, Uri};
+use serde::Serialize;
+
+use super::{Result, ToResponse};
+
+/// Body sent to hyper; either the full body of a response or the stream it was replaced with
+pub(crate) type Body = UnsyncBoxBody<Bytes, Infallible>;
+
+type Chunks = Pin<Box<dyn Stream<Item = Bytes> + Send>>;
+
+/// Stream kept in a response's extensions until the response is sent. Responses are passed
+/// through the layers with full bodies so streamed responses keep an empty body until then.
+#[derive(Clone)]
+struct Streaming(Arc<Mutex<Option<Chunks>>>);
+
+/// Response with the content type whose body is sent as the stream produces chunks
+pub(crate) fn streaming<S>(content_type: &str, stream: S) -> hyper::Response<Full<Bytes>>
+where
+    S: Stream<Item = Bytes> + Send + 'static,
+{
+    let mut response = hyper::Response::builder()
+        .status(200)
+        .header("Content-Type", content_type)
+        .body(Full::new(Bytes::new()))
+        .unwrap();
+    response
+        .extensions_mut()
+        .insert(Streaming(Arc::new(Mutex::new(Some(Box::pin(stream))))));
+    response
+}
+
+/// Swap in the stream of a streamed response so each chunk is written as it is produced
+pub(crate) fn into_body(response: hyper::Response<Full<Bytes>>) -> hyper::Response<Body> {
+    let (mut parts, body) = response.into_parts();
+    let stream = parts
+        .extensions
+        .remove::<Streaming>()
+        .and_then(|Streaming(stream)| stream.lock().unwrap().take());
+    match stream {
+        Some(stream) => {
+            parts.headers.remove("Content-Length");
+            let body = StreamBody::new(stream.map(|chunk| Ok(Frame::data(chunk))));
+            hyper::Response::from_parts(parts, BodyExt::boxed_unsync(body))
+        }
+        None => hyper::Response::from_parts(parts, body.boxed_unsync()),
+    }
+}
+
+/// Stream items as newline delimited json (NDJSON). Each item is sent as soon as the stream
+/// produces it so clients can process them while the rest are produced.
+///
+/// Layers can change the headers of the response but not the streamed body.
+///
+/// # Example
+/// ```
+/// use futures::Stream;
+/// use tela::{prelude::*, response::JsonLines};
+///
+/// #[derive(serde::Serialize)]
+/// struct Row { id: u32 }
+///
+/// fn rows() -> Vec<Row> {
+///     (1..=3).map(|id| Row { id }).collect()
+/// }
+///
+/// #[get("/export")]
+/// fn export() -> JsonLines<impl Stream<Item = Row> + Send> {
+///     JsonLines::new(futures::stream::iter(rows()))
+/// }
+/// ```
+pub struct JsonLines<S>(pub S);
+
+impl<S> JsonLines<S> {
+    pub fn new(stream: S) -> Self {
+        JsonLines(stream)
+    }
+}
+
+impl<S, T> ToResponse for JsonLines<S>
+where
+    S: Stream<Item = T> + Send + 'static,
+    T: Serialize,
+{
+    fn to_response(
+        self,
+        _method: &Method,
+        _uri: &Uri,
+        _headers: &HeaderMap,
+        _body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        let lines = self.0.scan((), |_, item| {
+            futures::future::ready(match serde_json::to_vec(&item) {
+                Ok(mut line) => {
+                    line.push(b'\n');
+                    Some(Bytes::from(line))
+                }
+                Err(error) => {
+                    // Headers are already sent so the stream can only be ended early
+                    eprintln!("Failed to serialize json line: {}", error);
+                    None
+                }
+            })
+        });
+        Ok(streaming("application/x-ndjson", lines))
+    }
+}
@@ -37,7 +37,7 @@ impl TemplateEngine for Handlebars {
         if !is_some {
             let mut engine = handlebars::Handlebars::new();
             match engine
-                .register_templates_directory(".hbs", &format!("{}/", path.norm_strip_slashes()))
+                .register_templates_directory(".hbs", format!("{}/", path.norm_strip_slashes()))
             {
                 Ok(_) => *HANDLEBARS.write().unwrap() = Some((engine, globals)),
                 Err(err) => panic!("Failed to initialize Handlebars templating engine: {}", err),
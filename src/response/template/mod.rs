@@ -1,13 +1,15 @@
 pub mod hbs;
 pub mod ttera;
-use std::{collections::BTreeMap, marker::PhantomData};
+use std::{any::type_name, collections::BTreeMap, marker::PhantomData, time::Duration};
+
+use bytes::Bytes;
 
 #[cfg(feature = "handlebars")]
 pub use hbs::Handlebars;
 #[cfg(feature = "tera")]
 pub use ttera::Tera;
 
-use super::{Result, ToErrorResponse, ToResponse};
+use super::{cache, Result, ToErrorResponse, ToResponse};
 
 #[macro_export]
 macro_rules! context {
@@ -53,15 +55,55 @@ pub struct Template<ENGINE: TemplateEngine>(
     pub String,
     pub BTreeMap<String, serde_json::Value>,
     PhantomData<ENGINE>,
+    Option<Duration>,
 );
 
 impl<ENGINE: TemplateEngine> Template<ENGINE> {
     pub fn new<T: Into<String>>(path: T, context: BTreeMap<String, serde_json::Value>) -> Self {
-        Template(path.into(), context, PhantomData)
+        Template(path.into(), context, PhantomData, None)
+    }
+
+    /// Reuse the rendered template for `ttl` when it is rendered with the same path and
+    /// context. Use for expensive pages that rarely change.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use tela::{prelude::*, response::Template};
+    /// # #[cfg(feature = "tera")]
+    /// use tela::response::template::Tera;
+    ///
+    /// fn recent_posts() -> Vec<String> {
+    ///     vec!["Hello".to_string(), "World".to_string()]
+    /// }
+    ///
+    /// # #[cfg(feature = "tera")]
+    /// #[get("/")]
+    /// fn home() -> Template<Tera> {
+    ///     template!("index.html", { posts: recent_posts() }).cached(Duration::from_secs(60))
+    /// }
+    /// #
+    /// # fn main() {}
+    /// ```
+    pub fn cached(mut self, ttl: Duration) -> Self {
+        self.3 = Some(ttl);
+        self
     }
 
     pub fn render(self) -> Result<String> {
-        ENGINE::render(ENGINE::parse_path(&self.0), self.1)
+        let path = ENGINE::parse_path(&self.0);
+        match self.3 {
+            Some(ttl) => {
+                // The context is a sorted map so equal contexts serialize the same
+                let key = (
+                    type_name::<ENGINE>(),
+                    &path,
+                    serde_json::to_string(&self.1).unwrap_or_default(),
+                );
+                cache::render(key, ttl, || ENGINE::render(path.clone(), self.1.clone()))
+            }
+            None => ENGINE::render(path, self.1),
+        }
     }
 }
 
@@ -70,7 +112,8 @@ impl<T: TemplateEngine> ToResponse for Template<T> {
         self,
         _method: &hyper::Method,
         _uri: &hyper::Uri,
-        _body: String,
+        _headers: &hyper::HeaderMap,
+        _body: &Bytes,
     ) -> Result<hyper::Response<http_body_util::Full<bytes::Bytes>>> {
         self.render().map(|text| {
             hyper::Response::builder()
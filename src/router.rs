@@ -1,63 +1,438 @@
-use std::{collections::HashMap, convert::Infallible, ffi::OsStr, fs, path::Path, sync::Arc};
+use std::{
+    any::Any,
+    collections::HashMap,
+    convert::Infallible,
+    ffi::OsStr,
+    fs,
+    future::Future,
+    net::SocketAddr,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use bytes::Bytes;
-use http_body_util::{BodyExt, Full};
-use hyper::{Method, Uri};
-use tokio::sync::{
-    mpsc::{self, Sender},
-    oneshot,
+use futures::FutureExt;
+use http_body_util::{combinators::UnsyncBoxBody, Full};
+use hyper::{
+    header::{HeaderName, HeaderValue, DATE, SERVER},
+    HeaderMap, Method, Uri,
 };
+use tokio::runtime::{Handle, RuntimeFlavor};
 
 use crate::{
-    errors::{default_error_page, StatusCode},
-    request::{Catch, Endpoint},
-    uri::index,
+    assets::{self, EmbeddedAssets, Favicon, Icon, Manifest, WellKnown, MANIFEST},
+    cookie::{random_key, SECRET, SECURE},
+    errors::{ErrorContext, ErrorRenderer, HtmlErrorRenderer, StatusCode},
+    layer::{Layer, Next, Request, Response, Upgrade},
+    request::{BodyHook, BodyLimits, BodyStream, Catch, Endpoint, Head},
+    response::{stream, JsonFormat, JSON_FORMAT},
+    shutdown::{Shutdown, SHUTDOWN},
+    state::{StateMap, STATE},
+    uri::{index_patterns, Pattern},
+    websocket::{WebSocket, WebSocketConfig, WebSocketRoute},
 };
 
-/// Commands sent through channel to router
-#[derive(Debug)]
-pub enum Command {
-    Get {
-        method: Method,
-        path: String,
-        response: oneshot::Sender<Option<Route>>,
-    },
-    Error {
-        code: u16,
-        response: oneshot::Sender<Option<ErrorHandler>>,
-    },
+/// Run an endpoint or catch, turning a panic into a 500 error. The panic message is
+/// used as the reason so it reaches the catch and error page.
+fn catch_panic<F>(handler: F) -> crate::response::Result<hyper::Response<Full<Bytes>>>
+where
+    F: FnOnce() -> crate::response::Result<hyper::Response<Full<Bytes>>>,
+{
+    match panic::catch_unwind(AssertUnwindSafe(handler)) {
+        Ok(result) => result,
+        Err(payload) => Err((500, panic_reason(payload))),
+    }
+}
+
+/// Reason for a panic from its payload
+fn panic_reason(payload: Box<dyn Any + Send>) -> String {
+    let message = match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => match payload.downcast_ref::<String>() {
+            Some(message) => message.clone(),
+            None => "Unknown panic payload".to_string(),
+        },
+    };
+    format!("Handler panicked: {}", message)
+}
+
+/// Reasons are sent in the `Tela-Reason` header so they can't have control characters
+pub(crate) fn header_safe(reason: &str) -> String {
+    reason.replace(|c: char| c.is_control(), " ")
 }
 
+/// Function that changes every request before it is routed; see `Server::map_request`
+pub type MapRequest = Arc<dyn Fn(Request) -> Request + Send + Sync>;
+/// Function that changes every response before it is sent; see `Server::map_response`
+pub type MapResponse = Arc<dyn Fn(Response) -> Response + Send + Sync>;
+/// Function that gives the `Date` header of each response; see `Server::date_header_with`
+pub type DateHeader = Arc<dyn Fn() -> String + Send + Sync>;
+
 #[derive(Debug, Clone)]
 pub struct Route(pub Arc<dyn Endpoint>);
 
+/// Route in the router along with its documentation; see `Router::routes`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteInfo {
+    pub method: Method,
+    pub path: String,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ErrorHandler(pub Arc<dyn Catch>);
 
+/// Settings of a router that its layers, handlers, and catches read while it handles a
+/// request, ex: its managed state. They are set for the request's task so servers in the
+/// same process don't share them.
+#[derive(Debug, Clone)]
+pub(crate) struct Scope {
+    json: JsonFormat,
+    state: Arc<StateMap>,
+    secret: Arc<[u8]>,
+    manifest: Arc<Manifest>,
+    secure: bool,
+    shutdown: Shutdown,
+}
+
+impl Scope {
+    /// Run a future with the settings
+    pub(crate) async fn run<F: Future>(&self, future: F) -> F::Output {
+        let future = SECRET.scope(self.secret.clone(), future);
+        let future = MANIFEST.scope(self.manifest.clone(), future);
+        let future = SECURE.scope(self.secure, future);
+        let future = SHUTDOWN.scope(self.shutdown.clone(), future);
+        JSON_FORMAT
+            .scope(self.json, STATE.scope(self.state.clone(), future))
+            .await
+    }
+
+    /// Run a function with the settings; for code that isn't on the request's task, ex: an
+    /// endpoint on a blocking thread
+    pub(crate) fn run_sync<R>(&self, f: impl FnOnce() -> R) -> R {
+        let f = || SECRET.sync_scope(self.secret.clone(), f);
+        let f = || MANIFEST.sync_scope(self.manifest.clone(), f);
+        let f = || SECURE.sync_scope(self.secure, f);
+        let f = || SHUTDOWN.sync_scope(self.shutdown.clone(), f);
+        JSON_FORMAT.sync_scope(self.json, || STATE.sync_scope(self.state.clone(), f))
+    }
+
+    /// Shutdown of the server the settings are for
+    pub(crate) fn shutdown(&self) -> &Shutdown {
+        &self.shutdown
+    }
+}
+
+/// Routes and catches frozen when the router is served. The table is never changed
+/// after it is built so it is shared between requests without a lock.
+#[derive(Debug)]
+struct RouteTable {
+    routes: HashMap<Method, Vec<Route>>,
+    paths: HashMap<Method, Vec<Pattern>>,
+    catch: HashMap<u16, ErrorHandler>,
+    scope: Scope,
+}
+
+impl RouteTable {
+    fn endpoint(&self, method: &Method, path: &str) -> Option<&Route> {
+        let paths = self.paths.get(method)?;
+        index_patterns(path, paths).map(|index| &self.routes[method][index])
+    }
+
+    /// Catch for the code or the catch all, `0`, if there isn't one
+    fn catch(&self, code: u16) -> Option<&ErrorHandler> {
+        self.catch.get(&code).or_else(|| self.catch.get(&0))
+    }
+}
+
 #[derive(Clone)]
 pub struct Router {
-    channel: Option<Sender<Command>>,
+    table: Option<Arc<RouteTable>>,
     router: HashMap<Method, Vec<Route>>,
     catch: HashMap<u16, ErrorHandler>,
     assets: String,
+    asset_mount: String,
+    asset_extensions: Option<Vec<String>>,
+    fingerprint: bool,
+    embedded: Vec<EmbeddedAssets>,
+    favicon: Option<Icon>,
+    well_known: Option<WellKnown>,
+    #[cfg(feature = "acme")]
+    acme: Option<crate::acme::Acme>,
+    layers: Vec<Arc<dyn Layer>>,
+    request_maps: Vec<MapRequest>,
+    response_maps: Vec<MapResponse>,
+    server: Option<HeaderValue>,
+    date: Option<DateHeader>,
+    default_headers: HeaderMap,
+    body: BodyLimits,
+    json: JsonFormat,
+    state: StateMap,
+    secret: Arc<[u8]>,
+    tls: bool,
+    shutdown: Shutdown,
+    errors: Arc<dyn ErrorRenderer>,
+    websockets: Vec<WebSocketRoute>,
 }
 impl Router {
     pub fn new() -> Self {
         Router {
-            channel: None,
+            table: None,
             router: HashMap::new(),
             catch: HashMap::new(),
             assets: "assets/".to_string(),
+            asset_mount: String::new(),
+            asset_extensions: None,
+            fingerprint: false,
+            embedded: Vec::new(),
+            favicon: None,
+            well_known: None,
+            #[cfg(feature = "acme")]
+            acme: None,
+            layers: Vec::new(),
+            request_maps: Vec::new(),
+            response_maps: Vec::new(),
+            server: None,
+            date: None,
+            default_headers: HeaderMap::new(),
+            body: BodyLimits::default(),
+            json: JsonFormat::new(),
+            state: StateMap::default(),
+            secret: random_key().into(),
+            tls: false,
+            shutdown: Shutdown::new(),
+            errors: Arc::new(HtmlErrorRenderer),
+            websockets: Vec::new(),
         }
     }
 
+    /// Set how errors without a catch are rendered
+    pub fn errors(&mut self, renderer: Arc<dyn ErrorRenderer>) {
+        self.errors = renderer;
+    }
+
+    /// Add a layer that runs around every request. Layers run in the order they are added.
+    pub fn layer(&mut self, layer: Arc<dyn Layer>) {
+        self.layers.push(layer);
+    }
+
+    /// Change every request before it is routed. Maps run in the order they are added.
+    pub fn map_request(&mut self, map: MapRequest) {
+        self.request_maps.push(map);
+    }
+
+    /// Change every response before it is sent. Maps run in the order they are added.
+    pub fn map_response(&mut self, map: MapResponse) {
+        self.response_maps.push(map);
+    }
+
+    /// `Server` header added to responses that don't have one. `None` doesn't add one.
+    pub fn server_header(&mut self, value: Option<HeaderValue>) {
+        self.server = value;
+    }
+
+    /// `Date` header added to responses that don't have one. `None` leaves it to the
+    /// connection, which sends the current time.
+    pub fn date_header(&mut self, date: Option<DateHeader>) {
+        self.date = date;
+    }
+
+    /// Header added to every response that doesn't have one with the same name. Headers a
+    /// route sets, including its own defaults, are kept.
+    pub fn default_header(&mut self, name: HeaderName, value: HeaderValue) {
+        self.default_headers.insert(name, value);
+    }
+
+    /// How `JSON` responses from the router's handlers and catches are serialized; see
+    /// `response::JsonFormat`
+    pub fn json_format(&mut self, format: JsonFormat) {
+        self.json = format;
+    }
+
+    /// Share a value with the router's layers, handlers, and catches; see `state::State`.
+    /// Managing a value of the same type replaces it.
+    pub fn manage<T: Send + Sync + 'static>(&mut self, value: T) {
+        self.state.insert(value);
+    }
+
+    /// Key the router's signed cookies and urls are signed with. Without one a random key is
+    /// used so they stop being valid when the server restarts.
+    pub fn secret<T: AsRef<[u8]>>(&mut self, key: T) {
+        self.secret = key.as_ref().into();
+    }
+
+    /// Largest request body, in bytes, that is read for a handler. `None` doesn't limit them.
+    pub fn body_limit(&mut self, bytes: Option<usize>) {
+        self.body.limit = bytes;
+    }
+
+    /// Longest time a client has to send a request body. `None` waits for as long as it takes.
+    pub fn body_timeout(&mut self, timeout: Option<Duration>) {
+        self.body.timeout = timeout;
+    }
+
+    /// Hook told about the progress of reading each request body
+    pub fn body_progress(&mut self, hook: BodyHook) {
+        self.body.progress = Some(hook);
+    }
+
+    pub(crate) fn map_request_with(&self, request: Request) -> Request {
+        self.request_maps
+            .iter()
+            .fold(request, |request, map| map(request))
+    }
+
+    pub(crate) fn map_response_with(&self, response: Response) -> Response {
+        self.response_maps
+            .iter()
+            .fold(response, |response, map| map(response))
+    }
+
+    /// Upgrade requests to the path to websockets and pass them to the handler.
+    /// Upgrades go through the layers first and a layer that responds, ex: `RequireAuth`,
+    /// refuses it. Headers the layers add are copied to the handshake response.
+    pub fn websocket<F, Fut>(&mut self, path: &str, config: WebSocketConfig, handler: F)
+    where
+        F: Fn(WebSocket) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.websockets
+            .push(WebSocketRoute::new(path, config, handler));
+    }
+
     pub fn assets(&mut self, path: String) {
         self.assets = path;
     }
 
+    /// Serve static files from the path under the mount path; ex: `/static/app.css` from
+    /// `web/app.css`
+    pub fn assets_at(&mut self, mount: &str, path: String) {
+        self.asset_mount = format!("/{}", mount.trim_matches('/'))
+            .trim_end_matches('/')
+            .to_string();
+        self.assets = path;
+    }
+
+    /// Whether the router is served over TLS; cookies with a login are marked `Secure` when it is
+    pub(crate) fn served_over_tls(&mut self, tls: bool) {
+        self.tls = tls;
+    }
+
+    /// Shutdown of the server the router is served by, see `ShutdownSignal`
+    pub(crate) fn served_until(&mut self, shutdown: Shutdown) {
+        self.shutdown = shutdown;
+    }
+
+    /// Path static files are served from
+    pub(crate) fn asset_path(&self) -> &str {
+        &self.assets
+    }
+
+    /// Url path static files are served under, empty when they are served from the root
+    pub(crate) fn asset_mount(&self) -> &str {
+        &self.asset_mount
+    }
+
+    /// Only serve static files with these extensions
+    pub fn asset_extensions(&mut self, extensions: Vec<String>) {
+        self.asset_extensions = Some(
+            extensions
+                .into_iter()
+                .map(|e| e.trim_start_matches('.').to_lowercase())
+                .collect(),
+        );
+    }
+
+    /// Also serve each asset under a name with a hash of its contents, ex: `app.1f2e3d4c.css`,
+    /// with headers to cache it forever. The assets are hashed when the routes are served.
+    pub fn fingerprint_assets(&mut self) {
+        self.fingerprint = true;
+    }
+
+    /// Serve files compiled into the binary with `include_dir!` under the mount path; ex:
+    /// `/static/app.css`. Embedded files are served before files in the asset directory.
+    #[cfg(feature = "embed")]
+    pub fn embedded_assets(&mut self, mount: &str, dir: &'static include_dir::Dir<'static>) {
+        self.embedded.push(EmbeddedAssets::new(mount, dir));
+    }
+
+    /// Serve the icon at `/favicon.ico` with headers to cache it for a day
+    pub fn favicon(&mut self, favicon: Favicon) {
+        match Icon::new(favicon) {
+            Ok(icon) => self.favicon = Some(icon),
+            Err(error) => eprintln!("Failed to read favicon: {}", error),
+        }
+    }
+
+    /// Serve the files in the directory under `/.well-known/`
+    pub fn well_known(&mut self, dir: PathBuf) {
+        self.well_known = Some(WellKnown::new(dir));
+    }
+
+    /// Answer the acme http-01 challenges for the certificate
+    #[cfg(feature = "acme")]
+    pub fn acme(&mut self, acme: crate::acme::Acme) {
+        self.acme = Some(acme);
+    }
+
+    /// Start getting the acme certificate, if there is one, once the router is served
+    pub(crate) fn start_acme(&self) {
+        #[cfg(feature = "acme")]
+        if let Some(acme) = &self.acme {
+            acme.start();
+        }
+    }
+
+    /// Response to a pending acme http-01 challenge for the path
+    #[cfg_attr(not(feature = "acme"), allow(unused_variables))]
+    pub(crate) fn acme_challenge(&self, path: &str) -> Option<hyper::Response<Full<Bytes>>> {
+        #[cfg(feature = "acme")]
+        return self.acme.as_ref()?.respond(path);
+        #[cfg(not(feature = "acme"))]
+        None
+    }
+
+    /// Resolve a request path to a file inside of the asset directory.
+    ///
+    /// Paths with `..` or that resolve outside of the asset directory, ex: through a symlink,
+    /// don't resolve. Neither do extensions that aren't allowed.
+    fn resolve_asset(&self, path: &str, extension: &str) -> Option<PathBuf> {
+        if let Some(extensions) = &self.asset_extensions {
+            if !extensions.contains(&extension.to_lowercase()) {
+                return None;
+            }
+        }
+
+        assets::resolve(Path::new(&self.assets), path)
+    }
+
+    /// Whether `serve_routes` has been called
+    pub(crate) fn is_served(&self) -> bool {
+        self.table.is_some()
+    }
+
+    /// Paths of all GET routes that don't have any uri captures
+    pub(crate) fn static_paths(&self) -> Vec<String> {
+        match self.router.get(&Method::GET) {
+            Some(routes) => routes
+                .iter()
+                .map(|r| r.0.path())
+                .filter(|path| !path.contains(':'))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Handle errors with the catch for each of its codes. Codes that already have a catch
+    /// keep it.
     pub fn catch(&mut self, catch: Arc<dyn Catch>) {
-        if !self.catch.contains_key(&catch.code()) {
-            self.catch.insert(catch.code(), ErrorHandler(catch));
+        for code in catch.codes() {
+            self.catch
+                .entry(code)
+                .or_insert_with(|| ErrorHandler(catch.clone()));
         }
     }
 
@@ -73,88 +448,125 @@ impl Router {
         }
     }
 
-    /// Start listener thread for handling access to router
-    ///
-    /// Creates mpsc channel and returns Sender handle. The thread that this method
-    /// creates is the only instance of the router that should exists.
-    pub fn serve_routes(&mut self) {
-        let (tx, mut rx) = mpsc::channel::<Command>(32);
-        let router = self.router.clone();
-        let catch = self.catch.clone();
-
-        tokio::spawn(async move {
-            'watcher: while let Some(cmd) = rx.recv().await {
-                use Command::*;
-
-                match cmd {
-                    Get {
-                        method,
-                        path,
-                        response,
-                    } => {
-                        match router.get(&method) {
-                            Some(data) => {
-                                match index(
-                                    &path,
-                                    &data.iter().map(|r| r.0.path()).collect::<Vec<String>>(),
-                                ) {
-                                    Some(index) => {
-                                        response.send(Some(data[index].clone())).unwrap();
-                                        continue 'watcher;
-                                    }
-                                    _ => {}
-                                }
-                            }
-                            _ => {}
-                        };
-                        response.send(None).unwrap();
-                    }
-                    Error { code, response } => {
-                        if catch.contains_key(&code) {
-                            response
-                                .send(catch.get(&code).map(|eh| eh.clone()))
-                                .unwrap()
-                        } else if catch.contains_key(&0) {
-                            response.send(catch.get(&0).map(|eh| eh.clone())).unwrap()
-                        } else {
-                            response.send(None).unwrap()
-                        }
+    /// Every route with its method and documentation, sorted by path then method
+    pub fn routes(&self) -> Vec<RouteInfo> {
+        let mut routes: Vec<RouteInfo> = self
+            .router
+            .iter()
+            .flat_map(|(method, routes)| {
+                routes.iter().map(move |Route(endpoint)| {
+                    let metadata = endpoint.metadata();
+                    RouteInfo {
+                        method: method.clone(),
+                        path: endpoint.path(),
+                        description: metadata.description,
+                        tags: metadata.tags,
                     }
-                }
-            }
-        });
+                })
+            })
+            .collect();
+        routes.sort_by(|a, b| (&a.path, a.method.as_str()).cmp(&(&b.path, b.method.as_str())));
+        routes
+    }
+
+    /// Path of the route that matches the request; ex: `/users/:id` for `/users/42`
+    pub(crate) fn route_path(&self, method: &Method, path: &str) -> Option<String> {
+        let table = self.table.as_ref()?;
+        table
+            .endpoint(method, path)
+            .map(|Route(endpoint)| endpoint.path())
+    }
+
+    /// Freeze the routes and catches so they can be served.
+    ///
+    /// Routes and catches added after this are not served until this is called again. Fails
+    /// if the assets are fingerprinted and can't be read, or if a route's path isn't a valid
+    /// pattern; ex: `/files/:...path/:...rest`. The route and the problem with it are in the
+    /// error so it is found when the server starts.
+    pub fn serve_routes(&mut self) -> std::io::Result<()> {
+        let manifest = match self.fingerprint {
+            true => Manifest::new(&self.assets, &self.asset_mount).map_err(|error| {
+                std::io::Error::new(
+                    error.kind(),
+                    format!(
+                        "Failed to fingerprint the assets in {:?}: {}",
+                        self.assets, error
+                    ),
+                )
+            })?,
+            false => Manifest::default(),
+        };
+
+        // Patterns are parsed once here instead of on every request
+        let mut routes = HashMap::new();
+        let mut paths = HashMap::new();
+        for (method, endpoints) in self.router.iter() {
+            let patterns = endpoints
+                .iter()
+                .map(|route| {
+                    Pattern::parse(&route.0.path()).map_err(|error| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!("Route {} {} is invalid: {}", method, route.0.path(), error),
+                        )
+                    })
+                })
+                .collect::<std::io::Result<_>>()?;
+            routes.insert(method.clone(), endpoints.clone());
+            paths.insert(method.clone(), patterns);
+        }
 
-        self.channel = Some(tx);
+        self.table = Some(Arc::new(RouteTable {
+            routes,
+            paths,
+            catch: self.catch.clone(),
+            scope: self.scope(manifest),
+        }));
+        Ok(())
+    }
+
+    fn scope(&self, manifest: Manifest) -> Scope {
+        Scope {
+            json: self.json,
+            state: Arc::new(self.state.clone()),
+            secret: self.secret.clone(),
+            manifest: Arc::new(manifest),
+            secure: self.tls,
+            shutdown: self.shutdown.clone(),
+        }
+    }
+
+    /// Fingerprinted asset paths and the asset paths they were created from once the routes
+    /// are served
+    pub(crate) fn fingerprinted(&self) -> Vec<(String, String)> {
+        self.table
+            .as_ref()
+            .map(|table| table.scope.manifest.fingerprinted())
+            .unwrap_or_default()
     }
 
     async fn error(
         &self,
-        uri: &Uri,
-        method: &Method,
-        body: &Vec<u8>,
+        head: Head,
+        body: &Bytes,
         code: u16,
         reason: String,
-        channel: Sender<Command>,
+        table: &RouteTable,
     ) -> std::result::Result<hyper::Response<Full<Bytes>>, Infallible> {
-        let (error_tx, error_rx) = oneshot::channel();
-        match channel
-            .send(Command::Error {
-                code: code.clone(),
-                response: error_tx,
-            })
-            .await
-        {
-            Ok(_) => {}
-            Err(error) => eprintln!("{:?}", error),
-        };
+        let reason = header_safe(&reason);
+        let (uri, method) = (&head.uri, &head.method);
 
-        match error_rx.await.unwrap() {
+        match table.catch(code) {
             Some(ErrorHandler(handler)) => {
-                match handler.execute(
-                    code.clone(),
-                    StatusCode::from(code.clone()).message(),
+                let result = AssertUnwindSafe(handler.execute(
+                    code,
+                    StatusCode::from(code).message(),
                     reason.clone(),
-                ) {
+                    &head,
+                ))
+                .catch_unwind()
+                .await;
+                match result.unwrap_or_else(|payload| Err((500, panic_reason(payload)))) {
                     Ok(response) => {
                         Router::log_request(
                             &uri.path().to_string(),
@@ -165,25 +577,25 @@ impl Router {
                     }
                     Err((code, reason)) => {
                         Router::log_request(&uri.path().to_string(), method, &code);
-                        Ok(default_error_page(
-                            &code,
+                        Ok(self.errors.render(&ErrorContext::new(
+                            code,
                             &reason,
                             method,
                             uri,
-                            std::str::from_utf8(body).unwrap_or("").to_string(),
-                        ))
+                            std::str::from_utf8(body).unwrap_or(""),
+                        )))
                     }
                 }
             }
             None => {
                 Router::log_request(&uri.path().to_string(), method, &code);
-                Ok(default_error_page(
-                    &code,
+                Ok(self.errors.render(&ErrorContext::new(
+                    code,
                     &reason,
                     method,
                     uri,
-                    std::str::from_utf8(body).unwrap_or("").to_string(),
-                ))
+                    std::str::from_utf8(body).unwrap_or(""),
+                )))
             }
         }
     }
@@ -206,93 +618,751 @@ impl Router {
         );
     }
 
+    /// Respond to a request from a connection
     pub async fn parse(
         &self,
         request: hyper::Request<hyper::body::Incoming>,
+    ) -> Result<hyper::Response<UnsyncBoxBody<Bytes, Infallible>>, Infallible> {
+        let response = self.scoped(self.read(request)).await?;
+        let mut response = self.map_response_with(response);
+        if let Some(server) = &self.server {
+            response
+                .headers_mut()
+                .entry(SERVER)
+                .or_insert_with(|| server.clone());
+        }
+        if let Some(date) = &self.date {
+            if !response.headers().contains_key(DATE) {
+                if let Ok(value) = HeaderValue::from_str(&date()) {
+                    response.headers_mut().insert(DATE, value);
+                }
+            }
+        }
+        for (name, value) in self.default_headers.iter() {
+            response
+                .headers_mut()
+                .entry(name)
+                .or_insert_with(|| value.clone());
+        }
+        Ok(stream::into_body(response))
+    }
+
+    async fn read(
+        &self,
+        request: hyper::Request<hyper::body::Incoming>,
     ) -> Result<hyper::Response<Full<Bytes>>, Infallible> {
         // Get all needed information from request
-        let mut uri = request.uri().clone();
+        let uri = request.uri().clone();
         let method = request.method().clone();
         // Can be used for validation, authentication, and other features
-        let _headers = request.headers().clone();
-        let mut body = request.collect().await.unwrap().to_bytes().to_vec();
-
-        let (endpoint_tx, endpoint_rx) = oneshot::channel();
-        match &self.channel {
-            Some(channel) => {
-                let path = format!("{}{}", self.assets, uri.path());
-                let path = Path::new(&path);
-                if let Some(extension) = path.extension().and_then(OsStr::to_str) {
-                    match fs::read_to_string(path) {
-                        Ok(text) => {
+        let headers = request.headers().clone();
+        let remote = request.extensions().get::<SocketAddr>().copied();
+
+        if WebSocketRoute::is_upgrade(&headers) {
+            if let Some(socket) = self.websockets.iter().find(|s| s.matches(uri.path())) {
+                // The layers see the upgrade first so ones like `RequireAuth` protect
+                // websockets too
+                let checked = self
+                    .through_layers(
+                        Next::upgrade(self, &self.layers),
+                        Request {
+                            method: method.clone(),
+                            uri: uri.clone(),
+                            headers: headers.clone(),
+                            body: Bytes::new(),
+                            trailers: HeaderMap::new(),
+                            stream: BodyStream::default(),
+                            remote,
+                        },
+                    )
+                    .await?;
+                if checked.extensions().get::<Upgrade>().is_none() {
+                    return Ok(checked);
+                }
+
+                let scope = self.table.as_ref().map(|table| table.scope.clone());
+                return Ok(match socket.upgrade(request, scope) {
+                    Ok(mut response) => {
+                        for (name, value) in checked.headers().iter() {
+                            response
+                                .headers_mut()
+                                .entry(name)
+                                .or_insert_with(|| value.clone());
+                        }
+                        Router::log_request(&uri.path().to_string(), &method, &101);
+                        response
+                    }
+                    Err((code, reason)) => {
+                        Router::log_request(&uri.path().to_string(), &method, &code);
+                        let mut response = self
+                            .errors
+                            .render(&ErrorContext::new(code, &reason, &method, &uri, ""));
+                        if code == 426 {
+                            response
+                                .headers_mut()
+                                .insert("Sec-WebSocket-Version", "13".parse().unwrap());
+                        }
+                        response
+                    }
+                });
+            }
+        }
+
+        // Requests are mapped before their body is read so a rewritten path decides whether
+        // the endpoint streams it
+        let (parts, incoming) = request.into_parts();
+        let mut request = self.map_request_with(Request {
+            method: parts.method,
+            uri: parts.uri,
+            headers: parts.headers,
+            body: Bytes::new(),
+            trailers: HeaderMap::new(),
+            stream: BodyStream::default(),
+            remote,
+        });
+
+        let streams = self
+            .table
+            .as_ref()
+            .and_then(|table| table.endpoint(&request.method, request.uri.path()))
+            .map(|Route(endpoint)| endpoint.streams())
+            .unwrap_or(false);
+        if streams {
+            request.stream = BodyStream::new(incoming);
+            return self
+                .through_layers(Next::new(self, &self.layers), request)
+                .await;
+        }
+
+        match self
+            .body
+            .read(&request.method, &request.uri, &request.headers, incoming)
+            .await
+        {
+            Ok((body, trailers)) => {
+                request.body = body;
+                request.trailers = trailers;
+            }
+            Err((code, reason)) => {
+                Router::log_request(&request.uri.path().to_string(), &request.method, &code);
+                return Ok(self.errors.render(&ErrorContext::new(
+                    code,
+                    &header_safe(&reason),
+                    &request.method,
+                    &request.uri,
+                    "",
+                )));
+            }
+        };
+
+        self.through_layers(Next::new(self, &self.layers), request)
+            .await
+    }
+
+    /// Respond to a request that has already been read by running it through the layers.
+    /// The router must be served with `serve_routes` first.
+    pub(crate) async fn handle(
+        &self,
+        request: Request,
+    ) -> Result<hyper::Response<Full<Bytes>>, Infallible> {
+        self.scoped(self.through_layers(Next::new(self, &self.layers), request))
+            .await
+    }
+
+    /// Run a future with the router's settings; see `Scope`
+    async fn scoped<F: Future>(&self, future: F) -> F::Output {
+        match &self.table {
+            Some(table) => table.scope.run(future).await,
+            None => future.await,
+        }
+    }
+
+    async fn through_layers(
+        &self,
+        next: Next<'_>,
+        request: Request,
+    ) -> Result<hyper::Response<Full<Bytes>>, Infallible> {
+        let (method, uri) = (request.method.clone(), request.uri.clone());
+        match AssertUnwindSafe(next.run(request)).catch_unwind().await {
+            Ok(response) => Ok(response),
+            // A layer panicked so the request can't go through the catches
+            Err(payload) => {
+                let reason = header_safe(&panic_reason(payload));
+                Router::log_request(&uri.path().to_string(), &method, &500);
+                Ok(self
+                    .errors
+                    .render(&ErrorContext::new(500, &reason, &method, &uri, "")))
+            }
+        }
+    }
+
+    /// Respond to a request with the matching asset, endpoint, or error handler
+    pub(crate) async fn dispatch(&self, request: Request) -> hyper::Response<Full<Bytes>> {
+        self.respond(
+            request.uri,
+            request.method,
+            request.headers,
+            request.body,
+            request.trailers,
+            request.stream,
+        )
+        .await
+        .unwrap_or_else(|never| match never {})
+    }
+
+    async fn respond(
+        &self,
+        mut uri: Uri,
+        method: Method,
+        headers: HeaderMap,
+        body: Bytes,
+        trailers: HeaderMap,
+        stream: BodyStream,
+    ) -> Result<hyper::Response<Full<Bytes>>, Infallible> {
+        match &self.table {
+            Some(table) => {
+                if let Some(response) = self.acme_challenge(uri.path()) {
+                    Router::log_request(&uri.path().to_string(), &method, &200);
+                    return Ok(response);
+                }
+
+                let response = self
+                    .favicon
+                    .as_ref()
+                    .and_then(|icon| icon.respond(uri.path(), &headers))
+                    .or_else(|| {
+                        self.well_known
+                            .as_ref()
+                            .and_then(|well_known| well_known.respond(uri.path()))
+                    })
+                    .or_else(|| {
+                        self.embedded
+                            .iter()
+                            .find_map(|embedded| embedded.respond(uri.path(), &headers))
+                    });
+                if let Some(response) = response {
+                    Router::log_request(
+                        &uri.path().to_string(),
+                        &method,
+                        &response.status().into(),
+                    );
+                    return Ok(response);
+                }
+
+                // Paths outside of the asset mount are left to the routes
+                let asset = uri
+                    .path()
+                    .strip_prefix(&self.asset_mount)
+                    .filter(|asset| asset.starts_with('/'));
+                let extension = asset
+                    .and_then(|asset| Path::new(asset).extension())
+                    .and_then(OsStr::to_str);
+                if let (Some(asset), Some(extension)) = (asset, extension) {
+                    let fingerprinted = match self.fingerprint {
+                        true => table.scope.manifest.original(asset),
+                        false => None,
+                    };
+                    let asset = fingerprinted.as_deref().unwrap_or(asset);
+                    match self.resolve_asset(asset, extension).map(fs::read) {
+                        Some(Ok(contents)) => {
                             Router::log_request(&uri.path().to_string(), &method, &200);
                             let mut builder = hyper::Response::builder().status(200);
+                            if fingerprinted.is_some() {
+                                builder = builder.header("Cache-Control", assets::IMMUTABLE);
+                            }
 
-                            match mime_guess::from_ext(extension).first() {
-                                Some(mime) => {
-                                    builder = builder.header("Content-Type", mime.to_string())
-                                }
-                                _ => {}
-                            };
+                            if let Some(mime) = mime_guess::from_ext(extension).first() {
+                                builder = builder.header("Content-Type", mime.to_string());
+                            }
 
-                            return Ok(builder.body(Full::new(Bytes::from(text))).unwrap());
+                            return Ok(builder.body(Full::new(Bytes::from(contents))).unwrap());
                         }
                         _ => {
                             Router::log_request(&uri.path().to_string(), &method, &404);
-                            return Ok(default_error_page(
-                                &404,
-                                &"File not found".to_string(),
+                            return Ok(self.errors.render(&ErrorContext::new(
+                                404,
+                                "File not found",
                                 &method,
                                 &uri,
-                                std::str::from_utf8(body.as_slice())
-                                    .unwrap_or("")
-                                    .to_string(),
-                            ));
+                                std::str::from_utf8(&body).unwrap_or(""),
+                            )));
                         }
                     }
                 }
 
-                match channel
-                    .send(Command::Get {
-                        method: method.clone(),
-                        path: uri.path().to_string(),
-                        response: endpoint_tx,
-                    })
-                    .await
-                {
-                    Ok(_) => {}
-                    Err(error) => eprintln!("{}", error),
-                };
-
-                match endpoint_rx.await.unwrap() {
-                    Some(Route(endpoint)) => match endpoint.execute(&method, &mut uri, &mut body) {
-                        Ok(response) => {
-                            Router::log_request(
-                                &uri.path().to_string(),
-                                &method,
-                                &response.status().into(),
-                            );
-                            Ok(response)
-                        }
-                        Err((code, reason)) => {
-                            self.error(&uri, &method, &body, code, reason, channel.clone())
+                match table.endpoint(&method, uri.path()) {
+                    Some(Route(endpoint)) => {
+                        // Reading the stream blocks until the body arrives so the endpoint
+                        // can't run on the connection's task which is what receives the body.
+                        // A current thread runtime only has the connection's thread to drive
+                        // the futures `sync::block_on` waits on, so endpoints can't run on it.
+                        let blocking = endpoint.streams()
+                            || Handle::current().runtime_flavor() == RuntimeFlavor::CurrentThread;
+                        let result = match blocking {
+                            true => {
+                                let (endpoint, scope) = (endpoint.clone(), table.scope.clone());
+                                let (method, mut uri) = (method.clone(), uri.clone());
+                                let (headers, body) = (headers.clone(), body.clone());
+                                let (trailers, stream) = (trailers.clone(), stream.clone());
+                                tokio::task::spawn_blocking(move || {
+                                    catch_panic(|| {
+                                        scope.run_sync(|| {
+                                            endpoint.execute(
+                                                &method, &mut uri, &headers, &body, &trailers,
+                                                &stream,
+                                            )
+                                        })
+                                    })
+                                })
                                 .await
+                                .unwrap_or_else(|error| Err((500, error.to_string())))
+                            }
+                            false => catch_panic(|| {
+                                endpoint
+                                    .execute(&method, &mut uri, &headers, &body, &trailers, &stream)
+                            }),
+                        };
+                        match result {
+                            Ok(response) => {
+                                Router::log_request(
+                                    &uri.path().to_string(),
+                                    &method,
+                                    &response.status().into(),
+                                );
+                                Ok(response)
+                            }
+                            Err((code, reason)) => {
+                                let head = Head {
+                                    method,
+                                    uri,
+                                    headers,
+                                };
+                                self.error(head, &body, code, reason, table).await
+                            }
                         }
-                    },
+                    }
                     None => {
-                        self.error(
-                            &uri,
-                            &method,
-                            &body,
-                            404,
-                            "Page not found in router".to_string(),
-                            channel.clone(),
-                        )
-                        .await
+                        let head = Head {
+                            method,
+                            uri,
+                            headers,
+                        };
+                        let reason = "Page not found in router".to_string();
+                        self.error(head, &body, 404, reason, table).await
                     }
                 }
             }
-            _ => panic!("Unable to communicate with router"),
+            _ => panic!("Routes must be served with `serve_routes` before handling requests"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::{
+        cookie::CookieJar,
+        request::{Body, ContentLength, Form, IfMatch, Json, QueryMap, RequestData, ToParam},
+    };
+
+    type Extract = fn(&mut RequestData) -> crate::response::Result<String>;
+
+    /// Path, headers, and body of a request along with the status it gets
+    type Case<'a> = (&'a str, Vec<(&'a str, &'a [u8])>, &'a [u8], u16);
+
+    /// Endpoint that only reads a parameter from the request
+    #[derive(Debug)]
+    struct Param(&'static str, Extract);
+
+    impl Endpoint for Param {
+        fn methods(&self) -> Vec<Method> {
+            vec![Method::POST]
+        }
+
+        fn path(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn execute(
+            &self,
+            method: &Method,
+            uri: &mut Uri,
+            headers: &HeaderMap,
+            body: &Bytes,
+            trailers: &HeaderMap,
+            stream: &BodyStream,
+        ) -> crate::response::Result<hyper::Response<Full<Bytes>>> {
+            let mut data = RequestData(
+                uri.clone(),
+                method.clone(),
+                body.clone(),
+                headers.clone(),
+                trailers.clone(),
+                stream.clone(),
+            );
+            let text = (self.1)(&mut data)?;
+            Ok(hyper::Response::new(Full::new(Bytes::from(text))))
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct Named {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    fn router() -> Router {
+        let params: [Param; 13] = [
+            Param("/cookies", |data| {
+                let jar: CookieJar = data.to_param()?;
+                Ok(jar.iter().map(|c| c.name().to_string()).collect())
+            }),
+            Param("/body", |data| {
+                let Body(body): Body<String> = data.to_param()?;
+                Ok(body)
+            }),
+            Param("/form", |data| {
+                let _: Form<HashMap<String, String>> = data.to_param()?;
+                Ok(String::new())
+            }),
+            Param("/json", |data| {
+                let _: Json<Named> = data.to_param()?;
+                Ok(String::new())
+            }),
+            Param("/length", |data| {
+                let ContentLength(length) = data.to_param()?;
+                Ok(length.to_string())
+            }),
+            Param("/if-match", |data| {
+                let tags: IfMatch = data.to_param()?;
+                Ok(tags.0.join(","))
+            }),
+            Param("/query", |data| {
+                let query: QueryMap = data.to_param()?;
+                Ok(query.get("q").unwrap_or_default().to_string())
+            }),
+            Param("/block-on", |_| {
+                Ok(crate::sync::block_on(async {
+                    tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+                    "done".to_string()
+                }))
+            }),
+            Param("/json-format", |_| {
+                crate::response::to_json(&serde_json::json!({ "user_id": 1 }))
+                    .map_err(|error| (500, error.to_string()))
+            }),
+            Param("/sign", |_| Ok(crate::cookie::sign("a", "1"))),
+            Param("/verify", |data| {
+                let jar: CookieJar = data.to_param()?;
+                Ok(jar.get_signed("a").unwrap_or_default().to_string())
+            }),
+            Param("/state", |data| {
+                let name: Option<crate::state::State<String>> = data.to_param()?;
+                Ok(name.map(|name| name.to_string()).unwrap_or_default())
+            }),
+            Param("/asset", |_| Ok(crate::assets::url("app.css"))),
+        ];
+
+        let mut router = Router::new();
+        for param in params {
+            router.route(Arc::new(param));
+        }
+        router.serve_routes().unwrap();
+        router
+    }
+
+    fn request(uri: &str, headers: &[(&str, &[u8])], body: &[u8]) -> Request {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(
+                HeaderName::from_bytes(name.as_bytes()).unwrap(),
+                HeaderValue::from_bytes(value).unwrap(),
+            );
+        }
+        Request {
+            method: Method::POST,
+            uri: uri.parse().unwrap(),
+            headers: map,
+            body: Bytes::copy_from_slice(body),
+            trailers: HeaderMap::new(),
+            stream: BodyStream::default(),
+            remote: None,
+        }
+    }
+
+    async fn status(router: &Router, request: Request) -> u16 {
+        router.handle(request).await.unwrap().status().as_u16()
+    }
+
+    #[tokio::test]
+    async fn malformed_requests_are_client_errors() {
+        let router = router();
+        let form = (
+            "Content-Type",
+            b"application/x-www-form-urlencoded" as &[u8],
+        );
+        let json = ("Content-Type", b"application/json" as &[u8]);
+        let cases: Vec<Case> = vec![
+            // Cookies that aren't utf-8 are ignored
+            ("/cookies", vec![("Cookie", b"a=1; b=\xff\xfe")], b"", 200),
+            ("/cookies", vec![("Cookie", b"\x80")], b"", 200),
+            ("/cookies", vec![("Cookie", b";;=;=;")], b"", 200),
+            ("/body", vec![], b"\xff\xfe\xfd", 400),
+            ("/form", vec![form], b"name=\xc3\x28", 400),
+            ("/form", vec![form], b"a[=1&a]]=2", 400),
+            (
+                "/form",
+                vec![(
+                    "Content-Type",
+                    b"application/x-www-form-urlencoded; charset=koi8-r",
+                )],
+                b"a=1",
+                415,
+            ),
+            ("/json", vec![json], b"{\"name\": ", 400),
+            ("/json", vec![json], b"\xff", 400),
+            ("/json", vec![("Content-Type", b"\xfftext")], b"{}", 415),
+            ("/length", vec![("Content-Length", b"twelve")], b"", 400),
+            ("/length", vec![("Content-Length", b"\xff")], b"", 400),
+            ("/length", vec![("Content-Length", b"-1")], b"", 400),
+            ("/length", vec![], b"", 411),
+            ("/if-match", vec![("If-Match", b"\"a\xff\"")], b"", 200),
+            ("/query?q=%ff%fe&%zz", vec![], b"", 200),
+        ];
+
+        for (uri, headers, body, expected) in cases {
+            let request = request(uri, &headers, body);
+            assert_eq!(
+                status(&router, request).await,
+                expected,
+                "{} {:?} {:?}",
+                uri,
+                headers,
+                body
+            );
         }
     }
+
+    /// `#[tokio::test]` runs on a current thread runtime where `block_in_place` panics
+    #[tokio::test]
+    async fn block_on_works_on_a_current_thread_runtime() {
+        let response = router()
+            .handle(request("/block-on", &[], b""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(
+            &http_body_util::BodyExt::collect(response.into_body())
+                .await
+                .unwrap()
+                .to_bytes()[..],
+            b"done"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn block_on_works_on_a_multi_thread_runtime() {
+        let response = router()
+            .handle(request("/block-on", &[], b""))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    }
+
+    /// Random header values and bodies are never a server error
+    #[tokio::test]
+    async fn random_requests_dont_fail() {
+        let router = router();
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+
+        let paths = [
+            "/cookies",
+            "/body",
+            "/form",
+            "/json",
+            "/length",
+            "/if-match",
+            "/query",
+        ];
+        let names = ["Cookie", "Content-Type", "Content-Length", "If-Match"];
+        for _ in 0..2000 {
+            // Header values may have any byte except control characters other than tab
+            let mut value = (0..next() % 24)
+                .map(|_| (next() % 256) as u8)
+                .filter(|&b| b == b'\t' || (b >= 0x20 && b != 0x7f))
+                .collect::<Vec<u8>>();
+            value.extend_from_slice(b"=;,\"");
+            let body = (0..next() % 32)
+                .map(|_| (next() % 256) as u8)
+                .collect::<Vec<u8>>();
+            let path = paths[(next() % paths.len() as u64) as usize];
+            let name = names[(next() % names.len() as u64) as usize];
+
+            let request = request(path, &[(name, &value)], &body);
+            let code = status(&router, request).await;
+            assert!(
+                code < 500,
+                "{} {}: {:?} {:?} -> {}",
+                path,
+                name,
+                value,
+                body,
+                code
+            );
+        }
+    }
+
+    /// Body of the response to a request as text
+    async fn text(router: &Router, uri: &str, headers: &[(&str, &[u8])]) -> String {
+        let response = router.handle(request(uri, headers, b"")).await.unwrap();
+        let body = http_body_util::BodyExt::collect(response.into_body())
+            .await
+            .unwrap()
+            .to_bytes();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    async fn settings_are_per_router() {
+        let mut camel = router();
+        camel.json_format(
+            JsonFormat::new()
+                .pretty(true)
+                .keys(crate::response::KeyCase::Camel),
+        );
+        camel.manage("camel".to_string());
+        let mut plain = router();
+        plain.manage("plain".to_string());
+        let assets = std::env::temp_dir().join(format!(
+            "tela-router-assets-{}-{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        for (router, name) in [(&mut camel, "camel"), (&mut plain, "plain")] {
+            let dir = assets.join(name);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("app.css"), name).unwrap();
+            router.assets(dir.to_string_lossy().to_string());
+            router.fingerprint_assets();
+            router.serve_routes().unwrap();
+        }
+
+        assert_eq!(
+            text(&camel, "/json-format", &[]).await,
+            "{\n  \"userId\": 1\n}"
+        );
+        assert_eq!(text(&plain, "/json-format", &[]).await, "{\"user_id\":1}");
+        assert_eq!(text(&camel, "/state", &[]).await, "camel");
+        assert_eq!(text(&plain, "/state", &[]).await, "plain");
+        assert_eq!(text(&router(), "/state", &[]).await, "");
+
+        // Each router serves the fingerprints of its own assets
+        let (camel_css, plain_css) = (
+            text(&camel, "/asset", &[]).await,
+            text(&plain, "/asset", &[]).await,
+        );
+        assert!(camel_css.starts_with("/app.") && camel_css.ends_with(".css"));
+        assert!(plain_css.starts_with("/app.") && plain_css.ends_with(".css"));
+        assert_ne!(camel_css, plain_css);
+        assert_eq!(text(&router(), "/asset", &[]).await, "/app.css");
+
+        // Cookies signed by one router are only valid for routers with the same secret
+        let cookie = format!("a={}", text(&camel, "/sign", &[]).await);
+        let headers: &[(&str, &[u8])] = &[("Cookie", cookie.as_bytes())];
+        assert_eq!(text(&camel, "/verify", headers).await, "1");
+        assert_eq!(text(&plain, "/verify", headers).await, "");
+        for router in [&mut camel, &mut plain] {
+            router.secret("shared");
+            router.serve_routes().unwrap();
+        }
+        let cookie = format!("a={}", text(&camel, "/sign", &[]).await);
+        let headers: &[(&str, &[u8])] = &[("Cookie", cookie.as_bytes())];
+        assert_eq!(text(&plain, "/verify", headers).await, "1");
+
+        // Outside of a request the defaults are used
+        assert_eq!(
+            crate::response::to_json(&serde_json::json!({ "user_id": 1 })).unwrap(),
+            "{\"user_id\":1}"
+        );
+        assert!(crate::state::get::<String>().is_none());
+        assert_eq!(crate::assets::url("app.css"), "/app.css");
+        fs::remove_dir_all(assets).ok();
+    }
+
+    /// Endpoints run on a blocking thread
+    #[tokio::test]
+    async fn settings_are_per_router_on_a_current_thread_runtime() {
+        settings_are_per_router().await;
+    }
+
+    /// Endpoints run on the connection's thread
+    #[tokio::test(flavor = "multi_thread")]
+    async fn settings_are_per_router_on_a_multi_thread_runtime() {
+        settings_are_per_router().await;
+    }
+
+    #[tokio::test]
+    async fn assets_are_served_under_their_mount() {
+        let dir = std::env::temp_dir().join(format!("tela-router-mount-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("app.css"), "body {}").unwrap();
+
+        let mut router = router();
+        router.assets_at("/static/", dir.to_string_lossy().to_string());
+        router.fingerprint_assets();
+        router.serve_routes().unwrap();
+
+        let url = text(&router, "/asset", &[]).await;
+        assert!(
+            url.starts_with("/static/app.") && url.ends_with(".css"),
+            "{}",
+            url
+        );
+        assert_eq!(text(&router, &url, &[]).await, "body {}");
+        assert_eq!(text(&router, "/static/app.css", &[]).await, "body {}");
+        assert_eq!(status(&router, request("/app.css", &[], b"")).await, 404);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn invalid_routes_fail_to_serve() {
+        let mut router = Router::new();
+        router.route(Arc::new(Param("/files/:...path/:...rest", |_| {
+            Ok(String::new())
+        })));
+        let error = router.serve_routes().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(error
+            .to_string()
+            .starts_with("Route POST /files/:...path/:...rest is invalid"));
+        assert!(!router.is_served());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_assets_fail_to_serve() {
+        let dir =
+            std::env::temp_dir().join(format!("tela-router-unreadable-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        std::os::unix::fs::symlink(dir.join("missing.css"), dir.join("app.css")).unwrap();
+
+        let mut router = Router::new();
+        router.assets(dir.to_string_lossy().to_string());
+        router.fingerprint_assets();
+        let error = router.serve_routes().unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Failed to fingerprint the assets"));
+        assert!(!router.is_served());
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
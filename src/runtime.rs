@@ -0,0 +1,76 @@
+use std::{future::Future, io::Error as IoError};
+
+use tokio::runtime::Builder;
+
+/// Changes to the runtime's builder; see `runtime_entry`
+pub type Configure = Box<dyn FnOnce(&mut Builder)>;
+
+/// Kind of tokio runtime `#[tela::main]` starts
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    /// Runtime with a pool of worker threads
+    #[default]
+    MultiThread,
+    /// Runtime that runs everything on the current thread. Handlers run on tokio's blocking
+    /// threads so `sync::block_on` has the runtime's thread to drive its future.
+    CurrentThread,
+}
+
+/// Runtime settings from `#[tela::main(flavor = "...", workers = 4, thread_name = "...")]`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RuntimeConfig {
+    pub flavor: Flavor,
+    /// Number of worker threads; tokio uses one per core by default. Only used by the
+    /// multi thread flavor.
+    pub workers: Option<usize>,
+    /// Name of the runtime's threads
+    pub thread_name: Option<String>,
+}
+
+/// Start a tokio runtime with the settings and run the future on it to completion. This is
+/// what `#[tela::main]` expands to.
+///
+/// `configure` is called with the runtime's builder after the settings are applied so it can
+/// change anything else, or override them. Errors from building the runtime are returned;
+/// ex: the system can't start more threads.
+///
+/// # Example
+/// ```no_run
+/// use tela::{runtime_entry, Flavor, RuntimeConfig, Server};
+/// use tokio::runtime::Builder;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// let config = RuntimeConfig {
+///     flavor: Flavor::CurrentThread,
+///     ..Default::default()
+/// };
+/// let configure = |builder: &mut Builder| {
+///     builder.event_interval(31);
+/// };
+/// runtime_entry(config, Some(Box::new(configure)), async {
+///     Server::new().serve(3000).await
+/// })??;
+/// # Ok(())
+/// # }
+/// ```
+pub fn runtime_entry<F: Future>(
+    config: RuntimeConfig,
+    configure: Option<Configure>,
+    future: F,
+) -> Result<F::Output, IoError> {
+    let mut builder = match config.flavor {
+        Flavor::MultiThread => Builder::new_multi_thread(),
+        Flavor::CurrentThread => Builder::new_current_thread(),
+    };
+    builder.enable_all();
+    if let Some(workers) = config.workers {
+        builder.worker_threads(workers);
+    }
+    if let Some(name) = config.thread_name {
+        builder.thread_name(name);
+    }
+    if let Some(configure) = configure {
+        configure(&mut builder);
+    }
+    Ok(builder.build()?.block_on(future))
+}
@@ -1,15 +1,96 @@
 use crate::response::template::TemplateEngine;
-use std::{error::Error, net::SocketAddr, sync::Arc};
+use std::{
+    convert::Infallible,
+    error::Error,
+    future::Future,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    sync::Arc,
+};
 
-use hyper::{server::conn::http1, service::service_fn};
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{
+    header::{HeaderName, HeaderValue, HOST, LOCATION},
+    server::conn::http1,
+    service::service_fn,
+};
 use tokio::net::TcpListener;
+#[cfg(feature = "tls")]
+use tokio_rustls::{
+    rustls::{server::ResolvesServerCert, ServerConfig},
+    TlsAcceptor,
+};
 
 use crate::{
+    assets::Favicon,
+    errors::ErrorRenderer,
+    layer::{Layer, Request, Response},
     prelude::{Catch, Endpoint},
+    request::BodyProgress,
+    shutdown::Shutdown,
     support::TokioIo,
+    websocket::{WebSocket, WebSocketConfig},
     Router,
 };
 
+/// Accepts TLS sessions for `Server::serve_tls`; never created without the `tls` feature
+#[cfg(feature = "tls")]
+type Acceptor = TlsAcceptor;
+#[cfg(not(feature = "tls"))]
+type Acceptor = std::convert::Infallible;
+
+/// Longest time a client has to finish the TLS handshake
+#[cfg(feature = "tls")]
+const HANDSHAKE: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Whether the server is bound to localhost, the network, or a specific ip.
+///
+/// Port `0` binds to any free port; use `Server::on_bind` to get the port that was picked.
+///
+/// # Example
+/// ```no_run
+/// use tela::{Server, Socket};
+///
+/// #[tela::main]
+/// async fn main() {
+///     Server::new()
+///         .serve(Socket::Network(3000).env())
+///         .await
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Socket {
+    Local(u16),
+    Network(u16),
+    Addr(IpAddr, u16),
+}
+
+impl Socket {
+    /// Use the port from the `PORT` environment variable if it is set, keeping the address;
+    /// ex: when a platform decides the port the app listens on
+    pub fn env(self) -> Self {
+        let port = match std::env::var("PORT")
+            .ok()
+            .and_then(|port| port.parse().ok())
+        {
+            Some(port) => port,
+            None => return self,
+        };
+        match self {
+            Socket::Local(_) => Socket::Local(port),
+            Socket::Network(_) => Socket::Network(port),
+            Socket::Addr(ip, _) => Socket::Addr(ip, port),
+        }
+    }
+}
+
+impl Default for Socket {
+    fn default() -> Self {
+        Socket::Local(3000)
+    }
+}
+
 pub trait IntoSocketAddr {
     fn into_socket_addr(self) -> SocketAddr;
 }
@@ -26,6 +107,31 @@ impl IntoSocketAddr for ([u8; 4], u16) {
     }
 }
 
+impl IntoSocketAddr for (IpAddr, u16) {
+    fn into_socket_addr(self) -> SocketAddr {
+        SocketAddr::from(self)
+    }
+}
+
+impl IntoSocketAddr for SocketAddr {
+    fn into_socket_addr(self) -> SocketAddr {
+        self
+    }
+}
+
+impl IntoSocketAddr for Socket {
+    fn into_socket_addr(self) -> SocketAddr {
+        match self {
+            Socket::Local(port) => SocketAddr::from(([127, 0, 0, 1], port)),
+            Socket::Network(port) => SocketAddr::from(([0, 0, 0, 0], port)),
+            Socket::Addr(ip, port) => SocketAddr::from((ip, port)),
+        }
+    }
+}
+
+/// Called with the address the server is bound to; see `Server::on_bind`
+type OnBind = Box<dyn FnOnce(SocketAddr) + Send>;
+
 /// Contains a router and handles setting up:
 /// * routes
 /// * error handlers
@@ -35,12 +141,12 @@ impl IntoSocketAddr for ([u8; 4], u16) {
 /// Serves requests from the given port based on uri path and request method.
 ///
 /// # Example
-/// ```
-/// use tela::{prelude::*, Server};
+/// ```no_run
+/// use tela::{prelude::*, response::HTML, Server};
 ///
 /// #[get("/")]
 /// fn home() -> HTML<String> {
-///     html!(<h1>"Hello, world"!</h1>)
+///     html!(<h1>"Hello, world!"</h1>)
 /// }
 ///
 /// #[tela::main]
@@ -53,6 +159,9 @@ impl IntoSocketAddr for ([u8; 4], u16) {
 /// ```
 pub struct Server {
     router: Router,
+    date_header: bool,
+    redirect: Option<u16>,
+    on_bind: Option<OnBind>,
 }
 
 #[cfg(feature = "handlebars")]
@@ -91,6 +200,9 @@ impl Server {
     pub fn new() -> Self {
         Server {
             router: Router::new(),
+            date_header: true,
+            redirect: None,
+            on_bind: None,
         }
     }
 
@@ -100,18 +212,137 @@ impl Server {
         self
     }
 
+    /// Set where static files should be served from and the url path they are served under;
+    /// ex: `/static/app.css` from `web/app.css`
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tela::Server;
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .assets_at("/static", "web")
+    ///         .serve(3000)
+    ///         .await
+    /// }
+    /// ```
+    pub fn assets_at<T: Into<String>>(mut self, mount: &str, path: T) -> Self {
+        self.router.assets_at(mount, path.into());
+        self
+    }
+
+    /// Only serve static files with the given extensions; ex: `&["css", "js", "png"]`
+    ///
+    /// By default any file inside of the asset path is served.
+    pub fn asset_extensions(mut self, extensions: &[&str]) -> Self {
+        self.router
+            .asset_extensions(extensions.iter().map(|e| e.to_string()).collect());
+        self
+    }
+
+    /// Serve each asset under a fingerprinted name, ex: `app.1f2e3d4c.css`, that is cached
+    /// forever. Use `asset!("app.css")` to get the fingerprinted url of an asset.
+    ///
+    /// The assets are hashed when the server starts so changes made while it is running
+    /// aren't picked up.
+    pub fn fingerprint_assets(mut self) -> Self {
+        self.router.fingerprint_assets();
+        self
+    }
+
+    /// Serve files compiled into the binary under the mount path so the binary can be deployed
+    /// on its own. Each file has an ETag made from a hash of its contents.
+    ///
+    /// Requires the `embed` feature and a dependency on `include_dir`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use include_dir::{include_dir, Dir};
+    /// use tela::Server;
+    ///
+    /// static ASSETS: Dir = include_dir!("$CARGO_MANIFEST_DIR/assets");
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .embedded_assets("/static", &ASSETS)
+    ///         .serve(3000)
+    ///         .await
+    /// }
+    /// ```
+    #[cfg(feature = "embed")]
+    pub fn embedded_assets(mut self, mount: &str, dir: &'static include_dir::Dir<'static>) -> Self {
+        self.router.embedded_assets(mount, dir);
+        self
+    }
+
+    /// Serve an icon at `/favicon.ico`; its bytes, ex: `include_bytes!("favicon.ico")`, or
+    /// the path to a file. Png, svg, and gif icons are sent with their own content type.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tela::Server;
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .favicon("web/favicon.ico")
+    ///         .serve(3000)
+    ///         .await
+    /// }
+    /// ```
+    pub fn favicon<F: Into<Favicon>>(mut self, favicon: F) -> Self {
+        self.router.favicon(favicon.into());
+        self
+    }
+
+    /// Serve the files in the directory under `/.well-known/`; ex: acme challenges written by
+    /// certbot and `security.txt`. Acme challenges are never cached and `security.txt` is
+    /// sent as utf-8 text.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tela::Server;
+    ///
+    /// // certbot certonly --webroot -w /var/www
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .well_known("/var/www/.well-known")
+    ///         .serve(80)
+    ///         .await
+    /// }
+    /// ```
+    pub fn well_known<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.router.well_known(dir.into());
+        self
+    }
+
+    /// Answer the acme http-01 challenges for the certificate and start getting it when the
+    /// server is served. Add it to the server listening on port 80; see `acme::Acme`.
+    #[cfg(feature = "acme")]
+    pub fn acme(mut self, acme: crate::acme::Acme) -> Self {
+        self.router.acme(acme);
+        self
+    }
+
     /// Add a route to the router
     ///
     /// Must have `impl Endpoint`.
     /// Wrap a method with a request macro; ex: `#[get('/')]`.
     ///
     /// # Example
-    /// ```
-    /// use tela::prelude::*;
+    /// ```no_run
+    /// use tela::{prelude::*, Server};
+    ///
     /// #[get("/")]
-    /// fn home() -> String { ... }
+    /// fn home() -> String {
+    ///     "Home".to_string()
+    /// }
     ///
-    /// async main() {
+    /// #[tela::main]
+    /// async fn main() {
     ///     Server::new()
     ///         .route(home)
     ///         .serve(3000)
@@ -130,14 +361,21 @@ impl Server {
     /// with `Arc::new()`
     ///
     /// # Example
-    /// ```
-    /// use tela::prelude::*;
+    /// ```no_run
+    /// use tela::{prelude::*, Server};
+    ///
     /// #[get("/")]
-    /// fn home() -> String { ... }
+    /// fn home() -> String {
+    ///     "Home".to_string()
+    /// }
+    ///
     /// #[get("/blog")]
-    /// fn blog() -> String { ... }
+    /// fn blog() -> String {
+    ///     "Blog".to_string()
+    /// }
     ///
-    /// async main() {
+    /// #[tela::main]
+    /// async fn main() {
     ///     Server::new()
     ///         .routes(group![home, blog])
     ///         .serve(3000)
@@ -151,20 +389,382 @@ impl Server {
         self
     }
 
+    /// Export a span for every request to an OpenTelemetry collector; see `Otel`.
+    ///
+    /// It is added as a layer, so add it first to have the span cover every other layer.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tela::{otel::Otel, prelude::*, Server};
+    ///
+    /// #[get("/")]
+    /// fn home() -> &'static str {
+    ///     "Home"
+    /// }
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .otel(Otel::new().service_name("blog").resource("service.version", "1.2.0"))
+    ///         .route(home)
+    ///         .serve(3000)
+    ///         .await
+    /// }
+    /// ```
+    #[cfg(feature = "otel")]
+    pub fn otel(self, otel: crate::otel::Otel) -> Self {
+        self.layer(otel)
+    }
+
+    /// Add a layer that runs around every request
+    ///
+    /// Layers run in the order they are added with the first being the outer most.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tela::{layer::DebugPanel, Server};
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .layer(DebugPanel::new(50))
+    ///         .serve(3000)
+    ///         .await
+    /// }
+    /// ```
+    pub fn layer<T: Layer + 'static>(mut self, layer: T) -> Self {
+        self.router.layer(Arc::new(layer));
+        self
+    }
+
+    /// Change every request before it is routed and goes through the layers; ex: rewriting
+    /// paths or normalizing headers. The body hasn't been read yet so it is empty.
+    ///
+    /// Maps run in the order they are added. Websocket upgrades aren't mapped.
+    ///
+    /// # Example
+    /// ```
+    /// use tela::Server;
+    ///
+    /// Server::new().map_request(|mut request| {
+    ///     if let Some(path) = request.uri.path().strip_prefix("/v1") {
+    ///         request.uri = path.parse().unwrap_or(request.uri);
+    ///     }
+    ///     request
+    /// });
+    /// ```
+    pub fn map_request<F>(mut self, map: F) -> Self
+    where
+        F: Fn(Request) -> Request + Send + Sync + 'static,
+    {
+        self.router.map_request(Arc::new(map));
+        self
+    }
+
+    /// Change every response before it is sent, after the layers and catches; ex: adding
+    /// security headers in one place.
+    ///
+    /// Maps run in the order they are added.
+    ///
+    /// # Example
+    /// ```
+    /// use tela::Server;
+    ///
+    /// Server::new().map_response(|mut response| {
+    ///     response
+    ///         .headers_mut()
+    ///         .insert("X-Content-Type-Options", "nosniff".parse().unwrap());
+    ///     response
+    /// });
+    /// ```
+    pub fn map_response<F>(mut self, map: F) -> Self
+    where
+        F: Fn(Response) -> Response + Send + Sync + 'static,
+    {
+        self.router.map_response(Arc::new(map));
+        self
+    }
+
+    /// Upgrade requests to the path to websockets and pass each connection to the handler.
+    /// Panics if the path isn't a valid route pattern.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use futures::StreamExt;
+    /// use tela::{websocket::WebSocket, Server};
+    ///
+    /// async fn echo(mut socket: WebSocket) {
+    ///     while let Some(Ok(message)) = socket.next().await {
+    ///         if message.is_text() && socket.send(message).await.is_err() {
+    ///             break;
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .websocket("/echo", echo)
+    ///         .serve(3000)
+    ///         .await
+    /// }
+    /// ```
+    pub fn websocket<F, Fut>(self, path: &str, handler: F) -> Self
+    where
+        F: Fn(WebSocket) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.websocket_with(path, WebSocketConfig::default(), handler)
+    }
+
+    /// Same as `websocket` with limits on the connections; see `WebSocketConfig`
+    pub fn websocket_with<F, Fut>(mut self, path: &str, config: WebSocketConfig, handler: F) -> Self
+    where
+        F: Fn(WebSocket) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.router.websocket(path, config, handler);
+        self
+    }
+
+    /// `Server` header sent with every response that doesn't set its own; ex: `my-app/1.0`.
+    /// No `Server` header is sent by default. Values that aren't valid in a header are ignored.
+    pub fn server_header<T: AsRef<str>>(mut self, value: T) -> Self {
+        if let Ok(value) = HeaderValue::from_str(value.as_ref()) {
+            self.router.server_header(Some(value));
+        }
+        self
+    }
+
+    /// Header sent with every response that doesn't set it; ex: `Cache-Control: no-store`
+    /// for an api. Set defaults for some routes with `DefaultHeader`. Names or values that
+    /// aren't valid in a header are ignored.
+    ///
+    /// # Example
+    /// ```
+    /// use tela::Server;
+    ///
+    /// Server::new()
+    ///     .default_header("X-Content-Type-Options", "nosniff")
+    ///     .default_header("Cache-Control", "no-store");
+    /// ```
+    pub fn default_header<N: AsRef<str>, V: AsRef<str>>(mut self, name: N, value: V) -> Self {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_ref().as_bytes()),
+            HeaderValue::from_str(value.as_ref()),
+        ) {
+            self.router.default_header(name, value);
+        }
+        self
+    }
+
+    /// Largest request body, in bytes, that is read for a handler. Requests with a larger
+    /// `Content-Length` are rejected before their body is read, and bodies that grow past the
+    /// limit while they are read, ex: chunked ones, stop being read. Both get a
+    /// `413 Payload Too Large`. Bodies aren't limited by default.
+    ///
+    /// Routes that take a `BodyStream` read the body themselves so they aren't limited; check
+    /// their `ContentLength` instead.
+    pub fn body_limit(mut self, bytes: usize) -> Self {
+        self.router.body_limit(Some(bytes));
+        self
+    }
+
+    /// Longest time a client has to send a request body before it gets a
+    /// `408 Request Timeout`; ex: so slow clients can't hold a connection open
+    pub fn body_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.router.body_timeout(Some(timeout));
+        self
+    }
+
+    /// Call the hook as each request body is read and once it has been read; ex: to record
+    /// body sizes or stop uploads early. Returning an error stops reading the body and
+    /// responds with the error.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use tela::Server;
+    ///
+    /// static BODY_BYTES: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// Server::new()
+    ///     .body_limit(10 * 1024 * 1024)
+    ///     .body_progress(|progress| {
+    ///         if progress.done {
+    ///             BODY_BYTES.fetch_add(progress.received, Ordering::Relaxed);
+    ///         }
+    ///         Ok(())
+    ///     });
+    /// ```
+    pub fn body_progress<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&BodyProgress) -> crate::response::Result<()> + Send + Sync + 'static,
+    {
+        self.router.body_progress(Arc::new(hook));
+        self
+    }
+
+    /// Whether the `Date` header is sent with every response, on by default
+    pub fn date_header(mut self, enabled: bool) -> Self {
+        self.date_header = enabled;
+        self
+    }
+
+    /// `Date` header sent with every response that doesn't set its own, instead of the current
+    /// time; ex: a fixed date so responses can be compared in tests. Values that aren't valid
+    /// in a header are ignored. It is sent even when `date_header` is off.
+    ///
+    /// # Example
+    /// ```
+    /// use tela::Server;
+    ///
+    /// Server::new().date_header_with(|| "Thu, 01 Jan 2026 00:00:00 GMT".to_string());
+    /// ```
+    pub fn date_header_with<F>(mut self, date: F) -> Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        self.router.date_header(Some(Arc::new(date)));
+        self
+    }
+
+    /// Also listen for plain http on the port, ex: `80`, and permanently redirect (301) every
+    /// request to the same url on the https port the server is served at. Pending acme
+    /// challenges are still answered over http. Use it with `serve_tls`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::sync::Arc;
+    /// use tela::{prelude::*, Server};
+    /// # #[cfg(feature = "acme")]
+    /// use tela::acme::Acme;
+    ///
+    /// #[get("/")]
+    /// fn home() -> &'static str {
+    ///     "Home"
+    /// }
+    ///
+    /// # #[cfg(feature = "acme")]
+    /// #[tela::main]
+    /// async fn main() {
+    ///     let acme = Acme::new(["example.com"]).contact("mailto:admin@example.com");
+    ///     Server::new()
+    ///         .acme(acme.clone())
+    ///         .route(home)
+    ///         .redirect_http_to_https(80)
+    ///         .serve_tls(([0, 0, 0, 0], 443), Arc::new(acme))
+    ///         .await
+    /// }
+    /// # #[cfg(not(feature = "acme"))]
+    /// # fn main() {}
+    /// ```
+    pub fn redirect_http_to_https(mut self, port: u16) -> Self {
+        self.redirect = Some(port);
+        self
+    }
+
+    /// Call `on_bind` with the address the server is bound to before it accepts connections.
+    /// When serving on port `0` the address has the port the system picked; ex: for tests.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use tela::Server;
+    /// # async fn example() {
+    /// let (bound, port) = tokio::sync::oneshot::channel();
+    /// tokio::spawn(async move {
+    ///     Server::new()
+    ///         .on_bind(move |addr| bound.send(addr.port()).unwrap())
+    ///         .serve(0)
+    ///         .await
+    /// });
+    /// let port = port.await.unwrap();
+    /// # }
+    /// ```
+    pub fn on_bind<F: FnOnce(SocketAddr) + Send + 'static>(mut self, on_bind: F) -> Self {
+        self.on_bind = Some(Box::new(on_bind));
+        self
+    }
+
+    /// Share a value with the server's handlers. Handlers get it with a `State<T>` parameter.
+    /// Each server has its own state, even in the same process.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tela::{sync::Channel, Server};
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .manage(Channel::<String>::new(100))
+    ///         .serve(3000)
+    ///         .await
+    /// }
+    /// ```
+    pub fn manage<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.router.manage(value);
+        self
+    }
+
+    /// Key used to sign cookies and urls; ex: flash messages. Without a secret a random key
+    /// is used so signed cookies stop being valid when the server restarts. Each server has
+    /// its own key, even in the same process.
+    pub fn secret<T: AsRef<[u8]>>(mut self, key: T) -> Self {
+        self.router.secret(key);
+        self
+    }
+
+    /// How `JSON` responses are serialized; ex: pretty printed or with camelCase keys.
+    /// See `response::JsonFormat`.
+    pub fn json_format(mut self, format: crate::response::JsonFormat) -> Self {
+        self.router.json_format(format);
+        self
+    }
+
+    /// Set how errors are rendered when there isn't a catch for them
+    ///
+    /// The default renders html. Use `JsonErrorRenderer` for api servers. Request bodies and
+    /// backtraces are never rendered in release builds.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tela::{JsonErrorRenderer, Server};
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .errors(JsonErrorRenderer)
+    ///         .serve(3000)
+    ///         .await
+    /// }
+    /// ```
+    pub fn errors<T: ErrorRenderer + 'static>(mut self, renderer: T) -> Self {
+        self.router.errors(Arc::new(renderer));
+        self
+    }
+
     /// Add a error handler to the router
     ///
     /// Must have `impl Catch`.
-    /// Wrap a method with a catch macro; ex: `#[catch(404)]`.
+    /// Wrap a method with a catch macro; ex: `#[catch(404)]`. A catch can handle more than
+    /// one code, `#[catch(500, 503)]`, or every code without its own catch, `#[catch(all)]`.
+    ///
+    /// Catches can be async and take any of `StatusCode`, `Error`, and `Head` as arguments.
+    /// If two catches handle the same code the first one added is used.
     ///
     /// # Example
-    /// ```
-    /// use tela::prelude::*;
-    /// #[catch(404)]
-    /// fn not_found(...) -> String { ... }
+    /// ```no_run
+    /// use tela::{prelude::*, request::Head, Server, StatusCode};
     ///
-    /// async main() {
+    /// #[catch(404, 410)]
+    /// async fn not_found(status: StatusCode, head: Head) -> String {
+    ///     format!("{} {}", status as u16, head.uri.path())
+    /// }
+    ///
+    /// #[tela::main]
+    /// async fn main() {
     ///     Server::new()
-    ///         .catch(not_found]
+    ///         .catch(not_found)
     ///         .serve(3000)
     ///         .await
     /// }
@@ -181,14 +781,21 @@ impl Server {
     /// with `Arc::new()`
     ///
     /// # Example
-    /// ```
-    /// use tela::prelude::*;
+    /// ```no_run
+    /// use tela::{prelude::*, Error, Server};
+    ///
     /// #[catch(404)]
-    /// fn not_found(...) -> String { ... }
+    /// fn not_found() -> String {
+    ///     "Page not found".to_string()
+    /// }
+    ///
     /// #[catch(500)]
-    /// fn internal_server(...) -> String { ... }
+    /// fn internal_server(error: Error) -> String {
+    ///     format!("Something went wrong: {}", error.reason)
+    /// }
     ///
-    /// async main() {
+    /// #[tela::main]
+    /// async fn main() {
     ///     Server::new()
     ///         .catches(group![not_found, internal_server])
     ///         .serve(3000)
@@ -202,19 +809,83 @@ impl Server {
         self
     }
 
-    /// Serve the current router at the given socket
+    /// Render the routes to static files instead of serving them.
+    ///
+    /// See `tela::ssg::export` for how routes are rendered and crawled.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tela::{prelude::*, response::HTML, Server};
+    ///
+    /// #[get("/")]
+    /// fn home() -> HTML<String> {
+    ///     html!(<a href="/blog">"Blog"</a>)
+    /// }
+    ///
+    /// #[get("/blog")]
+    /// fn blog() -> HTML<String> {
+    ///     html!(<h1>"Blog"</h1>)
+    /// }
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .routes(group![home, blog])
+    ///         .export("dist", &[])
+    ///         .await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn export<P: AsRef<std::path::Path>>(
+        &mut self,
+        out_dir: P,
+        routes: &[&str],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        for path in crate::ssg::export(&mut self.router, out_dir, routes).await? {
+            println!("Exported {}", path.display());
+        }
+        Ok(())
+    }
+
+    /// Print a table of every route with its method and documentation
+    pub fn print_routes(&self) {
+        let routes = self.router.routes();
+        let width = routes
+            .iter()
+            .map(|route| route.path.len())
+            .max()
+            .unwrap_or_default()
+            .max(4);
+        println!("{:<7} {:<width$} DESCRIPTION", "METHOD", "PATH");
+        for route in routes {
+            let mut description = route.description.unwrap_or_default();
+            if !route.tags.is_empty() {
+                description = format!("[{}] {}", route.tags.join(", "), description);
+            }
+            let line = format!(
+                "{:<7} {:<width$} {}",
+                route.method.as_str(),
+                route.path,
+                description
+            );
+            println!("{}", line.trim_end());
+        }
+    }
+
+    /// Serve the current router at the given socket; ex: a port on localhost, an ip and port,
+    /// or a `Socket`. Use `socket!` to take the port from the `PORT` environment variable.
     ///
     /// This method returns a Future and should have `.await` called
     /// on it in an async method.
     ///
     /// # Example
-    /// ```
-    /// use tela::server;
+    /// ```no_run
+    /// use tela::Server;
     ///
     /// #[tela::main]
-    /// async main() {
+    /// async fn main() {
     ///     Server::new()
-    ///         serve(3000)
+    ///         .serve(3000)
     ///         .await
     /// }
     /// ```
@@ -222,27 +893,442 @@ impl Server {
         &mut self,
         addr: ADDR,
     ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.serve_with_shutdown(addr, std::future::pending()).await
+    }
+
+    /// Same as `serve` but shuts down gracefully once `signal` completes.
+    ///
+    /// New connections stop being accepted, every `ShutdownSignal` completes so long running
+    /// handlers can end, and open connections finish their current request before closing.
+    /// Returns once every connection and websocket has closed.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use tela::{prelude::*, Server};
+    ///
+    /// #[get("/")]
+    /// fn home() -> &'static str {
+    ///     "Home"
+    /// }
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     Server::new()
+    ///         .route(home)
+    ///         .serve_with_shutdown(3000, async {
+    ///             tokio::signal::ctrl_c().await.ok();
+    ///         })
+    ///         .await
+    /// }
+    /// ```
+    pub async fn serve_with_shutdown<ADDR: IntoSocketAddr, F: Future<Output = ()>>(
+        &mut self,
+        addr: ADDR,
+        signal: F,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.serve_on(addr, signal, None).await
+    }
+
+    /// Serve the router over TLS (https) with certificates from the resolver; ex: an
+    /// `acme::Acme` or a `rustls::server::ResolvesServerCertUsingSni`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::sync::Arc;
+    /// use tela::{prelude::*, Server};
+    /// # #[cfg(feature = "acme")]
+    /// use tela::acme::Acme;
+    ///
+    /// #[get("/")]
+    /// fn home() -> &'static str {
+    ///     "Home"
+    /// }
+    ///
+    /// # #[cfg(feature = "acme")]
+    /// #[tela::main]
+    /// async fn main() {
+    ///     let acme = Acme::new(["example.com"]).contact("mailto:admin@example.com");
+    ///     Server::new()
+    ///         .route(home)
+    ///         .serve_tls(([0, 0, 0, 0], 443), Arc::new(acme))
+    ///         .await
+    /// }
+    /// # #[cfg(not(feature = "acme"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(feature = "tls")]
+    pub async fn serve_tls<ADDR: IntoSocketAddr>(
+        &mut self,
+        addr: ADDR,
+        certificates: Arc<dyn ResolvesServerCert>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_cert_resolver(certificates);
+        // Connections are served with HTTP/1.1
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+        self.serve_on(addr, std::future::pending(), Some(acceptor))
+            .await
+    }
+
+    async fn serve_on<ADDR: IntoSocketAddr, F: Future<Output = ()>>(
+        &mut self,
+        addr: ADDR,
+        signal: F,
+        tls: Option<Acceptor>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // Set by `cargo tela routes` to list the routes without serving them
+        #[cfg(feature = "dev")]
+        if std::env::var_os("TELA_ROUTES").is_some() {
+            self.print_routes();
+            return Ok(());
+        }
+        // Set by `cargo tela dev` so pages reload when the server restarts
+        #[cfg(feature = "dev")]
+        if std::env::var_os("TELA_RELOAD").is_some() {
+            self.router.layer(Arc::new(crate::layer::LiveReload::new()));
+        }
+
         let addr: SocketAddr = addr.into_socket_addr();
 
-        let listener = TcpListener::bind(addr.clone()).await?;
-        println!("Server started at https://{}", addr);
+        let listener = TcpListener::bind(addr).await?;
+        let local = listener.local_addr()?;
+        let scheme = if tls.is_some() { "https" } else { "http" };
+        println!("Server started at {}://{}", scheme, local);
 
-        self.router.serve_routes();
+        // Every serve gets its own shutdown so servers in the same process don't wait on
+        // each other
+        let shutdown = Shutdown::new();
+        self.router.served_over_tls(tls.is_some());
+        self.router.served_until(shutdown.clone());
+        self.router.serve_routes()?;
+        self.router.start_acme();
 
+        if let Some(port) = self.redirect {
+            let redirect = TcpListener::bind(SocketAddr::new(local.ip(), port)).await?;
+            println!("Redirecting http://{} to https", redirect.local_addr()?);
+            tokio::task::spawn(redirect_http(
+                redirect,
+                self.router.clone(),
+                local.port(),
+                shutdown.clone(),
+            ));
+        }
+        if let Some(on_bind) = self.on_bind.take() {
+            on_bind(local);
+        }
+
+        tokio::pin!(signal);
         loop {
-            let (stream, _) = listener.accept().await?;
-            let io = TokioIo::new(stream);
+            let (stream, remote) = tokio::select! {
+                accepted = listener.accept() => accepted?,
+                _ = &mut signal => break,
+            };
 
             let rh = self.router.clone();
+            let guard = shutdown.guard();
+            let shutdown = shutdown.clone();
+            let date_header = self.date_header;
 
-            tokio::task::spawn(async move {
-                if let Err(err) = http1::Builder::new()
-                    .serve_connection(io, service_fn(|req| rh.parse(req)))
-                    .await
-                {
-                    println!("Error serving connection: {:?}", err);
+            match &tls {
+                #[cfg(feature = "tls")]
+                Some(acceptor) => {
+                    let acceptor = acceptor.clone();
+                    tokio::task::spawn(async move {
+                        // Clients that never finish the handshake don't hold the connection
+                        match tokio::time::timeout(HANDSHAKE, acceptor.accept(stream)).await {
+                            Ok(Ok(stream)) => {
+                                connection(TokioIo::new(stream), rh, remote, date_header, shutdown)
+                                    .await
+                            }
+                            Ok(Err(error)) => println!("TLS handshake failed: {}", error),
+                            Err(_) => println!("TLS handshake timed out"),
+                        }
+                        drop(guard);
+                    });
+                }
+                #[cfg(not(feature = "tls"))]
+                Some(never) => match *never {},
+                None => {
+                    tokio::task::spawn(async move {
+                        connection(TokioIo::new(stream), rh, remote, date_header, shutdown).await;
+                        drop(guard);
+                    });
                 }
+            }
+        }
+
+        println!("Shutting down");
+        shutdown.trigger();
+        shutdown.drained().await;
+        Ok(())
+    }
+}
+
+/// Redirect requests from the listener to https until the server shuts down
+async fn redirect_http(listener: TcpListener, router: Router, https_port: u16, shutdown: Shutdown) {
+    let mut signal = shutdown.signal();
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(_) => continue,
+            },
+            _ = &mut signal => break,
+        };
+
+        let router = router.clone();
+        let guard = shutdown.guard();
+        tokio::task::spawn(async move {
+            let _ = http1::Builder::new()
+                .serve_connection(
+                    TokioIo::new(stream),
+                    service_fn(|req: hyper::Request<hyper::body::Incoming>| {
+                        let response = redirect(&req, &router, https_port);
+                        async move { Ok::<_, Infallible>(response) }
+                    }),
+                )
+                .await;
+            drop(guard);
+        });
+    }
+}
+
+/// `301 Moved Permanently` to the request's url over https, or the answer to an acme
+/// challenge
+fn redirect(
+    req: &hyper::Request<hyper::body::Incoming>,
+    router: &Router,
+    https_port: u16,
+) -> hyper::Response<Full<Bytes>> {
+    if let Some(response) = router.acme_challenge(req.uri().path()) {
+        return response;
+    }
+
+    let host = req
+        .headers()
+        .get(HOST)
+        .and_then(|host| host.to_str().ok())
+        .or_else(|| req.uri().host());
+    let Some(host) = host.filter(|host| !host.is_empty()) else {
+        return hyper::Response::builder()
+            .status(400)
+            .body(Full::new(Bytes::from("Missing host")))
+            .unwrap();
+    };
+    // Drop the http port; ex: `example.com:80` or `[::1]:8080`
+    let host = match host.rfind(':') {
+        Some(index) if !host[index..].contains(']') => &host[..index],
+        _ => host,
+    };
+    let path = req
+        .uri()
+        .path_and_query()
+        .map(|path| path.as_str())
+        .unwrap_or("/");
+    let location = match https_port {
+        443 => format!("https://{}{}", host, path),
+        port => format!("https://{}:{}{}", host, port, path),
+    };
+
+    match hyper::Response::builder()
+        .status(301)
+        .header(LOCATION, location)
+        .body(Full::new(Bytes::new()))
+    {
+        Ok(response) => response,
+        Err(_) => hyper::Response::builder()
+            .status(400)
+            .body(Full::new(Bytes::from("Invalid host")))
+            .unwrap(),
+    }
+}
+
+/// Serve requests from a connection until it closes or the server shuts down
+async fn connection<I>(
+    io: I,
+    router: Router,
+    remote: SocketAddr,
+    date_header: bool,
+    shutdown: Shutdown,
+) where
+    I: hyper::rt::Read + hyper::rt::Write + Unpin + Send + 'static,
+{
+    let connection = http1::Builder::new()
+        .auto_date_header(date_header)
+        .serve_connection(
+            io,
+            service_fn(|mut req: hyper::Request<hyper::body::Incoming>| {
+                req.extensions_mut().insert(remote);
+                router.parse(req)
+            }),
+        )
+        .with_upgrades();
+    tokio::pin!(connection);
+
+    let result = tokio::select! {
+        result = connection.as_mut() => result,
+        _ = shutdown.signal() => {
+            connection.as_mut().graceful_shutdown();
+            connection.await
+        }
+    };
+    if let Err(err) = result {
+        println!("Error serving connection: {:?}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::socket;
+
+    #[tokio::test]
+    async fn on_bind_gets_the_picked_port() {
+        let (bound, addr) = tokio::sync::oneshot::channel();
+        let server = tokio::spawn(async move {
+            Server::new()
+                .on_bind(move |addr| bound.send(addr).unwrap())
+                .serve(([127, 0, 0, 1], 0))
+                .await
+                .unwrap()
+        });
+        let addr = addr.await.unwrap();
+        assert_eq!(addr.ip(), IpAddr::from([127, 0, 0, 1]));
+        assert_ne!(addr.port(), 0);
+
+        let response = get(addr, "/missing").await;
+        assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn servers_shut_down_one_after_the_other() {
+        for _ in 0..2 {
+            let (bound, addr) = tokio::sync::oneshot::channel();
+            let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+            let server = tokio::spawn(async move {
+                Server::new()
+                    .on_bind(move |addr| bound.send(addr).unwrap())
+                    .serve_with_shutdown(([127, 0, 0, 1], 0), async {
+                        let _ = stopped.await;
+                    })
+                    .await
+                    .unwrap()
             });
+            let addr = addr.await.unwrap();
+
+            // A shutdown of the server before doesn't close this one's connections
+            let response = get(addr, "/missing").await;
+            assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+
+            stop.send(()).unwrap();
+            tokio::time::timeout(Duration::from_secs(5), server)
+                .await
+                .expect("the server didn't shut down")
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn sockets_use_the_port_from_env() {
+        let local = SocketAddr::from(([127, 0, 0, 1], 3000));
+        let cases: [(Option<&str>, fn() -> Socket, SocketAddr); 8] = [
+            (None, || socket!(), local),
+            (
+                None,
+                || socket!(8080),
+                SocketAddr::from(([127, 0, 0, 1], 8080)),
+            ),
+            (
+                Some("4000"),
+                || socket!(),
+                SocketAddr::from(([127, 0, 0, 1], 4000)),
+            ),
+            (
+                Some("4000"),
+                || Socket::Network(8080).env(),
+                SocketAddr::from(([0, 0, 0, 0], 4000)),
+            ),
+            (
+                Some("4000"),
+                || socket!([10, 0, 0, 1], 8080),
+                SocketAddr::from(([10, 0, 0, 1], 4000)),
+            ),
+            (
+                Some("0"),
+                || socket!(),
+                SocketAddr::from(([127, 0, 0, 1], 0)),
+            ),
+            (Some("port"), || socket!(), local),
+            (Some("70000"), || socket!(), local),
+        ];
+        for (port, socket, expected) in cases {
+            match port {
+                Some(port) => std::env::set_var("PORT", port),
+                None => std::env::remove_var("PORT"),
+            }
+            assert_eq!(socket().into_socket_addr(), expected, "PORT={:?}", port);
         }
+        std::env::remove_var("PORT");
+    }
+
+    #[tokio::test]
+    async fn sockets_on_port_0_report_the_picked_port() {
+        let (bound, addr) = tokio::sync::oneshot::channel();
+        let server = tokio::spawn(async move {
+            Server::new()
+                .on_bind(move |addr| bound.send(addr).unwrap())
+                .serve(Socket::Addr(IpAddr::from([127, 0, 0, 1]), 0))
+                .await
+                .unwrap()
+        });
+        let addr = addr.await.unwrap();
+        assert_ne!(addr.port(), 0);
+        let response = get(addr, "/missing").await;
+        assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+        server.abort();
+    }
+
+    /// Only `cargo tela`, which enables the `dev` feature, can change how the server runs
+    #[cfg(not(feature = "dev"))]
+    #[tokio::test]
+    async fn dev_tools_need_the_dev_feature() {
+        std::env::set_var("TELA_ROUTES", "1");
+        let (bound, addr) = tokio::sync::oneshot::channel();
+        let server = tokio::spawn(async move {
+            Server::new()
+                .on_bind(move |addr| bound.send(addr).unwrap())
+                .serve(([127, 0, 0, 1], 0))
+                .await
+                .unwrap()
+        });
+        let addr = addr.await;
+        std::env::remove_var("TELA_ROUTES");
+        assert!(addr.is_ok(), "the routes were printed instead of served");
+        server.abort();
+    }
+
+    async fn get(addr: SocketAddr, path: &str) -> String {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(
+                format!(
+                    "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+                    path
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
     }
 }
@@ -0,0 +1,573 @@
+#[cfg(feature = "redis")]
+mod redis;
+#[cfg(feature = "sqlx")]
+mod sql;
+mod store;
+
+#[cfg(feature = "redis")]
+pub use redis::RedisStore;
+#[cfg(feature = "sqlx")]
+pub use sql::SqlStore;
+pub use store::{MemoryStore, Session, SessionStore, Sessions, FORMAT_VERSION, SESSION_COOKIE};
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{HeaderMap, Method, Uri};
+use serde::{
+    de::{DeserializeOwned, IgnoredAny},
+    Serialize,
+};
+
+use crate::{
+    cookie::{random_key, secure, Cookie, CookieJar, SameSite},
+    request::{RequestData, ToParam},
+    response::{Result, ToResponse},
+    state,
+    sync::block_on,
+};
+
+/// Name of the signed cookie the logged in user, or their login's id, is stored in
+pub const AUTH_COOKIE: &str = "_auth";
+
+/// Name of the signed cookie a user waiting on a second factor is stored in
+pub const PENDING_COOKIE: &str = "_auth_pending";
+
+/// Seconds a login lasts when `Sessions` aren't managed
+const AUTH_AGE: i64 = 60 * 60 * 24;
+
+/// Seconds a user has to finish a login with a second factor
+const PENDING_AGE: i64 = 300;
+
+/// Seconds since the unix epoch
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Value of a signed cookie that starts with the time it was issued, if it was issued at most
+/// `max_age` seconds ago. The time is signed with the value so a copy of the cookie stops
+/// working after `max_age` even if the browser keeps it.
+fn fresh<'a>(jar: &'a CookieJar, name: &str, max_age: i64) -> Option<&'a str> {
+    let (issued, value) = jar.get_signed(name)?.split_once('.')?;
+    let age = now() - issued.parse::<i64>().ok()?;
+    match (0..=max_age).contains(&age) {
+        true => Some(value),
+        false => None,
+    }
+}
+
+/// Seconds a login lasts
+fn auth_age(sessions: Option<&Sessions>) -> i64 {
+    sessions.map_or(AUTH_AGE, |sessions| sessions.ttl.as_secs() as i64)
+}
+
+/// Id of the request's login in the `Sessions` store
+fn login_id(headers: &HeaderMap, sessions: &Sessions) -> Option<String> {
+    let jar = CookieJar::from_headers(headers);
+    fresh(&jar, AUTH_COOKIE, auth_age(Some(sessions))).map(String::from)
+}
+
+/// Logged in user kept in the `Sessions` store under the id in the auth cookie
+async fn stored_user<U: DeserializeOwned>(
+    headers: &HeaderMap,
+    sessions: &Sessions,
+) -> Result<Option<U>> {
+    let Some(id) = login_id(headers, sessions) else {
+        return Ok(None);
+    };
+    let data = sessions
+        .store
+        .load(&id)
+        .await
+        .map_err(|error| (503, format!("Failed to load login: {}", error)))?;
+    Ok(data.and_then(|data| store::decode(&data)))
+}
+
+/// Logged in user serialized into the auth cookie
+fn cookie_user<U: DeserializeOwned>(headers: &HeaderMap) -> Option<U> {
+    fresh(&CookieJar::from_headers(headers), AUTH_COOKIE, AUTH_AGE).and_then(decode)
+}
+
+/// Logged in user of the request, from the store if `Sessions` are managed and otherwise from
+/// the auth cookie
+fn user<U: DeserializeOwned>(headers: &HeaderMap) -> Result<Option<U>> {
+    match state::get::<Sessions>() {
+        Some(sessions) => block_on(stored_user(headers, &sessions)),
+        None => Ok(cookie_user(headers)),
+    }
+}
+
+/// User waiting on a second factor read from the pending cookie. It stops working after
+/// `PENDING_AGE`.
+fn pending_user<U: DeserializeOwned>(headers: &HeaderMap) -> Option<U> {
+    fresh(
+        &CookieJar::from_headers(headers),
+        PENDING_COOKIE,
+        PENDING_AGE,
+    )
+    .and_then(decode)
+}
+
+fn decode<U: DeserializeOwned>(value: &str) -> Option<U> {
+    URL_SAFE_NO_PAD
+        .decode(value)
+        .ok()
+        .and_then(|value| serde_json::from_slice::<U>(&value).ok())
+}
+
+/// Whether the request has a login that hasn't expired or been logged out
+pub(crate) async fn is_authenticated(headers: &HeaderMap) -> bool {
+    match state::get::<Sessions>() {
+        Some(sessions) => matches!(
+            stored_user::<IgnoredAny>(headers, &sessions).await,
+            Ok(Some(_))
+        ),
+        None => cookie_user::<IgnoredAny>(headers).is_some(),
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Change<U> {
+    Login(U),
+    Pending(U),
+    Logout,
+}
+
+/// Logged in user of the request. Log a user in or out and return the session with the
+/// response, `(session, response)`, to update the auth cookie.
+///
+/// With `Sessions` managed, the user is kept in the session store and the auth cookie only
+/// has a signed id for it, so logging out ends the login on the server even if a copy of the
+/// cookie is kept. Without a store the user is serialized into the signed cookie, which can't
+/// be changed by the client but can be read, so store an id and what is needed to show the user
+/// instead of the whole account. Either way a login lasts the `Sessions` ttl, or a day without
+/// one, and the cookie is marked `Secure` when the server is served over TLS. Use
+/// `layer::RequireAuth` to protect pages and apis.
+///
+/// For two factor logins, `pending` stores a user that still has to give a second factor,
+/// ex: an `auth::Totp` code, without logging them in. `complete` logs the pending user in.
+///
+/// # Example
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use tela::{
+///     prelude::*,
+///     request::Form,
+///     response::Redirect,
+///     session::{AuthSession, CurrentUser},
+/// };
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct User {
+///     id: u32,
+///     name: String,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Login {
+///     name: String,
+///     password: String,
+/// }
+///
+/// fn check_password(form: Login) -> Option<User> {
+///     (form.password == "secret").then(|| User { id: 1, name: form.name })
+/// }
+///
+/// #[post("/login")]
+/// fn login(session: AuthSession<User>, Form(form): Form<Login>) -> (AuthSession<User>, Redirect) {
+///     match check_password(form) {
+///         Some(user) => (session.login(user), Redirect::to("/")),
+///         None => (session, Redirect::to("/login")),
+///     }
+/// }
+///
+/// #[post("/logout")]
+/// fn logout(session: AuthSession<User>) -> (AuthSession<User>, Redirect) {
+///     (session.logout(), Redirect::to("/"))
+/// }
+///
+/// #[get("/")]
+/// fn home(user: Option<CurrentUser<User>>) -> String {
+///     match user {
+///         Some(CurrentUser(user)) => format!("Hello, {}", user.name),
+///         None => "Hello, guest".to_string(),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AuthSession<U> {
+    /// Id of the request's login in the `Sessions` store
+    id: Option<String>,
+    user: Option<U>,
+    pending: Option<U>,
+    /// Whether the request had a pending cookie that needs to be removed
+    had_pending: bool,
+    change: Option<Change<U>>,
+}
+
+impl<U> AuthSession<U> {
+    /// Session without a user; ex: to log a user in from an oauth callback. Use the
+    /// `AuthSession` parameter to get the user of the request.
+    pub fn new() -> Self {
+        AuthSession {
+            id: None,
+            user: None,
+            pending: None,
+            had_pending: false,
+            change: None,
+        }
+    }
+
+    /// User that is logged in, or was just logged in with `login`
+    pub fn current_user(&self) -> Option<&U> {
+        match &self.change {
+            Some(Change::Login(user)) => Some(user),
+            Some(Change::Logout) => None,
+            _ => self.user.as_ref(),
+        }
+    }
+
+    pub fn is_authenticated(&self) -> bool {
+        self.current_user().is_some()
+    }
+
+    /// Log the user in when the session is sent with the response
+    pub fn login(mut self, user: U) -> Self {
+        self.change = Some(Change::Login(user));
+        self
+    }
+
+    /// Store a user that passed the first factor of a two factor login. The user isn't
+    /// logged in until `complete` is called, and has a few minutes to do so.
+    pub fn pending(mut self, user: U) -> Self {
+        self.change = Some(Change::Pending(user));
+        self
+    }
+
+    /// User waiting to give a second factor
+    pub fn pending_user(&self) -> Option<&U> {
+        match &self.change {
+            Some(Change::Pending(user)) => Some(user),
+            Some(_) => None,
+            None => self.pending.as_ref(),
+        }
+    }
+
+    /// Log in the pending user after their second factor was verified. Does nothing if
+    /// there isn't a pending user.
+    pub fn complete(mut self) -> Self {
+        let pending = match self.change.take() {
+            Some(Change::Pending(user)) => Some(user),
+            change => {
+                self.change = change;
+                self.pending.take()
+            }
+        };
+        if let Some(user) = pending {
+            self.change = Some(Change::Login(user));
+        }
+        self
+    }
+
+    /// Log the user out when the session is sent with the response. With `Sessions` managed
+    /// the login is removed from the store.
+    pub fn logout(mut self) -> Self {
+        self.change = Some(Change::Logout);
+        self
+    }
+}
+
+impl<U> Default for AuthSession<U> {
+    fn default() -> Self {
+        AuthSession::new()
+    }
+}
+
+impl<U: DeserializeOwned> ToParam<AuthSession<U>> for RequestData {
+    fn to_param(&mut self) -> Result<AuthSession<U>> {
+        let pending = pending_user(&self.3);
+        Ok(AuthSession {
+            id: state::get::<Sessions>().and_then(|sessions| login_id(&self.3, &sessions)),
+            user: user(&self.3)?,
+            had_pending: pending.is_some(),
+            pending,
+            change: None,
+        })
+    }
+}
+
+/// Set or remove the auth cookies if the user logged in or out
+impl<U: Serialize, T: ToResponse> ToResponse for (AuthSession<U>, T) {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        let (session, response) = self;
+        let encode = |user: &U| {
+            serde_json::to_vec(user)
+                .map(|user| URL_SAFE_NO_PAD.encode(user))
+                .map_err(|error| (500, format!("Failed to store logged in user: {}", error)))
+        };
+        let cookie = |name: &str, value: String| {
+            Cookie::new(name, value)
+                .with_path("/")
+                .with_http_only(true)
+                .with_same_site(SameSite::Lax)
+                .with_secure(secure())
+        };
+
+        let sessions = state::get::<Sessions>();
+        let remove = |id: &str| match &sessions {
+            Some(sessions) => block_on(sessions.store.remove(id))
+                .map_err(|error| (503, format!("Failed to remove login: {}", error))),
+            None => Ok(()),
+        };
+
+        let mut jar = CookieJar::default();
+        match session.change {
+            Some(Change::Login(user)) => {
+                // A new id for every login so an id from before it can't be used
+                if let Some(id) = &session.id {
+                    remove(id)?;
+                }
+                let value = match &sessions {
+                    Some(sessions) => {
+                        let id = URL_SAFE_NO_PAD.encode(random_key());
+                        block_on(
+                            sessions
+                                .store
+                                .store(&id, store::encode(&user)?, sessions.ttl),
+                        )
+                        .map_err(|error| (503, format!("Failed to store login: {}", error)))?;
+                        id
+                    }
+                    None => encode(&user)?,
+                };
+                jar.set_signed(
+                    cookie(AUTH_COOKIE, format!("{}.{}", now(), value))
+                        .with_max_age(auth_age(sessions.as_deref())),
+                );
+                if session.had_pending {
+                    jar.delete_with(PENDING_COOKIE, Some("/"), None);
+                }
+            }
+            Some(Change::Pending(user)) => jar.set_signed(
+                cookie(PENDING_COOKIE, format!("{}.{}", now(), encode(&user)?))
+                    .with_max_age(PENDING_AGE),
+            ),
+            Some(Change::Logout) => {
+                if let Some(id) = &session.id {
+                    remove(id)?;
+                }
+                jar.delete_with(AUTH_COOKIE, Some("/"), None);
+                if session.had_pending {
+                    jar.delete_with(PENDING_COOKIE, Some("/"), None);
+                }
+            }
+            None => {}
+        }
+        (jar, response).to_response(method, uri, headers, body)
+    }
+}
+
+/// Logged in user of the request. Requests without one get a `401 Unauthorized`; use
+/// `Option<CurrentUser<U>>` for pages that guests can see.
+#[derive(Debug, Clone)]
+pub struct CurrentUser<U>(pub U);
+
+impl<U: DeserializeOwned> ToParam<CurrentUser<U>> for RequestData {
+    fn to_param(&mut self) -> Result<CurrentUser<U>> {
+        user(&self.3)?
+            .map(CurrentUser)
+            .ok_or_else(|| (401, "Login required".to_string()))
+    }
+}
+
+impl<U: DeserializeOwned> ToParam<Option<CurrentUser<U>>> for RequestData {
+    fn to_param(&mut self) -> Result<Option<CurrentUser<U>>> {
+        Ok(user(&self.3)?.map(CurrentUser))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::header::{COOKIE, SET_COOKIE};
+
+    use super::*;
+    use crate::cookie::{sign, with_secret};
+
+    fn request(name: &str, value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            COOKIE,
+            format!("{}={}", name, sign(name, value)).parse().unwrap(),
+        );
+        headers
+    }
+
+    fn pending(issued: i64) -> HeaderMap {
+        request(
+            PENDING_COOKIE,
+            &format!("{}.{}", issued, URL_SAFE_NO_PAD.encode("\"ann\"")),
+        )
+    }
+
+    #[test]
+    fn pending_users_expire() {
+        with_secret(|| {
+            assert_eq!(pending_user::<String>(&pending(now())), Some("ann".into()));
+            assert_eq!(
+                pending_user::<String>(&pending(now() - PENDING_AGE + 5)),
+                Some("ann".into())
+            );
+            assert_eq!(
+                pending_user::<String>(&pending(now() - PENDING_AGE - 1)),
+                None
+            );
+            assert_eq!(pending_user::<String>(&pending(now() + 60)), None);
+        })
+    }
+
+    #[test]
+    fn pending_cookie_isnt_a_login() {
+        with_secret(|| {
+            let headers = pending(now());
+            let value = CookieJar::from_headers(&headers)
+                .get(PENDING_COOKIE)
+                .unwrap()
+                .value()
+                .to_string();
+            let headers = request(AUTH_COOKIE, &value);
+            assert_eq!(cookie_user::<String>(&headers), None);
+        })
+    }
+
+    #[test]
+    fn pending_session_sets_a_timestamped_cookie() {
+        with_secret(|| {
+            let session = AuthSession::<String>::new().pending("ann".to_string());
+            let response = (session, "Enter your code")
+                .to_response(
+                    &Method::POST,
+                    &Uri::from_static("/login"),
+                    &HeaderMap::new(),
+                    &Bytes::new(),
+                )
+                .unwrap();
+            let set = response
+                .headers()
+                .get(SET_COOKIE)
+                .unwrap()
+                .to_str()
+                .unwrap();
+            assert!(set.contains("Max-Age=300"));
+
+            let mut headers = HeaderMap::new();
+            headers.insert(COOKIE, set.split(';').next().unwrap().parse().unwrap());
+            assert_eq!(pending_user::<String>(&headers), Some("ann".into()));
+            assert_eq!(cookie_user::<String>(&headers), None);
+        })
+    }
+
+    /// `Set-Cookie` of the response to a session, and the `Cookie` a browser would send back
+    fn respond(session: AuthSession<String>) -> (String, HeaderMap) {
+        let response = (session, "")
+            .to_response(
+                &Method::POST,
+                &Uri::from_static("/login"),
+                &HeaderMap::new(),
+                &Bytes::new(),
+            )
+            .unwrap();
+        let set = response
+            .headers()
+            .get(SET_COOKIE)
+            .map(|value| value.to_str().unwrap().to_string())
+            .unwrap_or_default();
+        let mut headers = HeaderMap::new();
+        headers.insert(COOKIE, set.split(';').next().unwrap().parse().unwrap());
+        (set, headers)
+    }
+
+    /// Run `f` as a request to a server that manages sessions kept in memory
+    fn with_sessions<R>(f: impl FnOnce() -> R) -> R {
+        let mut state = crate::state::StateMap::default();
+        state.insert(Sessions::new(MemoryStore::new()));
+        crate::state::STATE.sync_scope(std::sync::Arc::new(state), || with_secret(f))
+    }
+
+    #[test]
+    fn cookie_logins_expire() {
+        with_secret(|| {
+            let (set, headers) = respond(AuthSession::new().login("ann".to_string()));
+            assert!(set.contains("Max-Age=86400"));
+            assert!(!set.contains("Secure"));
+            assert_eq!(cookie_user::<String>(&headers), Some("ann".into()));
+
+            let login = |issued: i64| {
+                request(
+                    AUTH_COOKIE,
+                    &format!("{}.{}", issued, URL_SAFE_NO_PAD.encode("\"ann\"")),
+                )
+            };
+            assert_eq!(
+                cookie_user::<String>(&login(now() - AUTH_AGE + 5)),
+                Some("ann".into())
+            );
+            assert_eq!(cookie_user::<String>(&login(now() - AUTH_AGE - 1)), None);
+            // A signed user without the time it was issued never expires so it isn't accepted
+            let unissued = request(AUTH_COOKIE, &URL_SAFE_NO_PAD.encode("\"ann\""));
+            assert_eq!(cookie_user::<String>(&unissued), None);
+        })
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn logout_removes_the_stored_login() {
+        with_sessions(|| {
+            let (set, headers) = respond(AuthSession::new().login("ann".to_string()));
+            assert!(!set.contains("ann") && !set.contains("ImFubiI"));
+            assert_eq!(user::<String>(&headers).unwrap(), Some("ann".into()));
+
+            // The copy of the cookie stops working once the user logs out
+            let session: AuthSession<String> = AuthSession {
+                id: login_id(&headers, &state::get::<Sessions>().unwrap()),
+                ..AuthSession::new()
+            };
+            let (set, _) = respond(session.logout());
+            assert!(set.starts_with("_auth=;"));
+            assert_eq!(user::<String>(&headers).unwrap(), None);
+        })
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn logging_in_again_replaces_the_stored_login() {
+        with_sessions(|| {
+            let (_, first) = respond(AuthSession::new().login("ann".to_string()));
+            let session: AuthSession<String> = AuthSession {
+                id: login_id(&first, &state::get::<Sessions>().unwrap()),
+                ..AuthSession::new()
+            };
+            let (_, second) = respond(session.login("bob".to_string()));
+            assert_eq!(user::<String>(&first).unwrap(), None);
+            assert_eq!(user::<String>(&second).unwrap(), Some("bob".into()));
+        })
+    }
+
+    #[test]
+    fn logins_are_secure_over_tls() {
+        with_secret(|| {
+            let (set, _) = crate::cookie::SECURE.sync_scope(true, || {
+                respond(AuthSession::new().login("ann".to_string()))
+            });
+            assert!(set.contains("; Secure"));
+        })
+    }
+}
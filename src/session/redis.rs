@@ -0,0 +1,97 @@
+use std::{io, time::Duration};
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+
+use super::SessionStore;
+use crate::redis::{invalid, Client, Reply};
+
+/// Store that keeps sessions in redis so they are shared between server processes. Redis
+/// removes sessions when they expire.
+///
+/// # Example
+/// ```no_run
+/// use tela::{session::{RedisStore, Sessions}, Server};
+///
+/// #[tela::main]
+/// async fn main() {
+///     let store = RedisStore::new("redis://:secret@cache:6379").prefix("app:session:");
+///     Server::new()
+///         .manage(Sessions::new(store))
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+#[derive(Debug)]
+pub struct RedisStore {
+    client: Client,
+    prefix: String,
+}
+
+impl RedisStore {
+    /// Connect to `host:port` or a `redis://[:password@]host[:port]` url. The port defaults
+    /// to `6379`.
+    pub fn new<A: AsRef<str>>(address: A) -> Self {
+        RedisStore {
+            client: Client::new(address.as_ref()),
+            prefix: "session:".to_string(),
+        }
+    }
+
+    /// Password sent with `AUTH` when connecting
+    pub fn password<P: Into<String>>(mut self, password: P) -> Self {
+        self.client.address.password = Some(password.into());
+        self
+    }
+
+    /// Prefix of the keys sessions are stored under. Defaults to `session:`.
+    pub fn prefix<P: Into<String>>(mut self, prefix: P) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    fn key(&self, id: &str) -> String {
+        format!("{}{}", self.prefix, id)
+    }
+}
+
+impl SessionStore for RedisStore {
+    fn load<'a>(&'a self, id: &'a str) -> BoxFuture<'a, io::Result<Option<Bytes>>> {
+        Box::pin(async move {
+            match self.client.call(&[b"GET", self.key(id).as_bytes()]).await? {
+                Reply::Bulk(data) => Ok(data),
+                reply => Err(invalid(format!("Unexpected GET reply {:?}", reply))),
+            }
+        })
+    }
+
+    fn store<'a>(
+        &'a self,
+        id: &'a str,
+        data: Bytes,
+        ttl: Duration,
+    ) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let ttl = ttl.as_millis().max(1).to_string();
+            self.client
+                .call(&[
+                    b"SET",
+                    self.key(id).as_bytes(),
+                    &data,
+                    b"PX",
+                    ttl.as_bytes(),
+                ])
+                .await
+                .map(|_| ())
+        })
+    }
+
+    fn remove<'a>(&'a self, id: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            self.client
+                .call(&[b"DEL", self.key(id).as_bytes()])
+                .await
+                .map(|_| ())
+        })
+    }
+}
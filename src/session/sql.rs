@@ -0,0 +1,210 @@
+use std::{
+    io,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use sqlx::{
+    database::HasArguments, ColumnIndex, Database, Decode, Encode, Executor, IntoArguments, Pool,
+    Row, Type,
+};
+
+use super::{store::CLEANUP_INTERVAL, SessionStore};
+
+/// Seconds since the unix epoch
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn failed(error: sqlx::Error) -> io::Error {
+    io::Error::other(error)
+}
+
+/// Store that keeps sessions in a table of the database so they are shared between server
+/// processes. Works with the Postgres, MySQL, and SQLite drivers.
+///
+/// The table has an `id`, the session's `data`, and when it `expires` in seconds since the
+/// unix epoch; create it with `migrate`. Expired sessions are deleted when they are loaded,
+/// and all of them at most once a minute when a session is stored.
+///
+/// # Example
+/// Needs sqlx's `postgres` feature and a database.
+/// ```ignore
+/// use sqlx::postgres::PgPoolOptions;
+/// use tela::{session::{Sessions, SqlStore}, Server};
+///
+/// #[tela::main]
+/// async fn main() {
+///     let url = std::env::var("DATABASE_URL")?;
+///     let pool = PgPoolOptions::new().connect(&url).await?;
+///     let store = SqlStore::new(pool).table("user_sessions");
+///     store.migrate().await?;
+///
+///     Server::new()
+///         .manage(Sessions::new(store))
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SqlStore<DB: Database> {
+    pool: Pool<DB>,
+    table: String,
+    cleaned: Mutex<Instant>,
+}
+
+impl<DB: Database> SqlStore<DB> {
+    /// Store that uses the `sessions` table
+    pub fn new(pool: Pool<DB>) -> Self {
+        SqlStore {
+            pool,
+            table: "sessions".to_string(),
+            cleaned: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Name of the table sessions are stored in
+    pub fn table<T: Into<String>>(mut self, table: T) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    fn is_postgres() -> bool {
+        DB::NAME == "PostgreSQL"
+    }
+
+    /// Bind parameter `n` of a query, starting at 1
+    fn param(n: usize) -> String {
+        match Self::is_postgres() {
+            true => format!("${}", n),
+            false => "?".to_string(),
+        }
+    }
+
+    /// Whether it has been long enough since expired sessions were last deleted
+    fn cleanup(&self) -> bool {
+        let mut cleaned = self.cleaned.lock().unwrap();
+        if cleaned.elapsed() >= CLEANUP_INTERVAL {
+            *cleaned = Instant::now();
+            return true;
+        }
+        false
+    }
+}
+
+impl<DB: Database> SqlStore<DB>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+{
+    /// Create the table if it doesn't exist
+    pub async fn migrate(&self) -> Result<(), sqlx::Error> {
+        let (id, data) = match DB::NAME {
+            "PostgreSQL" => ("TEXT", "BYTEA"),
+            "MySQL" => ("VARCHAR(128)", "BLOB"),
+            _ => ("TEXT", "BLOB"),
+        };
+        let sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} (id {} PRIMARY KEY, data {} NOT NULL, expires BIGINT NOT NULL)",
+            self.table, id, data
+        );
+        sqlx::query(&sql).execute(&self.pool).await.map(|_| ())
+    }
+}
+
+impl<DB: Database> SessionStore for SqlStore<DB>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+    for<'q> &'q str: Encode<'q, DB> + Type<DB>,
+    for<'q> Vec<u8>: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    usize: ColumnIndex<DB::Row>,
+{
+    fn load<'a>(&'a self, id: &'a str) -> BoxFuture<'a, io::Result<Option<Bytes>>> {
+        Box::pin(async move {
+            let sql = format!(
+                "SELECT data, expires FROM {} WHERE id = {}",
+                self.table,
+                Self::param(1)
+            );
+            let row = match sqlx::query(&sql)
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(failed)?
+            {
+                Some(row) => row,
+                None => return Ok(None),
+            };
+            let expires: i64 = row.try_get(1).map_err(failed)?;
+            if expires <= now() {
+                self.remove(id).await?;
+                return Ok(None);
+            }
+            let data: Vec<u8> = row.try_get(0).map_err(failed)?;
+            Ok(Some(Bytes::from(data)))
+        })
+    }
+
+    fn store<'a>(
+        &'a self,
+        id: &'a str,
+        data: Bytes,
+        ttl: Duration,
+    ) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let now = now();
+            if self.cleanup() {
+                let sql = format!(
+                    "DELETE FROM {} WHERE expires <= {}",
+                    self.table,
+                    Self::param(1)
+                );
+                sqlx::query(&sql)
+                    .bind(now)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(failed)?;
+            }
+
+            let update = match DB::NAME {
+                "MySQL" => "ON DUPLICATE KEY UPDATE data = VALUES(data), expires = VALUES(expires)",
+                _ => "ON CONFLICT (id) DO UPDATE SET data = excluded.data, expires = excluded.expires",
+            };
+            let sql = format!(
+                "INSERT INTO {} (id, data, expires) VALUES ({}, {}, {}) {}",
+                self.table,
+                Self::param(1),
+                Self::param(2),
+                Self::param(3),
+                update
+            );
+            sqlx::query(&sql)
+                .bind(id)
+                .bind(data.to_vec())
+                .bind(now + ttl.as_secs().max(1) as i64)
+                .execute(&self.pool)
+                .await
+                .map(|_| ())
+                .map_err(failed)
+        })
+    }
+
+    fn remove<'a>(&'a self, id: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let sql = format!("DELETE FROM {} WHERE id = {}", self.table, Self::param(1));
+            sqlx::query(&sql)
+                .bind(id)
+                .execute(&self.pool)
+                .await
+                .map(|_| ())
+                .map_err(failed)
+        })
+    }
+}
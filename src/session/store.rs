@@ -0,0 +1,384 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    io,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use http_body_util::Full;
+use hyper::{HeaderMap, Method, Uri};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    cookie::{random_key, secure, Cookie, CookieJar, SameSite},
+    request::{RequestData, ToParam},
+    response::{Result, ToResponse},
+    state,
+    sync::block_on,
+};
+
+/// Name of the signed cookie the session id is stored in
+pub const SESSION_COOKIE: &str = "_session";
+
+/// Version of the format session data is stored in. It is the first byte of the stored
+/// data; data stored with another version is treated as an empty session.
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Shortest time between removing every expired session from a store
+pub(crate) const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Where session data is kept between requests. Stores only see bytes; `Session` handles
+/// serializing and versioning the data.
+///
+/// `MemoryStore` keeps sessions in the process. `RedisStore`, with the `redis` feature, and
+/// `SqlStore`, with the `sqlx` feature, share sessions between server processes.
+pub trait SessionStore: Send + Sync + Debug {
+    /// Data of the session, or `None` if it doesn't exist or has expired
+    fn load<'a>(&'a self, id: &'a str) -> BoxFuture<'a, io::Result<Option<Bytes>>>;
+
+    /// Create or replace the session's data. It expires after `ttl`.
+    fn store<'a>(
+        &'a self,
+        id: &'a str,
+        data: Bytes,
+        ttl: Duration,
+    ) -> BoxFuture<'a, io::Result<()>>;
+
+    /// Delete the session
+    fn remove<'a>(&'a self, id: &'a str) -> BoxFuture<'a, io::Result<()>>;
+}
+
+/// Store that keeps sessions in the process, so they are lost when the server restarts.
+/// Expired sessions are removed when they are loaded, and all of them at most once a
+/// minute when a session is stored.
+#[derive(Debug)]
+pub struct MemoryStore {
+    sessions: Mutex<HashMap<String, (Bytes, Instant)>>,
+    cleaned: Mutex<Instant>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        MemoryStore {
+            sessions: Mutex::new(HashMap::new()),
+            cleaned: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        MemoryStore::new()
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn load<'a>(&'a self, id: &'a str) -> BoxFuture<'a, io::Result<Option<Bytes>>> {
+        Box::pin(async move {
+            let mut sessions = self.sessions.lock().unwrap();
+            match sessions.get(id) {
+                Some((_, expires)) if *expires <= Instant::now() => {
+                    sessions.remove(id);
+                    Ok(None)
+                }
+                session => Ok(session.map(|(data, _)| data.clone())),
+            }
+        })
+    }
+
+    fn store<'a>(
+        &'a self,
+        id: &'a str,
+        data: Bytes,
+        ttl: Duration,
+    ) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            let now = Instant::now();
+            let mut sessions = self.sessions.lock().unwrap();
+            {
+                let mut cleaned = self.cleaned.lock().unwrap();
+                if now.duration_since(*cleaned) >= CLEANUP_INTERVAL {
+                    sessions.retain(|_, (_, expires)| *expires > now);
+                    *cleaned = now;
+                }
+            }
+            sessions.insert(id.to_string(), (data, now + ttl));
+            Ok(())
+        })
+    }
+
+    fn remove<'a>(&'a self, id: &'a str) -> BoxFuture<'a, io::Result<()>> {
+        Box::pin(async move {
+            self.sessions.lock().unwrap().remove(id);
+            Ok(())
+        })
+    }
+}
+
+/// Store that `Session` uses. Manage it with the server.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use tela::{session::{MemoryStore, Sessions}, Server};
+///
+/// Server::new()
+///     .manage(Sessions::new(MemoryStore::new()).ttl(Duration::from_secs(3600)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Sessions {
+    pub(super) store: Arc<dyn SessionStore>,
+    pub(super) ttl: Duration,
+}
+
+impl Sessions {
+    /// Sessions kept in the store that expire a day after they are last changed
+    pub fn new<S: SessionStore + 'static>(store: S) -> Self {
+        Sessions {
+            store: Arc::new(store),
+            ttl: Duration::from_secs(60 * 60 * 24),
+        }
+    }
+
+    /// How long a session lasts after it is last changed
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+}
+
+fn sessions() -> Result<Arc<Sessions>> {
+    state::get::<Sessions>().ok_or_else(|| {
+        (
+            500,
+            "Sessions are not managed by the server; add them with `Server::manage(Sessions::new(store))`"
+                .to_string(),
+        )
+    })
+}
+
+/// Data stored with the format version in front of it
+pub(super) fn encode<T: Serialize>(data: &T) -> Result<Bytes> {
+    let mut encoded = vec![FORMAT_VERSION];
+    serde_json::to_writer(&mut encoded, data)
+        .map_err(|error| (500, format!("Failed to store session: {}", error)))?;
+    Ok(Bytes::from(encoded))
+}
+
+/// Data of a stored session. Data with another format version, or that no longer matches
+/// the type, is ignored.
+pub(super) fn decode<T: DeserializeOwned>(data: &[u8]) -> Option<T> {
+    match data.split_first() {
+        Some((&FORMAT_VERSION, data)) => serde_json::from_slice(data).ok(),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Change<T> {
+    Set(T),
+    Clear,
+}
+
+/// Data kept for the client between requests in the managed `Sessions` store. Only the
+/// session's id is sent to the client, in a signed cookie. Set or clear the data and return
+/// the session with the response, `(session, response)`, to save it.
+///
+/// Data is stored as json so it can be read back into a different type, but data that no
+/// longer matches the type is treated as an empty session.
+///
+/// # Example
+/// ```no_run
+/// use serde::{Deserialize, Serialize};
+/// use tela::{prelude::*, session::{MemoryStore, Session, Sessions}, Server};
+///
+/// #[derive(Clone, Default, Serialize, Deserialize)]
+/// struct Cart {
+///     items: Vec<u32>,
+/// }
+///
+/// #[post("/cart/:item")]
+/// fn add(item: u32, session: Session<Cart>) -> (Session<Cart>, String) {
+///     let mut cart = session.get().cloned().unwrap_or_default();
+///     cart.items.push(item);
+///     let message = format!("{} items in the cart", cart.items.len());
+///     (session.set(cart), message)
+/// }
+///
+/// #[tela::main]
+/// async fn main() {
+///     Server::new()
+///         .manage(Sessions::new(MemoryStore::new()))
+///         .route(add)
+///         .serve(3000)
+///         .await
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Session<T> {
+    id: Option<String>,
+    data: Option<T>,
+    change: Option<Change<T>>,
+}
+
+impl<T> Session<T> {
+    /// Data of the session, or the data that was just set
+    pub fn get(&self) -> Option<&T> {
+        match &self.change {
+            Some(Change::Set(data)) => Some(data),
+            Some(Change::Clear) => None,
+            None => self.data.as_ref(),
+        }
+    }
+
+    /// Store the data when the session is sent with the response
+    pub fn set(mut self, data: T) -> Self {
+        self.change = Some(Change::Set(data));
+        self
+    }
+
+    /// Delete the session when it is sent with the response
+    pub fn clear(mut self) -> Self {
+        self.change = Some(Change::Clear);
+        self
+    }
+}
+
+impl<T: DeserializeOwned> ToParam<Session<T>> for RequestData {
+    fn to_param(&mut self) -> Result<Session<T>> {
+        let sessions = sessions()?;
+        let id = CookieJar::from_headers(&self.3)
+            .get_signed(SESSION_COOKIE)
+            .map(String::from);
+        let data = match &id {
+            Some(id) => block_on(sessions.store.load(id))
+                .map_err(|error| (503, format!("Failed to load session: {}", error)))?,
+            None => None,
+        };
+        Ok(Session {
+            id,
+            data: data.and_then(|data| decode(&data)),
+            change: None,
+        })
+    }
+}
+
+/// Save or delete the session if it was changed
+impl<T: Serialize, R: ToResponse> ToResponse for (Session<T>, R) {
+    fn to_response(
+        self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &Bytes,
+    ) -> Result<hyper::Response<Full<Bytes>>> {
+        let (session, response) = self;
+        let mut jar = CookieJar::default();
+        match session.change {
+            Some(Change::Set(data)) => {
+                let sessions = sessions()?;
+                let id = session
+                    .id
+                    .unwrap_or_else(|| URL_SAFE_NO_PAD.encode(random_key()));
+                block_on(sessions.store.store(&id, encode(&data)?, sessions.ttl))
+                    .map_err(|error| (503, format!("Failed to store session: {}", error)))?;
+                jar.set_signed(
+                    Cookie::new(SESSION_COOKIE, id)
+                        .with_path("/")
+                        .with_http_only(true)
+                        .with_same_site(SameSite::Lax)
+                        .with_secure(secure())
+                        .with_max_age(sessions.ttl.as_secs() as i64),
+                );
+            }
+            Some(Change::Clear) => {
+                if let Some(id) = session.id {
+                    let sessions = sessions()?;
+                    block_on(sessions.store.remove(&id))
+                        .map_err(|error| (503, format!("Failed to remove session: {}", error)))?;
+                    jar.delete_with(SESSION_COOKIE, Some("/"), None);
+                }
+            }
+            None => {}
+        }
+        (jar, response).to_response(method, uri, headers, body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn memory_sessions_expire() {
+        let store = MemoryStore::new();
+        let data = Bytes::from_static(b"\x01{}");
+        store
+            .store("short", data.clone(), Duration::from_millis(20))
+            .await
+            .unwrap();
+        store
+            .store("long", data.clone(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(store.load("short").await.unwrap(), Some(data.clone()));
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(store.load("short").await.unwrap(), None);
+        assert!(!store.sessions.lock().unwrap().contains_key("short"));
+        assert_eq!(store.load("long").await.unwrap(), Some(data.clone()));
+
+        // Storing the session again starts its ttl over
+        store
+            .store("short", data.clone(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(store.load("short").await.unwrap(), Some(data));
+        store.remove("short").await.unwrap();
+        assert_eq!(store.load("short").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn expired_sessions_are_cleaned_up() {
+        let store = MemoryStore::new();
+        let data = Bytes::from_static(b"\x01{}");
+        store
+            .store("a", data.clone(), Duration::ZERO)
+            .await
+            .unwrap();
+        store
+            .store("b", data.clone(), Duration::ZERO)
+            .await
+            .unwrap();
+        // Not cleaned up until the interval has passed
+        assert_eq!(store.sessions.lock().unwrap().len(), 2);
+
+        let Some(cleaned) = Instant::now().checked_sub(CLEANUP_INTERVAL) else {
+            return;
+        };
+        *store.cleaned.lock().unwrap() = cleaned;
+        store
+            .store("c", data, Duration::from_secs(60))
+            .await
+            .unwrap();
+        let sessions = store.sessions.lock().unwrap();
+        assert_eq!(sessions.keys().collect::<Vec<_>>(), vec!["c"]);
+    }
+
+    #[test]
+    fn data_from_another_format_is_ignored() {
+        let data = encode(&vec![1, 2]).unwrap();
+        assert_eq!(data[0], FORMAT_VERSION);
+        assert_eq!(decode::<Vec<u32>>(&data), Some(vec![1, 2]));
+        assert_eq!(decode::<String>(&data), None);
+
+        let mut old = data.to_vec();
+        old[0] = FORMAT_VERSION + 1;
+        assert_eq!(decode::<Vec<u32>>(&old), None);
+        assert_eq!(decode::<Vec<u32>>(&[]), None);
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use futures::future::BoxFuture;
+use tokio::sync::{watch, Notify};
+
+use crate::{
+    request::{RequestData, ToParam},
+    response::Result,
+};
+
+tokio::task_local! {
+    /// Shutdown of the server handling the request
+    pub(crate) static SHUTDOWN: Shutdown;
+}
+
+/// Shutdown state of a single server; its signal and the connections and websockets it
+/// waits on before it finishes
+#[derive(Clone, Debug)]
+pub(crate) struct Shutdown(Arc<Shared>);
+
+#[derive(Debug)]
+struct Shared {
+    signal: watch::Sender<bool>,
+    tasks: AtomicUsize,
+    finished: Notify,
+}
+
+impl Shutdown {
+    pub(crate) fn new() -> Self {
+        Shutdown(Arc::new(Shared {
+            signal: watch::channel(false).0,
+            tasks: AtomicUsize::new(0),
+            finished: Notify::new(),
+        }))
+    }
+
+    /// Shutdown of the server handling the request.
+    ///
+    /// # Panics
+    /// Outside of a request there is no server to wait on, ex: in a task started with
+    /// `tokio::spawn`. Create the `ShutdownSignal` before spawning the task.
+    pub(crate) fn current() -> Self {
+        SHUTDOWN
+            .try_with(Shutdown::clone)
+            .expect("ShutdownSignal must be created while handling a request of a server")
+    }
+
+    /// Tell every `ShutdownSignal` of the server that it is shutting down
+    pub(crate) fn trigger(&self) {
+        self.0.signal.send_replace(true);
+    }
+
+    /// Wait until every connection and websocket of the server has finished
+    pub(crate) async fn drained(&self) {
+        loop {
+            let finished = self.0.finished.notified();
+            if self.0.tasks.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            finished.await;
+        }
+    }
+
+    /// Held by a connection or websocket so the shutdown waits for it to finish
+    pub(crate) fn guard(&self) -> TaskGuard {
+        self.0.tasks.fetch_add(1, Ordering::SeqCst);
+        TaskGuard(self.clone())
+    }
+
+    pub(crate) fn signal(&self) -> ShutdownSignal {
+        ShutdownSignal {
+            receiver: self.0.signal.subscribe(),
+            waiting: None,
+        }
+    }
+}
+
+/// Held by a connection or websocket so a graceful shutdown waits for it to finish
+pub(crate) struct TaskGuard(Shutdown);
+
+impl TaskGuard {
+    /// Signal of the server the guard is held for
+    pub(crate) fn signal(&self) -> ShutdownSignal {
+        self.0.signal()
+    }
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        let shared = &(self.0).0;
+        if shared.tasks.fetch_sub(1, Ordering::SeqCst) == 1 {
+            shared.finished.notify_waiters();
+        }
+    }
+}
+
+/// Completes when the server starts a graceful shutdown, see `Server::serve_with_shutdown`.
+///
+/// Long running handlers, ex: websockets, long polls, and streams, can wait on it to end
+/// cleanly instead of being cut off. It can also be used as a route parameter.
+///
+/// # Panics
+/// `ShutdownSignal::new` panics outside of a request, ex: in a task started with
+/// `tokio::spawn`, since there is no server to wait on. Create it before spawning the task.
+///
+/// # Example
+/// ```
+/// use futures::StreamExt;
+/// use tela::{shutdown::ShutdownSignal, websocket::WebSocket};
+///
+/// async fn feed(mut socket: WebSocket) {
+///     let mut shutdown = ShutdownSignal::new();
+///     loop {
+///         tokio::select! {
+///             _ = &mut shutdown => {
+///                 let _ = socket.close().await;
+///                 break;
+///             }
+///             message = socket.next() => match message {
+///                 Some(Ok(message)) => {
+///                     if socket.send(message).await.is_err() {
+///                         break;
+///                     }
+///                 }
+///                 _ => break,
+///             },
+///         }
+///     }
+/// }
+/// ```
+pub struct ShutdownSignal {
+    receiver: watch::Receiver<bool>,
+    waiting: Option<BoxFuture<'static, ()>>,
+}
+
+impl ShutdownSignal {
+    /// Signal of the server handling the request
+    pub fn new() -> Self {
+        Shutdown::current().signal()
+    }
+
+    /// Whether the server is shutting down
+    pub fn is_shutdown(&self) -> bool {
+        *self.receiver.borrow()
+    }
+}
+
+impl Default for ShutdownSignal {
+    fn default() -> Self {
+        ShutdownSignal::new()
+    }
+}
+
+impl Clone for ShutdownSignal {
+    fn clone(&self) -> Self {
+        ShutdownSignal {
+            receiver: self.receiver.clone(),
+            waiting: None,
+        }
+    }
+}
+
+impl Future for ShutdownSignal {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.waiting.is_none() {
+            let mut receiver = self.receiver.clone();
+            self.waiting = Some(Box::pin(async move {
+                // The sender is dropped with the server once it has shut down
+                let _ = receiver.wait_for(|shutdown| *shutdown).await;
+            }));
+        }
+        self.waiting.as_mut().unwrap().as_mut().poll(cx)
+    }
+}
+
+impl ToParam<ShutdownSignal> for RequestData {
+    fn to_param(&mut self) -> Result<ShutdownSignal> {
+        Ok(ShutdownSignal::new())
+    }
+}
@@ -0,0 +1,332 @@
+use std::{
+    fmt::Display,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::Mac;
+use hyper::Uri;
+
+use crate::{
+    auth::constant_eq,
+    cookie::mac,
+    request::{RequestData, ToParam},
+    response::Result,
+};
+
+/// Query parameter the signature is sent in. It is always the last parameter.
+const SIGNATURE: &str = "signature";
+
+/// Query parameter with when the url expires, in seconds since the unix epoch
+const EXPIRES: &str = "expires";
+
+/// Signature of a path and query. Prefixed so a signed cookie's signature can't be used for
+/// a url.
+fn signature(payload: &str) -> String {
+    let mut mac = mac();
+    mac.update(b"url:");
+    mac.update(payload.as_bytes());
+    URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+}
+
+/// Why a signed url was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlError {
+    /// The url doesn't have a signature
+    Unsigned,
+    /// The signature doesn't match the url; it was changed or signed with another secret
+    Invalid,
+    /// The url was signed correctly but has expired
+    Expired,
+}
+
+impl UrlError {
+    /// Status code of the error response; `410 Gone` for expired urls and `403 Forbidden`
+    /// otherwise
+    pub fn status(&self) -> u16 {
+        match self {
+            UrlError::Expired => 410,
+            _ => 403,
+        }
+    }
+}
+
+impl Display for UrlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UrlError::Unsigned => write!(f, "Url is not signed"),
+            UrlError::Invalid => write!(f, "Url signature is invalid"),
+            UrlError::Expired => write!(f, "Url has expired"),
+        }
+    }
+}
+
+impl std::error::Error for UrlError {}
+
+/// Url signed with the server's secret so it can be shared with a client, ex: a link to a
+/// private download, without the client being able to change it. The signature and when
+/// the url expires are added to the end of its query.
+///
+/// The url must already be percent encoded, ex: built with `url!`, since it has to match
+/// what the client requests exactly. Set the secret with `Server::secret` so urls stay valid
+/// across restarts and server processes. Urls are signed with the secret of the server
+/// handling the request, so sign them in a handler. Use the `SignedUrl` parameter to only run
+/// a handler for valid urls.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use tela::{prelude::*, response::FileResponse, sign::{SignedUrl, Url}};
+///
+/// #[get("/reports/:id")]
+/// fn report(id: u32) -> String {
+///     Url::new(url!("/downloads/report-{id}.pdf"))
+///         .expires_in(Duration::from_secs(600))
+///         .sign()
+/// }
+///
+/// #[get("/downloads/:file")]
+/// fn download(_signed: SignedUrl, file: String) -> FileResponse {
+///     FileResponse::new(format!("downloads/{}", file)).attachment()
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Url {
+    url: String,
+    expires: Option<u64>,
+}
+
+impl Url {
+    /// Url to sign; a path, ex: `/downloads/report.pdf?v=2`, or a full url. Only the path and
+    /// query are signed.
+    pub fn new<U: Into<String>>(url: U) -> Self {
+        Url {
+            url: url.into(),
+            expires: None,
+        }
+    }
+
+    /// Expire the url after the duration
+    pub fn expires_in(self, duration: Duration) -> Self {
+        self.expires_at(SystemTime::now() + duration)
+    }
+
+    /// Expire the url at the time. Urls without an expiration are valid until the secret
+    /// changes.
+    pub fn expires_at(mut self, time: SystemTime) -> Self {
+        self.expires = Some(
+            time.duration_since(UNIX_EPOCH)
+                .map(|time| time.as_secs())
+                .unwrap_or(0),
+        );
+        self
+    }
+
+    /// Url with the expiration and signature added to its query
+    pub fn sign(&self) -> String {
+        let (url, fragment) = match self.url.split_once('#') {
+            Some((url, fragment)) => (url, Some(fragment)),
+            None => (self.url.as_str(), None),
+        };
+        let mut url = url.to_string();
+        if let Some(expires) = self.expires {
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str(&format!("{}={}", EXPIRES, expires));
+        }
+
+        // Only the path and query are signed since that is all the server sees
+        let path = match url.split_once("://") {
+            Some((_, rest)) => match rest.find(['/', '?']) {
+                Some(index) if rest[index..].starts_with('/') => rest[index..].to_string(),
+                Some(index) => format!("/{}", &rest[index..]),
+                None => "/".to_string(),
+            },
+            None => url.clone(),
+        };
+        let signature = signature(&path);
+        url.push(if url.contains('?') { '&' } else { '?' });
+        url.push_str(&format!("{}={}", SIGNATURE, signature));
+        if let Some(fragment) = fragment {
+            url.push('#');
+            url.push_str(fragment);
+        }
+        url
+    }
+
+    /// Check the signature and expiration of a requested url. Returns when the url expires,
+    /// if it does.
+    pub fn verify(uri: &Uri) -> std::result::Result<Option<SystemTime>, UrlError> {
+        let query = uri.query().ok_or(UrlError::Unsigned)?;
+        let (payload, signature) = match query.rsplit_once('&') {
+            Some((query, last)) => (format!("{}?{}", uri.path(), query), last),
+            None => (uri.path().to_string(), query),
+        };
+        let signature = signature
+            .strip_prefix(SIGNATURE)
+            .and_then(|signature| signature.strip_prefix('='))
+            .ok_or(UrlError::Unsigned)?;
+        if !constant_eq(self::signature(&payload).as_bytes(), signature.as_bytes()) {
+            return Err(UrlError::Invalid);
+        }
+
+        // The last expiration is used since it is the one that was added when signing
+        let expires = payload
+            .split_once('?')
+            .map(|(_, query)| query)
+            .unwrap_or("")
+            .split('&')
+            .filter_map(|pair| pair.strip_prefix(EXPIRES)?.strip_prefix('='))
+            .next_back();
+        match expires {
+            Some(expires) => {
+                let expires = expires.parse::<u64>().map_err(|_| UrlError::Invalid)?;
+                let expires = UNIX_EPOCH + Duration::from_secs(expires);
+                match SystemTime::now() < expires {
+                    true => Ok(Some(expires)),
+                    false => Err(UrlError::Expired),
+                }
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+/// Proof that the request's url was signed with `Url::sign` and hasn't expired. Requests
+/// with a missing or changed signature get a `403 Forbidden`, and expired urls get a
+/// `410 Gone`, without running the handler.
+#[derive(Debug, Clone, Copy)]
+pub struct SignedUrl {
+    /// When the url expires, if it does
+    pub expires: Option<SystemTime>,
+}
+
+impl ToParam<SignedUrl> for RequestData {
+    fn to_param(&mut self) -> Result<SignedUrl> {
+        Url::verify(&self.0)
+            .map(|expires| SignedUrl { expires })
+            .map_err(|error| (error.status(), error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use hyper::{HeaderMap, Method};
+
+    use super::*;
+    use crate::{cookie::with_secret, request::BodyStream};
+
+    fn verify(url: &str) -> std::result::Result<Option<SystemTime>, UrlError> {
+        Url::verify(&url.parse::<Uri>().unwrap())
+    }
+
+    /// Status of the `SignedUrl` parameter for a request to the url
+    fn status(url: &str) -> Option<u16> {
+        let mut data = RequestData(
+            url.parse().unwrap(),
+            Method::GET,
+            Bytes::new(),
+            HeaderMap::new(),
+            HeaderMap::new(),
+            BodyStream::default(),
+        );
+        let signed: Result<SignedUrl> = data.to_param();
+        signed.err().map(|(code, _)| code)
+    }
+
+    #[test]
+    fn signed_urls_verify() {
+        with_secret(|| {
+            for url in ["/downloads/report.pdf", "/search?q=a%20b&page=2", "/"] {
+                let signed = Url::new(url).sign();
+                assert!(signed.starts_with(url));
+                assert_eq!(verify(&signed), Ok(None), "{}", signed);
+                assert_eq!(status(&signed), None);
+            }
+
+            let signed = Url::new("/report")
+                .expires_in(Duration::from_secs(60))
+                .sign();
+            let expires = verify(&signed).unwrap().unwrap();
+            assert!(expires > SystemTime::now());
+
+            // The fragment isn't sent to the server so it stays after the signature
+            let signed = Url::new("/docs?v=2#usage").sign();
+            assert!(signed.ends_with("#usage"));
+            assert_eq!(verify(signed.trim_end_matches("#usage")), Ok(None));
+        })
+    }
+
+    #[test]
+    fn tampered_urls_are_rejected() {
+        with_secret(|| {
+            let signed = Url::new("/downloads/report.pdf?user=1").sign();
+            for tampered in [
+                signed.replace("user=1", "user=2"),
+                signed.replace("/downloads/report.pdf", "/downloads/secret.pdf"),
+                signed.replace("?user=1&", "?user=1&admin=true&"),
+                signed.replace("?user=1&", "?"),
+            ] {
+                assert_eq!(verify(&tampered), Err(UrlError::Invalid), "{}", tampered);
+                assert_eq!(status(&tampered), Some(403));
+            }
+
+            assert_eq!(verify("/downloads/report.pdf"), Err(UrlError::Unsigned));
+            assert_eq!(
+                verify("/downloads/report.pdf?user=1"),
+                Err(UrlError::Unsigned)
+            );
+            let other = with_secret(|| Url::new("/downloads/report.pdf?user=1").sign());
+            assert_eq!(verify(&other), Err(UrlError::Invalid));
+        })
+    }
+
+    #[test]
+    fn appended_parameters_are_rejected() {
+        with_secret(|| {
+            let expired = Url::new("/report")
+                .expires_at(UNIX_EPOCH + Duration::from_secs(60))
+                .sign();
+            let unlimited = Url::new("/report").sign();
+
+            // The signature has to be the last parameter, so nothing can be added after it
+            let extended = format!("{}&expires=99999999999", expired);
+            assert_eq!(verify(&extended), Err(UrlError::Unsigned));
+            let extended = format!("{}&expires=1", unlimited);
+            assert_eq!(verify(&extended), Err(UrlError::Unsigned));
+
+            // A second signature isn't the one for the url
+            let (_, signature) = unlimited.rsplit_once("signature=").unwrap();
+            let resigned = format!("{}&signature={}", expired, signature);
+            assert_eq!(verify(&resigned), Err(UrlError::Invalid));
+            let resigned = format!("/report?expires=99999999999&signature={}", signature);
+            assert_eq!(verify(&resigned), Err(UrlError::Invalid));
+        })
+    }
+
+    #[test]
+    fn expired_urls_are_gone() {
+        with_secret(|| {
+            let signed = Url::new("/report?id=1")
+                .expires_at(SystemTime::now() - Duration::from_secs(1))
+                .sign();
+            assert_eq!(verify(&signed), Err(UrlError::Expired));
+            assert_eq!(status(&signed), Some(410));
+        })
+    }
+
+    #[test]
+    fn full_urls_sign_their_path_and_query() {
+        with_secret(|| {
+            let signed = Url::new("https://example.com/downloads/report.pdf?v=2").sign();
+            assert!(signed.starts_with("https://example.com/downloads/report.pdf?v=2&signature="));
+            let path = signed.strip_prefix("https://example.com").unwrap();
+            assert_eq!(verify(path), Ok(None));
+            assert_eq!(verify(&signed), Ok(None));
+
+            let signed = Url::new("https://example.com?v=2").sign();
+            let (_, query) = signed.split_once('?').unwrap();
+            assert_eq!(verify(&format!("/?{}", query)), Ok(None));
+        })
+    }
+}
@@ -0,0 +1,181 @@
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use hyper::{HeaderMap, Method, Uri};
+
+use crate::{layer::Request, request::BodyStream, Router};
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Collect the local links from the `href` attributes of an html page; ex: `href="/blog"`
+fn links(html: &str) -> Vec<String> {
+    let mut links = Vec::new();
+    for part in html.split("href=").skip(1) {
+        let quote = match part.chars().next() {
+            Some(quote) if quote == '"' || quote == '\'' => quote,
+            _ => continue,
+        };
+        let link = match part[1..].split(quote).next() {
+            Some(link) => link,
+            None => continue,
+        };
+        if link.starts_with('/') && !link.starts_with("//") {
+            let link = link.split(['#', '?']).next().unwrap_or("/");
+            links.push(link.to_string());
+        }
+    }
+    links
+}
+
+/// File a route is written to. Routes with an extension are written as is, all other routes
+/// are written to an `index.html` inside a directory of the same name.
+fn output_path(out_dir: &Path, route: &str) -> PathBuf {
+    let route = route.trim_matches('/');
+    if route.is_empty() {
+        return out_dir.join("index.html");
+    }
+    match Path::new(route).extension() {
+        Some(_) => out_dir.join(route),
+        None => out_dir.join(route).join("index.html"),
+    }
+}
+
+/// Recursively copy the contents of a directory
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Render GET routes to html files so the site can be hosted statically.
+///
+/// Each listed route is requested from the router without starting a server and the
+/// response body is written to `out_dir`; ex: `/blog` -> `out_dir/blog/index.html`. If no routes are
+/// listed every GET route without uri captures is rendered. Local links, `href="/..."`, found in
+/// rendered pages are followed so routes with captures are exported when a page links to them.
+///
+/// The router's asset directory is copied into `out_dir` as well, including the fingerprinted
+/// copies of the assets if they were fingerprinted. Returns the files that were written.
+///
+/// # Example
+/// ```no_run
+/// use std::sync::Arc;
+/// use tela::{prelude::*, response::HTML, Router};
+///
+/// #[get("/")]
+/// fn home() -> HTML<String> {
+///     html!(<a href="/blog/hello-world">"Hello, world"</a>)
+/// }
+///
+/// #[get("/blog/:slug")]
+/// fn post(slug: String) -> HTML<String> {
+///     html!(<h1>{slug}</h1>)
+/// }
+///
+/// #[tela::main]
+/// async fn main() {
+///     let mut router = Router::new();
+///     router.route(Arc::new(home));
+///     router.route(Arc::new(post));
+///
+///     tela::ssg::export(&mut router, "dist", &["/", "/blog/hello-world"]).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn export<P: AsRef<Path>>(
+    router: &mut Router,
+    out_dir: P,
+    routes: &[&str],
+) -> Result<Vec<PathBuf>> {
+    let out_dir = out_dir.as_ref();
+    if !router.is_served() {
+        router.serve_routes()?;
+    }
+
+    let mut queue: Vec<String> = match routes.len() {
+        0 => router.static_paths(),
+        _ => routes.iter().map(|r| r.to_string()).collect(),
+    };
+    let listed: HashSet<String> = queue.iter().cloned().collect();
+    let mut visited = HashSet::new();
+    let mut written = Vec::new();
+
+    while !queue.is_empty() {
+        let route = queue.remove(0);
+        if !visited.insert(route.clone()) {
+            continue;
+        }
+
+        let uri = route.parse::<Uri>()?;
+        let request = router.map_request_with(Request {
+            method: Method::GET,
+            uri,
+            headers: HeaderMap::new(),
+            body: Bytes::new(),
+            trailers: HeaderMap::new(),
+            stream: BodyStream::default(),
+            remote: None,
+        });
+        let response = router.map_response_with(router.handle(request).await?);
+        let status = response.status();
+        if !status.is_success() {
+            // Crawled links may point to pages that don't exist; only fail on listed routes
+            if listed.contains(&route) {
+                return Err(
+                    format!("Failed to export {:?}: responded with {}", route, status).into(),
+                );
+            }
+            continue;
+        }
+
+        let is_html = response
+            .headers()
+            .get("Content-Type")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.starts_with("text/html"))
+            .unwrap_or(false);
+        let body = response.into_body().collect().await?.to_bytes();
+
+        if is_html {
+            for link in links(&String::from_utf8_lossy(&body)) {
+                // Links with an extension are assets which are copied below
+                if Path::new(&link).extension().is_none() && !visited.contains(&link) {
+                    queue.push(link);
+                }
+            }
+        }
+
+        let path = output_path(out_dir, &route);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, &body)?;
+        written.push(path);
+    }
+
+    let assets = Path::new(router.asset_path());
+    if assets.is_dir() {
+        let mount = out_dir.join(router.asset_mount().trim_start_matches('/'));
+        copy_dir(assets, &mount)?;
+        // Fingerprinted names are only in the manifest so write a copy under each of them
+        for (fingerprinted, path) in router.fingerprinted() {
+            fs::copy(assets.join(&path), mount.join(&fingerprinted))?;
+        }
+    }
+
+    Ok(written)
+}
@@ -0,0 +1,95 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    ops::Deref,
+    sync::Arc,
+};
+
+use crate::{
+    request::{RequestData, ToParam},
+    response::Result,
+};
+
+/// Values managed by a router, by their type
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StateMap(HashMap<TypeId, Arc<dyn Any + Send + Sync>>);
+
+impl StateMap {
+    /// Managing a value of the same type replaces it
+    pub(crate) fn insert<T: Send + Sync + 'static>(&mut self, value: T) {
+        self.0.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.0
+            .get(&TypeId::of::<T>())
+            .cloned()
+            .and_then(|value| value.downcast::<T>().ok())
+    }
+}
+
+tokio::task_local! {
+    /// State of the router handling the request; see `Server::manage`
+    pub(crate) static STATE: Arc<StateMap>;
+}
+
+/// Value of the type managed by the server handling the request. Outside of a request there
+/// isn't any state.
+pub fn get<T: Send + Sync + 'static>() -> Option<Arc<T>> {
+    STATE.try_with(|state| state.get::<T>()).ok().flatten()
+}
+
+/// Value shared with the handlers through `Server::manage`. Responds with a 500 if a value of the
+/// type isn't managed. Each server has its own state.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, state::State, Server};
+///
+/// struct Config { name: String }
+///
+/// #[get("/")]
+/// fn home(config: State<Config>) -> String {
+///     format!("Welcome to {}", config.name)
+/// }
+///
+/// Server::new()
+///     .manage(Config { name: "tela".to_string() })
+///     .route(home);
+/// ```
+#[derive(Debug)]
+pub struct State<T>(pub Arc<T>);
+
+impl<T> Clone for State<T> {
+    fn clone(&self) -> Self {
+        State(self.0.clone())
+    }
+}
+
+impl<T> Deref for State<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Send + Sync + 'static> ToParam<State<T>> for RequestData {
+    fn to_param(&mut self) -> Result<State<T>> {
+        get::<T>().map(State).ok_or_else(|| {
+            (
+                500,
+                format!(
+                    "State of type {} is not managed by the server",
+                    std::any::type_name::<T>()
+                ),
+            )
+        })
+    }
+}
+
+impl<T: Send + Sync + 'static> ToParam<Option<State<T>>> for RequestData {
+    fn to_param(&mut self) -> Result<Option<State<T>>> {
+        Ok(get::<T>().map(State))
+    }
+}
@@ -0,0 +1,328 @@
+use std::{fmt::Debug, future::Future, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use futures::StreamExt;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc,
+};
+
+use crate::backplane::Backplane;
+
+/// First wait before subscribing to a backplane again, doubled after each failure
+const RETRY: Duration = Duration::from_millis(500);
+
+/// Run a future to completion from inside of a handler; ex: a database query.
+///
+/// Handlers aren't async so this blocks the handler's thread until the future is done while
+/// other requests keep being served. On a current thread runtime, ex:
+/// `#[tela::main(flavor = "current_thread")]`, handlers are run on tokio's blocking threads so
+/// the runtime's thread is free to drive the future. Outside of a handler it panics on a
+/// current thread runtime's thread, since nothing else could drive the future.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(future))
+}
+
+/// Publish and subscribe channel for sending values between handlers; ex: chat messages or live
+/// updates.
+///
+/// Manage the channel with the server so every handler can get it with `State<Channel<T>>`.
+/// Values are only received by subscribers that exist when the value is published. A
+/// subscriber that falls more than `capacity` values behind skips the oldest ones.
+///
+/// Create it with `Channel::with_backplane` to also deliver values to the subscribers in
+/// every other server process.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, request::Body, state::State, sync::Channel, Server};
+///
+/// #[derive(Clone)]
+/// struct Message(String);
+///
+/// #[post("/messages")]
+/// fn send(channel: State<Channel<Message>>, Body(text): Body<String>) -> String {
+///     let received = channel.publish(Message(text));
+///     format!("Sent to {} subscribers", received)
+/// }
+///
+/// #[get("/messages/next")]
+/// fn next(channel: State<Channel<Message>>) -> Result<String> {
+///     match channel.subscribe().blocking_recv() {
+///         Some(Message(text)) => response!(text),
+///         None => response!(503, "Channel closed"),
+///     }
+/// }
+///
+/// Server::new()
+///     .manage(Channel::<Message>::new(100))
+///     .routes(group![send, next]);
+/// ```
+#[derive(Debug)]
+pub struct Channel<T: Clone> {
+    sender: broadcast::Sender<T>,
+    relay: Option<Arc<Relay<T>>>,
+}
+
+impl<T: Clone> Clone for Channel<T> {
+    fn clone(&self) -> Self {
+        Channel {
+            sender: self.sender.clone(),
+            relay: self.relay.clone(),
+        }
+    }
+}
+
+/// Sends published values to the backplane
+struct Relay<T>(Box<dyn Fn(&T) + Send + Sync>);
+
+impl<T> Debug for Relay<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Relay")
+    }
+}
+
+impl<T: Clone + Send + 'static> Channel<T> {
+    /// Create a channel that keeps up to `capacity` values for slow subscribers
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Channel {
+            sender,
+            relay: None,
+        }
+    }
+
+    /// Send a value to every subscriber. Returns the number of subscribers in this process it
+    /// was sent to.
+    pub fn publish(&self, value: T) -> usize {
+        if let Some(relay) = &self.relay {
+            (relay.0)(&value);
+        }
+        self.sender.send(value).unwrap_or(0)
+    }
+
+    /// Receive every value published from now on
+    pub fn subscribe(&self) -> Subscription<T> {
+        Subscription {
+            receiver: self.sender.subscribe(),
+        }
+    }
+
+    /// Number of active subscribers
+    pub fn subscribers(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl<T: Clone + Send + Serialize + DeserializeOwned + 'static> Channel<T> {
+    /// Create a channel whose values are also sent through the backplane on `topic` so
+    /// subscribers in every server process sharing it receive them.
+    ///
+    /// Values are sent as json. Local subscribers get a value as soon as it is published and
+    /// other processes get it once the backplane delivers it. Must be called inside of the
+    /// tokio runtime, ex: in `#[tela::main]`, since the channel runs tasks that publish to and
+    /// listen on the backplane.
+    ///
+    /// The channel subscribes again if the backplane's subscription ends, ex: when its
+    /// connection drops. Values published by other processes in between are missed.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::sync::Arc;
+    /// use serde::{Deserialize, Serialize};
+    /// use tela::{backplane::MemoryBackplane, sync::Channel, Server};
+    ///
+    /// #[derive(Clone, Serialize, Deserialize)]
+    /// struct Message(String);
+    ///
+    /// #[tela::main]
+    /// async fn main() {
+    ///     let backplane = Arc::new(MemoryBackplane::default());
+    ///     Server::new()
+    ///         .manage(Channel::<Message>::with_backplane(100, "chat", backplane))
+    ///         .serve(3000)
+    ///         .await
+    /// }
+    /// ```
+    pub fn with_backplane<S: Into<String>>(
+        capacity: usize,
+        topic: S,
+        backplane: Arc<dyn Backplane>,
+    ) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        let topic: String = topic.into();
+        // Messages are `<origin>\n<json>` so a process skips the values it published itself
+        let origin: String = crate::cookie::random_key()[..8]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        // Published through one task so other processes receive values in order
+        let (outbox, mut queued) = mpsc::unbounded_channel::<Bytes>();
+        {
+            let (backplane, topic) = (backplane.clone(), topic.clone());
+            tokio::spawn(async move {
+                while let Some(message) = queued.recv().await {
+                    if let Err(error) = backplane.publish(&topic, message).await {
+                        eprintln!(
+                            "Failed to publish to backplane topic {:?}: {}",
+                            topic, error
+                        );
+                    }
+                }
+            });
+        }
+
+        {
+            let (sender, origin) = (sender.clone(), origin.clone());
+            tokio::spawn(async move {
+                let mut retry = RETRY;
+                loop {
+                    match backplane.subscribe(&topic).await {
+                        Ok(mut messages) => {
+                            while let Some(message) = messages.next().await {
+                                // Delivering messages again starts the backoff over
+                                retry = RETRY;
+                                let Some(split) = message.iter().position(|byte| *byte == b'\n')
+                                else {
+                                    continue;
+                                };
+                                if &message[..split] == origin.as_bytes() {
+                                    continue;
+                                }
+                                if let Ok(value) =
+                                    serde_json::from_slice::<T>(&message[split + 1..])
+                                {
+                                    let _ = sender.send(value);
+                                }
+                            }
+                            eprintln!(
+                                "Subscription to backplane topic {:?} ended; subscribing again",
+                                topic
+                            );
+                        }
+                        Err(error) => {
+                            eprintln!(
+                                "Failed to subscribe to backplane topic {:?}: {}",
+                                topic, error
+                            );
+                        }
+                    }
+                    tokio::time::sleep(retry).await;
+                    retry = (retry * 2).min(Duration::from_secs(30));
+                }
+            });
+        }
+
+        let relay = move |value: &T| match serde_json::to_vec(value) {
+            Ok(json) => {
+                let mut message = Vec::with_capacity(origin.len() + 1 + json.len());
+                message.extend_from_slice(origin.as_bytes());
+                message.push(b'\n');
+                message.extend(json);
+                let _ = outbox.send(Bytes::from(message));
+            }
+            Err(error) => eprintln!("Failed to serialize value for the backplane: {}", error),
+        };
+
+        Channel {
+            sender,
+            relay: Some(Arc::new(Relay(Box::new(relay)))),
+        }
+    }
+}
+
+/// Values published to a `Channel` after subscribing
+#[derive(Debug)]
+pub struct Subscription<T: Clone> {
+    receiver: broadcast::Receiver<T>,
+}
+
+impl<T: Clone + Send + 'static> Subscription<T> {
+    /// Wait for the next value. Returns `None` once the channel is closed.
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(value) => return Some(value),
+                // Values that were missed are skipped
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Wait for the next value from a handler. Returns `None` once the channel is closed.
+    pub fn blocking_recv(&mut self) -> Option<T> {
+        block_on(self.recv())
+    }
+
+    /// Next value if one has already been published
+    pub fn try_recv(&mut self) -> Option<T> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(value) => return Some(value),
+                Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    use futures::{future::BoxFuture, stream::BoxStream};
+
+    use super::*;
+    use crate::backplane::MemoryBackplane;
+
+    /// Backplane whose first subscription ends right away, ex: a dropped connection
+    #[derive(Debug, Default)]
+    struct Flaky {
+        memory: MemoryBackplane,
+        dropped: AtomicBool,
+    }
+
+    impl Backplane for Flaky {
+        fn publish<'a>(&'a self, topic: &'a str, message: Bytes) -> BoxFuture<'a, io::Result<()>> {
+            self.memory.publish(topic, message)
+        }
+
+        fn subscribe<'a>(
+            &'a self,
+            topic: &'a str,
+        ) -> BoxFuture<'a, io::Result<BoxStream<'static, Bytes>>> {
+            match self.dropped.swap(true, Ordering::SeqCst) {
+                true => self.memory.subscribe(topic),
+                false => Box::pin(async { Ok(futures::stream::empty().boxed()) }),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn ended_subscriptions_are_made_again() {
+        let backplane = Arc::new(Flaky::default());
+        let channel = Channel::<String>::with_backplane(8, "chat", backplane.clone());
+        let mut subscription = channel.subscribe();
+
+        // Published by another process until the channel has subscribed again
+        let received = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let message = Bytes::from_static(b"other\n\"hello\"");
+                backplane.publish("chat", message).await.unwrap();
+                tokio::select! {
+                    value = subscription.recv() => break value,
+                    _ = tokio::time::sleep(Duration::from_millis(50)) => {}
+                }
+            }
+        })
+        .await
+        .expect("the channel didn't subscribe again");
+        assert_eq!(received.as_deref(), Some("hello"));
+    }
+}
@@ -1,7 +1,126 @@
-use std::{collections::HashMap, fmt::Debug};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Debug, Display},
+    ops::Range,
+    sync::{Arc, RwLock},
+};
 
+use lazy_static::lazy_static;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+
+type Validate = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+lazy_static! {
+    static ref TYPES: RwLock<HashMap<String, Validate>> = RwLock::new(HashMap::new());
+}
+
+/// Characters that are encoded in a path segment
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Characters that are encoded in a query key or value
+const QUERY: &AsciiSet = &SEGMENT.add(b'&').add(b'=').add(b'+');
+
+/// Percent decode part of a uri; ex: `hello%20world` -> `hello world`. Invalid utf-8 is replaced.
+pub fn decode(value: &str) -> String {
+    percent_decode_str(value).decode_utf8_lossy().to_string()
+}
+
+/// Percent encode a single path segment; ex: `a b/c` -> `a%20b%2Fc`
+pub fn encode(segment: &str) -> String {
+    utf8_percent_encode(segment, SEGMENT).to_string()
+}
+
+/// Build a path from a route pattern by filling in its captures; ex:
+/// `build("/blog/:slug", &[("slug", "hello world")])` -> `/blog/hello%20world`.
+///
+/// Capture values are encoded. Catch all values keep their `/` between segments.
+/// Captures without a value are left empty.
+pub fn build(pattern: &str, captures: &[(&str, &str)]) -> String {
+    let value = |name: &String| {
+        captures
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| *value)
+            .unwrap_or("")
+    };
+    let path = Token::parse(&pattern.to_string())
+        .iter()
+        .map(|token| match token {
+            Token::Segment(segment) => segment.clone(),
+            Token::Capture(name) | Token::TypedCapture(name, _) => encode(value(name)),
+            Token::CatchAll(name) | Token::RawCatchAll(name) => value(name)
+                .split('/')
+                .map(encode)
+                .collect::<Vec<String>>()
+                .join("/"),
+        })
+        .collect::<Vec<String>>()
+        .join("/");
+    format!("/{}", path)
+}
+
+/// Build an encoded query from key value pairs; ex: `[("q", "a&b")]` -> `q=a%26b`
+pub fn query(pairs: &[(&str, &str)]) -> String {
+    pairs
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{}={}",
+                utf8_percent_encode(key, QUERY),
+                utf8_percent_encode(value, QUERY)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+/// Path of a uri with its query and fragment split off; see `parts`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Parts {
+    pub path: String,
+    /// Text after `?` without the `?`, still encoded; ex: `tab=posts`
+    pub query: Option<String>,
+    /// Text after `#` without the `#`, still encoded
+    pub fragment: Option<String>,
+}
+
+/// Split a uri into its path, query, and fragment; ex: `/users/42?tab=posts#top` ->
+/// `/users/42`, `tab=posts`, and `top`.
+///
+/// The fragment starts at the first `#`, even if a `?` follows it, and the query at the
+/// first `?` before that. Encoded `%3F` and `%23` stay in the path.
+pub fn parts<StrLike: Into<String>>(uri: StrLike) -> Parts {
+    let uri = Into::<String>::into(uri);
+    let (rest, fragment) = match uri.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment.to_string())),
+        None => (uri.as_str(), None),
+    };
+    let (path, query) = match rest.split_once('?') {
+        Some((path, query)) => (path, Some(query.to_string())),
+        None => (rest, None),
+    };
+    Parts {
+        path: path.to_string(),
+        query,
+        fragment,
+    }
+}
+
+/// Split the path of a uri into its segments; ex: `/users/42?tab=posts` -> `["users", "42"]`.
+/// The query and fragment are ignored; see `parts`. Segments are not decoded.
 pub fn split<StrLike: Into<String> + Clone>(uri: StrLike) -> Vec<String> {
-    let mut uri = Into::<String>::into(uri);
+    let mut uri = parts(uri).path;
     if uri.starts_with("/") {
         uri = (&uri[1..]).to_string();
     }
@@ -11,11 +130,110 @@ pub fn split<StrLike: Into<String> + Clone>(uri: StrLike) -> Vec<String> {
     uri.split("/").map(|s| s.to_string()).collect()
 }
 
-#[derive(Debug)]
+/// Type a capture's value must have for a uri to match; ex: `:id<uuid>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CaptureType {
+    /// `int`; ex: `-42`
+    Int,
+    /// `uint`; ex: `42`
+    Uint,
+    /// `float`; a finite number, ex: `1.5` or `-3`
+    Float,
+    /// `bool`; `true` or `false`
+    Bool,
+    /// `uuid`; hex in the `8-4-4-4-12` form
+    Uuid,
+    /// Any other name, which is checked by the validator registered for it with `register_type`
+    /// or `register_enum`. Unregistered types never match.
+    Custom(String),
+}
+
+impl CaptureType {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "int" => CaptureType::Int,
+            "uint" => CaptureType::Uint,
+            "float" => CaptureType::Float,
+            "bool" => CaptureType::Bool,
+            "uuid" => CaptureType::Uuid,
+            name => CaptureType::Custom(name.to_string()),
+        }
+    }
+
+    /// Whether a decoded capture value has this type
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            CaptureType::Int => value.parse::<i64>().is_ok(),
+            CaptureType::Uint => value.parse::<u64>().is_ok(),
+            CaptureType::Float => value.parse::<f64>().is_ok_and(f64::is_finite),
+            CaptureType::Bool => value == "true" || value == "false",
+            CaptureType::Uuid => {
+                value.len() == 36
+                    && value.char_indices().all(|(i, c)| match i {
+                        8 | 13 | 18 | 23 => c == '-',
+                        _ => c.is_ascii_hexdigit(),
+                    })
+            }
+            CaptureType::Custom(name) => match TYPES.read().unwrap().get(name) {
+                Some(validate) => validate(value),
+                None => false,
+            },
+        }
+    }
+}
+
+/// Add a capture type that is checked with `validate`; ex: a slug, or a regex from another
+/// crate. Registering a name again replaces its validator.
+///
+/// # Example
+/// ```
+/// use tela::{prelude::*, response::HTML, uri};
+///
+/// uri::register_type("slug", |value| {
+///     value.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+/// });
+///
+/// #[get("/blog/:slug<slug>")]
+/// fn post(slug: String) -> HTML<String> {
+///     html!(<h1>{slug}</h1>)
+/// }
+/// ```
+pub fn register_type<N, F>(name: N, validate: F)
+where
+    N: Into<String>,
+    F: Fn(&str) -> bool + Send + Sync + 'static,
+{
+    TYPES
+        .write()
+        .unwrap()
+        .insert(name.into(), Arc::new(validate));
+}
+
+/// Add a capture type that matches one of the variants; ex:
+/// `register_enum("color", ["red", "green", "blue"])` for `/paint/:color<color>`
+pub fn register_enum<N, I, V>(name: N, variants: I)
+where
+    N: Into<String>,
+    I: IntoIterator<Item = V>,
+    V: Into<String>,
+{
+    let variants: Vec<String> = variants.into_iter().map(Into::into).collect();
+    register_type(name, move |value| {
+        variants.iter().any(|variant| variant == value)
+    });
+}
+
+/// Part of a route pattern. Captures are percent decoded, ex: `:name` and `:...path`, unless
+/// the catch all is marked raw with `%`, ex: `:...%path`, which keeps the segments as they are in the uri.
+///
+/// A capture can require a type, ex: `:id<uuid>`, so uris with other values don't match.
+#[derive(Debug, Clone)]
 pub enum Token {
     Segment(String),
     Capture(String),
+    TypedCapture(String, CaptureType),
     CatchAll(String),
+    RawCatchAll(String),
 }
 
 impl Token {
@@ -33,10 +251,17 @@ impl Token {
     }
 
     fn capture(segment: &String) -> Token {
-        if segment.starts_with(":...") {
-            Token::CatchAll(segment[4..].to_string())
-        } else if segment.starts_with(":") {
-            Token::Capture(segment.strip_prefix(":").unwrap().to_string())
+        if let Some(name) = segment.strip_prefix(":...%") {
+            Token::RawCatchAll(name.to_string())
+        } else if let Some(name) = segment.strip_prefix(":...") {
+            Token::CatchAll(name.to_string())
+        } else if let Some(capture) = segment.strip_prefix(':') {
+            match capture.strip_suffix('>').and_then(|c| c.split_once('<')) {
+                Some((name, kind)) => {
+                    Token::TypedCapture(name.to_string(), CaptureType::from_name(kind.trim()))
+                }
+                None => Token::Capture(capture.to_string()),
+            }
         } else {
             Token::Capture(segment.to_string())
         }
@@ -49,73 +274,227 @@ impl Token {
             .collect()
     }
 }
-/// None means no match
-/// Some(rank) means the uri works and this is the ranking
-pub fn compare<S: Into<String> + Clone, P: Into<String> + Clone>(uri: &S, pattern: &P) -> Match {
-    let uri = split(uri.clone());
-    let pattern = Token::parse(pattern);
+/// Problem with a route pattern found by `Pattern::parse`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pub message: String,
+    /// Byte range of the segment in the pattern that caused the error
+    pub span: Range<usize>,
+}
 
-    if pattern.len() == 0 {
-        return Match::Discard;
+impl Display for PatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid route pattern at {}..{}: {}",
+            self.span.start, self.span.end, self.message
+        )
     }
+}
 
-    let mut props: HashMap<String, String> = HashMap::new();
-    let mut u = 0;
-    let mut p = 0;
-    let mut catch_all = false;
+impl std::error::Error for PatternError {}
 
-    while u < uri.len() && p < pattern.len() {
-        match &pattern[p] {
-            Token::Segment(pseg) => {
-                if pseg == &uri[u] {
-                    u += 1;
-                    p += 1;
-                } else {
-                    return Match::Discard;
+/// Route pattern that has been checked for mistakes; ex: `/blog/:slug` or `/files/:...path/raw`.
+///
+/// The pattern is split into tokens once so matching a uri doesn't parse the pattern again.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    tokens: Vec<Token>,
+}
+
+impl Pattern {
+    /// Parse a route pattern, returning an error instead of panicking if it can't be matched.
+    ///
+    /// Captures need a name, capture types need a closing `>` and a name, catch alls can't
+    /// have a type and must be followed by a plain segment or end the pattern, and each
+    /// capture name can only be used once.
+    pub fn parse(pattern: &str) -> Result<Pattern, PatternError> {
+        let path = parts(pattern).path;
+        let mut offset = usize::from(path.starts_with('/'));
+        let trimmed = &path[offset..];
+        let trimmed = trimmed.strip_suffix('/').unwrap_or(trimmed);
+
+        let mut tokens = Vec::new();
+        let mut names = HashSet::new();
+        let mut catch_all: Option<Range<usize>> = None;
+        for segment in trimmed.split('/') {
+            let span = offset..offset + segment.len();
+            offset = span.end + 1;
+            let error = |message: String| PatternError {
+                message,
+                span: span.clone(),
+            };
+
+            let token = if segment.starts_with(':') {
+                Token::capture(&segment.to_string())
+            } else {
+                Token::Segment(segment.to_string())
+            };
+
+            if let Some(span) = catch_all.take() {
+                if !matches!(token, Token::Segment(_)) {
+                    return Err(PatternError {
+                        message:
+                            "A catch all must be followed by a plain segment or end the pattern"
+                                .to_string(),
+                        span,
+                    });
                 }
             }
-            Token::Capture(name) => {
-                props.insert(name.clone(), uri[u].to_string());
-                u += 1;
-                p += 1;
+
+            let name = match &token {
+                Token::Segment(_) => None,
+                Token::Capture(name) => {
+                    if name.contains('<') || name.contains('>') {
+                        return Err(error(format!(
+                            "Capture type in `{}` must be closed with `>` at the end",
+                            segment
+                        )));
+                    }
+                    Some(name.clone())
+                }
+                Token::TypedCapture(name, kind) => {
+                    if *kind == CaptureType::Custom(String::new()) {
+                        return Err(error(format!("Capture `{}` has an empty type", segment)));
+                    }
+                    Some(name.clone())
+                }
+                Token::CatchAll(name) | Token::RawCatchAll(name) => {
+                    if name.contains('<') {
+                        return Err(error(format!("Catch all `{}` can't have a type", segment)));
+                    }
+                    catch_all = Some(span.clone());
+                    Some(name.clone())
+                }
+            };
+
+            if let Some(name) = name {
+                if name.is_empty() {
+                    return Err(error(format!("Capture `{}` is missing a name", segment)));
+                }
+                if !names.insert(name.clone()) {
+                    return Err(error(format!("Capture `{}` is used more than once", name)));
+                }
             }
-            Token::CatchAll(name) => {
-                catch_all = true;
-                if p < pattern.len() - 1 {
+            tokens.push(token);
+        }
+
+        Ok(Pattern { tokens })
+    }
+
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Match a uri against the pattern. Captured values are percent decoded unless the
+    /// catch all is raw.
+    ///
+    /// Segments are split before decoding, so `%2F` stays in one capture, and plain segments
+    /// are compared without decoding. Captures are data, not paths: `/users/%2e%2e` matches
+    /// `/users/:id` with `..` as the id, so check a value before using it as a file path.
+    pub fn compare(&self, uri: &str) -> Match {
+        let uri = split(uri);
+        let pattern = &self.tokens;
+
+        if pattern.is_empty() {
+            return Match::Discard;
+        }
+
+        let mut props: HashMap<String, String> = HashMap::new();
+        let mut u = 0;
+        let mut p = 0;
+        let mut catch_all = false;
+
+        while u < uri.len() && p < pattern.len() {
+            match &pattern[p] {
+                Token::Segment(pseg) => {
+                    if pseg == &uri[u] {
+                        u += 1;
+                        p += 1;
+                    } else {
+                        return Match::Discard;
+                    }
+                }
+                Token::Capture(name) => {
+                    props.insert(name.clone(), decode(&uri[u]));
+                    u += 1;
                     p += 1;
-                    if let Token::Segment(pseg) = &pattern[p] {
-                        // iterate until segment found or return None
-                        let start = u.clone();
-                        match uri[start..].iter().position(|r| r == pseg) {
-                            Some(index) => {
-                                props.insert(name.clone(), uri[start..start + index].join("/"));
-                                p += 1;
-                                u += index;
+                }
+                Token::TypedCapture(name, kind) => {
+                    let value = decode(&uri[u]);
+                    if !kind.matches(&value) {
+                        return Match::Discard;
+                    }
+                    props.insert(name.clone(), value);
+                    u += 1;
+                    p += 1;
+                }
+                Token::CatchAll(name) | Token::RawCatchAll(name) => {
+                    catch_all = true;
+                    let raw = matches!(pattern[p], Token::RawCatchAll(_));
+                    let join = |segments: &[String]| match raw {
+                        true => segments.join("/"),
+                        false => segments
+                            .iter()
+                            .map(|segment| decode(segment))
+                            .collect::<Vec<String>>()
+                            .join("/"),
+                    };
+                    if p < pattern.len() - 1 {
+                        p += 1;
+                        if let Token::Segment(pseg) = &pattern[p] {
+                            // iterate until segment found or return None
+                            match uri[u..].iter().position(|r| r == pseg) {
+                                Some(index) => {
+                                    props.insert(name.clone(), join(&uri[u..u + index]));
+                                    p += 1;
+                                    // Past the segment that ended the catch all
+                                    u += index + 1;
+                                }
+                                None => return Match::Discard,
                             }
-                            None => return Match::Discard,
+                        } else {
+                            // Rejected by `Pattern::parse`
+                            return Match::Discard;
                         }
                     } else {
-                        panic!("Expected path capture to have a normal segment following it")
+                        props.insert(name.clone(), join(&uri[u..]));
+                        p += 1;
+                        u += uri.len();
                     }
-                } else {
-                    props.insert(name.clone(), (&uri[u..]).join("/"));
-                    p += 1;
-                    u += uri.len();
                 }
             }
         }
-    }
 
-    if (u == uri.len() && p < pattern.len()) || (p == pattern.len() && u < uri.len()) {
-        Match::Discard
-    } else {
-        let count = (pattern.len() - props.values().into_iter().count()) as u8;
-        if catch_all {
-            Match::Partial(count, props)
+        if (u == uri.len() && p < pattern.len()) || (p == pattern.len() && u < uri.len()) {
+            Match::Discard
         } else {
-            Match::Full(count, props)
+            let count = (pattern.len() - props.len()) as u8;
+            if catch_all {
+                Match::Partial(count, props)
+            } else {
+                Match::Full(count, props)
+            }
         }
     }
+
+    /// Captures from the uri if it matches the pattern; ex: `/users/:id` and `/users/42` ->
+    /// `{"id": "42"}`
+    pub fn match_uri(&self, uri: &str) -> Option<HashMap<String, String>> {
+        match self.compare(uri) {
+            Match::Full(_, props) | Match::Partial(_, props) => Some(props),
+            Match::Discard => None,
+        }
+    }
+}
+
+/// Match a uri against a pattern string. The pattern is parsed on every call; parse it once
+/// with `Pattern::parse` when matching many uris.
+pub fn compare<S: Into<String> + Clone, P: Into<String> + Clone>(uri: &S, pattern: &P) -> Match {
+    match Pattern::parse(&pattern.clone().into()) {
+        Ok(pattern) => pattern.compare(&uri.clone().into()),
+        Err(_) => Match::Discard,
+    }
 }
 
 pub fn props<S: Into<String> + Clone, P: Into<String> + Clone>(
@@ -133,7 +512,10 @@ pub fn parse_props<P: Into<String> + Clone>(pattern: &P) -> Vec<String> {
     let mut props = Vec::new();
     for token in Token::parse(pattern).iter() {
         match token {
-            Token::Capture(name) | Token::CatchAll(name) => {
+            Token::Capture(name)
+            | Token::TypedCapture(name, _)
+            | Token::CatchAll(name)
+            | Token::RawCatchAll(name) => {
                 props.push(name.clone());
             }
             _ => (),
@@ -149,32 +531,36 @@ pub enum Match {
     Discard,
 }
 
+/// Index of the route the uri should go to when more than one matches:
+///
+/// 1. Matches without a catch all win over matches with one
+/// 2. Then the match with the most plain segments wins; ex: `/users/me` over `/users/:id`
+/// 3. Then the route that was added first wins
 pub fn index(uri: &String, routes: &Vec<String>) -> Option<usize> {
-    let mut ranks: Vec<(u8, usize)> = Vec::new();
-    let mut full: Option<(u8, usize)> = None;
-    for (i, pattern) in routes.iter().enumerate() {
-        match compare(uri, pattern) {
-            Match::Full(exact, _) => match &full {
-                Some((e, _)) => {
-                    if e < &exact {
-                        full = Some((exact, i))
-                    }
-                }
-                None => full = Some((exact, i)),
-            },
-            Match::Partial(rank, _) => {
-                ranks.push((rank, i));
-            }
-            _ => (),
-        }
-    }
-    ranks.sort_by(|f, s| (s.1 as u8).cmp(&(f.1 as u8)));
+    best(routes.iter().map(|pattern| compare(uri, pattern)))
+}
+
+/// Same as `index` for patterns that have already been parsed
+pub fn index_patterns(uri: &str, patterns: &[Pattern]) -> Option<usize> {
+    best(patterns.iter().map(|pattern| pattern.compare(uri)))
+}
 
-    match full {
-        Some((_e, index)) => Some(index),
-        None if ranks.len() > 0 => Some(ranks[0].1),
-        _ => None,
+/// Index of the best match; see `index`
+fn best<I: Iterator<Item = Match>>(matches: I) -> Option<usize> {
+    // (full, rank, index) of the best match so far
+    let mut best: Option<(bool, u8, usize)> = None;
+    for (i, found) in matches.enumerate() {
+        let (full, rank) = match found {
+            Match::Full(rank, _) => (true, rank),
+            Match::Partial(rank, _) => (false, rank),
+            Match::Discard => continue,
+        };
+        // Only a strictly better match replaces the current one so ties keep the first route
+        if best.is_none_or(|(f, r, _)| (full, rank) > (f, r)) {
+            best = Some((full, rank, i));
+        }
     }
+    best.map(|(_, _, index)| index)
 }
 
 pub fn find<'a, StrLike: Into<String> + Clone>(
@@ -187,3 +573,207 @@ pub fn find<'a, StrLike: Into<String> + Clone>(
     )
     .map(|index| (routes[index]).to_string())
 }
+
+/// Whether two strings are the same, optionally ignoring ascii case. Used by `routes!` at
+/// compile time.
+#[doc(hidden)]
+pub const fn same(a: &str, b: &str, ignore_case: bool) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        let equal = match ignore_case {
+            true => a[i].eq_ignore_ascii_case(&b[i]),
+            false => a[i] == b[i],
+        };
+        if !equal {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Whether a path is in the list more than once. Used by `routes!` to reject duplicate paths
+/// at compile time.
+#[doc(hidden)]
+pub const fn has_duplicate(paths: &[&str]) -> bool {
+    let mut i = 0;
+    while i < paths.len() {
+        let mut j = i + 1;
+        while j < paths.len() {
+            if same(paths[i], paths[j], false) {
+                return true;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    false
+}
+
+/// Whether a handler accepts the method; ex: `get`. Used by `routes!` at compile time.
+#[doc(hidden)]
+pub const fn has_method(methods: &[&str], method: &str) -> bool {
+    let mut i = 0;
+    while i < methods.len() {
+        if same(methods[i], method, true) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn captures(pattern: &str, uri: &str) -> Option<HashMap<String, String>> {
+        Pattern::parse(pattern).unwrap().match_uri(uri)
+    }
+
+    #[test]
+    fn route_tables_are_checked() {
+        let cases: [(&[&str], bool); 5] = [
+            (&[], false),
+            (&["/"], false),
+            (&["/", "/static", "/users/:id"], false),
+            (&["/", "/static", "/"], true),
+            (&["/users", "/Users"], false),
+        ];
+        for (paths, duplicate) in cases {
+            assert_eq!(has_duplicate(paths), duplicate, "{:?}", paths);
+        }
+
+        assert!(has_method(&["GET", "POST"], "post"));
+        assert!(!has_method(&["GET"], "put"));
+        assert!(!has_method(&[], "get"));
+        assert!(!same("/users", "/users/", false));
+    }
+
+    #[test]
+    fn query_and_fragment_are_split_off() {
+        let cases = [
+            ("/users/42", "/users/42", None, None),
+            ("/users/42?tab=posts", "/users/42", Some("tab=posts"), None),
+            ("/users/42#top", "/users/42", None, Some("top")),
+            (
+                "/users/42?tab=posts#top",
+                "/users/42",
+                Some("tab=posts"),
+                Some("top"),
+            ),
+            (
+                "/users/42#top?tab=posts",
+                "/users/42",
+                None,
+                Some("top?tab=posts"),
+            ),
+            ("/users/42?", "/users/42", Some(""), None),
+            ("/a%3Fb%23c?q=%23", "/a%3Fb%23c", Some("q=%23"), None),
+        ];
+        for (uri, path, query, fragment) in cases {
+            let parts = parts(uri);
+            assert_eq!(parts.path, path, "{}", uri);
+            assert_eq!(parts.query.as_deref(), query, "{}", uri);
+            assert_eq!(parts.fragment.as_deref(), fragment, "{}", uri);
+        }
+
+        assert_eq!(split("/users/42/?tab=posts#top"), vec!["users", "42"]);
+        assert_eq!(split("/a%2Fb/c%3Fd"), vec!["a%2Fb", "c%3Fd"]);
+        let id = captures("/users/:id", "/users/42?tab=posts#top");
+        assert_eq!(id.unwrap()["id"], "42");
+    }
+
+    #[test]
+    fn captures_are_decoded() {
+        let cases = [
+            ("/users/:id", "/users/a%20b", Some("a b")),
+            ("/users/:id", "/users/a%2Fb", Some("a/b")),
+            ("/users/:id", "/users/%3F%23", Some("?#")),
+            ("/users/:id", "/users/caf%C3%A9", Some("café")),
+            ("/users/:id", "/users/%FF", Some("\u{FFFD}")),
+            ("/users/:id", "/users/%2e%2e", Some("..")),
+            ("/users/:id", "/users/%2E", Some(".")),
+            ("/users/:id", "/users/a%2Fb/c", None),
+            ("/users/:id<int>", "/users/%2D4%32", Some("-42")),
+        ];
+        for (pattern, uri, id) in cases {
+            let found = captures(pattern, uri);
+            assert_eq!(
+                found.as_ref().map(|props| props["id"].as_str()),
+                id,
+                "{}",
+                uri
+            );
+        }
+
+        let path = captures("/files/:...path", "/files/a%20b/%2e%2e/c%2Fd").unwrap();
+        assert_eq!(path["path"], "a b/../c/d");
+        let raw = captures("/files/:...%path", "/files/a%20b/%2e%2e/c%2Fd").unwrap();
+        assert_eq!(raw["path"], "a%20b/%2e%2e/c%2Fd");
+    }
+
+    #[test]
+    fn best_match_wins() {
+        // (routes in the order they were added, uri, index of the route it goes to)
+        let cases: &[(&[&str], &str, Option<usize>)] = &[
+            (&["/users/:id", "/users/me"], "/users/me", Some(1)),
+            (&["/users/me", "/users/:id"], "/users/me", Some(0)),
+            (&["/users/me", "/users/:id"], "/users/42", Some(1)),
+            // Full matches win over partial ones, even with fewer plain segments
+            (&["/users/:...rest", "/users/:id"], "/users/42", Some(1)),
+            (&["/:a/:b", "/users/:...rest"], "/users/42", Some(0)),
+            (
+                &["/users/:...rest", "/:...rest"],
+                "/users/42/posts",
+                Some(0),
+            ),
+            (
+                &["/:...rest", "/users/:...rest"],
+                "/users/42/posts",
+                Some(1),
+            ),
+            // Ties go to the route added first
+            (&["/:a/b", "/a/:b"], "/a/b", Some(0)),
+            (&["/a/:b", "/:a/b"], "/a/b", Some(0)),
+            (&["/:...a", "/:...b"], "/a", Some(0)),
+            (&["/users/:id<int>", "/users/:name"], "/users/42", Some(0)),
+            (&["/users/:id<int>", "/users/:name"], "/users/me", Some(1)),
+            (&["/users/:id", "/posts/:id"], "/comments/1", None),
+            (&[], "/users/1", None),
+        ];
+        for (routes, uri, expected) in cases {
+            let patterns: Vec<Pattern> = routes
+                .iter()
+                .map(|route| Pattern::parse(route).unwrap())
+                .collect();
+            assert_eq!(
+                index_patterns(uri, &patterns),
+                *expected,
+                "{} {:?}",
+                uri,
+                routes
+            );
+            let routes: Vec<String> = routes.iter().map(|route| route.to_string()).collect();
+            assert_eq!(
+                index(&uri.to_string(), &routes),
+                *expected,
+                "{} {:?}",
+                uri,
+                routes
+            );
+        }
+    }
+
+    #[test]
+    fn plain_segments_are_not_decoded() {
+        assert!(captures("/users/me", "/users/me").is_some());
+        assert!(captures("/users/me", "/users/%6De").is_none());
+        assert!(captures("/a b", "/a%20b").is_none());
+        assert!(captures("/a%20b", "/a%20b").is_some());
+    }
+}
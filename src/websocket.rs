@@ -0,0 +1,703 @@
+use std::{fmt::Display, future::Future, marker::PhantomData, sync::Arc, time::Duration};
+
+use bytes::Bytes;
+use futures::{future::BoxFuture, SinkExt, StreamExt};
+use http_body_util::Full;
+use hyper::{
+    body::Incoming,
+    header::{
+        HeaderName, HeaderValue, CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY,
+        SEC_WEBSOCKET_PROTOCOL, SEC_WEBSOCKET_VERSION, UPGRADE,
+    },
+    HeaderMap, Method, Uri,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::{
+    sync::{mpsc, OwnedSemaphorePermit, Semaphore},
+    time::{sleep_until, Instant},
+};
+use tokio_tungstenite::{
+    tungstenite::{
+        client::IntoClientRequest,
+        error::UrlError,
+        handshake::derive_accept_key,
+        protocol::{frame::coding::CloseCode, CloseFrame, Role, WebSocketConfig as ProtocolConfig},
+    },
+    WebSocketStream,
+};
+
+pub use tokio_tungstenite::tungstenite::{Error, Message};
+
+use crate::{
+    client::{self, Io},
+    router::Scope,
+    shutdown::TaskGuard,
+    support::TokioIo,
+    uri::Pattern,
+};
+
+/// Limits enforced on the websockets of a route.
+///
+/// By default there isn't a limit on connections or idle time and messages can be up to 64 MiB.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use tela::{
+///     websocket::{WebSocket, WebSocketConfig},
+///     Server,
+/// };
+///
+/// async fn chat(mut socket: WebSocket) {
+///     socket.close().await.ok();
+/// }
+///
+/// Server::new().websocket_with(
+///     "/chat",
+///     WebSocketConfig::new()
+///         .max_connections(1000)
+///         .max_message_size(64 * 1024)
+///         .idle_timeout(Duration::from_secs(60)),
+///     chat,
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WebSocketConfig {
+    max_connections: Option<usize>,
+    max_message_size: Option<usize>,
+    max_frame_size: Option<usize>,
+    idle_timeout: Option<Duration>,
+    protocols: Vec<String>,
+}
+
+impl WebSocketConfig {
+    pub fn new() -> Self {
+        WebSocketConfig::default()
+    }
+
+    /// Max number of open connections. Upgrades past the limit are rejected with a 503.
+    pub fn max_connections(mut self, connections: usize) -> Self {
+        self.max_connections = Some(connections);
+        self
+    }
+
+    /// Max size of a message in bytes. Larger messages close the connection.
+    pub fn max_message_size(mut self, size: usize) -> Self {
+        self.max_message_size = Some(size);
+        self
+    }
+
+    /// Max size of a single frame in bytes. Larger frames close the connection.
+    pub fn max_frame_size(mut self, size: usize) -> Self {
+        self.max_frame_size = Some(size);
+        self
+    }
+
+    /// Close connections that don't receive a message for this long
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Subprotocols the route supports, in order of preference.
+    ///
+    /// The upgrade picks the first of these that the client asks for and the handler can read it
+    /// with `WebSocket::protocol`. If the client asks for protocols but none are supported the
+    /// upgrade still succeeds without a protocol, leaving it to the client to close the connection.
+    pub fn protocols<S: Display>(mut self, protocols: impl IntoIterator<Item = S>) -> Self {
+        self.protocols = protocols.into_iter().map(|p| p.to_string()).collect();
+        self
+    }
+
+    /// First supported protocol that the client asked for
+    fn negotiate(&self, headers: &HeaderMap) -> Option<String> {
+        let requested: Vec<&str> = headers
+            .get_all(SEC_WEBSOCKET_PROTOCOL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|value| value.trim())
+            .collect();
+        self.protocols
+            .iter()
+            .find(|protocol| requested.contains(&protocol.as_str()))
+            .cloned()
+    }
+
+    fn tungstenite(&self) -> ProtocolConfig {
+        let mut config = ProtocolConfig::default();
+        if self.max_message_size.is_some() {
+            config.max_message_size = self.max_message_size;
+        }
+        if self.max_frame_size.is_some() {
+            config.max_frame_size = self.max_frame_size;
+        }
+        config
+    }
+}
+
+/// Websocket connection passed to a websocket handler or opened with `connect`
+pub struct WebSocket {
+    stream: WebSocketStream<Box<dyn Io>>,
+    idle_timeout: Option<Duration>,
+    uri: Uri,
+    headers: HeaderMap,
+    protocol: Option<String>,
+    // Held until the connection is dropped so it counts towards the connection limit
+    _permit: Option<OwnedSemaphorePermit>,
+    // Held so a graceful shutdown waits for server connections to close
+    guard: Option<TaskGuard>,
+}
+
+impl WebSocket {
+    /// Wait for the next message. Returns `None` when the connection is closed or has been idle
+    /// for longer than the route's idle timeout.
+    pub async fn next(&mut self) -> Option<Result<Message, Error>> {
+        match self.idle_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, self.stream.next()).await {
+                Ok(message) => message,
+                Err(_) => {
+                    let _ = self
+                        .stream
+                        .close(Some(CloseFrame {
+                            code: CloseCode::Away,
+                            reason: "Idle timeout".into(),
+                        }))
+                        .await;
+                    None
+                }
+            },
+            None => self.stream.next().await,
+        }
+    }
+
+    pub async fn send(&mut self, message: Message) -> Result<(), Error> {
+        self.stream.send(message).await
+    }
+
+    /// Start the close handshake
+    pub async fn close(&mut self) -> Result<(), Error> {
+        self.stream.close(None).await
+    }
+
+    /// Uri the connection was upgraded from, or connected to for client connections
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// Headers of the upgrade request, or of the handshake response for client connections
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Subprotocol chosen during the upgrade, see `WebSocketConfig::protocols`
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// Serialize the value and send it as a text message
+    pub async fn send_json<T: Serialize>(&mut self, value: &T) -> Result<(), CodecError> {
+        let text = serde_json::to_string(value)?;
+        Ok(self.send(Message::Text(text)).await?)
+    }
+
+    /// Wait for the next text or binary message and deserialize it.
+    ///
+    /// Ping and pong messages are skipped. Returns `None` when the connection is closed.
+    pub async fn next_json<T: DeserializeOwned>(&mut self) -> Option<Result<T, CodecError>> {
+        loop {
+            match decode(self.next().await?) {
+                Decoded::Value(value) => return Some(value),
+                Decoded::Skip => continue,
+                Decoded::Closed => return None,
+            }
+        }
+    }
+
+    /// Hand the connection to a background task and return a sender and receiver for it.
+    ///
+    /// The task answers pings, completes close handshakes, and closes the connection once it
+    /// has been idle for longer than the idle timeout from the `WebSocketConfig` or when the
+    /// server shuts down gracefully. Only text,
+    /// binary, and close messages are passed to the receiver. The connection is closed once
+    /// every sender and the receiver have been dropped.
+    ///
+    /// # Example
+    /// ```
+    /// use futures::StreamExt;
+    /// use lazy_static::lazy_static;
+    /// use tela::{
+    ///     sync::Channel,
+    ///     websocket::{Message, WebSocket},
+    /// };
+    ///
+    /// lazy_static! {
+    ///     static ref CHAT: Channel<String> = Channel::new(100);
+    /// }
+    ///
+    /// async fn chat(socket: WebSocket) {
+    ///     let (sender, mut receiver) = socket.split_managed();
+    ///     let mut messages = CHAT.subscribe();
+    ///     tokio::spawn(async move {
+    ///         while let Some(message) = messages.recv().await {
+    ///             if sender.send(Message::Text(message.into())).await.is_err() {
+    ///                 break;
+    ///             }
+    ///         }
+    ///     });
+    ///     while let Some(Ok(Message::Text(message))) = receiver.next().await {
+    ///         CHAT.publish(message.to_string());
+    ///     }
+    /// }
+    /// ```
+    pub fn split_managed(mut self) -> (WebSocketSender, WebSocketReceiver) {
+        let (sender, mut outgoing) = mpsc::channel::<Message>(32);
+        let (incoming, receiver) = mpsc::channel(32);
+
+        tokio::spawn(async move {
+            let idle_timeout = self.idle_timeout;
+            let mut deadline = idle_timeout.map(|timeout| Instant::now() + timeout);
+            let mut sending = true;
+            // Only websockets of a server close when it shuts down
+            let mut shutdown = self.guard.as_ref().map(TaskGuard::signal);
+            loop {
+                tokio::select! {
+                    message = self.stream.next() => match message {
+                        // Pongs are queued when pings are read and written on flush
+                        Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_))) => {
+                            if self.stream.flush().await.is_err() {
+                                break;
+                            }
+                        }
+                        Some(message) => {
+                            deadline = idle_timeout.map(|timeout| Instant::now() + timeout);
+                            // Messages are dropped if the receiver is gone
+                            let _ = incoming.send(message).await;
+                        }
+                        None => break,
+                    },
+                    message = outgoing.recv(), if sending => match message {
+                        Some(message) => {
+                            if self.stream.send(message).await.is_err() {
+                                break;
+                            }
+                        }
+                        None => sending = false,
+                    },
+                    _ = incoming.closed(), if !sending => {
+                        self.finish(None).await;
+                        break;
+                    }
+                    _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                        self.finish(Some(CloseFrame {
+                            code: CloseCode::Away,
+                            reason: "Idle timeout".into(),
+                        }))
+                        .await;
+                        break;
+                    }
+                    _ = async { shutdown.as_mut().unwrap().await }, if shutdown.is_some() => {
+                        self.finish(Some(CloseFrame {
+                            code: CloseCode::Away,
+                            reason: "Server shutting down".into(),
+                        }))
+                        .await;
+                        break;
+                    }
+                }
+            }
+        });
+
+        (WebSocketSender { sender }, WebSocketReceiver { receiver })
+    }
+
+    /// Start the close handshake and wait a little while for the peer to finish it
+    async fn finish(&mut self, frame: Option<CloseFrame<'static>>) {
+        if self.stream.close(frame).await.is_ok() {
+            let _ = tokio::time::timeout(Duration::from_secs(5), async {
+                while self.stream.next().await.is_some() {}
+            })
+            .await;
+        }
+    }
+
+    /// Wrap the connection so it only receives `I` messages and only sends `O` messages
+    ///
+    /// # Example
+    /// ```
+    /// use futures::StreamExt;
+    /// use serde::{Deserialize, Serialize};
+    /// use tela::websocket::WebSocket;
+    ///
+    /// #[derive(Deserialize)]
+    /// enum Request { Join(String), Leave }
+    ///
+    /// #[derive(Serialize)]
+    /// enum Event { Joined(String), Left }
+    ///
+    /// async fn room(socket: WebSocket) {
+    ///     let mut socket = socket.typed::<Request, Event>();
+    ///     while let Some(Ok(request)) = socket.next().await {
+    ///         let event = match request {
+    ///             Request::Join(name) => Event::Joined(name),
+    ///             Request::Leave => Event::Left,
+    ///         };
+    ///         if socket.send(&event).await.is_err() {
+    ///             break;
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn typed<I, O>(self) -> TypedWebSocket<I, O>
+    where
+        I: DeserializeOwned,
+        O: Serialize,
+    {
+        TypedWebSocket {
+            socket: self,
+            _protocol: PhantomData,
+        }
+    }
+}
+
+enum Decoded<T> {
+    Value(Result<T, CodecError>),
+    Skip,
+    Closed,
+}
+
+/// Deserialize text and binary messages, skipping control messages
+fn decode<T: DeserializeOwned>(message: Result<Message, Error>) -> Decoded<T> {
+    let value = match message {
+        Ok(Message::Text(text)) => serde_json::from_str(&text),
+        Ok(Message::Binary(data)) => serde_json::from_slice(&data),
+        Ok(Message::Close(_)) => return Decoded::Closed,
+        Ok(_) => return Decoded::Skip,
+        Err(error) => return Decoded::Value(Err(error.into())),
+    };
+    Decoded::Value(value.map_err(CodecError::from))
+}
+
+/// Sending half of a managed websocket, see `WebSocket::split_managed`
+#[derive(Clone)]
+pub struct WebSocketSender {
+    sender: mpsc::Sender<Message>,
+}
+
+impl WebSocketSender {
+    /// Queue a message to be sent. Fails once the connection is closed.
+    pub async fn send(&self, message: Message) -> Result<(), Error> {
+        self.sender
+            .send(message)
+            .await
+            .map_err(|_| Error::ConnectionClosed)
+    }
+
+    /// Serialize the value and queue it to be sent as a text message
+    pub async fn send_json<T: Serialize>(&self, value: &T) -> Result<(), CodecError> {
+        let text = serde_json::to_string(value)?;
+        Ok(self.send(Message::Text(text)).await?)
+    }
+
+    /// Start the close handshake
+    pub async fn close(&self) -> Result<(), Error> {
+        self.send(Message::Close(None)).await
+    }
+
+    /// Whether the connection has been closed
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+/// Receiving half of a managed websocket, see `WebSocket::split_managed`
+pub struct WebSocketReceiver {
+    receiver: mpsc::Receiver<Result<Message, Error>>,
+}
+
+impl WebSocketReceiver {
+    /// Wait for the next text, binary, or close message. Returns `None` once the connection is
+    /// closed.
+    pub async fn next(&mut self) -> Option<Result<Message, Error>> {
+        self.receiver.recv().await
+    }
+
+    /// Wait for the next text or binary message and deserialize it. Returns `None` once the
+    /// connection is closed.
+    pub async fn next_json<T: DeserializeOwned>(&mut self) -> Option<Result<T, CodecError>> {
+        loop {
+            match decode(self.next().await?) {
+                Decoded::Value(value) => return Some(value),
+                Decoded::Skip => continue,
+                Decoded::Closed => return None,
+            }
+        }
+    }
+}
+
+/// Error sending or receiving a JSON message
+#[derive(Debug)]
+pub enum CodecError {
+    /// The connection failed
+    Socket(Error),
+    /// The message couldn't be serialized or deserialized
+    Json(serde_json::Error),
+}
+
+impl Display for CodecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CodecError::Socket(error) => write!(f, "websocket error: {}", error),
+            CodecError::Json(error) => write!(f, "invalid websocket message: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl From<Error> for CodecError {
+    fn from(value: Error) -> Self {
+        CodecError::Socket(value)
+    }
+}
+
+impl From<serde_json::Error> for CodecError {
+    fn from(value: serde_json::Error) -> Self {
+        CodecError::Json(value)
+    }
+}
+
+/// Websocket that receives `I` and sends `O` as JSON messages. Created with `WebSocket::typed`.
+pub struct TypedWebSocket<I, O> {
+    socket: WebSocket,
+    _protocol: PhantomData<fn(O) -> I>,
+}
+
+impl<I, O> TypedWebSocket<I, O>
+where
+    I: DeserializeOwned,
+    O: Serialize,
+{
+    /// Wait for the next message. Returns `None` when the connection is closed.
+    pub async fn next(&mut self) -> Option<Result<I, CodecError>> {
+        self.socket.next_json().await
+    }
+
+    pub async fn send(&mut self, message: &O) -> Result<(), CodecError> {
+        self.socket.send_json(message).await
+    }
+
+    /// Start the close handshake
+    pub async fn close(&mut self) -> Result<(), Error> {
+        self.socket.close().await
+    }
+
+    /// The underlying untyped connection
+    pub fn inner(&mut self) -> &mut WebSocket {
+        &mut self.socket
+    }
+
+    pub fn into_inner(self) -> WebSocket {
+        self.socket
+    }
+}
+
+type Handler = Arc<dyn Fn(WebSocket) -> BoxFuture<'static, ()> + Send + Sync>;
+
+/// Route that upgrades requests to websockets and passes them to a handler
+#[derive(Clone)]
+pub(crate) struct WebSocketRoute {
+    pattern: Pattern,
+    config: WebSocketConfig,
+    connections: Option<Arc<Semaphore>>,
+    handler: Handler,
+}
+
+impl WebSocketRoute {
+    pub(crate) fn new<F, Fut>(path: &str, config: WebSocketConfig, handler: F) -> Self
+    where
+        F: Fn(WebSocket) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        WebSocketRoute {
+            pattern: Pattern::parse(path)
+                .unwrap_or_else(|error| panic!("Websocket path {} is invalid: {}", path, error)),
+            connections: config
+                .max_connections
+                .map(|connections| Arc::new(Semaphore::new(connections))),
+            config,
+            handler: Arc::new(move |socket| Box::pin(handler(socket))),
+        }
+    }
+
+    pub(crate) fn matches(&self, path: &str) -> bool {
+        self.pattern.match_uri(path).is_some()
+    }
+
+    /// Whether the request asks to be upgraded to a websocket
+    pub(crate) fn is_upgrade(headers: &HeaderMap) -> bool {
+        let contains = |name, token: &str| {
+            headers
+                .get_all(name)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .any(|value| value.trim().eq_ignore_ascii_case(token))
+        };
+        contains(UPGRADE, "websocket") && contains(CONNECTION, "upgrade")
+    }
+
+    /// Complete the handshake and run the handler, with the router's settings, once the
+    /// connection is upgraded.
+    ///
+    /// Returns the status and reason if the upgrade is rejected.
+    pub(crate) fn upgrade(
+        &self,
+        request: hyper::Request<Incoming>,
+        scope: Option<Scope>,
+    ) -> Result<hyper::Response<Full<Bytes>>, (u16, String)> {
+        if request.method() != Method::GET {
+            return Err((405, "Websocket upgrades must be GET requests".to_string()));
+        }
+        if request
+            .headers()
+            .get(SEC_WEBSOCKET_VERSION)
+            .map(|version| version != "13")
+            .unwrap_or(true)
+        {
+            return Err((426, "Unsupported websocket version".to_string()));
+        }
+        let accept = match request.headers().get(SEC_WEBSOCKET_KEY) {
+            Some(key) => derive_accept_key(key.as_bytes()),
+            None => return Err((400, "Missing Sec-WebSocket-Key header".to_string())),
+        };
+
+        let permit = match &self.connections {
+            Some(connections) => match connections.clone().try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => return Err((503, "Too many websocket connections".to_string())),
+            },
+            None => None,
+        };
+
+        let protocol = self.config.negotiate(request.headers());
+        let mut response = hyper::Response::builder()
+            .status(101)
+            .header(UPGRADE, "websocket")
+            .header(CONNECTION, "Upgrade")
+            .header(SEC_WEBSOCKET_ACCEPT, accept);
+        if let Some(protocol) = &protocol {
+            response = response.header(SEC_WEBSOCKET_PROTOCOL, protocol);
+        }
+
+        let guard = scope.as_ref().map(|scope| scope.shutdown().guard());
+        let (uri, headers) = (request.uri().clone(), request.headers().clone());
+        let (config, handler) = (self.config.clone(), self.handler.clone());
+        let run = async move {
+            match hyper::upgrade::on(request).await {
+                Ok(upgraded) => {
+                    let stream = WebSocketStream::from_raw_socket(
+                        Box::new(TokioIo::new(upgraded)) as Box<dyn Io>,
+                        Role::Server,
+                        Some(config.tungstenite()),
+                    )
+                    .await;
+                    handler(WebSocket {
+                        stream,
+                        idle_timeout: config.idle_timeout,
+                        uri,
+                        headers,
+                        protocol,
+                        _permit: permit,
+                        guard,
+                    })
+                    .await
+                }
+                Err(error) => eprintln!("Failed to upgrade websocket: {}", error),
+            }
+        };
+        tokio::task::spawn(async move {
+            match scope {
+                Some(scope) => scope.run(run).await,
+                None => run.await,
+            }
+        });
+
+        Ok(response.body(Full::new(Bytes::new())).unwrap())
+    }
+}
+
+/// Open a websocket to a `ws://` or `wss://` uri.
+///
+/// `wss://` uris require the `tls` feature and verify the server against the webpki roots.
+///
+/// # Example
+/// ```no_run
+/// use tela::websocket::{connect, Message};
+///
+/// # async fn example() -> Result<(), tela::websocket::Error> {
+/// let mut socket = connect("wss://example.com/chat").await?;
+/// socket.send(Message::Text("Hello".into())).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn connect(uri: &str) -> Result<WebSocket, Error> {
+    connect_with(uri, WebSocketConfig::default()).await
+}
+
+/// Same as `connect` using the message limits, idle timeout, and protocols of the config.
+/// The protocols are requested in order and the one the server picked is available with
+/// `WebSocket::protocol`.
+pub async fn connect_with(uri: &str, config: WebSocketConfig) -> Result<WebSocket, Error> {
+    let mut request = uri.into_client_request()?;
+    if !config.protocols.is_empty() {
+        let protocols = config.protocols.join(", ");
+        request.headers_mut().insert(
+            "Sec-WebSocket-Protocol",
+            protocols
+                .parse()
+                .map_err(|_| Error::Url(UrlError::UnableToConnect(protocols)))?,
+        );
+    }
+
+    // tungstenite uses an older version of the http types so they are converted
+    let uri: Uri = request
+        .uri()
+        .to_string()
+        .parse()
+        .map_err(|_| Error::Url(UrlError::NoPathOrQuery))?;
+    if uri.host().is_none() {
+        return Err(Error::Url(UrlError::NoHostName));
+    }
+    if !matches!(uri.scheme_str(), Some("ws") | Some("wss")) {
+        return Err(Error::Url(UrlError::UnsupportedUrlScheme));
+    }
+    let io = client::connect(&uri).await?;
+
+    let (stream, response) =
+        tokio_tungstenite::client_async_with_config(request, io, Some(config.tungstenite()))
+            .await?;
+    let mut headers = HeaderMap::new();
+    for (name, value) in response.headers() {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_str().as_bytes()),
+            HeaderValue::from_bytes(value.as_bytes()),
+        ) {
+            headers.append(name, value);
+        }
+    }
+    let protocol = headers
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+
+    Ok(WebSocket {
+        stream,
+        idle_timeout: config.idle_timeout,
+        uri,
+        headers,
+        protocol,
+        _permit: None,
+        guard: None,
+    })
+}
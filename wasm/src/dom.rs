@@ -0,0 +1,258 @@
+use std::fmt::Display;
+
+use wasm_bindgen::{closure::Closure, JsCast, UnwrapThrowExt};
+use web_sys::{Document, DocumentFragment, Element, Event, EventTarget, Node};
+
+use crate::signal::{effect, Effect, Signal};
+
+/// Get the global document
+pub fn document() -> Document {
+    web_sys::window()
+        .expect_throw("No global `window` exists")
+        .document()
+        .expect_throw("No document exists on `window`")
+}
+
+/// Event listener that is removed when dropped
+pub struct Listener {
+    target: EventTarget,
+    event: String,
+    closure: Option<Closure<dyn FnMut(Event)>>,
+}
+
+impl Listener {
+    /// Keep the listener attached for the rest of the page's life
+    pub fn forget(mut self) {
+        if let Some(closure) = self.closure.take() {
+            closure.forget();
+        }
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        if let Some(closure) = self.closure.take() {
+            let _ = self
+                .target
+                .remove_event_listener_with_callback(&self.event, closure.as_ref().unchecked_ref());
+        }
+    }
+}
+
+/// Add an event listener to a node. The listener is removed when the returned
+/// `Listener` is dropped.
+///
+/// # Example
+/// ```no_run
+/// use tela_wasm::{dom::{document, on}, Signal};
+///
+/// let count = Signal::new(0);
+/// let button = document().query_selector("#increment").unwrap().unwrap();
+/// on(&button, "click", move |_| count.update(|count| *count += 1)).forget();
+/// ```
+pub fn on<F: FnMut(Event) + 'static>(target: &EventTarget, event: &str, handler: F) -> Listener {
+    let closure = Closure::<dyn FnMut(Event)>::new(handler);
+    target
+        .add_event_listener_with_callback(event, closure.as_ref().unchecked_ref())
+        .unwrap_throw();
+    Listener {
+        target: target.clone(),
+        event: event.to_string(),
+        closure: Some(closure),
+    }
+}
+
+/// Nodes built by the `html!` macro along with the event listeners attached to them.
+pub struct View {
+    fragment: DocumentFragment,
+    listeners: Vec<Listener>,
+}
+
+impl View {
+    pub fn new(document: &Document) -> Self {
+        View {
+            fragment: document.create_document_fragment(),
+            listeners: Vec::new(),
+        }
+    }
+
+    /// Append a top level node
+    pub fn append(&self, node: &Node) {
+        self.fragment.append_child(node).unwrap_throw();
+    }
+
+    /// Add an event listener that lives as long as the view
+    pub fn listen<F: FnMut(Event) + 'static>(
+        &mut self,
+        target: &EventTarget,
+        event: &str,
+        handler: F,
+    ) {
+        self.listeners.push(on(target, event, handler));
+    }
+
+    pub fn fragment(&self) -> &DocumentFragment {
+        &self.fragment
+    }
+
+    /// Move the nodes into the parent. The listeners stay attached as long as the
+    /// returned `Mounted` is kept.
+    pub fn mount(self, parent: &Node) -> Mounted {
+        parent.append_child(&self.fragment).unwrap_throw();
+        Mounted(self.listeners)
+    }
+}
+
+/// Listeners of a view that has been mounted
+pub struct Mounted(Vec<Listener>);
+
+impl Mounted {
+    /// Keep the listeners attached for the rest of the page's life
+    pub fn forget(self) {
+        for listener in self.0 {
+            listener.forget();
+        }
+    }
+}
+/// Keep the text of a node in sync with a signal
+///
+/// # Example
+/// ```no_run
+/// use tela_wasm::{dom::{bind_text, document}, Signal};
+///
+/// let count = Signal::new(0);
+/// let label = document().query_selector("#count").unwrap().unwrap();
+/// bind_text(&label, &count);
+/// ```
+pub fn bind_text<T: Display + 'static>(node: &Node, signal: &Signal<T>) -> Effect {
+    let node = node.clone();
+    let signal = signal.clone();
+    effect(move || {
+        signal.with(|value| node.set_text_content(Some(&value.to_string())));
+    })
+}
+
+/// Keep an attribute of an element in sync with a signal.
+///
+/// When the signal is `None` the attribute is removed.
+pub fn bind_attribute<T: Display + 'static>(
+    element: &Element,
+    name: &str,
+    signal: &Signal<Option<T>>,
+) -> Effect {
+    let element = element.clone();
+    let name = name.to_string();
+    let signal = signal.clone();
+    effect(move || {
+        signal.with(|value| {
+            let _ = match value {
+                Some(value) => element.set_attribute(&name, &value.to_string()),
+                None => element.remove_attribute(&name),
+            };
+        });
+    })
+}
+
+/// Value of an attribute given in a `{..spread}`. `None` means the attribute is left off.
+///
+/// `true` is an empty boolean attribute, ex: `disabled`, and `false` leaves it off. `Option`
+/// values are left off when `None`.
+pub trait Attribute {
+    fn into_attribute(self) -> Option<String>;
+}
+
+impl Attribute for String {
+    fn into_attribute(self) -> Option<String> {
+        Some(self)
+    }
+}
+
+impl Attribute for &String {
+    fn into_attribute(self) -> Option<String> {
+        Some(self.clone())
+    }
+}
+
+impl Attribute for &str {
+    fn into_attribute(self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl Attribute for bool {
+    fn into_attribute(self) -> Option<String> {
+        self.then(String::new)
+    }
+}
+
+impl<T: Attribute> Attribute for Option<T> {
+    fn into_attribute(self) -> Option<String> {
+        self.and_then(Attribute::into_attribute)
+    }
+}
+
+macro_rules! display_attribute {
+    ($($ty: ty),*) => {
+        $(
+            impl Attribute for $ty {
+                fn into_attribute(self) -> Option<String> {
+                    Some(self.to_string())
+                }
+            }
+        )*
+    };
+}
+
+display_attribute!(char, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+/// Set an attribute of an element built by `html!` using the merge rules of `spread`
+pub fn set_attribute(element: &Element, name: &str, value: Option<String>) {
+    let Some(value) = value else {
+        let _ = element.remove_attribute(name);
+        return;
+    };
+    let value = match (name, element.get_attribute(name)) {
+        ("class", Some(current)) if !current.is_empty() => {
+            let mut classes: Vec<&str> = current.split_whitespace().collect();
+            for class in value.split_whitespace() {
+                if !classes.contains(&class) {
+                    classes.push(class);
+                }
+            }
+            classes.join(" ")
+        }
+        ("style", Some(current)) if !current.trim().is_empty() && !value.trim().is_empty() => {
+            format!("{}; {}", current.trim().trim_end_matches(';'), value.trim())
+        }
+        _ => value,
+    };
+    element.set_attribute(name, &value).unwrap_throw();
+}
+
+/// Set every attribute from a `{..spread}` in `html!`.
+///
+/// Attributes are applied in the order they are written, with spreads applied where they are
+/// placed, and merge with the ones already set:
+/// * `class` adds its classes to the element's classes, skipping ones it already has
+/// * `style` adds its declarations after the element's declarations
+/// * Any other attribute replaces the earlier value
+/// * `false` and `None` remove the attribute and `true` sets an empty boolean attribute
+///
+/// # Example
+/// ```no_run
+/// use tela_wasm::html;
+///
+/// let attrs = [("class", "primary"), ("title", "Save")];
+/// let button = html! { <button class="btn" {..attrs} disabled={false}>"Save"</button> };
+/// // <button class="btn primary" title="Save">Save</button>
+/// ```
+pub fn spread<I, K, V>(element: &Element, attributes: I)
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: Attribute,
+{
+    for (name, value) in attributes {
+        set_attribute(element, name.as_ref(), value.into_attribute());
+    }
+}
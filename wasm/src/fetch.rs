@@ -0,0 +1,213 @@
+use std::fmt::Display;
+
+use serde::de::DeserializeOwned;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Headers, RequestInit};
+
+/// Error from building, sending, or reading a request
+#[derive(Debug)]
+pub struct Error(pub String);
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<JsValue> for Error {
+    fn from(value: JsValue) -> Self {
+        match value.as_string() {
+            Some(message) => Error(message),
+            None => Error(format!("{:?}", value)),
+        }
+    }
+}
+
+/// Request body along with the content type it implies
+#[derive(Debug, Clone, Default)]
+pub struct Body {
+    bytes: Vec<u8>,
+    content_type: Option<String>,
+}
+
+impl Body {
+    pub fn new<T: Into<Vec<u8>>>(bytes: T) -> Self {
+        Body {
+            bytes: bytes.into(),
+            content_type: None,
+        }
+    }
+
+    pub fn content_type<T: Into<String>>(mut self, content_type: T) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+}
+
+impl From<String> for Body {
+    fn from(value: String) -> Self {
+        Body::new(value).content_type("text/plain")
+    }
+}
+
+impl From<&str> for Body {
+    fn from(value: &str) -> Self {
+        Body::new(value).content_type("text/plain")
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(value: Vec<u8>) -> Self {
+        Body::new(value)
+    }
+}
+
+impl From<&[u8]> for Body {
+    fn from(value: &[u8]) -> Self {
+        Body::new(value)
+    }
+}
+
+impl From<serde_json::Value> for Body {
+    fn from(value: serde_json::Value) -> Self {
+        Body::new(value.to_string()).content_type("application/json")
+    }
+}
+
+/// Request that is sent with the browser's `fetch` api
+///
+/// Most of the time the `fetch!` macro is used to build and send the request.
+#[derive(Debug, Clone)]
+pub struct Request {
+    url: String,
+    method: String,
+    headers: Vec<(String, String)>,
+    body: Option<Body>,
+}
+
+impl Request {
+    pub fn new<T: Into<String>>(url: T) -> Self {
+        Request {
+            url: url.into(),
+            method: "GET".to_string(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    pub fn method<T: Into<String>>(mut self, method: T) -> Self {
+        self.method = method.into().to_uppercase();
+        self
+    }
+
+    pub fn header<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.headers.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn body<T: Into<Body>>(mut self, body: T) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Send the request with `window.fetch`
+    pub async fn send(self) -> Result<Response, Error> {
+        let init = RequestInit::new();
+        init.set_method(&self.method);
+
+        let headers = Headers::new()?;
+        for (key, value) in self.headers.iter() {
+            headers.set(key, value)?;
+        }
+
+        if let Some(body) = self.body {
+            if let Some(content_type) = body.content_type {
+                if !self
+                    .headers
+                    .iter()
+                    .any(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+                {
+                    headers.set("Content-Type", &content_type)?;
+                }
+            }
+            init.set_body(&js_sys::Uint8Array::from(body.bytes.as_slice()));
+        }
+        init.set_headers(&headers);
+
+        let request = web_sys::Request::new_with_str_and_init(&self.url, &init)?;
+        let window = web_sys::window().ok_or(Error("No global `window` exists".to_string()))?;
+        let response = JsFuture::from(window.fetch_with_request(&request)).await?;
+
+        Ok(Response(response.dyn_into::<web_sys::Response>()?))
+    }
+}
+
+/// Response from a `fetch` request
+#[derive(Debug, Clone)]
+pub struct Response(pub web_sys::Response);
+
+impl Response {
+    pub fn status(&self) -> u16 {
+        self.0.status()
+    }
+
+    /// Whether the status is in the range 200-299
+    pub fn ok(&self) -> bool {
+        self.0.ok()
+    }
+
+    pub fn header(&self, name: &str) -> Option<String> {
+        self.0.headers().get(name).ok().flatten()
+    }
+
+    pub async fn text(&self) -> Result<String, Error> {
+        let text = JsFuture::from(self.0.text()?).await?;
+        text.as_string()
+            .ok_or(Error("Response body was not text".to_string()))
+    }
+
+    pub async fn bytes(&self) -> Result<Vec<u8>, Error> {
+        let buffer = JsFuture::from(self.0.array_buffer()?).await?;
+        Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+    }
+
+    pub async fn json<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        let text = self.text().await?;
+        serde_json::from_str(&text).map_err(|err| Error(err.to_string()))
+    }
+}
+
+/// Build and send a request returning a future of `Result<Response, Error>`.
+///
+/// `fetch!(url, options...)` has the same syntax as the server's `tela::fetch!` so shared code
+/// can make requests from both. The options are all optional and can be in any order:
+/// `method`, `headers`, and `body`. The server only options, `sign` and `client`, are compile
+/// errors here since the browser has no secret to sign with or `Client` to send through.
+///
+/// A url that is a string literal can have `{name}` placeholders for variables in scope. The
+/// values are percent-encoded except for a placeholder at the start, the base url. See `url!`.
+///
+/// # Example
+/// ```no_run
+/// use tela_wasm::fetch;
+///
+/// # async fn create() -> Result<(), tela_wasm::Error> {
+/// let (base, id) = ("https://api.example.com", 42);
+/// let response = fetch!(
+///     "{base}/users/{id}",
+///     method: POST,
+///     headers: { "Accept": "application/json" },
+///     body: serde_json::json!({ "name": "Tela" })
+/// ).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[macro_export]
+macro_rules! fetch {
+    ($($tokens: tt)*) => {
+        $crate::bump::fetch_request!(wasm $crate::fetch::Request; $($tokens)*)
+    };
+}
@@ -0,0 +1,161 @@
+//! Bring the islands the server renders with `tela::hydrate!` to life on the client.
+//!
+//! The server's `Island::loader` script calls the function exported for each component with
+//! the island's element. `island!` generates that function. It either hydrates the island,
+//! attaching to the markup the server rendered, or re-renders it from a `dom` view.
+
+use serde::de::DeserializeOwned;
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use web_sys::Element;
+
+use crate::{
+    dom::{document, View},
+    Error,
+};
+
+/// Props the server embedded in an island
+pub fn props<P: DeserializeOwned>(island: &Element) -> Result<P, Error> {
+    let script = island.query_selector(":scope > script[data-props]")?;
+    let json = script
+        .and_then(|script| script.text_content())
+        .ok_or(Error("Island is missing its props".to_string()))?;
+    serde_json::from_str(&json).map_err(|err| Error(format!("Invalid island props: {}", err)))
+}
+
+/// Hydrate an island; call `hydrate` with the island's element and props.
+///
+/// The server rendered markup is kept, along with anything the user typed before the module
+/// loaded, so `hydrate` attaches listeners and bindings to the nodes that are already there.
+/// Throws a javascript error if the props can't be read.
+pub fn hydrate_with<P, F>(island: &Element, hydrate: F)
+where
+    P: DeserializeOwned,
+    F: FnOnce(&Element, P),
+{
+    let props = props(island).unwrap_throw();
+    hydrate(island, props);
+}
+
+/// Client re-render; replace the server rendered markup of an island with a view.
+///
+/// Unlike hydrating, the server's nodes and any state they hold, ex: text typed into an
+/// input, are thrown away. The view's listeners stay attached for the rest of the page's life.
+pub fn rerender(island: &Element, view: View) {
+    island.set_inner_html("");
+    view.mount(island).forget();
+}
+
+/// Read an island's props, build its view, and `rerender` the island with it. Throws a
+/// javascript error if the props can't be read.
+pub fn rerender_with<P, F>(island: &Element, render: F)
+where
+    P: DeserializeOwned,
+    F: FnOnce(P) -> View,
+{
+    let props = props(island).unwrap_throw();
+    rerender(island, render(props));
+}
+
+/// Every island on the page for a component
+fn islands(component: &str) -> Vec<Element> {
+    let islands = document().query_selector_all("tela-island").unwrap_throw();
+    (0..islands.length())
+        .filter_map(|i| islands.item(i))
+        .filter_map(|node| node.dyn_into::<Element>().ok())
+        .filter(|island| island.get_attribute("data-component").as_deref() == Some(component))
+        .collect()
+}
+
+/// Hydrate every island on the page for a component; for pages that don't use the server's
+/// `Island::loader`.
+///
+/// # Example
+/// ```no_run
+/// use tela_wasm::{dom::{bind_text, on}, island::hydrate_all, Signal};
+///
+/// #[derive(serde::Deserialize)]
+/// struct Counter { count: u32 }
+///
+/// hydrate_all("Counter", |island, props: Counter| {
+///     let count = Signal::new(props.count);
+///     let output = island.query_selector("output").unwrap().unwrap();
+///     let button = island.query_selector("button").unwrap().unwrap();
+///     bind_text(&output, &count);
+///     on(&button, "click", move |_| count.update(|count| *count += 1)).forget();
+/// });
+/// ```
+pub fn hydrate_all<P, F>(component: &str, hydrate: F)
+where
+    P: DeserializeOwned,
+    F: Fn(&Element, P),
+{
+    for island in islands(component) {
+        hydrate_with(&island, &hydrate);
+    }
+}
+
+/// Re-render every island on the page for a component from a view; see `rerender`.
+pub fn rerender_all<P, F>(component: &str, render: F)
+where
+    P: DeserializeOwned,
+    F: Fn(P) -> View,
+{
+    for island in islands(component) {
+        rerender_with(&island, &render);
+    }
+}
+
+/// Export the function `Island::loader` calls for a client component. The name must match the
+/// one given to `tela::hydrate!`.
+///
+/// * `hydrate: |island, props| ...` attaches to the server rendered markup; see `hydrate_with`.
+/// * `rerender: |props| html! { ... }` replaces the server rendered markup with a view once the
+///   module loads; see `rerender`.
+///
+/// # Example
+/// ```no_run
+/// use tela_wasm::{dom::{bind_text, on}, html, island, Signal};
+///
+/// #[derive(serde::Deserialize)]
+/// struct Counter { count: u32 }
+///
+/// island!(Counter, hydrate: |island, props: Counter| {
+///     let count = Signal::new(props.count);
+///     let output = island.query_selector("output").unwrap().unwrap();
+///     let button = island.query_selector("button").unwrap().unwrap();
+///     bind_text(&output, &count);
+///     on(&button, "click", move |_| count.update(|count| *count += 1)).forget();
+/// });
+///
+/// #[derive(serde::Deserialize)]
+/// struct Greeting { name: String }
+///
+/// island!(Greeting, rerender: |props: Greeting| html! { <p>"Hello, "{props.name}</p> });
+/// ```
+#[macro_export]
+macro_rules! island {
+    ($component: ident, hydrate: $hydrate: expr $(,)?) => {
+        $crate::island!(@export $component, |island| {
+            $crate::island::hydrate_with(island, $hydrate)
+        });
+    };
+    ($component: ident, rerender: $render: expr $(,)?) => {
+        $crate::island!(@export $component, |island| {
+            $crate::island::rerender_with(island, $render)
+        });
+    };
+    (@export $component: ident, $mount: expr) => {
+        const _: () = {
+            use $crate::bump::wasm_bindgen::prelude::wasm_bindgen;
+
+            #[wasm_bindgen(js_name = $component, wasm_bindgen = $crate::bump::wasm_bindgen)]
+            pub fn mount(
+                island: $crate::bump::web_sys::Element,
+                _props: $crate::bump::wasm_bindgen::JsValue,
+            ) {
+                let mount: fn(&$crate::bump::web_sys::Element) = $mount;
+                mount(&island);
+            }
+        };
+    };
+}
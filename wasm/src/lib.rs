@@ -0,0 +1,17 @@
+pub mod dom;
+pub mod fetch;
+pub mod island;
+pub mod signal;
+
+pub use fetch::{Body, Error, Request, Response};
+pub use signal::{effect, Effect, Signal};
+pub use tela_macros::{dom as html, url};
+
+/// Re-export needed dependencies for macros
+pub mod bump {
+    pub use serde_json;
+    pub use wasm_bindgen;
+    pub use web_sys;
+
+    pub use tela_macros::fetch_request;
+}
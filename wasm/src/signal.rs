@@ -0,0 +1,140 @@
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Debug,
+    rc::Rc,
+};
+
+struct EffectInner {
+    callback: Box<dyn Fn()>,
+    active: Cell<bool>,
+}
+
+thread_local! {
+    /// Effect that is currently running; signals read while it runs subscribe it
+    static CURRENT: RefCell<Option<Rc<EffectInner>>> = const { RefCell::new(None) };
+}
+
+impl EffectInner {
+    fn run(self: &Rc<Self>) {
+        if !self.active.get() {
+            return;
+        }
+        let previous = CURRENT.with(|current| current.replace(Some(self.clone())));
+        (self.callback)();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+/// Handle to an effect created with `effect`.
+///
+/// The effect stays alive as long as a signal it reads is alive. Call `dispose` to stop it
+/// from running again.
+pub struct Effect(Rc<EffectInner>);
+
+impl Effect {
+    pub fn dispose(self) {
+        self.0.active.set(false);
+    }
+}
+
+/// Run the callback now and again whenever a signal it read changes.
+///
+/// # Example
+/// ```
+/// use tela_wasm::{effect, Signal};
+///
+/// let count = Signal::new(0);
+/// effect({
+///     let count = count.clone();
+///     move || println!("count: {}", count.get())
+/// });
+/// count.set(1); // prints `count: 1`
+/// ```
+pub fn effect<F: Fn() + 'static>(callback: F) -> Effect {
+    let effect = Rc::new(EffectInner {
+        callback: Box::new(callback),
+        active: Cell::new(true),
+    });
+    effect.run();
+    Effect(effect)
+}
+
+struct SignalInner<T> {
+    value: RefCell<T>,
+    subscribers: RefCell<Vec<Rc<EffectInner>>>,
+}
+
+/// Reactive value. Effects that read the value are re-run when it changes.
+///
+/// Cloning a signal creates another handle to the same value.
+pub struct Signal<T>(Rc<SignalInner<T>>);
+
+impl<T> Clone for Signal<T> {
+    fn clone(&self) -> Self {
+        Signal(self.0.clone())
+    }
+}
+
+impl<T: Debug> Debug for Signal<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Signal({:?})", self.0.value.borrow())
+    }
+}
+
+impl<T: 'static> Signal<T> {
+    pub fn new(value: T) -> Self {
+        Signal(Rc::new(SignalInner {
+            value: RefCell::new(value),
+            subscribers: RefCell::new(Vec::new()),
+        }))
+    }
+
+    /// Subscribe the running effect, if there is one, to this signal
+    fn track(&self) {
+        CURRENT.with(|current| {
+            if let Some(effect) = current.borrow().as_ref() {
+                let mut subscribers = self.0.subscribers.borrow_mut();
+                if !subscribers.iter().any(|s| Rc::ptr_eq(s, effect)) {
+                    subscribers.push(effect.clone());
+                }
+            }
+        });
+    }
+
+    /// Re-run every active effect that has read this signal
+    fn notify(&self) {
+        let subscribers = {
+            let mut subscribers = self.0.subscribers.borrow_mut();
+            subscribers.retain(|effect| effect.active.get());
+            subscribers.clone()
+        };
+        for effect in subscribers {
+            effect.run();
+        }
+    }
+
+    /// Read the value by reference
+    pub fn with<R, F: FnOnce(&T) -> R>(&self, callback: F) -> R {
+        self.track();
+        callback(&self.0.value.borrow())
+    }
+
+    /// Replace the value and notify subscribers
+    pub fn set(&self, value: T) {
+        *self.0.value.borrow_mut() = value;
+        self.notify();
+    }
+
+    /// Change the value in place and notify subscribers
+    pub fn update<F: FnOnce(&mut T)>(&self, callback: F) {
+        callback(&mut self.0.value.borrow_mut());
+        self.notify();
+    }
+}
+
+impl<T: Clone + 'static> Signal<T> {
+    /// Get a copy of the value
+    pub fn get(&self) -> T {
+        self.with(|value| value.clone())
+    }
+}